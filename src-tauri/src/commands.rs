@@ -1,81 +1,152 @@
 use std::sync::atomic::Ordering;
+use std::sync::Arc;
 
 use tauri::{AppHandle, Manager, State};
-use tauri_plugin_store::StoreExt;
 
 use crate::config;
-use crate::state::{AppState, ConnectionStatus};
+use crate::state::AppState;
+use crate::tools::coding_agent::{history, permissions};
+use crate::ws::link::{overall_status, Link};
 use crate::ws::WsClient;
 
-const STORE_FILE: &str = "settings.json";
-
-/// Store a fresh auth token from the frontend.
+/// Start the WebSocket connection of one pairing, or of every paired one.
 #[tauri::command]
-pub async fn set_auth_token(
+pub async fn connect_ws(
     app: AppHandle,
     state: State<'_, AppState>,
-    token: String,
+    pairing: Option<String>,
 ) -> Result<(), String> {
-    let state_clone = (*state).clone();
-    *state.auth_token.write().await = Some(token);
-    tauri::async_runtime::spawn(async move {
-        crate::session::benchling::register_current_session_with_backend(&app, &state_clone).await;
-    });
+    for link in links(&state, pairing.as_deref())? {
+        if link.is_running() || link.auth_token.read().await.is_none() {
+            continue;
+        }
+        spawn_client(&app, &state, link);
+    }
     Ok(())
 }
 
-/// Start the WebSocket connection to the backend.
-#[tauri::command]
-pub async fn connect_ws(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
-    let current = state.ws_status.read().await.clone();
-    if matches!(
-        current,
-        ConnectionStatus::Connected | ConnectionStatus::Connecting | ConnectionStatus::Reconnecting
-    ) {
-        return Ok(());
-    }
-
-    let ws_url = crate::ws_url();
-
+/// Start a WS run loop for `link`. Callers check first that none is running.
+pub(crate) fn spawn_client(app: &AppHandle, state: &AppState, link: Arc<Link>) {
     // Clear any prior disconnect request so the run loop will stay connected.
-    state.shutdown_requested.store(false, Ordering::SeqCst);
+    link.shutdown_requested.store(false, Ordering::SeqCst);
 
-    let state_clone = (*state).clone();
+    let state_clone = state.clone();
     let app_clone = app.clone();
 
     tauri::async_runtime::spawn(async move {
-        let client = WsClient::new(app_clone, state_clone, ws_url);
+        let client = WsClient::new(app_clone, state_clone, link);
         client.run().await;
     });
+}
 
+/// Disconnect one pairing's WebSocket, or all of them.
+#[tauri::command]
+pub async fn disconnect_ws(state: State<'_, AppState>, pairing: Option<String>) -> Result<(), String> {
+    for link in links(&state, pairing.as_deref())? {
+        stop_client(&link);
+    }
     Ok(())
 }
 
-/// Disconnect the WebSocket.
-#[tauri::command]
-pub async fn disconnect_ws(state: State<'_, AppState>) -> Result<(), String> {
+fn stop_client(link: &Link) {
     // Record the intent durably before notifying: the message loop may consume the
     // notify permit and tear down the socket before the run loop re-checks, so the
     // flag — not the permit — is what keeps it from auto-reconnecting.
-    state.shutdown_requested.store(true, Ordering::SeqCst);
-    state.ws_shutdown.notify_one();
-    Ok(())
+    link.shutdown_requested.store(true, Ordering::SeqCst);
+    link.ws_shutdown.notify_one();
 }
 
-/// Get current connection status.
+/// The link named `pairing`, or every link when None. Errors are
+/// `pairing_invalid:`.
+fn links(state: &AppState, pairing: Option<&str>) -> Result<Vec<Arc<Link>>, String> {
+    match pairing {
+        None => Ok(state.links.all()),
+        Some(id) => state
+            .links
+            .get(id)
+            .map(|link| vec![link])
+            .ok_or_else(|| format!("pairing_invalid: no pairing {id:?}")),
+    }
+}
+
+/// Get the connection status across pairings: the best any of them has.
 #[tauri::command]
 pub async fn get_connection_status(
     state: State<'_, AppState>,
 ) -> Result<serde_json::Value, String> {
-    let status = state.ws_status.read().await.clone();
-    let device_id = state.device_id.read().await.clone();
-
+    let statuses = state.links.all().iter().map(|l| l.status()).collect::<Vec<_>>();
     Ok(serde_json::json!({
-        "status": status,
-        "device_id": device_id,
+        "status": overall_status(statuses),
+        "pairings": state.links.all().len(),
     }))
 }
 
+/// Every pairing with its server, folder subset and live status.
+#[tauri::command]
+pub async fn get_pairings(state: State<'_, AppState>) -> Result<Vec<serde_json::Value>, String> {
+    let mut pairings = Vec::new();
+    for link in state.links.all() {
+        let environment = link.environment();
+        let mut entry = serde_json::to_value(link.pairing()).unwrap_or_default();
+        entry["environment_name"] = environment.name.into();
+        entry["ws_url"] = environment.ws_url.into();
+        entry["status"] = serde_json::to_value(link.status()).unwrap_or_default();
+        entry["device_id"] = link.device_id.read().await.clone().into();
+        pairings.push(entry);
+    }
+    Ok(pairings)
+}
+
+/// Rename a pairing.
+#[tauri::command]
+pub async fn rename_pairing(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    name: String,
+) -> Result<(), String> {
+    crate::pairing::validate_name(&name)?;
+    edit_pairing(&app, &state, &id, |p| p.name = name.trim().to_string())?;
+    crate::tray::update_tray_status(&app, &state);
+    Ok(())
+}
+
+/// Narrow the scoped folders a pairing reaches, or with None give it all of
+/// them. A connected pairing pushes its new list to the backend right away.
+#[tauri::command]
+pub async fn set_pairing_folders(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    folders: Option<Vec<String>>,
+) -> Result<(), String> {
+    let link = edit_pairing(&app, &state, &id, |p| p.folders = folders)?;
+    link.folders_changed.notify_one();
+    Ok(())
+}
+
+/// Apply `edit` to pairing `id`, saved and live.
+fn edit_pairing(
+    app: &AppHandle,
+    state: &AppState,
+    id: &str,
+    edit: impl FnOnce(&mut crate::pairing::Pairing),
+) -> Result<Arc<Link>, String> {
+    let no_pairing = || format!("pairing_invalid: no pairing {id:?}");
+    let link = state.links.get(id).ok_or_else(no_pairing)?;
+    let mut settings = config::load_settings(app);
+    let saved = settings
+        .pairings
+        .get_or_insert_with(Vec::new)
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or_else(no_pairing)?;
+    edit(saved);
+    *link.pairing.write().expect("pairing lock poisoned") = saved.clone();
+    config::save_settings(app, &settings);
+    Ok(link)
+}
+
 /// Get scoped folders.
 #[tauri::command]
 pub async fn get_scoped_folders(state: State<'_, AppState>) -> Result<Vec<String>, String> {
@@ -160,12 +231,14 @@ pub async fn set_device_name(
     Ok(())
 }
 
-/// Claim a pairing code from the backend and store the returned device token.
+/// Claim a pairing code from the backend: a new pairing, named `name`, with
+/// the environment settings select, connected at once.
 #[tauri::command]
 pub async fn claim_pairing_code(
     app: AppHandle,
     state: State<'_, AppState>,
     code: String,
+    name: Option<String>,
 ) -> Result<(), String> {
     let device_name = state.device_name.read().await.clone();
     let scoped_folders = state.scoped_folders.read().await.clone();
@@ -178,14 +251,11 @@ pub async fn claim_pairing_code(
         "linux"
     };
 
-    // Build the API URL from WS URL (same host)
-    let ws_url = crate::ws_url();
-    let api_base = ws_url
-        .replace("ws://", "http://")
-        .replace("wss://", "https://")
-        .replace("/v1/desktop-agent/ws", "");
-
-    let url = format!("{}/v1/desktop-agent/pair/claim", api_base);
+    let profile = crate::environment::selected(&config::load_settings(&app));
+    let name = name
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| profile.name.clone());
+    let url = format!("{}/v1/desktop-agent/pair/claim", profile.api_base());
 
     let platform_version = crate::ws::os_version();
 
@@ -198,9 +268,7 @@ pub async fn claim_pairing_code(
         "app_version": env!("CARGO_PKG_VERSION"),
     });
 
-    // Make HTTP request using reqwest-lite via tokio_tungstenite's underlying HTTP
-    // We'll use a simple TCP + HTTP approach since we don't have reqwest
-    let client = http_client();
+    let client = state.network.current().http_client();
     let resp = client
         .post(&url)
         .header("Content-Type", "application/json")
@@ -233,124 +301,97 @@ pub async fn claim_pairing_code(
         .and_then(|v| v.as_str())
         .ok_or("Missing device_token in response")?;
 
-    // Store token persistently
-    store_token(&app, device_token)?;
+    // Store the pairing and its token persistently. Settings are read again:
+    // the request took a while.
+    let mut settings = config::load_settings(&app);
+    let pairings = settings.pairings.get_or_insert_with(Vec::new);
+    let pairing = crate::pairing::Pairing {
+        id: crate::pairing::new_id(&name, pairings),
+        name: name.trim().to_string(),
+        environment: profile.id.clone(),
+        folders: None,
+    };
+    crate::pairing::store_token(&app, &pairing.id, device_token)?;
+    pairings.push(pairing.clone());
+    config::save_settings(&app, &settings);
+    log::info!("Paired {} with {} ({})", pairing.id, profile.name, profile.ws_url);
 
     // Set token in runtime state for immediate WS connection
-    *state.auth_token.write().await = Some(device_token.to_string());
-
-    // Device is now paired — reflect that in the tray menu label.
-    crate::tray::update_tray_pairing(&app, true);
+    let link = Arc::new(Link::new(pairing, profile));
+    *link.auth_token.write().await = Some(device_token.to_string());
+    state.links.insert(link.clone());
+    crate::tray::update_tray_status(&app, &state);
 
     // Connect NOW (ENG-1582 bug 1): the token was stored but nothing kicked
     // the WS, so a freshly paired device showed Offline until an app restart.
-    // Same spawn/dedup logic as connect_ws.
-    {
-        let current = state.ws_status.read().await.clone();
-        if !matches!(
-            current,
-            ConnectionStatus::Connected
-                | ConnectionStatus::Connecting
-                | ConnectionStatus::Reconnecting
-        ) {
-            state.shutdown_requested.store(false, Ordering::SeqCst);
-            let state_clone = (*state).clone();
-            let app_clone = app.clone();
-            let ws_url = crate::ws_url();
-            tauri::async_runtime::spawn(async move {
-                WsClient::new(app_clone, state_clone, ws_url).run().await;
-            });
-        }
-    }
+    // The link is new, so no run loop can be racing it.
+    spawn_client(&app, &state, link);
 
     crate::session::benchling::register_current_session_with_backend(&app, &state).await;
 
-    // NOTE: we deliberately do NOT force a reconnect here, and that is correct
-    // for production. Pairing is reached from the PairingScreen, which only shows
-    // when there is no token and (in a release build) no live connection — so the
-    // frontend's subsequent connect_ws() is a clean first connect using the token
-    // just stored above.
-    //
-    // The one place this is imperfect is the DEV build: it auto-connects as a
-    // throwaway `dev_local` device on startup (see lib.rs, gated on
-    // debug_assertions), so a live "ghost" connection already exists when you
-    // pair. connect_ws() no-ops while already connected, so the new token does not
-    // take over until that ghost connection drops (heartbeat timeout, ~90s) or the
-    // user toggles Disconnect -> Connect. This is a dev-only ergonomic quirk, not a
-    // production bug, so it is intentionally left as-is.
-    //
-    // Do NOT "fix" this by calling disconnect_ws() then connect_ws() here: that can
-    // leave the old run() loop racing the `shutdown_requested` flag and spawn a
-    // second concurrent reconnect loop — the exact reconnect-race class ENG-758
-    // stabilized. If a real "switch account while connected" flow is ever needed,
-    // do it as a single-loop reconnect SIGNAL (a force_reconnect Notify handled
-    // inside the existing message loop), never as disconnect-then-reconnect.
+    // NOTE: a new pairing always gets a new link and run loop; an existing
+    // link is never re-pointed at another token. Do NOT implement "switch
+    // account while connected" as disconnect_ws() then connect_ws() on one
+    // link: that can leave the old run() loop racing the `shutdown_requested`
+    // flag and spawn a second concurrent reconnect loop — the exact
+    // reconnect-race class ENG-758 stabilized. Use the single-loop reconnect
+    // SIGNAL (`Link::ws_reconnect`, handled inside the existing message loop).
 
     Ok(())
 }
 
-/// Get stored device token from persistent store.
-#[tauri::command]
-pub fn get_stored_token(app: AppHandle) -> Result<Option<String>, String> {
-    let store = app
-        .store(STORE_FILE)
-        .map_err(|e| format!("Failed to open store: {e}"))?;
-
-    Ok(store
-        .get("device_token")
-        .and_then(|v| serde_json::from_value(v).ok()))
-}
-
-/// Unlink the device: drop the runtime token, delete it from the persistent
-/// store, and flip the tray label back to "Pair device". Shared by the
-/// `clear_token` command (user-initiated "Unlink Device") and the WS client's
-/// revocation path, which must unlink authoritatively in Rust because the app
-/// can run with no webview alive at startup (the window opens on demand), so
-/// no frontend exists to handle the `token_invalid` event.
-pub(crate) async fn clear_device_token(app: &AppHandle, state: &AppState) -> Result<(), String> {
-    // Clear runtime state
-    *state.auth_token.write().await = None;
-
-    // Clear persistent store
-    let store = app
-        .store(STORE_FILE)
-        .map_err(|e| format!("Failed to open store: {e}"))?;
-    let _ = store.delete("device_token");
+/// Unpair: stop the pairing's client, forget its token and drop it from
+/// settings and the tray. Shared by the `remove_pairing` commands and the
+/// WS client's revocation path, which must unlink authoritatively in Rust
+/// because the app can run with no webview alive at startup (the window
+/// opens on demand), so no frontend exists to handle the `token_invalid`
+/// event.
+pub(crate) async fn remove_pairing_link(
+    app: &AppHandle,
+    state: &AppState,
+    id: &str,
+) -> Result<(), String> {
+    if let Some(link) = state.links.remove(id) {
+        stop_client(&link);
+        *link.auth_token.write().await = None;
+    }
 
-    // Device is no longer paired — reflect that in the tray menu label.
-    crate::tray::update_tray_pairing(app, false);
+    // Clear persistent store (this pairing only)
+    crate::pairing::delete_token(app, id)?;
+    let mut settings = config::load_settings(app);
+    if let Some(pairings) = settings.pairings.as_mut() {
+        pairings.retain(|p| p.id != id);
+    }
+    config::save_settings(app, &settings);
 
+    crate::tray::update_tray_status(app, state);
     Ok(())
 }
 
-/// Clear stored device token (unlink device).
+/// Unpair one account.
 #[tauri::command]
-pub async fn clear_token(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
-    clear_device_token(&app, &state).await
+pub async fn remove_pairing(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    remove_pairing_link(&app, &state, &id).await
 }
 
-fn store_token(app: &AppHandle, token: &str) -> Result<(), String> {
-    let store = app
-        .store(STORE_FILE)
-        .map_err(|e| format!("Failed to open store: {e}"))?;
-    let _ = store.set(
-        "device_token",
-        serde_json::to_value(token).unwrap_or_default(),
-    );
+/// Unpair every account (the "Unlink Device" button).
+#[tauri::command]
+pub async fn remove_all_pairings(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    for link in state.links.all() {
+        remove_pairing_link(&app, &state, &link.id).await?;
+    }
     Ok(())
 }
 
-/// Get the WebSocket URL (so the frontend can determine the environment).
+/// The WebSocket URL new pairings connect to (so the frontend can determine
+/// the environment).
 #[tauri::command]
-pub fn get_ws_url() -> String {
-    crate::ws_url()
-}
-
-fn http_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .user_agent(format!("BeakrDesktop/{}", env!("CARGO_PKG_VERSION")))
-        .build()
-        .expect("failed to build HTTP client")
+pub fn get_ws_url(app: AppHandle) -> String {
+    crate::environment::selected(&config::load_settings(&app)).ws_url
 }
 
 /// Coding-agent settings surface for the webview (ENG-1528). The API key is
@@ -358,25 +399,98 @@ fn http_client() -> reqwest::Client {
 #[tauri::command]
 pub async fn get_coding_agent_settings(app: AppHandle) -> Result<serde_json::Value, String> {
     let settings = config::load_settings(&app);
+    let builtin = permissions::builtin_profiles().into_iter().map(|p| (p, true));
+    let custom = settings
+        .permission_profiles
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| (p, false));
+    let profiles: Vec<serde_json::Value> = builtin
+        .chain(custom)
+        .map(|(p, builtin)| {
+            serde_json::json!({
+                "name": p.name,
+                "tools": p.tools,
+                "bash_allow": p.bash_allow,
+                "builtin": builtin,
+            })
+        })
+        .collect();
     Ok(serde_json::json!({
-        "has_api_key": settings.anthropic_api_key.map(|k| !k.is_empty()).unwrap_or(false),
+        "has_api_key": crate::secrets::get(&app, crate::secrets::ANTHROPIC_API_KEY)?
+            .is_some_and(|k| !k.is_empty()),
         "claude_binary_path": settings.claude_binary_path,
         "default_cli": settings.default_cli,
+        "permission_profile": settings
+            .permission_profile
+            .unwrap_or_else(|| permissions::DEFAULT_PROFILE.to_string()),
+        "permission_profiles": profiles,
+        "coding_sandbox": settings.coding_sandbox.unwrap_or(true),
+        "sandbox_supported": cfg!(target_os = "linux"),
+        "usage_budget": settings.usage_budget.unwrap_or_default(),
+        "permission_relay": settings.permission_relay.unwrap_or(false),
+        "coding_desktop_tools": settings.coding_desktop_tools.unwrap_or(true),
     }))
 }
 
 /// Update coding-agent settings. `None` leaves a field unchanged; an empty
-/// string clears it.
+/// string clears it. `permission_profiles` replaces the user-defined list
+/// wholesale; `permission_profile` picks the ceiling, which must exist in the
+/// resulting set.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)] // one optional argument per setting
 pub async fn set_coding_agent_settings(
     app: AppHandle,
     api_key: Option<String>,
     claude_binary_path: Option<String>,
     default_cli: Option<String>,
+    permission_profile: Option<String>,
+    permission_profiles: Option<Vec<permissions::PermissionProfile>>,
+    coding_sandbox: Option<bool>,
+    usage_budget: Option<crate::tools::coding_agent::usage::UsageBudget>,
+    permission_relay: Option<bool>,
+    coding_desktop_tools: Option<bool>,
 ) -> Result<(), String> {
     let mut settings = config::load_settings(&app);
+    if let Some(enabled) = coding_desktop_tools {
+        settings.coding_desktop_tools = Some(enabled);
+    }
+    if let Some(enabled) = coding_sandbox {
+        settings.coding_sandbox = Some(enabled);
+    }
+    if let Some(enabled) = permission_relay {
+        settings.permission_relay = Some(enabled);
+    }
+    if let Some(budget) = usage_budget {
+        if budget.daily_tokens == Some(0) || budget.monthly_tokens == Some(0) {
+            return Err("a usage budget must allow at least one token; leave it empty for no cap".into());
+        }
+        settings.usage_budget = Some(budget);
+    }
+    if let Some(profiles) = permission_profiles {
+        for (i, profile) in profiles.iter().enumerate() {
+            permissions::validate(profile)?;
+            if profiles[..i].iter().any(|p| p.name == profile.name) {
+                return Err(format!("duplicate permission profile '{}'", profile.name));
+            }
+        }
+        settings.permission_profiles = Some(profiles);
+    }
+    if let Some(name) = permission_profile {
+        settings.permission_profile = Some(name);
+    }
+    if let Some(name) = &settings.permission_profile {
+        let custom = settings.permission_profiles.as_deref().unwrap_or_default();
+        if permissions::find(name, custom).is_none() {
+            return Err(format!("unknown permission profile '{name}'"));
+        }
+    }
     if let Some(key) = api_key {
-        settings.anthropic_api_key = Some(key);
+        if key.is_empty() {
+            crate::secrets::delete(&app, crate::secrets::ANTHROPIC_API_KEY)?;
+        } else {
+            crate::secrets::set(&app, crate::secrets::ANTHROPIC_API_KEY, &key)?;
+        }
     }
     if let Some(path) = claude_binary_path {
         settings.claude_binary_path = Some(path);
@@ -398,12 +512,8 @@ pub async fn set_coding_agent_settings(
     // when disconnected the next register carries fresh state anyway.
     if default_changed {
         let state = app.state::<AppState>();
-        let sender = state
-            .ws_outbound
-            .read()
-            .expect("ws_outbound lock poisoned")
-            .clone();
-        if let Some(sender) = sender {
+        let senders: Vec<_> = state.links.all().iter().filter_map(|l| l.outbound()).collect();
+        if !senders.is_empty() {
             let settings = config::load_settings(&app);
             let claude = crate::tools::coding_agent::readiness::detect(
                 "claude",
@@ -423,12 +533,14 @@ pub async fn set_coding_agent_settings(
                 settings.default_cli.as_deref(),
                 &agents,
             );
-            let _ = sender
-                .send(crate::ws::protocol::OutgoingMessage::ReadinessUpdate {
-                    coding_agents: agents,
-                    coding_agent_default: Some(default.to_string()),
-                })
-                .await;
+            for sender in senders {
+                let _ = sender
+                    .send(crate::ws::protocol::OutgoingMessage::ReadinessUpdate {
+                        coding_agents: agents.clone(),
+                        coding_agent_default: Some(default.to_string()),
+                    })
+                    .await;
+            }
         }
     }
     Ok(())
@@ -457,6 +569,84 @@ pub async fn get_coding_agent_readiness(
     Ok(vec![claude, codex])
 }
 
+/// Today's and this month's coding-run usage per CLI, with the budget it is
+/// measured against and what is left of it (null when uncapped).
+#[tauri::command]
+pub fn get_coding_usage(app: AppHandle) -> Result<serde_json::Value, String> {
+    let summary = crate::tools::coding_agent::usage::current_summary(&app);
+    let budget = config::load_settings(&app).usage_budget.unwrap_or_default();
+    Ok(serde_json::json!({
+        "remaining_tokens": budget.remaining(&summary),
+        "budget": budget,
+        "summary": summary,
+    }))
+}
+
+/// Past coding runs, newest first, optionally filtered by a search query
+/// (every word must match the prompt, folder, CLI, session, error or a
+/// changed file).
+#[tauri::command]
+pub fn list_coding_runs(
+    app: AppHandle,
+    query: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<history::RunRecord>, String> {
+    let dir = crate::tools::coding_agent::runs_dir(&app)?;
+    let query = query.unwrap_or_default();
+    Ok(history::list(&dir)
+        .into_iter()
+        .filter(|run| history::matches(run, &query))
+        .take(limit.unwrap_or(100))
+        .collect())
+}
+
+/// One past run with the tail of its log, for the history detail view.
+#[tauri::command]
+pub fn open_coding_run(app: AppHandle, request_id: String) -> Result<history::OpenedRun, String> {
+    let dir = crate::tools::coding_agent::runs_dir(&app)?;
+    history::open(&dir, &request_id)
+}
+
+/// Continue a past run's CLI session with a new prompt, from the app window.
+/// The run goes through the same path as an engine request, so the slot,
+/// scoped folders, permission ceiling and budget all apply. Returns the new
+/// run's request_id; the outcome arrives as `coding_run:finished`.
+#[tauri::command]
+pub fn resume_coding_run(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    request_id: String,
+    prompt: String,
+) -> Result<String, String> {
+    if prompt.trim().is_empty() {
+        return Err("bad_params: the prompt cannot be empty".into());
+    }
+    let dir = crate::tools::coding_agent::runs_dir(&app)?;
+    let past = history::load(&dir, &request_id)?;
+    let session_id = past.session_id.ok_or(
+        "bad_params: that run never started a CLI session, so there is nothing to resume",
+    )?;
+    if state
+        .active_coding_run
+        .read()
+        .expect("active run lock poisoned")
+        .is_some()
+    {
+        return Err("coding_run_busy: a coding run is already in progress on this device".into());
+    }
+    let params = serde_json::json!({
+        "prompt": prompt,
+        "working_dir": past.working_dir,
+        "cli": past.cli,
+        "session_id": session_id,
+    });
+    Ok(crate::tools::coding_agent::start_local_run(
+        app,
+        (*state).clone(),
+        params,
+    ))
+}
+
 /// The active coding run for the app window (ENG-1552 run visibility) — lets
 /// a window opened mid-run catch up before any `coding_run:changed` event.
 #[tauri::command]
@@ -492,3 +682,277 @@ pub fn open_run_terminal(state: State<'_, AppState>) -> Result<(), String> {
         .ok_or("this run has no live log")?;
     crate::tools::coding_agent::open_log_in_terminal(&log_path)
 }
+
+/// The chunks a run has streamed so far, for a window that opens mid-run (or
+/// on a past run) to catch up before following `coding_run:chunk` events.
+#[tauri::command]
+pub fn get_coding_run_chunks(
+    app: AppHandle,
+    request_id: String,
+) -> Result<Vec<serde_json::Value>, String> {
+    let dir = crate::tools::coding_agent::runs_dir(&app)?;
+    crate::tools::coding_agent::backfill_run_chunks(&dir, &request_id)
+}
+
+/// The local MCP server's settings and status. Unlike the API key, the
+/// token is returned: the user has to paste it into their MCP client.
+#[tauri::command]
+pub async fn get_mcp_server(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let settings = config::load_settings(&app);
+    let port = settings.mcp_port.unwrap_or(crate::mcp::DEFAULT_PORT);
+    let address = state.mcp.address();
+    let url_port = address.map_or(port, |a| a.port());
+    Ok(serde_json::json!({
+        "enabled": settings.mcp_server.unwrap_or(false),
+        "port": port,
+        "running": address.is_some(),
+        "url": format!("http://127.0.0.1:{url_port}{}", crate::mcp::ENDPOINT),
+        "token": crate::secrets::get(&app, crate::secrets::MCP_TOKEN)?,
+    }))
+}
+
+/// Update the local MCP server's settings and restart it to match. `None`
+/// leaves a field unchanged; `rotate_token` mints a new token, cutting off
+/// every client configured with the old one.
+#[tauri::command]
+pub async fn set_mcp_server(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    enabled: Option<bool>,
+    port: Option<u16>,
+    rotate_token: Option<bool>,
+) -> Result<(), String> {
+    let mut settings = config::load_settings(&app);
+    if let Some(enabled) = enabled {
+        settings.mcp_server = Some(enabled);
+    }
+    if let Some(port) = port {
+        if port < 1024 {
+            return Err(format!("port {port} is reserved; pick one from 1024 to 65535"));
+        }
+        settings.mcp_port = Some(port);
+    }
+    if rotate_token == Some(true) {
+        crate::secrets::set(&app, crate::secrets::MCP_TOKEN, &crate::mcp::new_token())?;
+    }
+    config::save_settings(&app, &settings);
+    crate::mcp::apply_settings(&app, &state).await
+}
+
+/// Whether the document search model is downloaded (see `semantic`).
+#[tauri::command]
+pub async fn get_semantic_model(
+    state: State<'_, AppState>,
+) -> Result<crate::semantic::ModelStatus, String> {
+    Ok(state.semantic_index.model_status())
+}
+
+/// Download the document search model over the app's proxy and CAs.
+#[tauri::command]
+pub async fn download_semantic_model(state: State<'_, AppState>) -> Result<(), String> {
+    let client = state.network.current().http_client();
+    state.semantic_index.download_model(&client).await
+}
+
+/// Proxy and extra-CA settings, plus the route new connections take now.
+#[tauri::command]
+pub async fn get_network_settings(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let settings = config::load_settings(&app);
+    Ok(serde_json::json!({
+        "proxy": settings.proxy.unwrap_or_default(),
+        "ca_bundles": settings.ca_bundles.unwrap_or_default(),
+        "active": state.network.current().describe(),
+    }))
+}
+
+/// Update the proxy and extra-CA settings. `None` leaves a field unchanged.
+/// The new route is resolved before anything is saved, so a bad proxy URL
+/// or an unreadable bundle is reported (`proxy_invalid:` /
+/// `ca_bundle_invalid:`) and the working route stays. Open connections keep
+/// their route; the next connect uses the new one.
+#[tauri::command]
+pub async fn set_network_settings(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    proxy: Option<crate::network::ProxySettings>,
+    ca_bundles: Option<Vec<String>>,
+) -> Result<(), String> {
+    let mut settings = config::load_settings(&app);
+    if let Some(proxy) = proxy {
+        settings.proxy = Some(proxy);
+    }
+    if let Some(ca_bundles) = ca_bundles {
+        settings.ca_bundles = Some(ca_bundles);
+    }
+    let egress = crate::network::resolve(
+        &settings.proxy.clone().unwrap_or_default(),
+        settings.ca_bundles.as_deref().unwrap_or_default(),
+    )
+    .await?;
+    config::save_settings(&app, &settings);
+    log::info!("Outbound connections: {}", egress.describe());
+    state.network.set(egress);
+    Ok(())
+}
+
+/// Check every pairing's path to its engine, and Benchling (see
+/// `diagnostics`). Takes up to a few seconds per unreachable step.
+#[tauri::command]
+pub async fn run_diagnostics(
+    state: State<'_, AppState>,
+) -> Result<crate::diagnostics::Report, String> {
+    Ok(crate::diagnostics::run(&state).await)
+}
+
+/// Run the diagnostics and write them, redacted, to `path` for the user to
+/// send to support. Errors are `export_failed:`.
+#[tauri::command]
+pub async fn export_support_bundle(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<(), String> {
+    let report = crate::diagnostics::run(&state).await;
+    let bundle = crate::diagnostics::support_bundle(&report)
+        .map_err(|e| format!("export_failed: {e}"))?;
+    std::fs::write(&path, bundle).map_err(|e| format!("export_failed: {path}: {e}"))?;
+    log::info!("Wrote a support bundle to {path}");
+    Ok(())
+}
+
+/// The app log's level (see `logging`).
+#[tauri::command]
+pub fn get_log_level() -> String {
+    crate::logging::level().to_string().to_lowercase()
+}
+
+/// Change the app log's level at once, and for later launches. Errors are
+/// `log_level_invalid:`.
+#[tauri::command]
+pub fn set_log_level(app: AppHandle, level: String) -> Result<(), String> {
+    crate::logging::set_level(&app, &level)
+}
+
+/// Write the app log, rotated files included, to `path` as one JSON-lines
+/// file. Already redacted as it was written. Errors are `export_failed:`.
+#[tauri::command]
+pub fn export_logs(app: AppHandle, path: String) -> Result<(), String> {
+    crate::logging::export(&app, std::path::Path::new(&path))?;
+    log::info!("Exported the app log to {path}");
+    Ok(())
+}
+
+/// Tool timings and reconnects since launch (see `metrics`), and whether
+/// heartbeats share them with the server.
+#[tauri::command]
+pub fn get_metrics(state: State<'_, AppState>) -> serde_json::Value {
+    serde_json::json!({
+        "snapshot": crate::metrics::snapshot(&state),
+        "share": state.metrics.sharing(),
+    })
+}
+
+/// Turn sharing metrics on heartbeats on or off, from the next heartbeat.
+#[tauri::command]
+pub fn set_share_metrics(app: AppHandle, state: State<'_, AppState>, share: bool) {
+    state.metrics.set_sharing(share);
+    let mut settings = config::load_settings(&app);
+    settings.share_metrics = Some(share);
+    config::save_settings(&app, &settings);
+    log::info!("Metrics sharing {}", if share { "on" } else { "off" });
+}
+
+/// Every server environment, which one new pairings use, and which have
+/// pairings.
+#[tauri::command]
+pub async fn get_environments(app: AppHandle) -> Result<serde_json::Value, String> {
+    let settings = config::load_settings(&app);
+    let pairings = crate::pairing::all(&settings);
+    let profiles: Vec<serde_json::Value> = crate::environment::all(&settings)
+        .into_iter()
+        .map(|p| {
+            let paired = pairings.iter().any(|pairing| pairing.environment == p.id);
+            let mut entry = serde_json::to_value(&p).unwrap_or_default();
+            entry["paired"] = paired.into();
+            entry
+        })
+        .collect();
+    Ok(serde_json::json!({
+        "active": crate::environment::selected(&settings).id,
+        "profiles": profiles,
+    }))
+}
+
+/// Add a server environment, or update one the user added before. Errors
+/// are `environment_invalid:`. Pairings with an edited one reconnect to it.
+#[tauri::command]
+pub async fn save_environment(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    profile: crate::environment::Profile,
+) -> Result<(), String> {
+    crate::environment::validate(&profile)?;
+    let mut settings = config::load_settings(&app);
+    let environments = settings.environments.get_or_insert_with(Vec::new);
+    match environments.iter_mut().find(|p| p.id == profile.id) {
+        Some(existing) => *existing = profile.clone(),
+        None => environments.push(profile.clone()),
+    }
+    config::save_settings(&app, &settings);
+    for link in state.links.all() {
+        let mut environment = link.environment.write().expect("environment lock poisoned");
+        if environment.id != profile.id || *environment == profile {
+            continue;
+        }
+        *environment = profile.clone();
+        if link.is_running() {
+            link.ws_reconnect.notify_one();
+        }
+    }
+    Ok(())
+}
+
+/// Remove a user-added environment. Not while it is selected or a pairing
+/// uses it: switch away or unpair first.
+#[tauri::command]
+pub async fn delete_environment(app: AppHandle, id: String) -> Result<(), String> {
+    let mut settings = config::load_settings(&app);
+    if crate::environment::selected(&settings).id == id {
+        return Err("environment_invalid: switch to another environment first".into());
+    }
+    if let Some(pairing) = crate::pairing::all(&settings)
+        .into_iter()
+        .find(|p| p.environment == id)
+    {
+        return Err(format!(
+            "environment_invalid: {:?} is paired with it; unpair it first",
+            pairing.name
+        ));
+    }
+    let environments = settings.environments.get_or_insert_with(Vec::new);
+    let before = environments.len();
+    environments.retain(|p| p.id != id);
+    if environments.len() == before {
+        return Err(format!("environment_invalid: no user environment {id:?}"));
+    }
+    config::save_settings(&app, &settings);
+    Ok(())
+}
+
+/// Select the server environment the next pairing code is claimed from.
+/// Existing pairings keep theirs.
+#[tauri::command]
+pub async fn set_environment(app: AppHandle, id: String) -> Result<(), String> {
+    let mut settings = config::load_settings(&app);
+    if crate::environment::find(&settings, &id).is_none() {
+        return Err(format!("environment_invalid: no environment {id:?}"));
+    }
+    settings.environment = Some(id);
+    config::save_settings(&app, &settings);
+    Ok(())
+}
@@ -2,6 +2,12 @@ use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 use tauri_plugin_store::StoreExt;
 
+use crate::environment::Profile;
+use crate::network::ProxySettings;
+use crate::pairing::Pairing;
+use crate::tools::coding_agent::permissions::PermissionProfile;
+use crate::tools::coding_agent::usage::UsageBudget;
+
 const STORE_FILE: &str = "settings.json";
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -9,11 +15,6 @@ pub struct Settings {
     pub scoped_folders: Vec<String>,
     pub device_name: Option<String>,
     pub auto_connect: bool,
-    /// User's own Anthropic API key for local Claude Code runs (ENG-1528,
-    /// DESIGN.md decision 5). Stored locally in the settings store like the
-    /// device token; never synced to Beakr cloud and never returned to the
-    /// webview (write-only — see `get_coding_agent_settings`).
-    pub anthropic_api_key: Option<String>,
     /// Optional explicit path to the `claude` binary (Settings override for
     /// the login-shell/well-known-path resolution).
     pub claude_binary_path: Option<String>,
@@ -29,6 +30,52 @@ pub struct Settings {
     /// auth_failed and flips this to `false`.
     pub claude_auth_ok: Option<bool>,
     pub codex_auth_ok: Option<bool>,
+    /// The user's permission-profile ceiling for coding runs (built-in or
+    /// one of `permission_profiles`). None = "default". The engine can
+    /// request a narrower profile per run, never a wider one.
+    pub permission_profile: Option<String>,
+    /// User-defined permission profiles, validated on save. Optional like
+    /// the other fields so partial saves leave the stored list alone.
+    pub permission_profiles: Option<Vec<PermissionProfile>>,
+    /// OS-level sandbox around coding runs (Linux; see `sandbox`). None =
+    /// on. The off switch exists for CLIs that break under confinement.
+    pub coding_sandbox: Option<bool>,
+    /// Local token caps across coding runs (see `usage::UsageBudget`). A
+    /// budget with both caps unset clears them.
+    pub usage_budget: Option<UsageBudget>,
+    /// Let the engine ask, per run, for the CLI's permission prompts to be
+    /// relayed to the web user instead of refused. None = off: a relayed
+    /// prompt can allow a tool past the permission-profile ceiling.
+    pub permission_relay: Option<bool>,
+    /// Serve the desktop tools (scoped folders, Benchling) to coding runs
+    /// over a per-run MCP endpoint. None = on.
+    pub coding_desktop_tools: Option<bool>,
+    /// Serve the file and Benchling tools to local MCP clients (see `mcp`).
+    /// None = off.
+    pub mcp_server: Option<bool>,
+    /// Loopback port for the MCP server. None = `mcp::DEFAULT_PORT`. Its
+    /// token is a secret (see `secrets::MCP_TOKEN`).
+    pub mcp_port: Option<u16>,
+    /// How outbound connections reach the internet (see `network`). None =
+    /// follow the system.
+    pub proxy: Option<ProxySettings>,
+    /// PEM files of extra CAs to trust on every outbound TLS connection,
+    /// e.g. a TLS-inspecting corporate proxy's.
+    pub ca_bundles: Option<Vec<String>>,
+    /// Id of the server environment new pairings are claimed from (see
+    /// `environment`). None = the build's default.
+    pub environment: Option<String>,
+    /// Environments the user added: staging, self-hosted.
+    pub environments: Option<Vec<Profile>>,
+    /// The accounts this device is paired with (see `pairing`). None until
+    /// per-environment tokens have been migrated.
+    pub pairings: Option<Vec<Pairing>>,
+    /// Level of the app log (see `logging`): error, warn, info, debug or
+    /// trace. None = info.
+    pub log_level: Option<String>,
+    /// Whether heartbeats carry aggregated tool timings (see `metrics`).
+    /// None = off.
+    pub share_metrics: Option<bool>,
 }
 
 pub fn load_settings(app: &AppHandle) -> Settings {
@@ -51,10 +98,6 @@ pub fn load_settings(app: &AppHandle) -> Settings {
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
 
-    let anthropic_api_key: Option<String> = store
-        .get("anthropic_api_key")
-        .and_then(|v| serde_json::from_value(v).ok());
-
     let claude_binary_path: Option<String> = store
         .get("claude_binary_path")
         .and_then(|v| serde_json::from_value(v).ok());
@@ -66,15 +109,75 @@ pub fn load_settings(app: &AppHandle) -> Settings {
     let claude_auth_ok: Option<bool> = store.get("claude_auth_ok").and_then(|v| v.as_bool());
     let codex_auth_ok: Option<bool> = store.get("codex_auth_ok").and_then(|v| v.as_bool());
 
+    let permission_profile: Option<String> = store
+        .get("permission_profile")
+        .and_then(|v| serde_json::from_value(v).ok());
+
+    let permission_profiles: Option<Vec<PermissionProfile>> = store
+        .get("permission_profiles")
+        .and_then(|v| serde_json::from_value(v).ok());
+
+    let coding_sandbox: Option<bool> = store.get("coding_sandbox").and_then(|v| v.as_bool());
+
+    let usage_budget: Option<UsageBudget> = store
+        .get("usage_budget")
+        .and_then(|v| serde_json::from_value(v).ok());
+
+    let permission_relay: Option<bool> = store.get("permission_relay").and_then(|v| v.as_bool());
+    let coding_desktop_tools: Option<bool> =
+        store.get("coding_desktop_tools").and_then(|v| v.as_bool());
+
+    let mcp_server: Option<bool> = store.get("mcp_server").and_then(|v| v.as_bool());
+    let mcp_port: Option<u16> = store
+        .get("mcp_port")
+        .and_then(|v| serde_json::from_value(v).ok());
+
+    let proxy: Option<ProxySettings> = store
+        .get("proxy")
+        .and_then(|v| serde_json::from_value(v).ok());
+    let ca_bundles: Option<Vec<String>> = store
+        .get("ca_bundles")
+        .and_then(|v| serde_json::from_value(v).ok());
+
+    let environment: Option<String> = store
+        .get("environment")
+        .and_then(|v| serde_json::from_value(v).ok());
+    let environments: Option<Vec<Profile>> = store
+        .get("environments")
+        .and_then(|v| serde_json::from_value(v).ok());
+    let pairings: Option<Vec<Pairing>> = store
+        .get("pairings")
+        .and_then(|v| serde_json::from_value(v).ok());
+    let log_level: Option<String> = store
+        .get("log_level")
+        .and_then(|v| serde_json::from_value(v).ok());
+    let share_metrics: Option<bool> = store
+        .get("share_metrics")
+        .and_then(|v| serde_json::from_value(v).ok());
+
     Settings {
         scoped_folders,
         device_name,
         auto_connect,
-        anthropic_api_key,
         claude_binary_path,
         default_cli,
         claude_auth_ok,
         codex_auth_ok,
+        permission_profile,
+        permission_profiles,
+        coding_sandbox,
+        usage_budget,
+        permission_relay,
+        coding_desktop_tools,
+        mcp_server,
+        mcp_port,
+        proxy,
+        ca_bundles,
+        environment,
+        environments,
+        pairings,
+        log_level,
+        share_metrics,
     }
 }
 
@@ -122,9 +225,6 @@ pub fn save_settings(app: &AppHandle, settings: &Settings) {
         serde_json::Value::Bool(settings.auto_connect),
     );
 
-    if let Some(ref key) = settings.anthropic_api_key {
-        store.set("anthropic_api_key", serde_json::to_value(key).unwrap_or_default());
-    }
     if let Some(ref path) = settings.claude_binary_path {
         store.set(
             "claude_binary_path",
@@ -134,4 +234,55 @@ pub fn save_settings(app: &AppHandle, settings: &Settings) {
     if let Some(ref cli) = settings.default_cli {
         store.set("default_cli", serde_json::to_value(cli).unwrap_or_default());
     }
+    if let Some(ref profile) = settings.permission_profile {
+        store.set(
+            "permission_profile",
+            serde_json::to_value(profile).unwrap_or_default(),
+        );
+    }
+    if let Some(enabled) = settings.coding_sandbox {
+        store.set("coding_sandbox", serde_json::Value::Bool(enabled));
+    }
+    if let Some(ref profiles) = settings.permission_profiles {
+        store.set(
+            "permission_profiles",
+            serde_json::to_value(profiles).unwrap_or_default(),
+        );
+    }
+    if let Some(ref budget) = settings.usage_budget {
+        store.set("usage_budget", serde_json::to_value(budget).unwrap_or_default());
+    }
+    if let Some(enabled) = settings.permission_relay {
+        store.set("permission_relay", serde_json::Value::Bool(enabled));
+    }
+    if let Some(enabled) = settings.coding_desktop_tools {
+        store.set("coding_desktop_tools", serde_json::Value::Bool(enabled));
+    }
+    if let Some(enabled) = settings.mcp_server {
+        store.set("mcp_server", serde_json::Value::Bool(enabled));
+    }
+    if let Some(port) = settings.mcp_port {
+        store.set("mcp_port", serde_json::Value::from(port));
+    }
+    if let Some(ref proxy) = settings.proxy {
+        store.set("proxy", serde_json::to_value(proxy).unwrap_or_default());
+    }
+    if let Some(ref bundles) = settings.ca_bundles {
+        store.set("ca_bundles", serde_json::to_value(bundles).unwrap_or_default());
+    }
+    if let Some(ref environment) = settings.environment {
+        store.set("environment", serde_json::to_value(environment).unwrap_or_default());
+    }
+    if let Some(ref environments) = settings.environments {
+        store.set("environments", serde_json::to_value(environments).unwrap_or_default());
+    }
+    if let Some(ref pairings) = settings.pairings {
+        store.set("pairings", serde_json::to_value(pairings).unwrap_or_default());
+    }
+    if let Some(ref level) = settings.log_level {
+        store.set("log_level", serde_json::to_value(level).unwrap_or_default());
+    }
+    if let Some(share) = settings.share_metrics {
+        store.set("share_metrics", serde_json::Value::Bool(share));
+    }
 }
@@ -0,0 +1,603 @@
+//! Connection diagnostics: what to look at when "the agent is offline".
+//!
+//! [`run`] checks every pairing's path to its engine, step by step — DNS,
+//! the route (direct or which proxy), TCP, TLS, the WebSocket handshake,
+//! a Ping round-trip — plus the local clock against the server's and
+//! whether Benchling answers. Each step is timed and says what it saw, so a
+//! report tells a captive portal from a TLS-inspecting proxy from a revoked
+//! token without anyone reading logs. [`support_bundle`] is the same report
+//! with secrets scrubbed (see `redact`), for the user to send us.
+//!
+//! A connected pairing is never disturbed: its handshake and round-trip come
+//! from the live connection. A disconnected one gets a probe connection that
+//! upgrades, pings and closes without registering.
+
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+use crate::network::Egress;
+use crate::state::{AppState, ConnectionStatus};
+use crate::ws::link::Link;
+
+/// Longest any one check may take.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Clock skew beyond this is worth a look; beyond `SKEW_FAIL` token expiry
+/// and certificate validity checks start to misfire.
+const SKEW_WARN: i64 = 30;
+const SKEW_FAIL: i64 = 300;
+/// A Ping round-trip slower than this makes every tool call sluggish.
+const SLOW_RTT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Pass,
+    Warn,
+    Fail,
+    /// Not run, because an earlier step failed or it does not apply.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    /// How long the step took; None when it was not timed.
+    pub elapsed_ms: Option<u64>,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(
+        name: &'static str,
+        outcome: Outcome,
+        elapsed: Option<Duration>,
+        detail: String,
+    ) -> Self {
+        Self {
+            name,
+            outcome,
+            elapsed_ms: elapsed.map(|d| d.as_millis() as u64),
+            detail,
+        }
+    }
+
+    fn skipped(name: &'static str, why: &str) -> Self {
+        Self::new(name, Outcome::Skipped, None, why.to_string())
+    }
+}
+
+/// One pairing's path to its engine.
+#[derive(Debug, Serialize)]
+pub struct PairingReport {
+    pub id: String,
+    pub name: String,
+    pub environment: String,
+    pub ws_url: String,
+    pub status: ConnectionStatus,
+    pub device_id: Option<String>,
+    pub checks: Vec<Check>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub generated_at: DateTime<Utc>,
+    pub app_version: &'static str,
+    pub platform: &'static str,
+    pub os_version: String,
+    /// The route outbound connections take (see `network`).
+    pub network: String,
+    pub benchling: Check,
+    pub pairings: Vec<PairingReport>,
+}
+
+/// Run every check, all pairings at once.
+pub async fn run(state: &AppState) -> Report {
+    let egress = state.network.current();
+    let links = state.links.all();
+    let pairings = futures_util::future::join_all(
+        links.iter().map(|link| check_pairing(state, &egress, link)),
+    );
+    let (pairings, benchling) = tokio::join!(pairings, check_benchling(state));
+    Report {
+        generated_at: Utc::now(),
+        app_version: env!("CARGO_PKG_VERSION"),
+        platform: std::env::consts::OS,
+        os_version: crate::ws::os_version(),
+        network: egress.describe(),
+        benchling,
+        pairings,
+    }
+}
+
+/// `report` as a JSON document safe to hand to support: every string
+/// redacted, the query strings of server URLs dropped.
+pub fn support_bundle(report: &Report) -> Result<String, String> {
+    let mut value = serde_json::to_value(report).map_err(|e| e.to_string())?;
+    if let Some(pairings) = value.get_mut("pairings").and_then(|p| p.as_array_mut()) {
+        for pairing in pairings {
+            if let Some(serde_json::Value::String(url)) = pairing.get_mut("ws_url") {
+                url.truncate(url.find('?').unwrap_or(url.len()));
+            }
+        }
+    }
+    crate::redact::json(&mut value);
+    serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+}
+
+async fn check_pairing(state: &AppState, egress: &Egress, link: &Link) -> PairingReport {
+    let pairing = link.pairing();
+    let profile = link.environment();
+    let status = link.status();
+    let device_id = link.device_id.read().await.clone();
+    let mut report = PairingReport {
+        id: pairing.id,
+        name: pairing.name,
+        environment: profile.name.clone(),
+        ws_url: profile.ws_url.clone(),
+        status: status.clone(),
+        device_id,
+        checks: Vec::new(),
+    };
+    let checks = &mut report.checks;
+
+    let target = match Target::parse(&profile.ws_url) {
+        Ok(target) => target,
+        Err(e) => {
+            checks.push(Check::new("url", Outcome::Fail, None, e));
+            return report;
+        }
+    };
+    let proxy = egress.proxy_for(&target.host);
+
+    checks.push(check_dns(&target, proxy.is_some()).await);
+    checks.push(Check::new(
+        "proxy",
+        Outcome::Pass,
+        None,
+        match proxy {
+            Some(proxy) => format!("via {proxy}"),
+            None => "direct".to_string(),
+        },
+    ));
+
+    let (tcp, stream) = check_tcp(egress, &target).await;
+    checks.push(tcp);
+    match stream {
+        Some(stream) if target.tls => checks.push(check_tls(egress, &target, stream).await),
+        Some(_) => checks.push(Check::skipped("tls", "plain ws:// connection")),
+        None => checks.push(Check::skipped("tls", "no TCP connection")),
+    }
+
+    if status == ConnectionStatus::Connected {
+        checks.push(Check::new(
+            "handshake",
+            Outcome::Pass,
+            None,
+            "101 Switching Protocols (live connection)".into(),
+        ));
+        checks.push(match link.heartbeat_rtt() {
+            Some(rtt) => rtt_check(rtt, "last Ping of the live connection"),
+            None => Check::skipped("heartbeat", "no Pong on the live connection yet"),
+        });
+    } else if checks.iter().any(|c| c.outcome == Outcome::Fail) {
+        checks.push(Check::skipped("handshake", "an earlier step failed"));
+        checks.push(Check::skipped("heartbeat", "an earlier step failed"));
+    } else {
+        match link.auth_token.read().await.clone() {
+            Some(token) => checks.extend(probe_handshake(egress, &profile.ws_url, &token).await),
+            None => {
+                checks.push(Check::skipped("handshake", "no device token"));
+                checks.push(Check::skipped("heartbeat", "no device token"));
+            }
+        }
+    }
+
+    checks.push(check_clock(state, &profile.api_base()).await);
+    report
+}
+
+/// Host and port a `ws(s)://` URL connects to.
+struct Target {
+    host: String,
+    port: u16,
+    tls: bool,
+}
+
+impl Target {
+    fn parse(ws_url: &str) -> Result<Self, String> {
+        let url = url::Url::parse(ws_url).map_err(|e| format!("{ws_url} is not a URL: {e}"))?;
+        let tls = url.scheme() == "wss";
+        let host = url
+            .host_str()
+            .ok_or_else(|| format!("{ws_url} names no host"))?
+            .trim_matches(['[', ']'])
+            .to_string();
+        let port = url.port().unwrap_or(if tls { 443 } else { 80 });
+        Ok(Self { host, port, tls })
+    }
+}
+
+async fn check_dns(target: &Target, proxied: bool) -> Check {
+    let started = Instant::now();
+    let lookup = tokio::time::timeout(
+        CHECK_TIMEOUT,
+        tokio::net::lookup_host((target.host.as_str(), target.port)),
+    )
+    .await;
+    let elapsed = Some(started.elapsed());
+    let found = match lookup {
+        Ok(Ok(addrs)) => Ok(addrs.map(|a| a.ip().to_string()).collect::<Vec<_>>()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("no answer in {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    match found {
+        Ok(mut ips) if !ips.is_empty() => {
+            ips.sort();
+            ips.dedup();
+            Check::new("dns", Outcome::Pass, elapsed, ips.join(", "))
+        }
+        Ok(_) => Check::new("dns", Outcome::Fail, elapsed, "no addresses".into()),
+        // Behind a proxy the proxy resolves the name; a local miss is normal
+        // on networks that only resolve internal names.
+        Err(e) if proxied => Check::new(
+            "dns",
+            Outcome::Warn,
+            elapsed,
+            format!("{e} (the proxy resolves it for connections)"),
+        ),
+        Err(e) => Check::new("dns", Outcome::Fail, elapsed, e),
+    }
+}
+
+async fn check_tcp(egress: &Egress, target: &Target) -> (Check, Option<TcpStream>) {
+    let started = Instant::now();
+    let dialed = tokio::time::timeout(CHECK_TIMEOUT, egress.dial(&target.host, target.port)).await;
+    let elapsed = Some(started.elapsed());
+    let address = format!("{}:{}", target.host, target.port);
+    match dialed {
+        Ok(Ok(stream)) => (
+            Check::new(
+                "tcp",
+                Outcome::Pass,
+                elapsed,
+                format!("connected to {address}"),
+            ),
+            Some(stream),
+        ),
+        Ok(Err(e)) => (
+            Check::new("tcp", Outcome::Fail, elapsed, format!("{address}: {e}")),
+            None,
+        ),
+        Err(_) => (
+            Check::new(
+                "tcp",
+                Outcome::Fail,
+                elapsed,
+                format!("{address}: no connection in {}s", CHECK_TIMEOUT.as_secs()),
+            ),
+            None,
+        ),
+    }
+}
+
+async fn check_tls(egress: &Egress, target: &Target, stream: TcpStream) -> Check {
+    let connector = match egress.tls_connector() {
+        Ok(connector) => tokio_native_tls::TlsConnector::from(connector),
+        Err(e) => return Check::new("tls", Outcome::Fail, None, e.to_string()),
+    };
+    let started = Instant::now();
+    let handshake =
+        tokio::time::timeout(CHECK_TIMEOUT, connector.connect(&target.host, stream)).await;
+    let elapsed = Some(started.elapsed());
+    match handshake {
+        Ok(Ok(_)) => Check::new("tls", Outcome::Pass, elapsed, "certificate trusted".into()),
+        // The usual cause behind a corporate proxy: it re-signs TLS with a
+        // CA this machine does not trust yet (Settings → Network).
+        Ok(Err(e)) => Check::new("tls", Outcome::Fail, elapsed, e.to_string()),
+        Err(_) => Check::new(
+            "tls",
+            Outcome::Fail,
+            elapsed,
+            format!("no handshake in {}s", CHECK_TIMEOUT.as_secs()),
+        ),
+    }
+}
+
+/// Upgrade with the device token, Ping once and close, without registering:
+/// the handshake check and the heartbeat check of a pairing that is not
+/// connected.
+async fn probe_handshake(egress: &Egress, ws_url: &str, token: &str) -> [Check; 2] {
+    let request = match crate::ws::handshake_request(ws_url, token) {
+        Ok(request) => request,
+        Err(e) => {
+            return [
+                Check::new("handshake", Outcome::Fail, None, e.to_string()),
+                Check::skipped("heartbeat", "no handshake"),
+            ]
+        }
+    };
+    let started = Instant::now();
+    let connected = tokio::time::timeout(CHECK_TIMEOUT, egress.connect_ws(request)).await;
+    let elapsed = Some(started.elapsed());
+    let (mut ws, response) = match connected {
+        Ok(Ok(pair)) => pair,
+        Ok(Err(e)) => {
+            let (outcome, detail) = handshake_failure(&e);
+            return [
+                Check::new("handshake", outcome, elapsed, detail),
+                Check::skipped("heartbeat", "no handshake"),
+            ];
+        }
+        Err(_) => {
+            return [
+                Check::new(
+                    "handshake",
+                    Outcome::Fail,
+                    elapsed,
+                    format!("no answer in {}s", CHECK_TIMEOUT.as_secs()),
+                ),
+                Check::skipped("heartbeat", "no handshake"),
+            ]
+        }
+    };
+    let handshake = Check::new(
+        "handshake",
+        Outcome::Pass,
+        elapsed,
+        response.status().to_string(),
+    );
+
+    let started = Instant::now();
+    let pong = tokio::time::timeout(CHECK_TIMEOUT, async {
+        ws.send(Message::Ping(b"diagnostics".to_vec())).await?;
+        while let Some(message) = ws.next().await {
+            if let Message::Pong(_) = message? {
+                return Ok(started.elapsed());
+            }
+        }
+        Err(tungstenite::Error::ConnectionClosed)
+    })
+    .await;
+    let _ = ws.close(None).await;
+    let heartbeat = match pong {
+        Ok(Ok(rtt)) => rtt_check(rtt, "probe connection"),
+        Ok(Err(e)) => Check::new("heartbeat", Outcome::Fail, None, e.to_string()),
+        Err(_) => Check::new(
+            "heartbeat",
+            Outcome::Fail,
+            None,
+            format!("no Pong in {}s", CHECK_TIMEOUT.as_secs()),
+        ),
+    };
+    [handshake, heartbeat]
+}
+
+/// What a failed upgrade means, by status code where there is one.
+fn handshake_failure(error: &tungstenite::Error) -> (Outcome, String) {
+    match error {
+        tungstenite::Error::Http(response) => {
+            let status = response.status();
+            let meaning = match status.as_u16() {
+                401 | 403 => "device token rejected: revoked, or the engine is restarting",
+                407 => "the proxy wants credentials",
+                426 => "the server does not speak this protocol version",
+                502..=504 => "the engine or a gateway in front of it is down",
+                _ => "unexpected answer to the upgrade",
+            };
+            (Outcome::Fail, format!("{status}: {meaning}"))
+        }
+        e => (Outcome::Fail, e.to_string()),
+    }
+}
+
+fn rtt_check(rtt: Duration, source: &str) -> Check {
+    let outcome = if rtt > SLOW_RTT {
+        Outcome::Warn
+    } else {
+        Outcome::Pass
+    };
+    Check::new(
+        "heartbeat",
+        outcome,
+        Some(rtt),
+        format!("Ping round-trip, {source}"),
+    )
+}
+
+/// The local clock against the `Date` header of the API.
+async fn check_clock(state: &AppState, api_base: &str) -> Check {
+    let client = state.network.current().http_client();
+    let started = Instant::now();
+    let sent = Utc::now();
+    let response = tokio::time::timeout(CHECK_TIMEOUT, client.get(api_base).send()).await;
+    let elapsed = started.elapsed();
+    let response = match response {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => return Check::new("clock", Outcome::Skipped, Some(elapsed), e.to_string()),
+        Err(_) => {
+            return Check::new(
+                "clock",
+                Outcome::Skipped,
+                Some(elapsed),
+                format!("{api_base} did not answer in {}s", CHECK_TIMEOUT.as_secs()),
+            )
+        }
+    };
+    let Some(date) = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|d| d.to_str().ok())
+    else {
+        return Check::skipped("clock", "the server sent no Date header");
+    };
+    // The server stamped its Date somewhere in the round-trip: compare it
+    // with the local clock halfway through.
+    let midpoint = sent + chrono::Duration::from_std(elapsed / 2).unwrap_or_default();
+    match skew_seconds(date, midpoint) {
+        Some(skew) => {
+            let (outcome, detail) = skew_outcome(skew);
+            Check::new("clock", outcome, Some(elapsed), detail)
+        }
+        None => Check::skipped("clock", "unreadable Date header"),
+    }
+}
+
+/// Seconds the local clock is behind the server's (negative: ahead), from
+/// an HTTP `Date` header and the local time it was stamped at.
+fn skew_seconds(date_header: &str, local: DateTime<Utc>) -> Option<i64> {
+    let server = DateTime::parse_from_rfc2822(date_header).ok()?;
+    Some((server.with_timezone(&Utc) - local).num_seconds())
+}
+
+fn skew_outcome(skew: i64) -> (Outcome, String) {
+    let outcome = match skew.abs() {
+        s if s > SKEW_FAIL => Outcome::Fail,
+        s if s > SKEW_WARN => Outcome::Warn,
+        _ => Outcome::Pass,
+    };
+    let detail = match skew {
+        0 => "in step with the server".to_string(),
+        s if s > 0 => format!("{s}s behind the server"),
+        s => format!("{}s ahead of the server", -s),
+    };
+    (outcome, detail)
+}
+
+/// Whether Benchling answers, and whether the captured session still works.
+async fn check_benchling(state: &AppState) -> Check {
+    let cookie = state
+        .benchling_session
+        .read()
+        .await
+        .as_ref()
+        .map(|s| s.session_cookie.clone());
+    let started = Instant::now();
+    let probe = tokio::time::timeout(
+        CHECK_TIMEOUT,
+        crate::session::benchling::probe_users_me(state, cookie.as_deref().unwrap_or("")),
+    )
+    .await;
+    let elapsed = Some(started.elapsed());
+    let (outcome, detail) = match (probe, cookie.is_some()) {
+        (Ok(Ok(Some(_))), _) => (Outcome::Pass, "reachable, signed in"),
+        (Ok(Ok(None)), true) => (Outcome::Warn, "reachable, but the session has expired"),
+        (Ok(Ok(None)), false) => (Outcome::Pass, "reachable, not connected"),
+        (Ok(Err(e)), _) => return Check::new("benchling", Outcome::Fail, elapsed, e),
+        (Err(_), _) => (Outcome::Fail, "no answer"),
+    };
+    Check::new("benchling", outcome, elapsed, detail.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skew_is_read_from_the_http_date_header() {
+        let local = DateTime::parse_from_rfc3339("2026-10-18T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            skew_seconds("Sun, 18 Oct 2026 10:00:42 GMT", local),
+            Some(42)
+        );
+        assert_eq!(
+            skew_seconds("Sun, 18 Oct 2026 09:59:00 GMT", local),
+            Some(-60)
+        );
+        assert_eq!(skew_seconds("yesterday", local), None);
+
+        assert_eq!(skew_outcome(0).0, Outcome::Pass);
+        assert_eq!(
+            skew_outcome(-45),
+            (Outcome::Warn, "45s ahead of the server".into())
+        );
+        assert_eq!(skew_outcome(3600).0, Outcome::Fail);
+    }
+
+    #[test]
+    fn ws_urls_name_host_port_and_tls() {
+        let target = Target::parse("wss://api.beakr.example/v1/desktop-agent/ws").unwrap();
+        assert_eq!(
+            (target.host.as_str(), target.port, target.tls),
+            ("api.beakr.example", 443, true)
+        );
+        let target = Target::parse("ws://[::1]:8000/v1/desktop-agent/ws").unwrap();
+        assert_eq!(
+            (target.host.as_str(), target.port, target.tls),
+            ("::1", 8000, false)
+        );
+        assert!(Target::parse("not a url").is_err());
+    }
+
+    #[tokio::test]
+    async fn a_disconnected_pairing_is_probed_without_registering() {
+        use crate::ws::mock_engine::MockEngine;
+
+        let mut engine = MockEngine::start().await;
+        engine.reject_handshakes([403]);
+        let state = AppState::new();
+        let mut profile = crate::environment::default_profile();
+        profile.ws_url = engine.url();
+        let pairing = crate::pairing::Pairing {
+            id: "lab".into(),
+            name: "Lab".into(),
+            environment: profile.id.clone(),
+            folders: None,
+        };
+        let link = Link::new(pairing, profile);
+        *link.auth_token.write().await = Some("token".into());
+        let egress = state.network.current();
+
+        let outcomes = |report: &PairingReport| {
+            report
+                .checks
+                .iter()
+                .map(|c| (c.name, c.outcome))
+                .collect::<Vec<_>>()
+        };
+
+        let rejected = check_pairing(&state, &egress, &link).await;
+        let handshake = &rejected.checks[4];
+        assert_eq!(
+            (handshake.name, handshake.outcome),
+            ("handshake", Outcome::Fail)
+        );
+        assert!(
+            handshake.detail.contains("device token rejected"),
+            "{handshake:?}"
+        );
+
+        let accepted = check_pairing(&state, &egress, &link).await;
+        assert_eq!(
+            outcomes(&accepted)[..6],
+            [
+                ("dns", Outcome::Pass),
+                ("proxy", Outcome::Pass),
+                ("tcp", Outcome::Pass),
+                ("tls", Outcome::Skipped),
+                ("handshake", Outcome::Pass),
+                ("heartbeat", Outcome::Pass),
+            ]
+        );
+        assert_eq!(engine.attempts().len(), 2);
+        assert!(!engine.connects_within(Duration::from_millis(100)).await);
+    }
+
+    #[test]
+    fn a_forbidden_handshake_is_explained() {
+        let response = tungstenite::http::Response::builder()
+            .status(403)
+            .body(None)
+            .unwrap();
+        let (outcome, detail) = handshake_failure(&tungstenite::Error::Http(response));
+        assert_eq!(outcome, Outcome::Fail);
+        assert!(detail.starts_with("403 Forbidden: device token rejected"));
+    }
+}
@@ -0,0 +1,229 @@
+//! Server environments: which Beakr backend the app pairs with and connects
+//! to.
+//!
+//! The built-in profiles cover production, the sandbox and a local backend
+//! (plus, in a build made with some other `BEAKR_WS_URL`, that URL);
+//! Settings adds named ones for staging or a self-hosted deployment. Each
+//! pairing names the profile it was claimed from (see `pairing`), so several
+//! can be connected at once; the one settings select is where the next
+//! pairing code is claimed.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+pub const PRODUCTION: &str = "production";
+pub const SANDBOX: &str = "sandbox";
+pub const LOCAL: &str = "local";
+/// The URL baked in with `BEAKR_WS_URL`, when there is one.
+pub const BUILD: &str = "build";
+
+/// The path every backend serves the desktop protocol on.
+const WS_PATH: &str = "/v1/desktop-agent/ws";
+
+/// One backend the app can talk to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Stable key for settings and the token store: lowercase letters,
+    /// digits and dashes.
+    pub id: String,
+    pub name: String,
+    /// The desktop-protocol endpoint, `ws(s)://host[:port]/v1/desktop-agent/ws`.
+    pub ws_url: String,
+    /// Shipped with the app; cannot be edited or removed.
+    #[serde(default, skip_deserializing)]
+    pub builtin: bool,
+}
+
+impl Profile {
+    fn builtin(id: &str, name: &str, ws_url: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            ws_url: ws_url.to_string(),
+            builtin: true,
+        }
+    }
+
+    /// The HTTP base of the same backend, for pairing and the connector
+    /// endpoints: the WS URL with its scheme swapped and path dropped.
+    pub fn api_base(&self) -> String {
+        self.ws_url
+            .replacen("ws://", "http://", 1)
+            .replacen("wss://", "https://", 1)
+            .replace(WS_PATH, "")
+            .trim_end_matches('/')
+            .to_string()
+    }
+}
+
+/// The profiles every install has, plus the build's `BEAKR_WS_URL` when it
+/// is none of them.
+pub fn builtins() -> Vec<Profile> {
+    let mut profiles = vec![
+        Profile::builtin(
+            PRODUCTION,
+            "Production",
+            "wss://api.thebeakr.com/v1/desktop-agent/ws",
+        ),
+        Profile::builtin(
+            SANDBOX,
+            "Sandbox",
+            "wss://api-sandbox.thebeakr.com/v1/desktop-agent/ws",
+        ),
+        Profile::builtin(
+            LOCAL,
+            "Local Dev",
+            "ws://localhost:8000/v1/desktop-agent/ws",
+        ),
+    ];
+    if let Some(url) = option_env!("BEAKR_WS_URL") {
+        if !profiles.iter().any(|p| p.ws_url == url) {
+            profiles.insert(0, Profile::builtin(BUILD, "Build default", url));
+        }
+    }
+    profiles
+}
+
+/// Selected until the user picks another: the build's `BEAKR_WS_URL`, else
+/// localhost in debug, else production — what the compile-time `ws_url`
+/// used to pick.
+pub fn default_id() -> &'static str {
+    match option_env!("BEAKR_WS_URL") {
+        Some(url) => {
+            let profiles = builtins();
+            [PRODUCTION, SANDBOX, LOCAL]
+                .into_iter()
+                .find(|id| profiles.iter().any(|p| p.id == *id && p.ws_url == url))
+                .unwrap_or(BUILD)
+        }
+        None if cfg!(debug_assertions) => LOCAL,
+        None => PRODUCTION,
+    }
+}
+
+pub fn default_profile() -> Profile {
+    builtins()
+        .into_iter()
+        .find(|p| p.id == default_id())
+        .expect("the default profile is built in")
+}
+
+/// Built-ins first, then the user's.
+pub fn all(settings: &config::Settings) -> Vec<Profile> {
+    let mut profiles = builtins();
+    profiles.extend(settings.environments.iter().flatten().cloned());
+    profiles
+}
+
+/// The profile settings select for new pairings; the default when none is,
+/// or the selected one was removed.
+pub fn selected(settings: &config::Settings) -> Profile {
+    let id = settings.environment.as_deref().unwrap_or(default_id());
+    find(settings, id).unwrap_or_else(default_profile)
+}
+
+pub fn find(settings: &config::Settings, id: &str) -> Option<Profile> {
+    all(settings).into_iter().find(|p| p.id == id)
+}
+
+/// Check a user profile before saving. Errors are `environment_invalid:`.
+pub fn validate(profile: &Profile) -> Result<(), String> {
+    let valid_id = !profile.id.is_empty()
+        && profile
+            .id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid_id {
+        return Err(format!(
+            "environment_invalid: id {:?} must be lowercase letters, digits and dashes",
+            profile.id
+        ));
+    }
+    if builtins().iter().any(|p| p.id == profile.id) {
+        return Err(format!(
+            "environment_invalid: {:?} is a built-in environment",
+            profile.id
+        ));
+    }
+    if profile.name.trim().is_empty() {
+        return Err("environment_invalid: name is empty".into());
+    }
+    let url = url::Url::parse(&profile.ws_url)
+        .map_err(|e| format!("environment_invalid: {}: {e}", profile.ws_url))?;
+    if !matches!(url.scheme(), "ws" | "wss") || url.host_str().is_none() {
+        return Err(format!(
+            "environment_invalid: {} must be a ws:// or wss:// URL",
+            profile.ws_url
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(id: &str, ws_url: &str) -> Profile {
+        Profile {
+            id: id.into(),
+            name: "Staging".into(),
+            ws_url: ws_url.into(),
+            builtin: false,
+        }
+    }
+
+    #[test]
+    fn api_base_drops_the_protocol_path() {
+        assert_eq!(
+            custom("staging", "wss://api.staging.example/v1/desktop-agent/ws").api_base(),
+            "https://api.staging.example"
+        );
+        assert_eq!(
+            custom("lab", "ws://10.0.0.5:8000/v1/desktop-agent/ws").api_base(),
+            "http://10.0.0.5:8000"
+        );
+        assert_eq!(
+            custom("proxied", "wss://lab.example/beakr/v1/desktop-agent/ws").api_base(),
+            "https://lab.example/beakr"
+        );
+    }
+
+    #[test]
+    fn user_profiles_are_validated() {
+        assert!(validate(&custom(
+            "staging",
+            "wss://api.staging.example/v1/desktop-agent/ws"
+        ))
+        .is_ok());
+        for bad in [
+            custom("Staging", "wss://a.example/v1/desktop-agent/ws"),
+            custom("", "wss://a.example/v1/desktop-agent/ws"),
+            custom(PRODUCTION, "wss://a.example/v1/desktop-agent/ws"),
+            custom("staging", "https://a.example/v1/desktop-agent/ws"),
+            custom("staging", "not a url"),
+        ] {
+            let err = validate(&bad).unwrap_err();
+            assert!(err.starts_with("environment_invalid:"), "{err}");
+        }
+    }
+
+    #[test]
+    fn selection_falls_back_to_the_default() {
+        let mut settings = config::Settings::default();
+        assert_eq!(selected(&settings).id, default_id());
+
+        settings.environments = Some(vec![custom(
+            "staging",
+            "wss://s.example/v1/desktop-agent/ws",
+        )]);
+        settings.environment = Some("staging".into());
+        assert_eq!(
+            selected(&settings).ws_url,
+            "wss://s.example/v1/desktop-agent/ws"
+        );
+
+        settings.environment = Some("removed".into());
+        assert_eq!(selected(&settings).id, default_id());
+    }
+}
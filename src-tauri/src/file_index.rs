@@ -12,12 +12,15 @@
 //! Denied directories (`node_modules`, `.git`, …) and denied files are pruned
 //! at build time, and the query path re-checks scope, so the index can never
 //! surface a path the tools would otherwise block.
+//!
+//! One index serves every pairing: it covers all the scoped folders, and each
+//! query keeps only the files under its caller's scope.
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Mutex, RwLock};
-use std::time::SystemTime;
+use std::time::{Instant, SystemTime};
 
 use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
 use nucleo_matcher::{Config, Matcher, Utf32Str};
@@ -37,8 +40,7 @@ pub struct FileMeta {
 /// A full snapshot of the indexed trees, keyed by directory.
 #[derive(Clone, Default)]
 struct IndexState {
-    /// The scoped-folder roots this snapshot was built from. A change forces a
-    /// full rebuild rather than an incremental refresh.
+    /// The roots this snapshot was built from.
     roots: Vec<String>,
     /// Per-directory modification time at snapshot time.
     dir_mtimes: HashMap<PathBuf, SystemTime>,
@@ -51,6 +53,9 @@ struct IndexState {
 /// Thread-safe metadata cache over the scoped folders.
 pub struct FileIndex {
     state: RwLock<IndexState>,
+    /// The roots to index: all scoped folders (see `set_roots`), plus any
+    /// root a caller's scope named that they did not include.
+    roots: Mutex<Vec<String>>,
     /// How many times each path has been read via the `read_file` tool. A
     /// frecency signal that boosts files the agent keeps returning to.
     access: Mutex<HashMap<PathBuf, u32>>,
@@ -58,6 +63,8 @@ pub struct FileIndex {
     /// the filesystem watcher or the periodic fallback). `ensure_fresh` only
     /// re-walks when this is set, so most searches skip the walk entirely.
     dirty: AtomicBool,
+    /// How long refreshes take (reported by `metrics`).
+    pub refresh_time: crate::metrics::Histogram,
 }
 
 impl Default for FileIndex {
@@ -70,8 +77,10 @@ impl FileIndex {
     pub fn new() -> Self {
         Self {
             state: RwLock::new(IndexState::default()),
+            roots: Mutex::new(Vec::new()),
             access: Mutex::new(HashMap::new()),
             dirty: AtomicBool::new(true), // first search builds the index
+            refresh_time: crate::metrics::Histogram::default(),
         }
     }
 
@@ -80,18 +89,56 @@ impl FileIndex {
         *self.access.lock().unwrap().entry(path.to_path_buf()).or_insert(0) += 1;
     }
 
+    /// How often `path` has been read (the frecency signal).
+    pub fn access_count(&self, path: &Path) -> u32 {
+        self.access.lock().unwrap().get(path).copied().unwrap_or(0)
+    }
+
+    /// Every indexed file under `scope`, in no particular order. For
+    /// consumers that keep their own per-file data in step with the index
+    /// (`semantic`).
+    pub fn files(&self, scope: &[String]) -> Vec<FileMeta> {
+        let guard = self.state.read().unwrap();
+        guard
+            .files
+            .values()
+            .flatten()
+            .filter(|f| in_scope(&f.path, scope))
+            .cloned()
+            .collect()
+    }
+
     /// Flag that the scoped trees may have changed; the next `ensure_fresh`
     /// will re-walk. Called by the filesystem watcher and the periodic fallback.
     pub fn mark_dirty(&self) {
         self.dirty.store(true, Ordering::Release);
     }
 
-    /// Refresh the index only if it has been marked dirty since the last walk.
-    /// A file change between the flag-clear and the walk simply re-sets the
-    /// flag, so the following search reconciles it — no update is lost.
-    pub fn ensure_fresh(&self, roots: &[String]) {
-        if self.dirty.swap(false, Ordering::AcqRel) {
-            self.refresh(roots);
+    /// Index `roots` (all the scoped folders) from the next refresh on.
+    pub fn set_roots(&self, roots: &[String]) {
+        *self.roots.lock().unwrap() = roots.to_vec();
+        self.mark_dirty();
+    }
+
+    /// Refresh the index if it has been marked dirty since the last walk, or
+    /// does not cover every root of `scope` yet. Such a root is indexed from
+    /// then on beside the others, so callers with different scopes share one
+    /// snapshot instead of swapping it between them. A file change between
+    /// the flag-clear and the walk simply re-sets the flag, so the following
+    /// search reconciles it — no update is lost.
+    pub fn ensure_fresh(&self, scope: &[String]) {
+        let roots = {
+            let mut roots = self.roots.lock().unwrap();
+            for root in scope {
+                if !roots.contains(root) {
+                    roots.push(root.clone());
+                }
+            }
+            roots.clone()
+        };
+        let stale = self.state.read().unwrap().roots != roots;
+        if self.dirty.swap(false, Ordering::AcqRel) || stale {
+            self.refresh(&roots);
         }
     }
 
@@ -101,14 +148,8 @@ impl FileIndex {
     /// swaps the result in under a brief write lock, so concurrent searches are
     /// never blocked by the walk itself.
     pub fn refresh(&self, roots: &[String]) {
-        let old = {
-            let guard = self.state.read().unwrap();
-            if guard.roots == roots {
-                guard.clone()
-            } else {
-                IndexState::default() // root set changed -> full rebuild
-            }
-        };
+        let started = Instant::now();
+        let old = self.state.read().unwrap().clone();
 
         let mut next = IndexState {
             roots: roots.to_vec(),
@@ -124,6 +165,7 @@ impl FileIndex {
         }
 
         *self.state.write().unwrap() = next;
+        self.refresh_time.observe(started.elapsed());
     }
 
     /// Ranked filename/path search over the cached metadata.
@@ -133,10 +175,12 @@ impl FileIndex {
     /// match quality, then frecency (how often the file has been read), then
     /// recency (newest first), then name for a stable order. `root_filter`, when
     /// set, restricts to a subtree (the tool's optional `path` parameter).
-    /// Returns at most `limit` hits.
+    /// Only files under the caller's `scope` are returned. Returns at most
+    /// `limit` hits.
     pub fn search_names(
         &self,
         query: &str,
+        scope: &[String],
         root_filter: Option<&Path>,
         file_types: Option<&[String]>,
         limit: usize,
@@ -155,6 +199,9 @@ impl FileIndex {
         let mut scored: Vec<(u32, u32, Option<SystemTime>, &FileMeta)> = Vec::new();
         for files in guard.files.values() {
             for file in files {
+                if !in_scope(&file.path, scope) {
+                    continue;
+                }
                 if let Some(root) = root_filter {
                     if !file.path.starts_with(root) {
                         continue;
@@ -199,6 +246,12 @@ impl FileIndex {
     }
 }
 
+/// Whether `path` is under one of the scoped `roots`. Index paths are built
+/// by joining onto the roots as given, so a prefix check is exact here.
+pub fn in_scope(path: &Path, roots: &[String]) -> bool {
+    roots.iter().any(|root| path.starts_with(root))
+}
+
 /// Path relative to whichever scoped root contains it, used as the fuzzy-match
 /// haystack so folder names are searchable. Falls back to the file name.
 fn rel_path(path: &Path, roots: &[String]) -> String {
@@ -331,7 +384,7 @@ mod tests {
         let index = FileIndex::new();
         index.refresh(&tree.scoped());
 
-        let hits = index.search_names("note", None, None, 20);
+        let hits = index.search_names("note", &tree.scoped(), None, None, 20);
         assert_eq!(found_names(&hits), vec!["notebook.md", "notes.md"]);
     }
 
@@ -344,7 +397,7 @@ mod tests {
         let index = FileIndex::new();
         index.refresh(&tree.scoped());
 
-        let hits = index.search_names("index", None, None, 20);
+        let hits = index.search_names("index", &tree.scoped(), None, None, 20);
         let paths: Vec<String> = hits.iter().map(|f| f.path.display().to_string()).collect();
         assert_eq!(hits.len(), 1, "got {paths:?}");
         assert!(paths[0].ends_with("src/index.js"), "got {paths:?}");
@@ -358,10 +411,10 @@ mod tests {
         let index = FileIndex::new();
         index.refresh(&tree.scoped());
 
-        let rs = index.search_names("a", None, Some(&["rs".to_string()]), 20);
+        let rs = index.search_names("a", &tree.scoped(), None, Some(&["rs".to_string()]), 20);
         assert_eq!(found_names(&rs), vec!["a.rs"]);
 
-        let capped = index.search_names("a", None, None, 1);
+        let capped = index.search_names("a", &tree.scoped(), None, None, 1);
         assert_eq!(capped.len(), 1);
     }
 
@@ -371,17 +424,32 @@ mod tests {
         tree.write("first.txt", "x");
         let index = FileIndex::new();
         index.refresh(&tree.scoped());
-        assert_eq!(index.search_names("first", None, None, 20).len(), 1);
+        assert_eq!(
+            index
+                .search_names("first", &tree.scoped(), None, None, 20)
+                .len(),
+            1
+        );
 
         // Add a file, refresh, and confirm it appears (the dir mtime changed).
         tree.write("second.txt", "x");
         index.refresh(&tree.scoped());
-        assert_eq!(index.search_names("second", None, None, 20).len(), 1);
+        assert_eq!(
+            index
+                .search_names("second", &tree.scoped(), None, None, 20)
+                .len(),
+            1
+        );
 
         // Remove the first file and confirm it drops out.
         fs::remove_file(tree.root.join("first.txt")).unwrap();
         index.refresh(&tree.scoped());
-        assert_eq!(index.search_names("first", None, None, 20).len(), 0);
+        assert_eq!(
+            index
+                .search_names("first", &tree.scoped(), None, None, 20)
+                .len(),
+            0
+        );
     }
 
     #[test]
@@ -393,7 +461,7 @@ mod tests {
         index.refresh(&tree.scoped());
 
         let subtree = tree.root.join("keep");
-        let hits = index.search_names("target", Some(&subtree), None, 20);
+        let hits = index.search_names("target", &tree.scoped(), Some(&subtree), None, 20);
         assert_eq!(hits.len(), 1);
         assert!(hits[0].path.starts_with(&subtree));
     }
@@ -406,7 +474,7 @@ mod tests {
         let index = FileIndex::new();
         index.refresh(&tree.scoped());
 
-        let hits = index.search_names("cfg", None, None, 20);
+        let hits = index.search_names("cfg", &tree.scoped(), None, None, 20);
         assert_eq!(hits[0].name, "cfg.txt", "closer match should rank first");
     }
 
@@ -422,28 +490,105 @@ mod tests {
         // reading b's copy it must rank first on frecency.
         let b = tree.root.join("b/config.txt");
         index.record_access(&b);
-        let hits = index.search_names("config", None, None, 20);
+        let hits = index.search_names("config", &tree.scoped(), None, None, 20);
         assert_eq!(hits[0].path, b, "the read file should rank first");
     }
 
+    #[test]
+    fn pairings_with_disjoint_scopes_see_only_their_own_files() {
+        let lab = TempTree::new("scope_lab");
+        let home = TempTree::new("scope_home");
+        lab.write("plate-map.csv", "x");
+        home.write("plate-notes.md", "x");
+        let index = FileIndex::new();
+
+        // A scope the index does not cover yet is added to it, not swapped in.
+        index.ensure_fresh(&lab.scoped());
+        assert!(index
+            .search_names("plate", &home.scoped(), None, None, 20)
+            .is_empty());
+        index.ensure_fresh(&home.scoped());
+        assert_eq!(
+            found_names(&index.search_names("plate", &home.scoped(), None, None, 20)),
+            ["plate-notes.md"]
+        );
+        assert_eq!(
+            found_names(&index.search_names("plate", &lab.scoped(), None, None, 20)),
+            ["plate-map.csv"]
+        );
+
+        // Alternating between them does not re-walk.
+        let walks = index.refresh_time.snapshot().count;
+        index.ensure_fresh(&lab.scoped());
+        index.ensure_fresh(&home.scoped());
+        assert_eq!(index.refresh_time.snapshot().count, walks);
+    }
+
+    #[test]
+    fn concurrent_searches_from_two_scopes_each_get_their_files() {
+        let lab = TempTree::new("concurrent_lab");
+        let home = TempTree::new("concurrent_home");
+        lab.write("plate-map.csv", "x");
+        home.write("plate-notes.md", "x");
+        let index = std::sync::Arc::new(FileIndex::new());
+        index.set_roots(&[lab.scoped(), home.scoped()].concat());
+
+        let searches: Vec<_> = [
+            (lab.scoped(), "plate-map.csv"),
+            (home.scoped(), "plate-notes.md"),
+        ]
+        .into_iter()
+        .map(|(scope, expected)| {
+            let index = index.clone();
+            std::thread::spawn(move || {
+                for _ in 0..50 {
+                    // Re-walks interleave with the other scope's searches.
+                    index.mark_dirty();
+                    index.ensure_fresh(&scope);
+                    let hits = index.search_names("plate", &scope, None, None, 20);
+                    assert_eq!(found_names(&hits), [expected]);
+                }
+            })
+        })
+        .collect();
+        for search in searches {
+            search.join().unwrap();
+        }
+    }
+
     #[test]
     fn ensure_fresh_only_rewalks_when_dirty() {
         let tree = TempTree::new("dirty");
         tree.write("first.txt", "x");
         let index = FileIndex::new(); // starts dirty
         index.ensure_fresh(&tree.scoped());
-        assert_eq!(index.search_names("first", None, None, 20).len(), 1);
+        assert_eq!(
+            index
+                .search_names("first", &tree.scoped(), None, None, 20)
+                .len(),
+            1
+        );
 
         // Change on disk but do NOT mark dirty: ensure_fresh is a no-op, so the
         // new file is not yet visible.
         tree.write("second.txt", "x");
         index.ensure_fresh(&tree.scoped());
-        assert_eq!(index.search_names("second", None, None, 20).len(), 0);
+        assert_eq!(
+            index
+                .search_names("second", &tree.scoped(), None, None, 20)
+                .len(),
+            0
+        );
 
         // Marking dirty (what the watcher / fallback do) triggers the rescan.
         index.mark_dirty();
         index.ensure_fresh(&tree.scoped());
-        assert_eq!(index.search_names("second", None, None, 20).len(), 1);
+        assert_eq!(
+            index
+                .search_names("second", &tree.scoped(), None, None, 20)
+                .len(),
+            1
+        );
     }
 
     #[test]
@@ -456,7 +601,7 @@ mod tests {
 
         // "chatinpt" is not a substring of "chat-input.tsx" (the dash breaks it)
         // but is a subsequence — the point of fuzzy matching.
-        let hits = index.search_names("chatinpt", None, None, 20);
+        let hits = index.search_names("chatinpt", &tree.scoped(), None, None, 20);
         assert_eq!(hits.len(), 1);
         assert_eq!(hits[0].name, "chat-input.tsx");
     }
@@ -472,7 +617,7 @@ mod tests {
         // "hooks" is only in the folder and "swr" only in the filename — a
         // basename-only match would miss this; path-aware matching finds it.
         let hits: Vec<String> = index
-            .search_names("swr hooks", None, None, 20)
+            .search_names("swr hooks", &tree.scoped(), None, None, 20)
             .iter()
             .map(|f| f.name.clone())
             .collect();
@@ -496,7 +641,7 @@ mod tests {
         index.ensure_fresh(&tree.scoped());
 
         let hits: Vec<String> = index
-            .search_names("chat input", None, None, 20)
+            .search_names("chat input", &tree.scoped(), None, None, 20)
             .iter()
             .map(|f| f.name.clone())
             .collect();
@@ -514,7 +659,11 @@ mod tests {
         keep.write("build/house-plan.md", "x");
         let idx1 = FileIndex::new();
         idx1.ensure_fresh(&keep.scoped());
-        assert_eq!(idx1.search_names("house plan", None, None, 20).len(), 1);
+        assert_eq!(
+            idx1.search_names("house plan", &keep.scoped(), None, None, 20)
+                .len(),
+            1
+        );
 
         // package.json beside `dist`: it is a build tree, so prune it.
         let proj = TempTree::new("proj");
@@ -522,7 +671,11 @@ mod tests {
         proj.write("dist/bundle.min.js", "x");
         let idx2 = FileIndex::new();
         idx2.ensure_fresh(&proj.scoped());
-        assert_eq!(idx2.search_names("bundle", None, None, 20).len(), 0);
+        assert_eq!(
+            idx2.search_names("bundle", &proj.scoped(), None, None, 20)
+                .len(),
+            0
+        );
     }
 
     // Benchmark, not a correctness test. Run with:
@@ -578,7 +731,7 @@ mod tests {
         let tq = Instant::now();
         for _ in 0..rounds {
             for q in &queries {
-                std::hint::black_box(index.search_names(q, None, None, 20).len());
+                std::hint::black_box(index.search_names(q, &scoped, None, None, 20).len());
             }
         }
         let cached = tq.elapsed();
@@ -43,13 +43,13 @@ pub fn spawn(state: AppState) {
     tauri::async_runtime::spawn(async move {
         loop {
             let folders = state.scoped_folders.read().await.clone();
+            state.file_index.set_roots(&folders);
             // Keep the watcher alive for as long as these roots are current.
             let _watcher = build_watcher(&folders, state.file_index.clone());
             // A new folder set makes these roots (and the watcher) stale.
             // Waits on the watcher's own channel — sharing folders_changed
             // with the WS client starved one of them (ENG-1624).
             state.watch_folders_changed.notified().await;
-            state.file_index.mark_dirty();
         }
     });
 }
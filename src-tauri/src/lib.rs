@@ -1,10 +1,21 @@
 mod commands;
 mod config;
+mod diagnostics;
+mod environment;
 mod file_index;
 mod file_watch;
+mod logging;
+mod mcp;
+mod metrics;
+mod network;
+mod pairing;
 mod process_group;
+mod redact;
+mod sandbox;
+mod secrets;
 mod search_filter;
 mod security;
+mod semantic;
 mod session;
 mod state;
 mod tools;
@@ -13,18 +24,14 @@ pub mod unicode;
 mod ws;
 
 use state::AppState;
+use tauri::Manager;
 use tauri_plugin_autostart::MacosLauncher;
 
-/// Returns the WebSocket URL based on build configuration.
-/// Priority: BEAKR_WS_URL env var (compile-time) > debug=localhost > release=production
-pub fn ws_url() -> String {
-    if let Some(url) = option_env!("BEAKR_WS_URL") {
-        return url.to_string();
-    }
-    if cfg!(debug_assertions) {
-        "ws://localhost:8000/v1/desktop-agent/ws".to_string()
-    } else {
-        "wss://api.thebeakr.com/v1/desktop-agent/ws".to_string()
+/// Startup: a bad proxy or CA setting is logged and connections go direct;
+/// Settings shows the error when the user next saves.
+async fn apply_network_settings(app: &tauri::AppHandle, state: &AppState) {
+    if let Err(e) = network::apply_settings(app, state).await {
+        log::error!("Network settings not applied, connecting directly: {e}");
     }
 }
 
@@ -36,11 +43,7 @@ fn spawn_benchling_liveness(app_handle: tauri::AppHandle, state: AppState) {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Default to showing info-level logs in dev mode
-    if std::env::var("RUST_LOG").is_err() {
-        std::env::set_var("RUST_LOG", "beakr_desktop=info");
-    }
-    env_logger::init();
+    logging::init();
 
     let app_state = AppState::new();
 
@@ -62,17 +65,44 @@ pub fn run() {
             // menu bars can swallow the tray icon). Dock click → RunEvent::Reopen
             // (handled in run()) → settings window.
 
+            logging::attach(app.handle());
+
+            // Before any token is read: older versions kept secrets in the
+            // JSON store, and one pairing per environment.
+            secrets::migrate_plaintext(app.handle());
+            pairing::migrate(app.handle());
+
+            // Downloaded on the first semantic search, then kept.
+            match app.path().app_cache_dir() {
+                Ok(dir) => app_state.semantic_index.set_model_dir(dir.join("models")),
+                Err(e) => log::warn!("No cache dir, semantic search disabled: {e}"),
+            }
+
             // Load persisted settings
             let settings = config::load_settings(app.handle());
-            let has_stored_token = {
-                use tauri_plugin_store::StoreExt;
-                app.handle()
-                    .store("settings.json")
-                    .ok()
-                    .and_then(|store| store.get("device_token"))
-                    .and_then(|v| v.as_str().map(|s| !s.is_empty()))
-                    .unwrap_or(false)
-            };
+            app_state
+                .metrics
+                .set_sharing(settings.share_metrics.unwrap_or(false));
+            // One link per pairing; the ones with a token connect below.
+            let mut paired = Vec::new();
+            for saved in pairing::all(&settings) {
+                let profile = environment::find(&settings, &saved.environment)
+                    .unwrap_or_else(|| {
+                        log::warn!("{}: environment {} is gone", saved.id, saved.environment);
+                        environment::default_profile()
+                    });
+                log::info!("Pairing {}: {} ({})", saved.id, profile.name, profile.ws_url);
+                let token = pairing::load_token(app.handle(), &saved.id).unwrap_or_else(|e| {
+                    log::error!("Could not read the {} device token: {e}", saved.id);
+                    None
+                });
+                let link = std::sync::Arc::new(ws::link::Link::new(saved, profile));
+                app_state.links.insert(link.clone());
+                if let Some(token) = token {
+                    paired.push((link, token));
+                }
+            }
+            let has_stored_token = !paired.is_empty();
 
             {
                 let state = app_state.clone();
@@ -89,6 +119,17 @@ pub fn run() {
                 });
             }
 
+            // Local MCP server, when the user has turned it on.
+            {
+                let app_handle = app.handle().clone();
+                let state = app_state.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = mcp::apply_settings(&app_handle, &state).await {
+                        log::error!("MCP server did not start: {e}");
+                    }
+                });
+            }
+
             // Enable launch-at-login by default so the agent stays available
             {
                 use tauri_plugin_autostart::ManagerExt;
@@ -99,10 +140,10 @@ pub fn run() {
                 }
             }
 
-            // Set up system tray, then set the pairing-aware menu label from the
-            // stored token (claim_pairing_code / clear_token keep it in sync after).
+            // Set up system tray, then a status line per pairing and the
+            // pairing-aware menu label (kept in sync from here on).
             tray::setup_tray(app.handle())?;
-            tray::update_tray_pairing(app.handle(), has_stored_token);
+            tray::update_tray_status(app.handle(), &app_state);
 
             // First run (no paired device): open the window on launch so a
             // Finder/Spotlight launch lands on the pairing screen instead of a
@@ -118,34 +159,25 @@ pub fn run() {
             #[cfg(debug_assertions)]
             tray::show_settings_window(app.handle());
 
-            // Auto-connect if we have a stored device token
-            // (In dev mode without a token, the frontend will handle connection)
+            // Auto-connect every pairing that has a stored device token
             if has_stored_token {
-                log::info!("Found stored device token, auto-connecting on startup");
+                log::info!("Found {} paired account(s), auto-connecting on startup", paired.len());
                 let app_handle = app.handle().clone();
                 let state_clone = app_state.clone();
 
                 tauri::async_runtime::spawn(async move {
-                    // Load token from store and set in state
-                    use tauri_plugin_store::StoreExt;
-                    if let Ok(store) = app_handle.store("settings.json") {
-                        if let Some(token) = store
-                            .get("device_token")
-                            .and_then(|v| serde_json::from_value::<String>(v).ok())
-                        {
-                            *state_clone.auth_token.write().await = Some(token);
-                        }
-                    }
+                    // The route first: everything below connects out.
+                    apply_network_settings(&app_handle, &state_clone).await;
 
-                    {
+                    for (link, token) in paired {
+                        *link.auth_token.write().await = Some(token);
                         let ws_app = app_handle.clone();
                         let ws_state = state_clone.clone();
                         tauri::async_runtime::spawn(async move {
                             // Brief delay to let state initialization complete
                             tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
-                            let ws_url = ws_url();
-                            let client = ws::WsClient::new(ws_app, ws_state, ws_url);
+                            let client = ws::WsClient::new(ws_app, ws_state, link);
                             client.run().await;
                         });
                     }
@@ -162,19 +194,39 @@ pub fn run() {
                     spawn_benchling_liveness(app_handle, state_clone);
                 });
             } else {
+                // Pairing is the first connection out; it waits for no one.
+                {
+                    let app_handle = app.handle().clone();
+                    let state = app_state.clone();
+                    tauri::async_runtime::spawn(async move {
+                        apply_network_settings(&app_handle, &state).await;
+                    });
+                }
+
                 // With no stored token there is nothing to restore yet, but keep
                 // the liveness watcher alive so a later pairing/login flow is
                 // monitored without needing an app restart.
                 spawn_benchling_liveness(app.handle().clone(), app_state.clone());
 
-                if cfg!(debug_assertions) {
+                // The opt-in dev identity (see `WsClient::connect_and_run`)
+                // rides an unsaved link to the selected environment.
+                if cfg!(debug_assertions) && std::env::var("BEAKR_DEV_IDENTITY").is_ok() {
                     log::info!("Dev mode: auto-connecting WebSocket client");
+                    let profile = environment::selected(&settings);
+                    let link = std::sync::Arc::new(ws::link::Link::new(
+                        pairing::Pairing {
+                            id: "dev".into(),
+                            name: "Dev".into(),
+                            environment: profile.id.clone(),
+                            folders: None,
+                        },
+                        profile,
+                    ));
                     let app_handle = app.handle().clone();
                     let state_clone = app_state.clone();
                     tauri::async_runtime::spawn(async move {
                         tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-                        let ws_url = ws_url();
-                        let client = ws::WsClient::new(app_handle, state_clone, ws_url);
+                        let client = ws::WsClient::new(app_handle, state_clone, link);
                         client.run().await;
                     });
                 }
@@ -183,7 +235,6 @@ pub fn run() {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
-            commands::set_auth_token,
             commands::connect_ws,
             commands::disconnect_ws,
             commands::get_connection_status,
@@ -194,15 +245,40 @@ pub fn run() {
             commands::get_autostart,
             commands::set_autostart,
             commands::claim_pairing_code,
-            commands::get_stored_token,
-            commands::clear_token,
+            commands::get_pairings,
+            commands::rename_pairing,
+            commands::set_pairing_folders,
+            commands::remove_pairing,
+            commands::remove_all_pairings,
             commands::get_ws_url,
             commands::get_coding_agent_settings,
             commands::set_coding_agent_settings,
             commands::get_active_coding_run,
             commands::stop_coding_run,
             commands::open_run_terminal,
+            commands::get_coding_run_chunks,
             commands::get_coding_agent_readiness,
+            commands::get_coding_usage,
+            commands::list_coding_runs,
+            commands::open_coding_run,
+            commands::resume_coding_run,
+            commands::get_mcp_server,
+            commands::set_mcp_server,
+            commands::get_network_settings,
+            commands::set_network_settings,
+            commands::run_diagnostics,
+            commands::export_support_bundle,
+            commands::get_log_level,
+            commands::set_log_level,
+            commands::export_logs,
+            commands::get_metrics,
+            commands::set_share_metrics,
+            commands::get_semantic_model,
+            commands::download_semantic_model,
+            commands::get_environments,
+            commands::save_environment,
+            commands::delete_environment,
+            commands::set_environment,
             session::commands::connect_session,
             session::commands::benchling_status,
         ])
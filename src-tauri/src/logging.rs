@@ -0,0 +1,362 @@
+//! The app log: JSON lines in `<app-log-dir>/beakr.log`, rotated by size.
+//!
+//! Launched at login on macOS the app has no terminal, so stderr alone lost
+//! exactly the logs support needs — reconnect loops, handshake 403s (see
+//! `ws::client`). Every record goes to the file, and to stderr as before.
+//! Each message is scrubbed by `redact` on the way in, so tokens, cookies
+//! and prompts never reach the disk and an exported log is safe to send.
+//!
+//! The logger is installed first thing in `run`, before the app log dir is
+//! known; records up to [`attach`] are held in memory and written once the
+//! file is open. The level is a setting (`config::Settings::log_level`)
+//! that [`set_level`] changes at runtime; `RUST_LOG` overrides it for a
+//! session. Dependencies log at warn at most, whatever the level.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use chrono::{SecondsFormat, Utc};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+const FILE_NAME: &str = "beakr.log";
+/// A log file is rotated once it would grow past this.
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated files kept besides the live one: `beakr.log.1` (newest) to
+/// `beakr.log.3`.
+const KEEP_ROTATED: usize = 3;
+/// Records held before the file is open; more are dropped, oldest first.
+const PENDING_MAX: usize = 1000;
+const CRATE_TARGET: &str = env!("CARGO_CRATE_NAME");
+
+/// One line of the log file.
+#[derive(Serialize)]
+struct Line<'a> {
+    ts: String,
+    level: &'static str,
+    target: &'a str,
+    msg: String,
+}
+
+/// The live log file and its rotated predecessors.
+struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path, max_bytes: u64) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(FILE_NAME);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file,
+            size,
+            max_bytes,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{line}")?;
+        self.size += len;
+        Ok(())
+    }
+
+    /// `beakr.log` becomes `beakr.log.1`, `.1` becomes `.2`, and so on; the
+    /// oldest falls off the end.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        for n in (1..KEEP_ROTATED).rev() {
+            let from = rotated(&self.dir, n);
+            if from.exists() {
+                std::fs::rename(&from, rotated(&self.dir, n + 1))?;
+            }
+        }
+        let live = self.dir.join(FILE_NAME);
+        std::fs::rename(&live, rotated(&self.dir, 1))?;
+        self.file = OpenOptions::new().create(true).append(true).open(&live)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated(dir: &Path, n: usize) -> PathBuf {
+    dir.join(format!("{FILE_NAME}.{n}"))
+}
+
+/// Every log file in `dir`, oldest first.
+fn files(dir: &Path) -> Vec<PathBuf> {
+    (1..=KEEP_ROTATED)
+        .rev()
+        .map(|n| rotated(dir, n))
+        .chain([dir.join(FILE_NAME)])
+        .filter(|p| p.exists())
+        .collect()
+}
+
+enum Sink {
+    /// Before `attach`: lines waiting for the file.
+    Pending(Vec<String>),
+    File(RotatingFile),
+    /// The file could not be opened or written: stderr only from here on.
+    Off,
+}
+
+struct Logger {
+    /// The level as a `LevelFilter` discriminant.
+    level: AtomicUsize,
+    sink: Mutex<Sink>,
+}
+
+static LOGGER: Logger = Logger {
+    level: AtomicUsize::new(LevelFilter::Info as usize),
+    sink: Mutex::new(Sink::Pending(Vec::new())),
+};
+
+impl Logger {
+    fn level(&self) -> LevelFilter {
+        level_from_usize(self.level.load(Ordering::Relaxed))
+    }
+
+    fn write(&self, line: String) {
+        let mut sink = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        match &mut *sink {
+            Sink::Pending(lines) => {
+                if lines.len() == PENDING_MAX {
+                    lines.remove(0);
+                }
+                lines.push(line);
+            }
+            Sink::File(file) => {
+                if let Err(e) = file.write_line(&line) {
+                    eprintln!("Log file disabled: {e}");
+                    *sink = Sink::Off;
+                }
+            }
+            Sink::Off => {}
+        }
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        allows(self.level(), metadata.target(), metadata.level())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let msg = crate::redact::text(&record.args().to_string());
+        let ts = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        eprintln!("[{ts} {:<5} {}] {msg}", record.level(), record.target());
+        let line = Line {
+            ts,
+            level: record.level().as_str(),
+            target: record.target(),
+            msg,
+        };
+        if let Ok(line) = serde_json::to_string(&line) {
+            self.write(line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Sink::File(file) = &mut *self.sink.lock().unwrap_or_else(|e| e.into_inner()) {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+/// Whether a record of `level` from `target` passes `filter`: this crate's
+/// at `filter`, everyone else's at warn at most.
+fn allows(filter: LevelFilter, target: &str, level: Level) -> bool {
+    let own = target == CRATE_TARGET || target.starts_with(&format!("{CRATE_TARGET}::"));
+    let filter = if own {
+        filter
+    } else {
+        filter.min(LevelFilter::Warn)
+    };
+    level <= filter
+}
+
+fn level_from_usize(n: usize) -> LevelFilter {
+    LevelFilter::iter()
+        .find(|l| *l as usize == n)
+        .unwrap_or(LevelFilter::Info)
+}
+
+/// A level setting as a filter. Errors are `log_level_invalid:`.
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    match level.trim().parse::<LevelFilter>() {
+        Ok(LevelFilter::Off) | Err(_) => Err(format!(
+            "log_level_invalid: {level:?} is not one of error, warn, info, debug, trace"
+        )),
+        Ok(filter) => Ok(filter),
+    }
+}
+
+/// The level `RUST_LOG` asks for, if any: a bare level, or the last
+/// `target=level` directive's.
+fn env_level() -> Option<LevelFilter> {
+    let value = std::env::var("RUST_LOG").ok()?;
+    let directive = value.rsplit(',').next()?;
+    let level = directive.rsplit('=').next()?;
+    level.trim().parse().ok()
+}
+
+/// Install the logger. Call once, before anything logs.
+pub fn init() {
+    if log::set_logger(&LOGGER).is_err() {
+        return;
+    }
+    apply(env_level().unwrap_or(LevelFilter::Info));
+}
+
+fn apply(filter: LevelFilter) {
+    LOGGER.level.store(filter as usize, Ordering::Relaxed);
+    log::set_max_level(filter);
+}
+
+/// Open the log file, write what was logged so far into it and apply the
+/// saved level (unless `RUST_LOG` set one).
+pub fn attach(app: &AppHandle) {
+    let saved = crate::config::load_settings(app).log_level;
+    if env_level().is_none() {
+        if let Some(filter) = saved.as_deref().and_then(|l| parse_level(l).ok()) {
+            apply(filter);
+        }
+    }
+    let opened = log_dir(app).and_then(|dir| {
+        RotatingFile::open(&dir, MAX_FILE_BYTES).map_err(|e| format!("{}: {e}", dir.display()))
+    });
+    let mut sink = LOGGER.sink.lock().unwrap_or_else(|e| e.into_inner());
+    let pending = match std::mem::replace(&mut *sink, Sink::Off) {
+        Sink::Pending(lines) => lines,
+        _ => Vec::new(),
+    };
+    match opened {
+        Ok(mut file) => {
+            for line in pending {
+                if file.write_line(&line).is_err() {
+                    break;
+                }
+            }
+            *sink = Sink::File(file);
+        }
+        Err(e) => eprintln!("Log file disabled: {e}"),
+    }
+}
+
+fn log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_log_dir()
+        .map_err(|e| format!("no app log dir: {e}"))
+}
+
+pub fn level() -> LevelFilter {
+    LOGGER.level()
+}
+
+/// Change the level now, and save it for the next launch.
+pub fn set_level(app: &AppHandle, level: &str) -> Result<(), String> {
+    let filter = parse_level(level)?;
+    apply(filter);
+    let mut settings = crate::config::load_settings(app);
+    settings.log_level = Some(filter.to_string().to_lowercase());
+    crate::config::save_settings(app, &settings);
+    log::info!("Log level set to {filter}");
+    Ok(())
+}
+
+/// Write every log file, oldest first, into `to` as one JSON-lines file.
+pub fn export(app: &AppHandle, to: &Path) -> Result<(), String> {
+    LOGGER.flush();
+    let dir = log_dir(app).map_err(|e| format!("export_failed: {e}"))?;
+    concat(&files(&dir), to).map_err(|e| format!("export_failed: {}: {e}", to.display()))
+}
+
+fn concat(files: &[PathBuf], to: &Path) -> std::io::Result<()> {
+    let mut out = File::create(to)?;
+    for path in files {
+        std::io::copy(&mut File::open(path)?, &mut out)?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_rotate_past_the_size_limit_and_the_oldest_falls_off() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(dir.path(), 20).unwrap();
+        for n in 0..6 {
+            file.write_line(&format!("line {n} of the log")).unwrap();
+        }
+
+        let names: Vec<_> = files(dir.path())
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            ["beakr.log.3", "beakr.log.2", "beakr.log.1", "beakr.log"]
+        );
+
+        let out = dir.path().join("export.jsonl");
+        concat(&files(dir.path()), &out).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(out).unwrap(),
+            "line 2 of the log\nline 3 of the log\nline 4 of the log\nline 5 of the log\n"
+        );
+
+        // Reopened, a file carries on where it was.
+        let reopened = RotatingFile::open(dir.path(), 20).unwrap();
+        assert_eq!(reopened.size, 18);
+    }
+
+    #[test]
+    fn dependencies_log_at_warn_at_most() {
+        let own = format!("{CRATE_TARGET}::ws::client");
+        assert!(allows(LevelFilter::Debug, &own, Level::Debug));
+        assert!(!allows(LevelFilter::Info, &own, Level::Debug));
+        assert!(!allows(
+            LevelFilter::Debug,
+            "tungstenite::protocol",
+            Level::Info
+        ));
+        assert!(allows(
+            LevelFilter::Debug,
+            "tungstenite::protocol",
+            Level::Warn
+        ));
+        assert!(!allows(LevelFilter::Error, "reqwest", Level::Warn));
+    }
+
+    #[test]
+    fn levels_parse_from_settings() {
+        assert_eq!(parse_level("debug"), Ok(LevelFilter::Debug));
+        assert_eq!(parse_level(" WARN "), Ok(LevelFilter::Warn));
+        assert!(parse_level("off")
+            .unwrap_err()
+            .starts_with("log_level_invalid:"));
+        assert!(parse_level("loud").is_err());
+        assert_eq!(
+            level_from_usize(LevelFilter::Trace as usize),
+            LevelFilter::Trace
+        );
+    }
+}
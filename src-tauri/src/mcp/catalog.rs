@@ -0,0 +1,284 @@
+//! The tools the local MCP server offers, with their input schemas.
+//!
+//! Same names and params as the engine's WS tool surface — a local client
+//! gets exactly what the engine gets. Left out on purpose: `reveal_file` and
+//! `open_terminal` (they act on the user's screen) and `run_coding_agent`
+//! (a streaming, slot-holding run is not a tool call).
+
+use serde_json::{json, Map, Value};
+
+#[derive(Clone, Copy)]
+enum Kind {
+    String,
+    Boolean,
+    Integer,
+    Strings,
+}
+
+struct Param {
+    name: &'static str,
+    kind: Kind,
+    required: bool,
+    description: &'static str,
+}
+
+pub struct Tool {
+    pub name: &'static str,
+    description: &'static str,
+    params: &'static [Param],
+}
+
+const fn req(name: &'static str, description: &'static str) -> Param {
+    Param {
+        name,
+        kind: Kind::String,
+        required: true,
+        description,
+    }
+}
+
+const fn opt(name: &'static str, kind: Kind, description: &'static str) -> Param {
+    Param {
+        name,
+        kind,
+        required: false,
+        description,
+    }
+}
+
+const PROJECT_FILTER: Param = opt(
+    "project_id",
+    Kind::String,
+    "Only items in this Benchling project (folder id).",
+);
+const QUERY: Param = req("query", "Full-text search terms.");
+const LIMIT: Param = opt("limit", Kind::Integer, "Max results (default 25, max 50).");
+const MODIFIED_AFTER: Param = opt(
+    "modified_after",
+    Kind::String,
+    "Only items modified after this ISO-8601 date.",
+);
+
+const SEARCH: &[Param] = &[QUERY, PROJECT_FILTER, LIMIT];
+const SEARCH_DATED: &[Param] = &[QUERY, PROJECT_FILTER, LIMIT, MODIFIED_AFTER];
+const IN_PROJECT: &[Param] = &[PROJECT_FILTER];
+
+pub const TOOLS: &[Tool] = &[
+    Tool {
+        name: "list_files",
+        description: "List a directory inside the folders shared with Beakr Desktop.",
+        params: &[
+            req("path", "Directory to list."),
+            opt("recursive", Kind::Boolean, "Recurse into subdirectories."),
+            opt(
+                "pattern",
+                Kind::String,
+                "Glob pattern to filter file names.",
+            ),
+            opt(
+                "sort_by",
+                Kind::String,
+                "\"name\" (default), \"modified\" or \"size\".",
+            ),
+            opt("order", Kind::String, "\"asc\" or \"desc\"."),
+            opt(
+                "max_results",
+                Kind::Integer,
+                "Cap the listing, applied after sorting.",
+            ),
+        ],
+    },
+    Tool {
+        name: "search_files",
+        description: "Search file names (or contents) in the shared folders.",
+        params: &[
+            req("query", "Search term."),
+            opt("path", Kind::String, "Only search under this directory."),
+            opt(
+                "search_content",
+                Kind::Boolean,
+                "Search inside file contents.",
+            ),
+            opt(
+                "file_types",
+                Kind::Strings,
+                "Only these extensions, e.g. [\"py\", \"csv\"].",
+            ),
+            opt("limit", Kind::Integer, "Max results (default 20)."),
+        ],
+    },
+    Tool {
+        name: "semantic_search_files",
+        description: "Find documents in the shared folders by meaning, not exact words.",
+        params: &[
+            req("query", "What the documents are about."),
+            opt("path", Kind::String, "Only search under this directory."),
+            opt(
+                "file_types",
+                Kind::Strings,
+                "Only these extensions, e.g. [\"md\", \"txt\"].",
+            ),
+            opt("limit", Kind::Integer, "Max results (default 10, max 50)."),
+        ],
+    },
+    Tool {
+        name: "read_file",
+        description: "Read a file in the shared folders (binary files come back base64).",
+        params: &[
+            req("path", "File to read."),
+            opt("max_lines", Kind::Integer, "Only the first N lines."),
+        ],
+    },
+    Tool {
+        name: "file_info",
+        description: "Size, type and timestamps of a file or directory in the shared folders.",
+        params: &[req("path", "File or directory to inspect.")],
+    },
+    Tool {
+        name: "benchling_list_projects",
+        description: "List the connected Benchling account's projects.",
+        params: &[],
+    },
+    Tool {
+        name: "benchling_get_project",
+        description: "One Benchling project by id.",
+        params: &[req("project_id", "Project (folder) id.")],
+    },
+    Tool {
+        name: "benchling_search",
+        description: "Ranked full-text search across every Benchling item type.",
+        params: SEARCH_DATED,
+    },
+    Tool {
+        name: "benchling_search_projects",
+        description: "Search Benchling projects.",
+        params: SEARCH,
+    },
+    Tool {
+        name: "benchling_list_entries",
+        description: "List Benchling notebook entries.",
+        params: IN_PROJECT,
+    },
+    Tool {
+        name: "benchling_get_entry",
+        description: "One Benchling notebook entry, with its content.",
+        params: &[req("entry_id", "Entry id (etr_...).")],
+    },
+    Tool {
+        name: "benchling_search_entries",
+        description: "Search Benchling notebook entries, titles and bodies.",
+        params: SEARCH_DATED,
+    },
+    Tool {
+        name: "benchling_list_protocols",
+        description: "List Benchling protocols.",
+        params: IN_PROJECT,
+    },
+    Tool {
+        name: "benchling_get_protocol",
+        description: "One Benchling protocol.",
+        params: &[req("protocol_id", "Protocol id (prt_...).")],
+    },
+    Tool {
+        name: "benchling_search_protocols",
+        description: "Search Benchling protocols.",
+        params: SEARCH,
+    },
+    Tool {
+        name: "benchling_list_dna_sequences",
+        description: "List Benchling DNA/RNA sequences and oligos.",
+        params: IN_PROJECT,
+    },
+    Tool {
+        name: "benchling_get_dna_sequence",
+        description: "One Benchling DNA/RNA sequence.",
+        params: &[req("sequence_id", "Sequence id (seq_...).")],
+    },
+    Tool {
+        name: "benchling_search_dna_sequences",
+        description: "Search Benchling DNA/RNA sequences.",
+        params: SEARCH,
+    },
+    Tool {
+        name: "benchling_list_aa_sequences",
+        description: "List Benchling protein (AA) sequences.",
+        params: IN_PROJECT,
+    },
+    Tool {
+        name: "benchling_get_aa_sequence",
+        description: "One Benchling protein (AA) sequence.",
+        params: &[req("aa_sequence_id", "AA sequence id (prtn_...).")],
+    },
+    Tool {
+        name: "benchling_search_aa_sequences",
+        description: "Search Benchling protein (AA) sequences.",
+        params: SEARCH,
+    },
+    Tool {
+        name: "benchling_list_custom_entities",
+        description: "List Benchling custom entities.",
+        params: IN_PROJECT,
+    },
+    Tool {
+        name: "benchling_get_custom_entity",
+        description: "One Benchling custom entity.",
+        params: &[req("entity_id", "Custom entity id.")],
+    },
+    Tool {
+        name: "benchling_search_custom_entities",
+        description: "Search Benchling custom entities.",
+        params: SEARCH,
+    },
+    Tool {
+        name: "benchling_list_files",
+        description: "List files uploaded to Benchling.",
+        params: IN_PROJECT,
+    },
+    Tool {
+        name: "benchling_get_file",
+        description: "One Benchling file's metadata.",
+        params: &[req("file_id", "File id (file_...).")],
+    },
+    Tool {
+        name: "benchling_search_files",
+        description: "Search files uploaded to Benchling.",
+        params: SEARCH,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Tool> {
+    TOOLS.iter().find(|t| t.name == name)
+}
+
+impl Tool {
+    /// The `tools/list` entry: name, description and a JSON Schema.
+    pub fn describe(&self) -> Value {
+        let mut properties = Map::new();
+        for p in self.params {
+            let mut schema = match p.kind {
+                Kind::String => json!({ "type": "string" }),
+                Kind::Boolean => json!({ "type": "boolean" }),
+                Kind::Integer => json!({ "type": "integer", "minimum": 1 }),
+                Kind::Strings => json!({ "type": "array", "items": { "type": "string" } }),
+            };
+            schema["description"] = Value::from(p.description);
+            properties.insert(p.name.to_string(), schema);
+        }
+        let required: Vec<&str> = self
+            .params
+            .iter()
+            .filter(|p| p.required)
+            .map(|p| p.name)
+            .collect();
+        json!({
+            "name": self.name,
+            "description": self.description,
+            "inputSchema": {
+                "type": "object",
+                "properties": properties,
+                "required": required,
+            },
+            "annotations": { "readOnlyHint": true },
+        })
+    }
+}
@@ -0,0 +1,303 @@
+//! Just enough HTTP/1.1 for the MCP endpoint: one request per connection,
+//! `Content-Length` bodies only, and `Connection: close` on every response.
+//!
+//! MCP's streamable-HTTP transport lets a server answer a POST with plain
+//! JSON, so there is no SSE and no keep-alive to manage. The admission checks
+//! live here too: a loopback listener is still reachable from any web page
+//! the user has open, so the Host/Origin checks (DNS rebinding) matter as
+//! much as the token.
+
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Upper bound on the request line plus headers.
+const MAX_HEAD: u64 = 16 * 1024;
+/// Upper bound on a JSON-RPC body. Tool arguments are paths and queries.
+pub const MAX_BODY: usize = 1024 * 1024;
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// First value of a header, matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+pub struct Response {
+    pub status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub fn empty(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn text(status: u16, message: &str) -> Self {
+        Self {
+            body: format!("{message}\n").into_bytes(),
+            ..Self::empty(status)
+        }
+        .header("Content-Type", "text/plain; charset=utf-8")
+    }
+
+    pub fn json(value: &Value) -> Self {
+        Self {
+            body: serde_json::to_vec(value).unwrap_or_default(),
+            ..Self::empty(200)
+        }
+        .header("Content-Type", "application/json")
+    }
+
+    pub fn header(mut self, name: &'static str, value: &str) -> Self {
+        self.headers.push((name, value.to_string()));
+        self
+    }
+}
+
+/// Read one request. A malformed or oversized one comes back as the error
+/// response to send instead.
+pub async fn read_request<R: AsyncRead + Unpin>(reader: R) -> Result<Request, Response> {
+    let mut reader = BufReader::new(reader);
+    let mut head_left = MAX_HEAD;
+    let request_line = read_head_line(&mut reader, &mut head_left).await?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(m), Some(p), Some(v)) if v.starts_with("HTTP/1.") => (m.to_string(), p.to_string()),
+        _ => return Err(Response::text(400, "Malformed request line")),
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_head_line(&mut reader, &mut head_left).await?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| Response::text(400, "Malformed header"))?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+    let mut request = Request {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+    };
+
+    if request.header("Transfer-Encoding").is_some() {
+        return Err(Response::text(
+            501,
+            "Chunked bodies are not supported; send Content-Length",
+        ));
+    }
+    let length = match request.header("Content-Length") {
+        Some(v) => v
+            .parse::<usize>()
+            .map_err(|_| Response::text(400, "Bad Content-Length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err(Response::text(413, "Request body too large"));
+    }
+    request.body = vec![0; length];
+    reader
+        .read_exact(&mut request.body)
+        .await
+        .map_err(|_| Response::text(400, "Truncated body"))?;
+    Ok(request)
+}
+
+/// One CRLF- (or LF-) terminated head line, charged against the head budget.
+async fn read_head_line<R: AsyncRead + Unpin>(
+    reader: &mut BufReader<R>,
+    head_left: &mut u64,
+) -> Result<String, Response> {
+    let mut line = String::new();
+    let n = reader
+        .take(*head_left)
+        .read_line(&mut line)
+        .await
+        .map_err(|_| Response::text(400, "Malformed request"))?;
+    if !line.ends_with('\n') {
+        return Err(if n as u64 == *head_left {
+            Response::text(431, "Request head too large")
+        } else {
+            Response::text(400, "Truncated request")
+        });
+    }
+    *head_left -= n as u64;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+pub async fn write_response<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    response: &Response,
+) -> std::io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        reason(response.status),
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(&response.body).await?;
+    writer.flush().await
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        501 => "Not Implemented",
+        _ => "",
+    }
+}
+
+/// Refuse what a browser page could send (a non-loopback Host or Origin)
+/// and anything without the bearer token. Ok means: route it.
+pub fn admit(request: &Request, token: &str) -> Result<(), Response> {
+    let host_ok = request
+        .header("Host")
+        .is_some_and(|host| is_loopback(strip_port(host)));
+    let origin_ok = match request.header("Origin") {
+        None => true,
+        Some(origin) => url::Url::parse(origin)
+            .ok()
+            .and_then(|u| u.host_str().map(is_loopback))
+            .unwrap_or(false),
+    };
+    if !host_ok || !origin_ok {
+        return Err(Response::text(
+            403,
+            "Only local clients may use this server",
+        ));
+    }
+    let presented = request
+        .header("Authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim);
+    match presented {
+        Some(presented) if constant_time_eq(presented.as_bytes(), token.as_bytes()) => Ok(()),
+        _ => Err(Response::text(401, "Missing or wrong bearer token")
+            .header("WWW-Authenticate", "Bearer realm=\"beakr-desktop\"")),
+    }
+}
+
+fn strip_port(host: &str) -> &str {
+    match host.rsplit_once(':') {
+        // "[::1]:8765" / "localhost:8765"; a bare "::1" has no brackets.
+        Some((name, port)) if port.bytes().all(|b| b.is_ascii_digit()) && !name.ends_with(':') => {
+            name
+        }
+        _ => host,
+    }
+}
+
+fn is_loopback(host: &str) -> bool {
+    matches!(host, "127.0.0.1" | "localhost" | "[::1]" | "::1")
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn parse(raw: &str) -> Result<Request, Response> {
+        read_request(raw.as_bytes()).await
+    }
+
+    #[tokio::test]
+    async fn reads_a_post_with_its_body() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+        let raw = format!(
+            "POST /mcp HTTP/1.1\r\nHost: 127.0.0.1:8765\r\ncontent-length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        let request = parse(&raw).await.ok().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/mcp");
+        assert_eq!(
+            request.header("Content-Length"),
+            Some(body.len().to_string().as_str())
+        );
+        assert_eq!(request.body, body.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn refuses_chunked_oversized_and_truncated_requests() {
+        let status = |r: Result<Request, Response>| r.err().map(|e| e.status);
+        let chunked = "POST /mcp HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n";
+        assert_eq!(status(parse(chunked).await), Some(501));
+        let huge = format!(
+            "POST /mcp HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY + 1
+        );
+        assert_eq!(status(parse(&huge).await), Some(413));
+        let short = "POST /mcp HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}";
+        assert_eq!(status(parse(short).await), Some(400));
+        let long_header = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(20_000));
+        assert_eq!(status(parse(&long_header).await), Some(431));
+    }
+
+    #[tokio::test]
+    async fn admits_only_loopback_callers_with_the_token() {
+        let request = |headers: &str| {
+            let raw = format!("POST /mcp HTTP/1.1\r\n{headers}\r\n");
+            async move { parse(&raw).await.ok().unwrap() }
+        };
+        let status = |r: Result<(), Response>| r.err().map(|e| e.status);
+        let auth = "Authorization: Bearer s3cret\r\n";
+
+        let ok = request(&format!("Host: 127.0.0.1:8765\r\n{auth}")).await;
+        assert!(admit(&ok, "s3cret").is_ok());
+        let ok = request(&format!(
+            "Host: localhost\r\nOrigin: http://localhost:3000\r\n{auth}"
+        ));
+        assert!(admit(&ok.await, "s3cret").is_ok());
+        let ok = request(&format!("Host: [::1]:8765\r\n{auth}")).await;
+        assert!(admit(&ok, "s3cret").is_ok());
+
+        let rebound = request(&format!("Host: attacker.example:8765\r\n{auth}")).await;
+        assert_eq!(status(admit(&rebound, "s3cret")), Some(403));
+        let page = request(&format!(
+            "Host: 127.0.0.1\r\nOrigin: https://evil.example\r\n{auth}"
+        ));
+        assert_eq!(status(admit(&page.await, "s3cret")), Some(403));
+        let opaque = request(&format!("Host: 127.0.0.1\r\nOrigin: null\r\n{auth}")).await;
+        assert_eq!(status(admit(&opaque, "s3cret")), Some(403));
+
+        let wrong = request("Host: 127.0.0.1\r\nAuthorization: Bearer s3creT\r\n").await;
+        assert_eq!(status(admit(&wrong, "s3cret")), Some(401));
+        let missing = request("Host: 127.0.0.1\r\n").await;
+        assert_eq!(status(admit(&missing, "s3cret")), Some(401));
+    }
+}
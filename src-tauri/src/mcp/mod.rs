@@ -0,0 +1,285 @@
+//! Optional local MCP server: the engine's read-only tool surface (the file
+//! tools and the Benchling tools), offered to MCP clients on this machine —
+//! local agents, editors — over streamable HTTP on loopback.
+//!
+//! Calls go through `tools::dispatch_request` with the user's scoped
+//! folders, so a local client gets exactly the engine's guarded access: the
+//! same scope checks, the same deny-list. Off by default; enabling it mints
+//! a bearer token the client must present. No stdio transport: that would
+//! need the client to spawn this GUI app, which cannot work while it runs.
+//!
+//! Coding runs get their own endpoint ([`RunEndpoint`]): an ephemeral port
+//! and a token that live as long as the run, so the CLI can reach the same
+//! tools whether or not the user enabled the server.
+
+mod catalog;
+mod http;
+mod rpc;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use tauri::AppHandle;
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::config;
+use crate::secrets;
+use crate::state::AppState;
+
+/// Used until the user picks another port in Settings.
+pub const DEFAULT_PORT: u16 = 8765;
+/// The endpoint path clients are configured with.
+pub const ENDPOINT: &str = "/mcp";
+
+/// A client gets this long to send its request; tool calls themselves are
+/// not timed out here.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A fresh bearer token: 128 random bits, hex.
+pub fn new_token() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// The running listener, if any. Held in `AppState` so Settings can restart
+/// it on a port or token change.
+#[derive(Default)]
+pub struct McpServer {
+    listening: std::sync::Mutex<Option<Listening>>,
+}
+
+struct Listening {
+    addr: SocketAddr,
+    accept_task: tokio::task::JoinHandle<()>,
+}
+
+impl McpServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn address(&self) -> Option<SocketAddr> {
+        self.lock().as_ref().map(|l| l.addr)
+    }
+
+    /// (Re)bind on `127.0.0.1:port` and serve with `token`. Any previous
+    /// listener is stopped first, so a restart on the same port works.
+    pub async fn start(
+        &self,
+        state: AppState,
+        port: u16,
+        token: String,
+    ) -> Result<SocketAddr, String> {
+        self.stop().await;
+        let (listener, addr) = bind(port).await?;
+        let access = Access {
+            token,
+            folders: None,
+            caller: "local client".into(),
+        };
+        let accept_task = spawn_accept(listener, state, access);
+        *self.lock() = Some(Listening { addr, accept_task });
+        log::info!("MCP server listening on http://{addr}{ENDPOINT}");
+        Ok(addr)
+    }
+
+    /// Stop accepting and wait for the listener to close. Calls in flight
+    /// finish on their own connections.
+    pub async fn stop(&self) {
+        let Some(old) = self.lock().take() else {
+            return;
+        };
+        old.accept_task.abort();
+        let _ = old.accept_task.await;
+        log::info!("MCP server on {} stopped", old.addr);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Listening>> {
+        self.listening.lock().expect("mcp server lock poisoned")
+    }
+}
+
+/// Start, restart or stop the server to match the saved settings, minting
+/// the token on first use. The token is kept in secret storage, never in
+/// `settings.json`.
+pub async fn apply_settings(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    let settings = config::load_settings(app);
+    if settings.mcp_server != Some(true) {
+        state.mcp.stop().await;
+        return Ok(());
+    }
+    let token = match secrets::get(app, secrets::MCP_TOKEN)? {
+        Some(token) if !token.is_empty() => token,
+        _ => {
+            let token = new_token();
+            secrets::set(app, secrets::MCP_TOKEN, &token)?;
+            token
+        }
+    };
+    let port = settings.mcp_port.unwrap_or(DEFAULT_PORT);
+    state
+        .mcp
+        .start(state.clone(), port, token)
+        .await
+        .map(|_| ())
+}
+
+/// A coding run's own endpoint: an ephemeral loopback port and a token
+/// valid for that run only, serving the folders the run was started with.
+/// Dropping it stops the listener.
+pub struct RunEndpoint {
+    addr: SocketAddr,
+    token: String,
+    accept_task: tokio::task::JoinHandle<()>,
+}
+
+impl RunEndpoint {
+    pub async fn start(
+        state: AppState,
+        request_id: &str,
+        scoped_folders: Vec<String>,
+    ) -> Result<Self, String> {
+        let (listener, addr) = bind(0).await?;
+        let token = new_token();
+        let access = Access {
+            token: token.clone(),
+            folders: Some(scoped_folders),
+            caller: format!("coding run {request_id}"),
+        };
+        let accept_task = spawn_accept(listener, state, access);
+        Ok(Self {
+            addr,
+            token,
+            accept_task,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}{ENDPOINT}", self.addr)
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl Drop for RunEndpoint {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+/// What a listener's clients may do: the token they present and the folders
+/// their calls are scoped to.
+struct Access {
+    token: String,
+    /// None = the user's scoped folders as they are at each call.
+    folders: Option<Vec<String>>,
+    /// Who the calls are logged as.
+    caller: String,
+}
+
+async fn bind(port: u16) -> Result<(TcpListener, SocketAddr), String> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("port_unavailable: 127.0.0.1:{port}: {e}"))?;
+    let addr = listener
+        .local_addr()
+        .map_err(|e| format!("port_unavailable: {e}"))?;
+    Ok((listener, addr))
+}
+
+fn spawn_accept(
+    listener: TcpListener,
+    state: AppState,
+    access: Access,
+) -> tokio::task::JoinHandle<()> {
+    let access = Arc::new(access);
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((conn, _)) => {
+                    let state = state.clone();
+                    let access = access.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = serve(conn, &state, &access).await {
+                            log::debug!("MCP connection ended: {e}");
+                        }
+                    });
+                }
+                Err(e) => {
+                    // EMFILE and friends: back off instead of spinning.
+                    log::warn!("MCP server accept failed: {e}");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+        }
+    })
+}
+
+/// One request, one response, close.
+async fn serve(mut conn: TcpStream, state: &AppState, access: &Access) -> std::io::Result<()> {
+    let response = match tokio::time::timeout(READ_TIMEOUT, http::read_request(&mut conn)).await {
+        Err(_) => return Ok(()),
+        Ok(Err(response)) => response,
+        Ok(Ok(request)) => route(&request, state, access).await,
+    };
+    http::write_response(&mut conn, &response).await
+}
+
+async fn route(request: &http::Request, state: &AppState, access: &Access) -> http::Response {
+    if request.path != ENDPOINT {
+        return http::Response::text(404, "Not found; the MCP endpoint is /mcp");
+    }
+    if let Err(refused) = http::admit(request, &access.token) {
+        log::warn!(
+            "MCP server refused a {} request: {}",
+            request.method,
+            refused.status
+        );
+        return refused;
+    }
+    // No server-initiated stream (GET) and no sessions to end (DELETE).
+    if request.method != "POST" {
+        return http::Response::empty(405).header("Allow", "POST");
+    }
+    let scoped_folders = match &access.folders {
+        Some(folders) => folders.clone(),
+        None => state.scoped_folders.read().await.clone(),
+    };
+    let host = DesktopTools {
+        state,
+        scoped_folders,
+        caller: &access.caller,
+    };
+    match rpc::handle(&request.body, &host).await {
+        Some(answer) => http::Response::json(&answer),
+        None => http::Response::empty(202),
+    }
+}
+
+/// Runs catalog tools against the live app, inside the caller's folders.
+struct DesktopTools<'a> {
+    state: &'a AppState,
+    scoped_folders: Vec<String>,
+    caller: &'a str,
+}
+
+impl rpc::ToolHost for DesktopTools<'_> {
+    async fn call(&self, tool: &str, arguments: Value) -> Result<Value, String> {
+        log::info!("MCP tool call from {}: {tool}", self.caller);
+        let started = Instant::now();
+        let result =
+            crate::tools::dispatch_request(tool, arguments, &self.scoped_folders, self.state)
+                .await;
+        self.state
+            .metrics
+            .record_call(tool, None, &result, false, started.elapsed());
+        result.map(|(data, _bytes)| data)
+    }
+}
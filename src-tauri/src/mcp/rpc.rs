@@ -0,0 +1,270 @@
+//! JSON-RPC 2.0 framing and the MCP methods the server answers:
+//! `initialize`, `ping`, `tools/list` and `tools/call`.
+//!
+//! Tool execution is behind [`ToolHost`] so this layer is testable without an
+//! `AppState`; the server's host calls `tools::dispatch_request`, which is
+//! where the scoped-folder and deny-list checks live.
+
+use serde_json::{json, Value};
+
+use super::catalog;
+
+/// Newest first; `initialize` echoes the client's version when it is one of
+/// these and offers the newest otherwise.
+pub const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+const INSTRUCTIONS: &str = "Read-only access to the folders the user shared with Beakr Desktop \
+and to their connected Benchling account. Paths outside the shared folders, and files on the \
+deny-list, are refused.";
+
+pub trait ToolHost {
+    /// Run one catalog tool. `Err` is a tool failure, reported to the client
+    /// as an `isError` result rather than a protocol error.
+    async fn call(&self, tool: &str, arguments: Value) -> Result<Value, String>;
+}
+
+/// Answer one HTTP body: a message or a batch. None when nothing needs an
+/// answer (notifications and client responses only).
+pub async fn handle(body: &[u8], host: &impl ToolHost) -> Option<Value> {
+    let message: Value = match serde_json::from_slice(body) {
+        Ok(message) => message,
+        Err(e) => {
+            return Some(error(
+                Value::Null,
+                PARSE_ERROR,
+                &format!("Parse error: {e}"),
+            ))
+        }
+    };
+    match message {
+        Value::Array(batch) if batch.is_empty() => {
+            Some(error(Value::Null, INVALID_REQUEST, "Empty batch"))
+        }
+        Value::Array(batch) => {
+            let mut answers = Vec::new();
+            for message in batch {
+                answers.extend(handle_message(message, host).await);
+            }
+            (!answers.is_empty()).then_some(Value::Array(answers))
+        }
+        message => handle_message(message, host).await,
+    }
+}
+
+async fn handle_message(message: Value, host: &impl ToolHost) -> Option<Value> {
+    if message.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return Some(error(
+            Value::Null,
+            INVALID_REQUEST,
+            "Not a JSON-RPC 2.0 message",
+        ));
+    }
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        // A response to a server request; this server never sends any.
+        return if message.get("result").is_some() || message.get("error").is_some() {
+            None
+        } else {
+            Some(error(Value::Null, INVALID_REQUEST, "Missing method"))
+        };
+    };
+    // Notifications (`notifications/initialized`, `.../cancelled`) need no
+    // answer and change nothing here.
+    let id = message.get("id")?.clone();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    match call_method(method, params, host).await {
+        Ok(result) => Some(json!({ "jsonrpc": "2.0", "id": id, "result": result })),
+        Err((code, message)) => Some(error(id, code, &message)),
+    }
+}
+
+async fn call_method(
+    method: &str,
+    params: Value,
+    host: &impl ToolHost,
+) -> Result<Value, (i64, String)> {
+    match method {
+        "initialize" => {
+            let requested = params.get("protocolVersion").and_then(Value::as_str);
+            let version = requested
+                .filter(|v| PROTOCOL_VERSIONS.contains(v))
+                .unwrap_or(PROTOCOL_VERSIONS[0]);
+            Ok(json!({
+                "protocolVersion": version,
+                "capabilities": { "tools": { "listChanged": false } },
+                "serverInfo": { "name": "beakr-desktop", "version": env!("CARGO_PKG_VERSION") },
+                "instructions": INSTRUCTIONS,
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "tools/list" => {
+            let tools: Vec<Value> = catalog::TOOLS.iter().map(catalog::Tool::describe).collect();
+            Ok(json!({ "tools": tools }))
+        }
+        "tools/call" => {
+            let name = params
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or((INVALID_PARAMS, "tools/call needs a tool name".to_string()))?;
+            let tool = catalog::find(name)
+                .ok_or_else(|| (INVALID_PARAMS, format!("Unknown tool: {name}")))?;
+            let arguments = match params.get("arguments") {
+                None | Some(Value::Null) => json!({}),
+                Some(args @ Value::Object(_)) => args.clone(),
+                Some(_) => return Err((INVALID_PARAMS, "arguments must be an object".to_string())),
+            };
+            Ok(match host.call(tool.name, arguments).await {
+                Ok(data) => tool_result(&data, false),
+                Err(e) => tool_result(&Value::String(e), true),
+            })
+        }
+        other => Err((METHOD_NOT_FOUND, format!("Method not found: {other}"))),
+    }
+}
+
+/// A `tools/call` result: the data as JSON text for every client, and as
+/// `structuredContent` for those that read it (objects only, per the spec).
+fn tool_result(data: &Value, is_error: bool) -> Value {
+    let text = match data {
+        Value::String(s) => s.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    };
+    let mut result = json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    });
+    if data.is_object() {
+        result["structuredContent"] = data.clone();
+    }
+    result
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records calls and answers `read_file` with a fixed object; anything
+    /// else fails the way an out-of-scope path does.
+    #[derive(Default)]
+    struct FakeHost {
+        calls: Mutex<Vec<(String, Value)>>,
+    }
+
+    impl ToolHost for FakeHost {
+        async fn call(&self, tool: &str, arguments: Value) -> Result<Value, String> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((tool.to_string(), arguments));
+            match tool {
+                "read_file" => Ok(json!({ "content": "hello" })),
+                _ => Err("Access denied: path is outside the shared folders".to_string()),
+            }
+        }
+    }
+
+    async fn rpc(host: &FakeHost, message: Value) -> Option<Value> {
+        handle(message.to_string().as_bytes(), host).await
+    }
+
+    #[tokio::test]
+    async fn initialize_negotiates_the_protocol_version() {
+        let host = FakeHost::default();
+        let init = |version: &str| {
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize",
+                    "params": { "protocolVersion": version, "capabilities": {} } })
+        };
+        let known = rpc(&host, init("2025-03-26")).await.unwrap();
+        assert_eq!(known["result"]["protocolVersion"], "2025-03-26");
+        assert!(known["result"]["capabilities"]["tools"].is_object());
+        let future = rpc(&host, init("2099-01-01")).await.unwrap();
+        assert_eq!(future["result"]["protocolVersion"], PROTOCOL_VERSIONS[0]);
+    }
+
+    #[tokio::test]
+    async fn tools_list_offers_the_read_only_surface_only() {
+        let answer = rpc(
+            &FakeHost::default(),
+            json!({ "jsonrpc": "2.0", "id": "a", "method": "tools/list" }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(answer["id"], "a");
+        let tools = answer["result"]["tools"].as_array().unwrap();
+        let names: Vec<&str> = tools.iter().map(|t| t["name"].as_str().unwrap()).collect();
+        assert!(names.contains(&"read_file") && names.contains(&"benchling_search"));
+        for hidden in ["reveal_file", "open_terminal", "run_coding_agent"] {
+            assert!(!names.contains(&hidden), "{hidden} must not be offered");
+        }
+        let read = tools.iter().find(|t| t["name"] == "read_file").unwrap();
+        assert_eq!(read["inputSchema"]["required"], json!(["path"]));
+    }
+
+    #[tokio::test]
+    async fn tools_call_reports_results_and_tool_failures() {
+        let host = FakeHost::default();
+        let call = |name: &str| {
+            json!({ "jsonrpc": "2.0", "id": 7, "method": "tools/call",
+                    "params": { "name": name, "arguments": { "path": "/etc/passwd" } } })
+        };
+        let ok = rpc(&host, call("read_file")).await.unwrap();
+        assert_eq!(ok["result"]["isError"], false);
+        assert_eq!(ok["result"]["structuredContent"]["content"], "hello");
+
+        let denied = rpc(&host, call("list_files")).await.unwrap();
+        assert_eq!(denied["result"]["isError"], true);
+        let text = denied["result"]["content"][0]["text"].as_str().unwrap();
+        assert!(text.starts_with("Access denied"), "{text}");
+
+        let hidden = rpc(&host, call("open_terminal")).await.unwrap();
+        assert_eq!(hidden["error"]["code"], INVALID_PARAMS);
+
+        let calls = host.calls.lock().unwrap();
+        let tools: Vec<&str> = calls.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(
+            tools,
+            ["read_file", "list_files"],
+            "unknown tools never reach the host"
+        );
+        assert_eq!(calls[0].1["path"], "/etc/passwd");
+    }
+
+    #[tokio::test]
+    async fn notifications_get_no_answer_and_bad_messages_get_errors() {
+        let host = FakeHost::default();
+        let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert_eq!(rpc(&host, initialized.clone()).await, None);
+        assert_eq!(rpc(&host, json!([initialized])).await, None);
+
+        let unknown = rpc(
+            &host,
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "resources/list" }),
+        );
+        assert_eq!(unknown.await.unwrap()["error"]["code"], METHOD_NOT_FOUND);
+        let garbage = handle(b"{not json", &host).await.unwrap();
+        assert_eq!(garbage["error"]["code"], PARSE_ERROR);
+        let old = rpc(&host, json!({ "id": 3, "method": "ping" }))
+            .await
+            .unwrap();
+        assert_eq!(old["error"]["code"], INVALID_REQUEST);
+
+        let batch = json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "ping" },
+            { "jsonrpc": "2.0", "method": "notifications/initialized" },
+        ]);
+        let answers = rpc(&host, batch).await.unwrap();
+        assert_eq!(
+            answers,
+            json!([{ "jsonrpc": "2.0", "id": 1, "result": {} }])
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! In-process metrics: how long tools take on users' machines, how they
+//! fail, how often the engine connection drops.
+//!
+//! Tool calls are counted and timed per tool, outcome and error prefix (the
+//! `code:` errors carry, e.g. `bad_params`), from both the engine
+//! (`ws::client`) and local MCP clients (`mcp`). Reconnects are counted per
+//! pairing, and `FileIndex` keeps a [`Histogram`] of its refreshes. Nothing
+//! leaves the process except through [`snapshot`], for the `get_metrics`
+//! command, and [`pairing_snapshot`], for a pairing's engine when it
+//! accepted the `metrics` feature and the user turned sharing on: that one
+//! holds the pairing's own calls and reconnects, never another pairing's or
+//! an MCP client's. Everything is cumulative since launch.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::state::AppState;
+
+/// Upper bounds of the histogram buckets, in milliseconds. Spans a cached
+/// name search (a few ms) to a Benchling call on a slow network.
+pub const BUCKETS_MS: [u64; 12] = [1, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// A latency distribution: counts per bucket of [`BUCKETS_MS`], plus one
+/// for anything slower.
+#[derive(Default)]
+pub struct Histogram {
+    buckets: [AtomicU64; BUCKETS_MS.len() + 1],
+    count: AtomicU64,
+    sum_ms: AtomicU64,
+    max_ms: AtomicU64,
+}
+
+impl Histogram {
+    pub fn observe(&self, elapsed: Duration) {
+        let ms = elapsed.as_millis() as u64;
+        let bucket = BUCKETS_MS
+            .iter()
+            .position(|&le| ms <= le)
+            .unwrap_or(BUCKETS_MS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_ms.fetch_add(ms, Ordering::Relaxed);
+        self.max_ms.fetch_max(ms, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let buckets: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .collect();
+        let count = self.count.load(Ordering::Relaxed);
+        HistogramSnapshot {
+            count,
+            sum_ms: self.sum_ms.load(Ordering::Relaxed),
+            max_ms: self.max_ms.load(Ordering::Relaxed),
+            p50_ms: quantile(&buckets, count, 0.5),
+            p95_ms: quantile(&buckets, count, 0.95),
+            buckets,
+        }
+    }
+}
+
+/// The upper bound of the bucket holding quantile `q`; None when empty or
+/// past the last bound.
+fn quantile(buckets: &[u64], count: u64, q: f64) -> Option<u64> {
+    if count == 0 {
+        return None;
+    }
+    let rank = ((count as f64) * q).ceil().max(1.0) as u64;
+    let mut seen = 0;
+    for (i, n) in buckets.iter().enumerate() {
+        seen += n;
+        if seen >= rank {
+            return BUCKETS_MS.get(i).copied();
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistogramSnapshot {
+    pub count: u64,
+    pub sum_ms: u64,
+    pub max_ms: u64,
+    /// Bucket bounds, so estimates: the true value is at most this.
+    pub p50_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    /// Counts per bucket of [`BUCKETS_MS`], then the overflow bucket.
+    pub buckets: Vec<u64>,
+}
+
+/// What a tool call's metrics are filed under.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct CallKey {
+    tool: String,
+    outcome: &'static str,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolSnapshot {
+    pub tool: String,
+    /// `ok`, `error` or `cancelled`.
+    pub outcome: &'static str,
+    /// The error's `code:` prefix, `other` when it has none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration: HistogramSnapshot,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+    pub since: DateTime<Utc>,
+    pub tools: Vec<ToolSnapshot>,
+    /// Reconnects per pairing id.
+    pub reconnects: BTreeMap<String, u64>,
+    pub index_refresh: HistogramSnapshot,
+}
+
+pub struct Metrics {
+    since: DateTime<Utc>,
+    calls: Mutex<BTreeMap<CallKey, Histogram>>,
+    /// The engine's calls again, per pairing id: what its heartbeat shares.
+    pairing_calls: Mutex<BTreeMap<String, BTreeMap<CallKey, Histogram>>>,
+    reconnects: Mutex<BTreeMap<String, u64>>,
+    /// Whether heartbeats may carry a snapshot (`Settings::share_metrics`).
+    share: AtomicBool,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            since: Utc::now(),
+            calls: Mutex::new(BTreeMap::new()),
+            pairing_calls: Mutex::new(BTreeMap::new()),
+            reconnects: Mutex::new(BTreeMap::new()),
+            share: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Metrics {
+    /// File a finished tool call, under `pairing` too when that pairing's
+    /// engine made it. `cancelled` wins over the error it produced.
+    pub fn record_call<T>(
+        &self,
+        tool: &str,
+        pairing: Option<&str>,
+        result: &Result<T, String>,
+        cancelled: bool,
+        elapsed: Duration,
+    ) {
+        let (outcome, error) = match result {
+            _ if cancelled => ("cancelled", None),
+            Ok(_) => ("ok", None),
+            Err(e) => ("error", Some(error_prefix(e).to_string())),
+        };
+        let key = CallKey {
+            tool: tool.to_string(),
+            outcome,
+            error,
+        };
+        if let Some(pairing) = pairing {
+            self.pairing_calls
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .entry(pairing.to_string())
+                .or_default()
+                .entry(key.clone())
+                .or_default()
+                .observe(elapsed);
+        }
+        self.calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key)
+            .or_default()
+            .observe(elapsed);
+    }
+
+    pub fn record_reconnect(&self, pairing: &str) {
+        *self
+            .reconnects
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(pairing.to_string())
+            .or_default() += 1;
+    }
+
+    pub fn sharing(&self) -> bool {
+        self.share.load(Ordering::Relaxed)
+    }
+
+    pub fn set_sharing(&self, share: bool) {
+        self.share.store(share, Ordering::Relaxed);
+    }
+}
+
+/// An error's `code:` prefix (lowercase, digits and underscores), or
+/// `other`. Only the prefix is kept: the rest names paths and records.
+fn error_prefix(error: &str) -> &str {
+    match error.split_once(':') {
+        Some((code, _))
+            if !code.is_empty()
+                && code.len() <= 40
+                && code
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') =>
+        {
+            code
+        }
+        _ => "other",
+    }
+}
+
+/// Everything recorded since launch.
+pub fn snapshot(state: &AppState) -> Snapshot {
+    let metrics = &state.metrics;
+    Snapshot {
+        since: metrics.since,
+        tools: tools(&metrics.calls.lock().unwrap_or_else(|e| e.into_inner())),
+        reconnects: metrics
+            .reconnects
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone(),
+        index_refresh: state.file_index.refresh_time.snapshot(),
+    }
+}
+
+/// What `pairing`'s heartbeat may carry: the calls its engine made and its
+/// own reconnects.
+pub fn pairing_snapshot(state: &AppState, pairing: &str) -> Snapshot {
+    let metrics = &state.metrics;
+    let tools = metrics
+        .pairing_calls
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(pairing)
+        .map(tools)
+        .unwrap_or_default();
+    let reconnects = metrics
+        .reconnects
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(pairing)
+        .map(|&n| BTreeMap::from([(pairing.to_string(), n)]))
+        .unwrap_or_default();
+    Snapshot {
+        since: metrics.since,
+        tools,
+        reconnects,
+        index_refresh: state.file_index.refresh_time.snapshot(),
+    }
+}
+
+fn tools(calls: &BTreeMap<CallKey, Histogram>) -> Vec<ToolSnapshot> {
+    calls
+        .iter()
+        .map(|(key, histogram)| ToolSnapshot {
+            tool: key.tool.clone(),
+            outcome: key.outcome,
+            error: key.error.clone(),
+            duration: histogram.snapshot(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histograms_bucket_and_estimate_quantiles() {
+        let histogram = Histogram::default();
+        for ms in [3, 4, 8, 40, 40, 90, 200, 200, 700, 20_000] {
+            histogram.observe(Duration::from_millis(ms));
+        }
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 10);
+        assert_eq!(snapshot.sum_ms, 21_285);
+        assert_eq!(snapshot.max_ms, 20_000);
+        assert_eq!(snapshot.buckets, [0, 2, 1, 0, 2, 1, 2, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(snapshot.p50_ms, Some(50));
+        // The 95th percentile is the overflow bucket: no bound to give.
+        assert_eq!(snapshot.p95_ms, None);
+        assert_eq!(Histogram::default().snapshot().p50_ms, None);
+    }
+
+    #[test]
+    fn calls_are_filed_by_tool_outcome_and_error_code() {
+        let metrics = Metrics::default();
+        let ms = Duration::from_millis;
+        metrics.record_call::<()>("read_file", None, &Ok(()), false, ms(3));
+        metrics.record_call::<()>("read_file", None, &Ok(()), false, ms(7));
+        metrics.record_call::<()>(
+            "read_file",
+            None,
+            &Err("not_found: /Users/ana/x".into()),
+            false,
+            ms(1),
+        );
+        metrics.record_call::<()>(
+            "read_file",
+            None,
+            &Err("Access denied — sensitive file".into()),
+            false,
+            ms(1),
+        );
+        metrics.record_call::<()>("run_coding_agent", None, &Err("x".into()), true, ms(9));
+
+        let calls = metrics.calls.lock().unwrap();
+        let filed: Vec<_> = calls
+            .iter()
+            .map(|(k, h)| {
+                (
+                    k.tool.as_str(),
+                    k.outcome,
+                    k.error.as_deref(),
+                    h.snapshot().count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            filed,
+            [
+                ("read_file", "error", Some("not_found"), 1),
+                ("read_file", "error", Some("other"), 1),
+                ("read_file", "ok", None, 2),
+                ("run_coding_agent", "cancelled", None, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_pairing_snapshot_holds_only_that_pairings_counters() {
+        let state = AppState::new();
+        assert!(!state.metrics.sharing(), "sharing is opt-in");
+        let ms = Duration::from_millis;
+        let metrics = &state.metrics;
+        metrics.record_call::<()>("read_file", Some("lab-a"), &Ok(()), false, ms(3));
+        metrics.record_call::<()>("list_files", Some("lab-b"), &Ok(()), false, ms(3));
+        metrics.record_call::<()>("search_files", None, &Ok(()), false, ms(3));
+        metrics.record_reconnect("lab-a");
+        metrics.record_reconnect("lab-b");
+
+        let shared = pairing_snapshot(&state, "lab-a");
+        let tools: Vec<&str> = shared.tools.iter().map(|t| t.tool.as_str()).collect();
+        assert_eq!(tools, ["read_file"]);
+        assert_eq!(
+            shared.reconnects,
+            BTreeMap::from([("lab-a".to_string(), 1)])
+        );
+
+        // The Diagnostics view still sees everything.
+        let all = snapshot(&state);
+        assert_eq!(all.tools.len(), 3);
+        assert_eq!(all.reconnects.len(), 2);
+    }
+}
@@ -0,0 +1,164 @@
+//! Pairings: the Beakr accounts this device is linked to, any number at once.
+//!
+//! A pairing names the server environment it was claimed from, keeps its own
+//! device token (in `secrets`) and can narrow the scoped folders that account
+//! reaches — a lab workspace and a personal one side by side, each seeing
+//! only its own projects. At runtime every pairing gets a `ws::link::Link`
+//! and its own `WsClient`.
+//!
+//! Before there could be several, a device had at most one pairing per
+//! environment, its token stored under the environment's id. `migrate` turns
+//! each of those into a pairing with that same id, so the tokens stay put.
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::config;
+use crate::environment;
+use crate::secrets;
+
+/// One account this device is paired with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pairing {
+    /// Stable key for settings and the token store.
+    pub id: String,
+    /// What the tray and Settings call it.
+    pub name: String,
+    /// Id of the server environment it was claimed from.
+    pub environment: String,
+    /// The scoped folders this account may reach; None for all of them.
+    #[serde(default)]
+    pub folders: Option<Vec<String>>,
+}
+
+impl Pairing {
+    /// What this account may reach of `scoped`: all of it, or the part the
+    /// pairing names. A folder removed from `scoped` drops out here too.
+    pub fn scope(&self, scoped: &[String]) -> Vec<String> {
+        match &self.folders {
+            None => scoped.to_vec(),
+            Some(subset) => scoped
+                .iter()
+                .filter(|f| subset.contains(f))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// The saved pairings, in the order they were made.
+pub fn all(settings: &config::Settings) -> Vec<Pairing> {
+    settings.pairings.clone().unwrap_or_default()
+}
+
+/// A fresh id for a pairing called `name`: its slug, numbered when taken.
+pub fn new_id(name: &str, existing: &[Pairing]) -> String {
+    let mut base = String::new();
+    for c in name.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            base.push(c);
+        } else if !base.is_empty() && !base.ends_with('-') {
+            base.push('-');
+        }
+    }
+    let base = match base.trim_end_matches('-') {
+        "" => "account".to_string(),
+        slug => slug.to_string(),
+    };
+    let taken = |id: &str| existing.iter().any(|p| p.id == id);
+    if !taken(&base) {
+        return base;
+    }
+    (2..)
+        .map(|n| format!("{base}-{n}"))
+        .find(|id| !taken(id))
+        .expect("some suffix is free")
+}
+
+/// Check a pairing's name before saving. Errors are `pairing_invalid:`.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("pairing_invalid: name is empty".into());
+    }
+    Ok(())
+}
+
+/// Turn the per-environment tokens of older versions into pairings, once.
+/// Runs after `secrets::migrate_plaintext`, before anything connects.
+pub fn migrate(app: &AppHandle) {
+    let mut settings = config::load_settings(app);
+    if settings.pairings.is_some() {
+        return;
+    }
+    let mut pairings = Vec::new();
+    for profile in environment::all(&settings) {
+        match load_token(app, &profile.id) {
+            Ok(Some(_)) => pairings.push(Pairing {
+                id: profile.id.clone(),
+                name: profile.name.clone(),
+                environment: profile.id,
+                folders: None,
+            }),
+            Ok(None) => {}
+            Err(e) => {
+                // Try again next launch rather than lose the pairing.
+                log::error!("Could not read the {} device token: {e}", profile.id);
+                return;
+            }
+        }
+    }
+    log::info!(
+        "Migrated {} pairing(s) from per-environment tokens",
+        pairings.len()
+    );
+    settings.pairings = Some(pairings);
+    config::save_settings(app, &settings);
+}
+
+/// The device token of pairing `id`, if any (see `secrets`).
+pub fn load_token(app: &AppHandle, id: &str) -> Result<Option<String>, String> {
+    Ok(secrets::get(app, &secrets::device_token(id))?.filter(|t| !t.is_empty()))
+}
+
+pub fn store_token(app: &AppHandle, id: &str, token: &str) -> Result<(), String> {
+    secrets::set(app, &secrets::device_token(id), token)
+}
+
+pub fn delete_token(app: &AppHandle, id: &str) -> Result<(), String> {
+    secrets::delete(app, &secrets::device_token(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairing(id: &str, folders: Option<&[&str]>) -> Pairing {
+        Pairing {
+            id: id.into(),
+            name: id.into(),
+            environment: environment::PRODUCTION.into(),
+            folders: folders.map(|f| f.iter().map(|s| s.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn ids_are_slugs_numbered_when_taken() {
+        assert_eq!(new_id("Acme Lab", &[]), "acme-lab");
+        assert_eq!(new_id("  Ünïcode!! ", &[]), "n-code");
+        assert_eq!(new_id("???", &[]), "account");
+
+        let existing = [pairing("acme-lab", None), pairing("acme-lab-2", None)];
+        assert_eq!(new_id("Acme Lab", &existing), "acme-lab-3");
+    }
+
+    #[test]
+    fn scope_narrows_to_the_subset_of_what_is_still_scoped() {
+        let scoped = vec!["/data/a".to_string(), "/data/b".to_string()];
+        assert_eq!(pairing("all", None).scope(&scoped), scoped);
+        assert_eq!(
+            pairing("some", Some(&["/data/b", "/data/gone"])).scope(&scoped),
+            vec!["/data/b".to_string()]
+        );
+        assert!(pairing("none", Some(&[])).scope(&scoped).is_empty());
+    }
+}
@@ -93,7 +93,6 @@ impl GroupChild {
         self.child.stderr.take()
     }
 
-    #[allow(dead_code)] // Codex stdin-prompt path, ENG-1529
     pub fn stdin_take(&mut self) -> Option<tokio::process::ChildStdin> {
         self.child.stdin.take()
     }
@@ -0,0 +1,346 @@
+//! Landlock + seccomp plumbing for [`super::confine`]. Raw syscalls through
+//! `libc` — the structs and flags below mirror `<linux/landlock.h>` and
+//! `<linux/filter.h>`.
+//!
+//! Everything that allocates or can fail in interesting ways (opening the
+//! allowed paths, building the ruleset, assembling the BPF program) happens
+//! in the parent. The `pre_exec` hook, which runs between fork and exec and
+//! must stay async-signal-safe, only makes three syscalls.
+
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use tokio::process::Command;
+
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+const LANDLOCK_RULE_NET_PORT: u32 = 2;
+
+const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+const ACCESS_FS_MAKE_CHAR: u64 = 1 << 6;
+const ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+const ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+const ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+const ACCESS_FS_MAKE_FIFO: u64 = 1 << 10;
+const ACCESS_FS_MAKE_BLOCK: u64 = 1 << 11;
+const ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+/// ABI 2: cross-directory rename/link.
+const ACCESS_FS_REFER: u64 = 1 << 13;
+/// ABI 3: truncate(2) and O_TRUNC.
+const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+
+/// ABI 4: TCP bind/connect by port.
+const ACCESS_NET_BIND_TCP: u64 = 1 << 0;
+const ACCESS_NET_CONNECT_TCP: u64 = 1 << 1;
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+    handled_access_net: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+#[repr(C)]
+struct NetPortAttr {
+    allowed_access: u64,
+    port: u64,
+}
+
+/// The kernel's landlock ABI version; 0 when landlock is unavailable
+/// (kernel too old, or not in the LSM list).
+pub fn landlock_abi() -> i32 {
+    // SAFETY: a version query takes no attribute pointer.
+    let abi = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<RulesetAttr>(),
+            0usize,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        )
+    };
+    abi.max(0) as i32
+}
+
+/// Every write-ish right this ABI can restrict. Reads and execs are left
+/// unhandled, i.e. unrestricted.
+fn write_access(abi: i32) -> u64 {
+    let mut access = ACCESS_FS_WRITE_FILE
+        | ACCESS_FS_REMOVE_DIR
+        | ACCESS_FS_REMOVE_FILE
+        | ACCESS_FS_MAKE_CHAR
+        | ACCESS_FS_MAKE_DIR
+        | ACCESS_FS_MAKE_REG
+        | ACCESS_FS_MAKE_SOCK
+        | ACCESS_FS_MAKE_FIFO
+        | ACCESS_FS_MAKE_BLOCK
+        | ACCESS_FS_MAKE_SYM;
+    if abi >= 2 {
+        access |= ACCESS_FS_REFER;
+    }
+    if abi >= 3 {
+        access |= ACCESS_FS_TRUNCATE;
+    }
+    access
+}
+
+/// A landlock ruleset built in the parent, ready to be enforced on the
+/// child with `landlock_restrict_self`.
+pub struct Ruleset {
+    fd: OwnedFd,
+    /// Whether TCP is restricted too (ABI >= 4).
+    pub restricts_net: bool,
+}
+
+impl Ruleset {
+    /// Writes allowed only beneath `writable` (paths that don't exist are
+    /// skipped); when the ABI allows, TCP connect only to `connect_ports`
+    /// (on any address: landlock has no notion of hosts) and no TCP bind at
+    /// all.
+    pub fn build(abi: i32, writable: &[PathBuf], connect_ports: &[u16]) -> io::Result<Self> {
+        let restricts_net = abi >= 4;
+        let handled_fs = write_access(abi);
+        let attr = RulesetAttr {
+            handled_access_fs: handled_fs,
+            handled_access_net: ACCESS_NET_BIND_TCP | ACCESS_NET_CONNECT_TCP,
+        };
+        // Pre-ABI-4 kernels reject the net field; pass only what they know.
+        let attr_size = if restricts_net {
+            std::mem::size_of::<RulesetAttr>()
+        } else {
+            std::mem::size_of::<u64>()
+        };
+        // SAFETY: attr outlives the call; size matches what we pass.
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr as *const RulesetAttr,
+                attr_size,
+                0u32,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: a fresh fd the kernel just handed us.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd as RawFd) };
+
+        for path in writable {
+            let Some(dir) = open_path(path) else { continue };
+            let is_dir = path.is_dir();
+            // A rule on a regular file may only carry file rights.
+            let allowed = if is_dir {
+                handled_fs
+            } else {
+                handled_fs & (ACCESS_FS_WRITE_FILE | ACCESS_FS_TRUNCATE)
+            };
+            let rule = PathBeneathAttr {
+                allowed_access: allowed,
+                parent_fd: dir.as_raw_fd(),
+            };
+            add_rule(&fd, LANDLOCK_RULE_PATH_BENEATH, &rule as *const _ as *const _)?;
+        }
+
+        if restricts_net {
+            for &port in connect_ports {
+                let rule = NetPortAttr {
+                    allowed_access: ACCESS_NET_CONNECT_TCP,
+                    port: u64::from(port),
+                };
+                add_rule(&fd, LANDLOCK_RULE_NET_PORT, &rule as *const _ as *const _)?;
+            }
+        }
+        Ok(Self { fd, restricts_net })
+    }
+}
+
+fn add_rule(ruleset: &OwnedFd, rule_type: u32, attr: *const libc::c_void) -> io::Result<()> {
+    // SAFETY: attr points at the #[repr(C)] struct matching rule_type.
+    let rc = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_add_rule,
+            ruleset.as_raw_fd(),
+            rule_type,
+            attr,
+            0u32,
+        )
+    };
+    if rc < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// O_PATH handle for a landlock rule; None if the path doesn't exist.
+fn open_path(path: &Path) -> Option<OwnedFd> {
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: c_path is a valid NUL-terminated string.
+    let fd = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+    if fd < 0 {
+        return None;
+    }
+    // SAFETY: a fresh fd we own.
+    Some(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xC000_003E;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xC000_00B7;
+/// x32 ABI syscalls share the x86_64 audit arch; deny the whole range.
+#[cfg(target_arch = "x86_64")]
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+/// Syscalls nothing a coding agent runs needs, and that would let the tree
+/// reach outside its confinement: debugging or reading other processes,
+/// (re)mounting, namespaces, kernel modules, eBPF. New namespaces also come
+/// from `clone`, filtered on `CLONE_NEW_FLAGS`, and `clone3`, whose flags
+/// sit behind a pointer seccomp cannot read.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const DENIED_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_ptrace,
+    libc::SYS_process_vm_readv,
+    libc::SYS_process_vm_writev,
+    libc::SYS_mount,
+    libc::SYS_umount2,
+    libc::SYS_pivot_root,
+    libc::SYS_chroot,
+    libc::SYS_setns,
+    libc::SYS_unshare,
+    libc::SYS_kexec_load,
+    libc::SYS_init_module,
+    libc::SYS_finit_module,
+    libc::SYS_delete_module,
+    libc::SYS_bpf,
+    libc::SYS_perf_event_open,
+];
+
+/// `clone` flags that create namespaces. All sit in the low 32 bits.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const CLONE_NEW_FLAGS: u32 = (libc::CLONE_NEWNS
+    | libc::CLONE_NEWCGROUP
+    | libc::CLONE_NEWUTS
+    | libc::CLONE_NEWIPC
+    | libc::CLONE_NEWUSER
+    | libc::CLONE_NEWPID
+    | libc::CLONE_NEWNET) as u32;
+
+// Classic BPF opcodes (<linux/filter.h>): BPF_LD|BPF_W|BPF_ABS,
+// BPF_JMP|BPF_JEQ|BPF_K, BPF_JMP|BPF_JGE|BPF_K, BPF_JMP|BPF_JSET|BPF_K,
+// BPF_RET|BPF_K.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const BPF_LD_W_ABS: u16 = 0x20;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const BPF_JMP_JEQ_K: u16 = 0x15;
+#[cfg(target_arch = "x86_64")]
+const BPF_JMP_JGE_K: u16 = 0x35;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const BPF_JMP_JSET_K: u16 = 0x45;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const BPF_RET_K: u16 = 0x06;
+/// Offsets into `struct seccomp_data`. `args[0]` is read as its low word:
+/// both architectures are little-endian.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const SECCOMP_DATA_NR: u32 = 0;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const SECCOMP_DATA_ARCH: u32 = 4;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const SECCOMP_DATA_ARG0: u32 = 16;
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn bpf(code: u16, jt: u8, jf: u8, k: u32) -> libc::sock_filter {
+    libc::sock_filter { code, jt, jf, k }
+}
+
+/// The seccomp program: kill on a foreign arch, EPERM for
+/// `DENIED_SYSCALLS` and for a `clone` with `CLONE_NEW_FLAGS`, ENOSYS for
+/// `clone3` (libc then falls back to `clone`), allow the rest. None on
+/// architectures we have no syscall table for.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+pub fn seccomp_filter() -> Option<Vec<libc::sock_filter>> {
+    let n = DENIED_SYSCALLS.len() as u8;
+    let mut prog = vec![
+        bpf(BPF_LD_W_ABS, 0, 0, SECCOMP_DATA_ARCH),
+        bpf(BPF_JMP_JEQ_K, 1, 0, AUDIT_ARCH),
+        bpf(BPF_RET_K, 0, 0, libc::SECCOMP_RET_KILL_PROCESS),
+        bpf(BPF_LD_W_ABS, 0, 0, SECCOMP_DATA_NR),
+    ];
+    // Layout from here: [x32 check], n × jeq, jeq clone3, jeq clone, ld
+    // flags, jset, ret allow, ret EPERM, ret ENOSYS. A denied number jumps
+    // over the remaining checks and the allow.
+    #[cfg(target_arch = "x86_64")]
+    prog.push(bpf(BPF_JMP_JGE_K, n + 5, 0, X32_SYSCALL_BIT));
+    for (i, nr) in DENIED_SYSCALLS.iter().enumerate() {
+        prog.push(bpf(BPF_JMP_JEQ_K, n - i as u8 + 4, 0, *nr as u32));
+    }
+    prog.push(bpf(BPF_JMP_JEQ_K, 5, 0, libc::SYS_clone3 as u32));
+    prog.push(bpf(BPF_JMP_JEQ_K, 0, 2, libc::SYS_clone as u32));
+    prog.push(bpf(BPF_LD_W_ABS, 0, 0, SECCOMP_DATA_ARG0));
+    prog.push(bpf(BPF_JMP_JSET_K, 1, 0, CLONE_NEW_FLAGS));
+    prog.push(bpf(BPF_RET_K, 0, 0, libc::SECCOMP_RET_ALLOW));
+    prog.push(bpf(
+        BPF_RET_K,
+        0,
+        0,
+        libc::SECCOMP_RET_ERRNO | libc::EPERM as u32,
+    ));
+    prog.push(bpf(
+        BPF_RET_K,
+        0,
+        0,
+        libc::SECCOMP_RET_ERRNO | libc::ENOSYS as u32,
+    ));
+    Some(prog)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn seccomp_filter() -> Option<Vec<libc::sock_filter>> {
+    None
+}
+
+/// Enforce `ruleset` and `filter` on the child between fork and exec.
+/// Both survive exec and are inherited by every descendant. A failure
+/// fails the spawn — a run never silently starts less confined than
+/// reported.
+pub fn install(cmd: &mut Command, ruleset: Option<&Ruleset>, filter: Option<Vec<libc::sock_filter>>) {
+    let ruleset_fd = ruleset.map(|r| r.fd.as_raw_fd());
+    // SAFETY: the hook only makes raw syscalls on memory prepared before
+    // the fork — no allocation, no locks.
+    unsafe {
+        cmd.pre_exec(move || {
+            // Required for an unprivileged landlock_restrict_self/seccomp,
+            // and stops setuid binaries from shedding the confinement.
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if let Some(fd) = ruleset_fd {
+                if libc::syscall(libc::SYS_landlock_restrict_self, fd, 0u32) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            if let Some(filter) = &filter {
+                let prog = libc::sock_fprog {
+                    len: filter.len() as libc::c_ushort,
+                    filter: filter.as_ptr() as *mut libc::sock_filter,
+                };
+                if libc::prctl(
+                    libc::PR_SET_SECCOMP,
+                    libc::SECCOMP_MODE_FILTER,
+                    &prog as *const libc::sock_fprog,
+                ) != 0
+                {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,281 @@
+//! OS-level confinement for coding runs.
+//!
+//! The CLIs' own guardrails (Claude's deny rules, Codex's `workspace-write`
+//! sandbox) are enforced by the very process a prompt injection is steering.
+//! On Linux the desktop app adds a kernel-enforced layer around the CLI's
+//! whole process tree, set up between fork and exec so every descendant
+//! inherits it:
+//!
+//! - Filesystem (landlock): writes only beneath the working dir, the CLI's
+//!   own state paths, `/tmp` and `/dev`. The last two are granted beyond
+//!   what a run asks for (compilers and shells need them) and the report's
+//!   note says so. Reads stay open — the CLIs load toolchains, node modules
+//!   and config from all over the disk.
+//! - Network: `HTTPS_PROXY` points at a per-run loopback CONNECT proxy that
+//!   only tunnels to the CLI's model API hosts; the per-host allowlist is
+//!   the proxy's alone. Landlock rules name ports, not addresses: where it
+//!   can restrict TCP (ABI 4, Linux 6.7+) all binds are denied and connects
+//!   are allowed only to the proxy's port and the run's local ports. That
+//!   keeps a client that ignores `HTTPS_PROXY` off 443 and the like, but a
+//!   connect to any host on one of those port numbers still goes through.
+//!   On older kernels the proxy is advisory and the report says so.
+//!   Landlock does not cover UDP. A run may also reach named loopback ports
+//!   directly (its desktop-tools MCP endpoint); `NO_PROXY` then covers
+//!   loopback only. The proxy's own upstream connections follow the app's
+//!   proxy settings.
+//! - Syscalls (seccomp): a short deny-list — ptrace, mounts, namespaces
+//!   (`unshare`, `setns`, `clone` with a `CLONE_NEW*` flag; `clone3` answers
+//!   ENOSYS so libc falls back to `clone`), kernel modules, eBPF.
+//!
+//! macOS and Windows: no-op, reported as such. What actually applied always
+//! rides the run's `session` chunk.
+
+mod proxy;
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tokio::process::Command;
+
+/// What a run may touch.
+pub struct Policy<'a> {
+    pub working_dir: &'a Path,
+    /// The CLI's own config/session/cache locations (see
+    /// `LocalCodingRunner::state_paths`).
+    pub state_paths: Vec<PathBuf>,
+    /// Hosts the egress proxy tunnels to, on 443 only.
+    pub allowed_hosts: &'a [&'static str],
+    /// Loopback ports the CLI connects to directly, bypassing the proxy.
+    pub local_ports: &'a [u16],
+    /// How the egress proxy itself reaches those hosts.
+    pub egress: std::sync::Arc<crate::network::Egress>,
+}
+
+/// The effective sandbox, as reported to the engine.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SandboxReport {
+    /// "landlock" | "none"
+    pub kind: &'static str,
+    /// "confined" (writes limited to `writable`) | "unrestricted"
+    pub filesystem: &'static str,
+    /// "allowlist" (hosts filtered by the proxy; the kernel allows TCP
+    /// connects to its port and `local_ports` only) | "proxy" (env only —
+    /// kernel can't restrict TCP) | "unrestricted"
+    pub network: &'static str,
+    pub seccomp: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub writable: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_hosts: Vec<String>,
+    /// Why confinement is partial or absent, when it is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl SandboxReport {
+    /// Add `note` to what the report says about the run's confinement.
+    pub fn add_note(&mut self, note: &str) {
+        self.note = Some(match self.note.take() {
+            Some(notes) => format!("{notes}; {note}"),
+            None => note.to_string(),
+        });
+    }
+
+    pub fn unconfined(note: &str) -> Self {
+        Self {
+            kind: "none",
+            filesystem: "unrestricted",
+            network: "unrestricted",
+            seccomp: false,
+            writable: Vec::new(),
+            allowed_hosts: Vec::new(),
+            note: Some(note.to_string()),
+        }
+    }
+}
+
+/// A configured sandbox. Keep it alive for the whole run: it owns the
+/// egress proxy the child talks through.
+pub struct Confinement {
+    pub report: SandboxReport,
+    _proxy: Option<proxy::EgressProxy>,
+    #[cfg(target_os = "linux")]
+    _ruleset: Option<linux::Ruleset>,
+}
+
+impl Confinement {
+    /// No sandbox, with the reason it is absent.
+    pub fn unconfined(note: &str) -> Self {
+        Self {
+            report: SandboxReport::unconfined(note),
+            _proxy: None,
+            #[cfg(target_os = "linux")]
+            _ruleset: None,
+        }
+    }
+}
+
+/// Configure `cmd` to spawn inside the sandbox. Call right before
+/// `GroupChild::spawn`. Errors are `spawn_failed:`; a kernel without
+/// landlock is not an error — the run proceeds and the report says what is
+/// missing.
+#[cfg(target_os = "linux")]
+pub async fn confine(cmd: &mut Command, policy: &Policy<'_>) -> Result<Confinement, String> {
+    let allowed_hosts: Vec<String> = policy.allowed_hosts.iter().map(|h| h.to_string()).collect();
+    let proxy = proxy::EgressProxy::start(allowed_hosts.clone(), policy.egress.clone())
+        .await
+        .map_err(|e| format!("spawn_failed: could not start the run's egress proxy: {e}"))?;
+    let proxy_url = proxy.url();
+    for var in ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"] {
+        cmd.env(var, &proxy_url);
+    }
+    // An inherited NO_PROXY would route matching hosts around the proxy;
+    // only loopback may skip it, and only for the run's own endpoints.
+    if policy.local_ports.is_empty() {
+        cmd.env_remove("NO_PROXY").env_remove("no_proxy");
+    } else {
+        cmd.env("NO_PROXY", "127.0.0.1,localhost").env("no_proxy", "127.0.0.1,localhost");
+    }
+
+    let mut writable = vec![policy.working_dir.to_path_buf()];
+    writable.extend(policy.state_paths.iter().cloned());
+    writable.extend([PathBuf::from("/tmp"), PathBuf::from("/dev")]);
+    writable.retain(|p| p.exists());
+    writable.sort();
+    writable.dedup();
+
+    let mut connect_ports = vec![proxy.port()];
+    connect_ports.extend(policy.local_ports);
+
+    let abi = linux::landlock_abi();
+    let ruleset = if abi >= 1 {
+        Some(
+            linux::Ruleset::build(abi, &writable, &connect_ports)
+                .map_err(|e| format!("spawn_failed: could not build the run sandbox: {e}"))?,
+        )
+    } else {
+        None
+    };
+    let filter = linux::seccomp_filter();
+
+    let mut notes: Vec<&str> = Vec::new();
+    let network = match &ruleset {
+        Some(r) if r.restricts_net => "allowlist",
+        Some(_) => {
+            notes.push("kernel landlock ABI < 4: network limited by proxy settings only");
+            "proxy"
+        }
+        None => {
+            notes.push("kernel has no landlock: filesystem and network are not confined");
+            "proxy"
+        }
+    };
+    if ruleset.is_some() {
+        notes.push("/tmp and /dev are writable too, shared with other processes");
+    }
+    if filter.is_none() {
+        notes.push("no seccomp filter for this architecture");
+    }
+    let report = SandboxReport {
+        kind: if ruleset.is_some() { "landlock" } else { "none" },
+        filesystem: if ruleset.is_some() { "confined" } else { "unrestricted" },
+        network,
+        seccomp: filter.is_some(),
+        writable: writable.iter().map(|p| p.display().to_string()).collect(),
+        allowed_hosts,
+        note: (!notes.is_empty()).then(|| notes.join("; ")),
+    };
+    linux::install(cmd, ruleset.as_ref(), filter);
+    Ok(Confinement {
+        report,
+        _proxy: Some(proxy),
+        _ruleset: ruleset,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub async fn confine(_cmd: &mut Command, _policy: &Policy<'_>) -> Result<Confinement, String> {
+    Ok(Confinement::unconfined("OS-level sandboxing is Linux-only"))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    /// Run `script` under `sh` inside a sandbox rooted at a fresh temp dir.
+    async fn run_confined(script: &str) -> (std::process::ExitStatus, SandboxReport) {
+        // Not under /tmp, which the sandbox leaves writable anyway.
+        let work = tempfile::tempdir_in(env!("CARGO_MANIFEST_DIR")).unwrap();
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c").arg(script).current_dir(work.path());
+        let confinement = confine(
+            &mut cmd,
+            &Policy {
+                working_dir: work.path(),
+                state_paths: Vec::new(),
+                allowed_hosts: &["api.anthropic.com"],
+                local_ports: &[],
+                egress: Default::default(),
+            },
+        )
+        .await
+        .unwrap();
+        let status = cmd.status().await.unwrap();
+        (status, confinement.report.clone())
+    }
+
+    #[tokio::test]
+    async fn writes_outside_the_working_dir_are_denied() {
+        let outside = tempfile::tempdir_in(env!("CARGO_MANIFEST_DIR")).unwrap();
+        let target = outside.path().join("escaped.txt");
+        let (status, report) = run_confined(&format!(
+            "echo ok > inside.txt && echo no > '{}'",
+            target.display()
+        ))
+        .await;
+        if report.kind == "none" {
+            eprintln!("skipping: {:?}", report.note);
+            return;
+        }
+        assert!(!status.success(), "write outside the sandbox succeeded");
+        assert!(!target.exists());
+        assert_eq!(report.filesystem, "confined");
+        assert!(report.seccomp);
+        assert!(report.note.unwrap().contains("/tmp and /dev are writable"));
+    }
+
+    #[tokio::test]
+    async fn new_namespaces_are_denied() {
+        if !Path::new("/usr/bin/unshare").exists() {
+            eprintln!("skipping: no unshare(1)");
+            return;
+        }
+        let (status, report) = run_confined("! /usr/bin/unshare -U true").await;
+        if !report.seccomp {
+            eprintln!("skipping: {:?}", report.note);
+            return;
+        }
+        assert!(
+            status.success(),
+            "a namespace was created inside the sandbox"
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_inside_the_working_dir_still_work() {
+        let (status, _) = run_confined("mkdir sub && echo ok > sub/f && rm sub/f").await;
+        assert!(status.success());
+    }
+
+    #[tokio::test]
+    async fn child_sees_the_proxy_and_no_no_proxy() {
+        let (status, report) =
+            run_confined(r#"case "$HTTPS_PROXY" in http://127.0.0.1:*) ;; *) exit 1;; esac; [ -z "$NO_PROXY" ]"#)
+                .await;
+        assert!(status.success());
+        assert_eq!(report.allowed_hosts, ["api.anthropic.com"]);
+    }
+}
@@ -0,0 +1,166 @@
+//! Per-run egress proxy: an HTTP CONNECT proxy on loopback that tunnels only
+//! to the run's allow-listed hosts on 443. The confined CLI reaches it via
+//! `HTTPS_PROXY`; on kernels with landlock TCP rules it is the only TCP
+//! endpoint the CLI can connect to at all.
+//!
+//! CONNECT-only on purpose: both CLIs speak TLS to their APIs, so a tunnel is
+//! all they need, and refusing plain-HTTP requests means the proxy never
+//! parses anything past the request line.
+//!
+//! Upstream tunnels dial through the app's own route (`network.rs`), so a
+//! run behind a corporate proxy reaches its API the way the app does.
+
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::network::Egress;
+
+/// Upper bound on a CONNECT request head. Real ones are ~100 bytes.
+const MAX_HEAD: usize = 8 * 1024;
+
+pub struct EgressProxy {
+    port: u16,
+    accept_task: tokio::task::JoinHandle<()>,
+}
+
+impl EgressProxy {
+    pub async fn start(allowed_hosts: Vec<String>, egress: Arc<Egress>) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let port = listener.local_addr()?.port();
+        let allowed = Arc::new(allowed_hosts);
+        let accept_task = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((conn, _)) => {
+                        let allowed = allowed.clone();
+                        let egress = egress.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = handle(conn, &allowed, &egress).await {
+                                log::debug!("Sandbox proxy connection ended: {e}");
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        // EMFILE and friends: back off instead of spinning.
+                        log::warn!("Sandbox proxy accept failed: {e}");
+                        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                    }
+                }
+            }
+        });
+        Ok(Self { port, accept_task })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The value for the child's `*_PROXY` environment variables.
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+}
+
+impl Drop for EgressProxy {
+    /// Stops accepting. Tunnels already open end with the CLI that owns them.
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+async fn handle(
+    mut client: TcpStream,
+    allowed: &[String],
+    egress: &Egress,
+) -> std::io::Result<()> {
+    let head = read_head(&mut client).await?;
+    let request_line = head.lines().next().unwrap_or_default();
+    match parse_connect(request_line) {
+        Some((host, port)) if is_allowed(host, port, allowed) => {
+            let mut upstream = match egress.dial(host, port).await {
+                Ok(s) => s,
+                Err(e) => {
+                    client.write_all(b"HTTP/1.1 502 Bad Gateway\r\n\r\n").await?;
+                    return Err(e);
+                }
+            };
+            client
+                .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                .await?;
+            tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+        }
+        _ => {
+            log::warn!("Sandbox proxy refused egress: {request_line}");
+            client.write_all(b"HTTP/1.1 403 Forbidden\r\n\r\n").await?;
+        }
+    }
+    Ok(())
+}
+
+/// Read up to the blank line ending the request head. The client sends
+/// nothing else until it sees our response, so nothing is over-read.
+async fn read_head(client: &mut TcpStream) -> std::io::Result<String> {
+    let mut buf = Vec::with_capacity(512);
+    let mut chunk = [0u8; 512];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() > MAX_HEAD {
+            return Err(std::io::Error::other("request head too large"));
+        }
+        let n = client.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// `CONNECT host:443 HTTP/1.1` → `("host", 443)`. Anything else is None.
+fn parse_connect(request_line: &str) -> Option<(&str, u16)> {
+    let mut parts = request_line.split_whitespace();
+    if parts.next()? != "CONNECT" {
+        return None;
+    }
+    let (host, port) = parts.next()?.rsplit_once(':')?;
+    Some((host, port.parse().ok()?))
+}
+
+fn is_allowed(host: &str, port: u16, allowed: &[String]) -> bool {
+    port == 443 && allowed.iter().any(|h| h.eq_ignore_ascii_case(host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_connect_to_allowed_hosts_on_443_passes() {
+        let allowed = vec!["api.anthropic.com".to_string()];
+        let check = |line: &str| {
+            parse_connect(line).is_some_and(|(host, port)| is_allowed(host, port, &allowed))
+        };
+        assert!(check("CONNECT api.anthropic.com:443 HTTP/1.1"));
+        assert!(check("CONNECT API.Anthropic.com:443 HTTP/1.1"));
+        assert!(!check("CONNECT api.anthropic.com:80 HTTP/1.1"));
+        assert!(!check("CONNECT evil.example:443 HTTP/1.1"));
+        assert!(!check("CONNECT api.anthropic.com.evil.example:443 HTTP/1.1"));
+        assert!(!check("GET http://api.anthropic.com/ HTTP/1.1"));
+        assert!(!check("CONNECT api.anthropic.com HTTP/1.1"));
+    }
+
+    #[tokio::test]
+    async fn refused_hosts_get_403_without_a_tunnel() {
+        let proxy = EgressProxy::start(vec!["api.anthropic.com".into()], Default::default())
+            .await
+            .unwrap();
+        let mut conn = TcpStream::connect(("127.0.0.1", proxy.port())).await.unwrap();
+        conn.write_all(b"CONNECT evil.example:443 HTTP/1.1\r\nHost: evil.example\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        conn.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 403"), "{response}");
+    }
+}
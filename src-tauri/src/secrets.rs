@@ -0,0 +1,508 @@
+//! Secrets: the device tokens, the user's Anthropic API key and the local
+//! MCP server's bearer token.
+//!
+//! They live in the platform secret store — the macOS Keychain, Windows
+//! Credential Manager, or the Secret Service (GNOME Keyring, KWallet) over
+//! D-Bus on Linux — under the service name `com.thebeakr.desktop`, never in
+//! `settings.json`. A Linux desktop without a Secret Service provider (a
+//! minimal window manager, a headless box) gets an encrypted vault file in
+//! the app data dir instead: ChaCha20-Poly1305 under a random key kept
+//! beside it, readable by this user only. That keeps the secrets out of
+//! anything that copies or syncs the settings file, but unlike the platform
+//! store it does not hold up against code running as the same user.
+//!
+//! Values stored before this module existed are moved here on the first
+//! launch that has it (`migrate_plaintext`) and scrubbed from the JSON store.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+
+/// The bundle identifier; what the platform store files entries under.
+const SERVICE: &str = "com.thebeakr.desktop";
+const VAULT_FILE: &str = "secrets.vault";
+const VAULT_KEY_FILE: &str = "secrets.key";
+const NONCE_LEN: usize = 12;
+
+pub const ANTHROPIC_API_KEY: &str = "anthropic_api_key";
+/// The token local MCP clients present (see `mcp`).
+pub const MCP_TOKEN: &str = "mcp_token";
+
+/// The secret holding a pairing's device token. Pairings migrated from
+/// per-environment tokens share the environment's id (see `pairing`).
+pub fn device_token(pairing_id: &str) -> String {
+    format!("device_token.{pairing_id}")
+}
+
+/// Serializes the vault's read-modify-write cycles.
+static VAULT_LOCK: Mutex<()> = Mutex::new(());
+/// Whether the "no platform store" warning was logged yet.
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+pub fn get(app: &AppHandle, name: &str) -> Result<Option<String>, String> {
+    open(app)?.get(name)
+}
+
+pub fn set(app: &AppHandle, name: &str, value: &str) -> Result<(), String> {
+    open(app)?.set(name, value)
+}
+
+pub fn delete(app: &AppHandle, name: &str) -> Result<(), String> {
+    open(app)?.delete(name)
+}
+
+fn open(app: &AppHandle) -> Result<Secrets<Platform>, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("secret_store_failed: no app data dir: {e}"))?;
+    Ok(Secrets {
+        keychain: Platform,
+        vault: Vault::new(&dir),
+    })
+}
+
+/// Move secrets the JSON store still holds in plaintext into secret
+/// storage, then delete them there. Run once at startup, before anything
+/// reads a token. A value that fails to move stays put and is retried on
+/// the next launch.
+pub fn migrate_plaintext(app: &AppHandle) {
+    let store = match app.store("settings.json") {
+        Ok(store) => store,
+        Err(e) => {
+            log::error!("Secret migration: failed to open store: {e}");
+            return;
+        }
+    };
+    let found = plaintext(|key| store.get(key));
+    if found.is_empty() {
+        return;
+    }
+    let mut moved_keys = Vec::new();
+    let mut failed_keys = Vec::new();
+    for (store_key, name, value) in found {
+        // A secret already stored wins over a stale plaintext copy.
+        let result = match get(app, &name) {
+            Ok(Some(_)) => Ok(()),
+            Ok(None) => set(app, &name, &value),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => moved_keys.push(store_key),
+            Err(e) => {
+                log::error!("Secret migration: could not move {name}: {e}");
+                failed_keys.push(store_key);
+            }
+        }
+    }
+    for key in moved_keys {
+        if !failed_keys.contains(&key) {
+            let _ = store.delete(&key);
+        }
+    }
+    if let Err(e) = store.save() {
+        log::error!("Secret migration: failed to save the scrubbed store: {e}");
+    } else if failed_keys.is_empty() {
+        log::info!("Moved plaintext secrets out of settings.json");
+    }
+}
+
+/// `(store key, secret name, value)` for every plaintext secret in the JSON
+/// store: the API key and the MCP token; the single device token from
+/// before environments, which belongs to the build's default one; and the
+/// per-environment map that briefly replaced it.
+fn plaintext(get: impl Fn(&str) -> Option<serde_json::Value>) -> Vec<(String, String, String)> {
+    let string = |key: &str| {
+        get(key)
+            .and_then(|v| v.as_str().map(str::to_string))
+            .filter(|s| !s.is_empty())
+    };
+    let mut found = Vec::new();
+    if let Some(key) = string(ANTHROPIC_API_KEY) {
+        found.push((ANTHROPIC_API_KEY.into(), ANTHROPIC_API_KEY.into(), key));
+    }
+    if let Some(token) = string(MCP_TOKEN) {
+        found.push((MCP_TOKEN.into(), MCP_TOKEN.into(), token));
+    }
+    let tokens: BTreeMap<String, String> = get("device_tokens")
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    for (profile_id, token) in tokens.into_iter().filter(|(_, t)| !t.is_empty()) {
+        found.push(("device_tokens".into(), device_token(&profile_id), token));
+    }
+    if let Some(token) = string("device_token") {
+        let name = device_token(crate::environment::default_id());
+        if !found.iter().any(|(_, n, _)| *n == name) {
+            found.push(("device_token".into(), name, token));
+        }
+    }
+    found
+}
+
+/// Why a platform store call failed.
+#[derive(Debug)]
+enum KeychainError {
+    /// No store to talk to, or it refused access: use the vault.
+    Unavailable(String),
+    Other(String),
+}
+
+/// The platform secret store, behind a seam for tests.
+trait Keychain {
+    fn get(&self, name: &str) -> Result<Option<String>, KeychainError>;
+    fn set(&self, name: &str, value: &str) -> Result<(), KeychainError>;
+    fn delete(&self, name: &str) -> Result<(), KeychainError>;
+}
+
+struct Platform;
+
+impl Platform {
+    fn entry(name: &str) -> Result<keyring::Entry, KeychainError> {
+        keyring::Entry::new(SERVICE, name).map_err(classify)
+    }
+}
+
+fn classify(e: keyring::Error) -> KeychainError {
+    match e {
+        keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_) => {
+            KeychainError::Unavailable(e.to_string())
+        }
+        other => KeychainError::Other(other.to_string()),
+    }
+}
+
+impl Keychain for Platform {
+    fn get(&self, name: &str) -> Result<Option<String>, KeychainError> {
+        match Self::entry(name)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(classify(e)),
+        }
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<(), KeychainError> {
+        Self::entry(name)?.set_password(value).map_err(classify)
+    }
+
+    fn delete(&self, name: &str) -> Result<(), KeychainError> {
+        match Self::entry(name)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(classify(e)),
+        }
+    }
+}
+
+/// The platform store, falling back to the vault when it is unavailable.
+struct Secrets<K> {
+    keychain: K,
+    vault: Vault,
+}
+
+impl<K: Keychain> Secrets<K> {
+    fn get(&self, name: &str) -> Result<Option<String>, String> {
+        match self.keychain.get(name) {
+            Ok(Some(value)) => Ok(Some(value)),
+            // Possibly written while the platform store was down.
+            Ok(None) => self.vault.get(name),
+            Err(KeychainError::Unavailable(e)) => {
+                warn_unavailable(&e);
+                self.vault.get(name)
+            }
+            Err(KeychainError::Other(e)) => Err(format!("secret_store_failed: {e}")),
+        }
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<(), String> {
+        match self.keychain.set(name, value) {
+            // Drop any copy the vault took while the platform store was down.
+            Ok(()) => self.vault.remove(name),
+            Err(KeychainError::Unavailable(e)) => {
+                warn_unavailable(&e);
+                self.vault.insert(name, value)
+            }
+            Err(KeychainError::Other(e)) => Err(format!("secret_store_failed: {e}")),
+        }
+    }
+
+    fn delete(&self, name: &str) -> Result<(), String> {
+        match self.keychain.delete(name) {
+            Ok(()) => {}
+            Err(KeychainError::Unavailable(e)) => warn_unavailable(&e),
+            Err(KeychainError::Other(e)) => return Err(format!("secret_store_failed: {e}")),
+        }
+        self.vault.remove(name)
+    }
+}
+
+fn warn_unavailable(reason: &str) {
+    if !WARNED.swap(true, Ordering::Relaxed) {
+        log::warn!("No platform secret store ({reason}); using the encrypted vault file");
+    }
+}
+
+/// The fallback: a name → value map, sealed with ChaCha20-Poly1305. The
+/// file is a random nonce followed by the ciphertext; the key file is 32
+/// random bytes. Both are written owner-only.
+struct Vault {
+    path: PathBuf,
+    key_path: PathBuf,
+}
+
+impl Vault {
+    fn new(dir: &Path) -> Self {
+        Self {
+            path: dir.join(VAULT_FILE),
+            key_path: dir.join(VAULT_KEY_FILE),
+        }
+    }
+
+    fn get(&self, name: &str) -> Result<Option<String>, String> {
+        let _guard = VAULT_LOCK.lock().expect("vault lock poisoned");
+        Ok(self.load()?.remove(name))
+    }
+
+    fn insert(&self, name: &str, value: &str) -> Result<(), String> {
+        let _guard = VAULT_LOCK.lock().expect("vault lock poisoned");
+        let mut entries = self.load()?;
+        entries.insert(name.to_string(), value.to_string());
+        self.save(&entries)
+    }
+
+    fn remove(&self, name: &str) -> Result<(), String> {
+        let _guard = VAULT_LOCK.lock().expect("vault lock poisoned");
+        if !self.path.exists() {
+            return Ok(());
+        }
+        let mut entries = self.load()?;
+        if entries.remove(name).is_some() {
+            self.save(&entries)?;
+        }
+        Ok(())
+    }
+
+    fn load(&self) -> Result<BTreeMap<String, String>, String> {
+        let sealed = match std::fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(format!("secret_store_failed: read vault: {e}")),
+        };
+        if sealed.len() < NONCE_LEN {
+            return Err("secret_store_failed: vault is truncated".into());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher()?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "secret_store_failed: vault does not decrypt with its key".to_string())?;
+        serde_json::from_slice(&plaintext)
+            .map_err(|e| format!("secret_store_failed: vault contents: {e}"))
+    }
+
+    fn save(&self, entries: &BTreeMap<String, String>) -> Result<(), String> {
+        let plaintext = serde_json::to_vec(entries).unwrap_or_default();
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher()?
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| "secret_store_failed: could not seal the vault".to_string())?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        write_private(&self.path, &sealed)
+    }
+
+    /// The vault's cipher, minting the key on first use.
+    fn cipher(&self) -> Result<ChaCha20Poly1305, String> {
+        let key = match std::fs::read(&self.key_path) {
+            Ok(key) if key.len() == 32 => key,
+            Ok(_) => return Err("secret_store_failed: vault key is malformed".into()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let mut key = vec![0u8; 32];
+                rand::thread_rng().fill_bytes(&mut key);
+                write_private(&self.key_path, &key)?;
+                key
+            }
+            Err(e) => return Err(format!("secret_store_failed: read vault key: {e}")),
+        };
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+}
+
+/// Replace `path` with `bytes`, readable by this user only. Written beside
+/// it first so a crash leaves the old file whole.
+fn write_private(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let fail = |e: std::io::Error| format!("secret_store_failed: write {}: {e}", path.display());
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(fail)?;
+    }
+    let tmp = path.with_extension("tmp");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp).map_err(fail)?;
+    file.write_all(bytes).map_err(fail)?;
+    file.sync_all().map_err(fail)?;
+    std::fs::rename(&tmp, path).map_err(fail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// A platform store that is either missing or an in-memory map.
+    #[derive(Default)]
+    struct FakeKeychain {
+        unavailable: bool,
+        entries: RefCell<BTreeMap<String, String>>,
+    }
+
+    impl Keychain for FakeKeychain {
+        fn get(&self, name: &str) -> Result<Option<String>, KeychainError> {
+            if self.unavailable {
+                return Err(KeychainError::Unavailable("no secret service".into()));
+            }
+            Ok(self.entries.borrow().get(name).cloned())
+        }
+
+        fn set(&self, name: &str, value: &str) -> Result<(), KeychainError> {
+            if self.unavailable {
+                return Err(KeychainError::Unavailable("no secret service".into()));
+            }
+            self.entries.borrow_mut().insert(name.into(), value.into());
+            Ok(())
+        }
+
+        fn delete(&self, name: &str) -> Result<(), KeychainError> {
+            if self.unavailable {
+                return Err(KeychainError::Unavailable("no secret service".into()));
+            }
+            self.entries.borrow_mut().remove(name);
+            Ok(())
+        }
+    }
+
+    fn secrets(dir: &Path, unavailable: bool) -> Secrets<FakeKeychain> {
+        Secrets {
+            keychain: FakeKeychain {
+                unavailable,
+                ..Default::default()
+            },
+            vault: Vault::new(dir),
+        }
+    }
+
+    #[test]
+    fn the_platform_store_is_used_when_present() {
+        let dir = tempfile::tempdir().unwrap();
+        let secrets = secrets(dir.path(), false);
+        secrets.set("device_token.production", "tok").unwrap();
+        assert_eq!(
+            secrets.get("device_token.production").unwrap().as_deref(),
+            Some("tok")
+        );
+        assert!(!dir.path().join(VAULT_FILE).exists(), "nothing on disk");
+        secrets.delete("device_token.production").unwrap();
+        assert_eq!(secrets.get("device_token.production").unwrap(), None);
+    }
+
+    #[test]
+    fn without_a_platform_store_secrets_are_sealed_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let secrets = secrets(dir.path(), true);
+        secrets.set(ANTHROPIC_API_KEY, "sk-ant-secret").unwrap();
+        assert_eq!(
+            secrets.get(ANTHROPIC_API_KEY).unwrap().as_deref(),
+            Some("sk-ant-secret")
+        );
+
+        let sealed = std::fs::read(dir.path().join(VAULT_FILE)).unwrap();
+        assert!(!String::from_utf8_lossy(&sealed).contains("sk-ant-secret"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            for file in [VAULT_FILE, VAULT_KEY_FILE] {
+                let mode = std::fs::metadata(dir.path().join(file))
+                    .unwrap()
+                    .permissions()
+                    .mode();
+                assert_eq!(mode & 0o777, 0o600, "{file}");
+            }
+        }
+
+        secrets.delete(ANTHROPIC_API_KEY).unwrap();
+        assert_eq!(secrets.get(ANTHROPIC_API_KEY).unwrap(), None);
+    }
+
+    #[test]
+    fn a_tampered_vault_is_an_error_not_an_empty_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let secrets = secrets(dir.path(), true);
+        secrets.set("device_token.local", "tok").unwrap();
+        let path = dir.path().join(VAULT_FILE);
+        let mut sealed = std::fs::read(&path).unwrap();
+        *sealed.last_mut().unwrap() ^= 1;
+        std::fs::write(&path, sealed).unwrap();
+        let err = secrets.get("device_token.local").unwrap_err();
+        assert!(err.starts_with("secret_store_failed:"), "{err}");
+    }
+
+    #[test]
+    fn a_value_from_the_vault_moves_to_the_platform_store_once_it_is_back() {
+        let dir = tempfile::tempdir().unwrap();
+        secrets(dir.path(), true)
+            .set("device_token.local", "old")
+            .unwrap();
+
+        let secrets = secrets(dir.path(), false);
+        assert_eq!(
+            secrets.get("device_token.local").unwrap().as_deref(),
+            Some("old")
+        );
+        secrets.set("device_token.local", "new").unwrap();
+        assert_eq!(secrets.vault.get("device_token.local").unwrap(), None);
+        assert_eq!(
+            secrets.get("device_token.local").unwrap().as_deref(),
+            Some("new")
+        );
+    }
+
+    #[test]
+    fn plaintext_secrets_are_found_in_every_old_layout() {
+        let store = serde_json::json!({
+            "anthropic_api_key": "sk-ant-x",
+            "mcp_token": "0f3a",
+            "device_token": "legacy",
+            "device_tokens": { "staging": "tok-s", "local": "" },
+            "device_name": "lab-mac",
+        });
+        let found = plaintext(|key| store.get(key).cloned());
+        let names: Vec<(&str, &str)> = found
+            .iter()
+            .map(|(key, name, _)| (key.as_str(), name.as_str()))
+            .collect();
+        let legacy = device_token(crate::environment::default_id());
+        assert_eq!(
+            names,
+            [
+                ("anthropic_api_key", "anthropic_api_key"),
+                ("mcp_token", "mcp_token"),
+                ("device_tokens", "device_token.staging"),
+                ("device_token", legacy.as_str()),
+            ]
+        );
+        assert!(plaintext(|_| None).is_empty());
+    }
+}
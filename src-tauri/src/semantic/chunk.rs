@@ -0,0 +1,179 @@
+//! Which files are documents, and how a document is cut into the passages
+//! that get embedded.
+//!
+//! The model reads at most a few hundred tokens at once, and one vector for a
+//! whole protocol would blur every section into the average. So a document
+//! is packed paragraph by paragraph into passages of about [`CHUNK_CHARS`];
+//! a paragraph longer than that is cut at word boundaries, each cut
+//! repeating the last [`OVERLAP_CHARS`] of the one before so a sentence
+//! across the cut is still whole in one of them.
+
+use std::path::Path;
+
+/// The extensions worth embedding: prose and lightly structured text.
+/// Binaries, images and code are left to the name and content searches.
+const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "txt", "md", "markdown", "rst", "org", "tex", "csv", "tsv", "json", "yaml", "yml", "xml",
+    "html", "htm",
+];
+
+/// Larger files are skipped: logs and exports, not notes.
+pub const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+/// About 200 tokens, within the model's window.
+pub const CHUNK_CHARS: usize = 800;
+pub const OVERLAP_CHARS: usize = 150;
+/// Past this, the rest of a document is not embedded.
+pub const MAX_CHUNKS: usize = 64;
+
+/// One passage to embed, and the line it starts on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Piece {
+    pub line: usize,
+    pub text: String,
+}
+
+/// Whether `path` has a document extension.
+pub fn is_document(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| DOCUMENT_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Cut `text` into passages, at most [`MAX_CHUNKS`].
+pub fn split(text: &str) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    let mut start_line = 1;
+    for (line, paragraph) in paragraphs(text) {
+        for part in windows(&paragraph) {
+            if !current.is_empty() && current.len() + 1 + part.len() > CHUNK_CHARS {
+                pieces.push(Piece {
+                    line: start_line,
+                    text: std::mem::take(&mut current),
+                });
+                if pieces.len() == MAX_CHUNKS {
+                    return pieces;
+                }
+            }
+            if current.is_empty() {
+                start_line = line;
+            } else {
+                current.push('\n');
+            }
+            current.push_str(part);
+        }
+    }
+    if !current.is_empty() {
+        pieces.push(Piece {
+            line: start_line,
+            text: current,
+        });
+    }
+    pieces
+}
+
+/// The non-blank runs of lines, each joined into one line, with the
+/// (1-based) line it starts on.
+fn paragraphs(text: &str) -> Vec<(usize, String)> {
+    let mut out: Vec<(usize, String)> = Vec::new();
+    let mut open = false;
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            open = false;
+            continue;
+        }
+        match out.last_mut() {
+            Some((_, paragraph)) if open => {
+                paragraph.push(' ');
+                paragraph.push_str(line);
+            }
+            _ => out.push((n + 1, line.to_string())),
+        }
+        open = true;
+    }
+    out
+}
+
+/// `paragraph` in cuts of at most [`CHUNK_CHARS`], overlapping by about
+/// [`OVERLAP_CHARS`].
+fn windows(paragraph: &str) -> Vec<&str> {
+    if paragraph.len() <= CHUNK_CHARS {
+        return vec![paragraph];
+    }
+    let mut out = Vec::new();
+    let mut start = 0;
+    loop {
+        let mut end = floor_boundary(paragraph, start + CHUNK_CHARS);
+        if end < paragraph.len() {
+            // Back up to the last space, unless the cut is one long word.
+            if let Some(space) = paragraph[start..end].rfind(' ') {
+                if space > 0 {
+                    end = start + space;
+                }
+            }
+        }
+        out.push(paragraph[start..end].trim());
+        if end >= paragraph.len() {
+            return out;
+        }
+        let back = floor_boundary(paragraph, end.saturating_sub(OVERLAP_CHARS).max(start + 1));
+        // Start the next cut on a word.
+        start = match paragraph[back..end].find(' ') {
+            Some(space) => back + space + 1,
+            None => back,
+        };
+    }
+}
+
+/// The largest char boundary of `s` at or before `at`.
+fn floor_boundary(s: &str, at: usize) -> usize {
+    let mut at = at.min(s.len());
+    while !s.is_char_boundary(at) {
+        at -= 1;
+    }
+    at
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paragraphs_pack_into_passages_that_remember_their_line() {
+        let text = "Title\n\nfirst paragraph\ncontinues here\n\n\nsecond one\n";
+        assert_eq!(
+            split(text),
+            [Piece {
+                line: 1,
+                text: "Title\nfirst paragraph continues here\nsecond one".into(),
+            }]
+        );
+
+        let para = "word ".repeat(150); // 750 chars: one per passage
+        let text = format!("{para}\n\n{para}\n\n{para}");
+        let lines: Vec<usize> = split(&text).iter().map(|p| p.line).collect();
+        assert_eq!(lines, [1, 3, 5]);
+        assert!(is_document(Path::new("/a/Notes.MD")));
+        assert!(!is_document(Path::new("/a/plot.png")));
+    }
+
+    #[test]
+    fn a_long_paragraph_is_cut_at_words_with_overlap() {
+        let words: Vec<String> = (0..600).map(|n| format!("w{n}")).collect();
+        let text = words.join(" ");
+        let pieces = split(&text);
+        assert!(pieces.len() > 1);
+        for pair in pieces.windows(2) {
+            assert!(pair[0].text.len() <= CHUNK_CHARS);
+            let last = pair[0].text.rsplit(' ').next().unwrap();
+            // No word is cut, and the next passage repeats the tail.
+            assert!(words.iter().any(|w| w == last));
+            assert!(pair[1].text.contains(&format!("{last} ")));
+        }
+        assert!(pieces.last().unwrap().text.ends_with("w599"));
+
+        let huge = "x".repeat(CHUNK_CHARS * (MAX_CHUNKS + 5));
+        assert_eq!(split(&huge).len(), MAX_CHUNKS);
+    }
+}
@@ -0,0 +1,559 @@
+//! Semantic search over the documents in the scoped folders.
+//!
+//! The name search (`FileIndex::search_names`) and the content search both
+//! need the user's words to be in the file: asking for "the protocol where we
+//! improved transfection efficiency" finds nothing when the notes say
+//! "better lipofection yield". This index keeps, next to the `FileIndex`, a
+//! vector for every passage of every document (see `chunk`), made on the
+//! device by a small CPU-only model (see `model`). A query is embedded the
+//! same way; a file scores its best passage's cosine similarity, nudged by
+//! the frecency and recency the name search ranks by (see [`score`]).
+//!
+//! Building is lazy and incremental: each `semantic_search_files` request
+//! first embeds the documents that are new or changed since their last
+//! embedding, within the request's budget, then searches what is embedded.
+//! A large folder is covered over a few requests, and each response says how
+//! many documents are still pending. The vectors live in memory only.
+
+mod chunk;
+mod model;
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock, TryLockError};
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use crate::file_index::{self, FileMeta};
+use crate::tools::budget::Budget;
+
+/// Turns passages into vectors. The app's is `model::LocalModel`; tests use
+/// a deterministic stand-in.
+pub trait Embedder: Send + Sync {
+    /// One vector per text, all of one length.
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String>;
+}
+
+/// Similarity dominates; frecency and recency only reorder files that are
+/// about equally close to the query.
+const FRECENCY_WEIGHT: f32 = 0.05;
+const RECENCY_WEIGHT: f32 = 0.05;
+/// Reads after which the frecency boost is full.
+const FRECENCY_SATURATION: f32 = 10.0;
+const RECENCY_HALF_LIFE_DAYS: f32 = 30.0;
+/// A file whose best passage is less similar than this is not a hit,
+/// however often it was read.
+pub const MIN_SIMILARITY: f32 = 0.25;
+
+struct Passage {
+    line: usize,
+    /// The passage's first line, shown as the hit's context.
+    preview: String,
+    /// Unit length, so a dot product is the cosine.
+    vector: Vec<f32>,
+}
+
+/// A document's passages as of its `modified`.
+struct Embedded {
+    modified: Option<SystemTime>,
+    passages: Vec<Passage>,
+}
+
+/// One ranked file.
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub path: PathBuf,
+    pub name: String,
+    pub score: f32,
+    pub line: usize,
+    pub preview: String,
+}
+
+/// How much of the scoped documents is searchable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub indexed: usize,
+    /// Documents new or changed since they were embedded; not searched.
+    pub pending: usize,
+}
+
+/// Whether the model can be used, for Settings.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ModelStatus {
+    pub ready: bool,
+    pub downloading: bool,
+}
+
+pub struct SemanticIndex {
+    /// Where the model is downloaded to, set at startup.
+    model_dir: OnceLock<PathBuf>,
+    /// Loaded on first use, once downloaded: it takes memory.
+    embedder: Mutex<Option<Arc<dyn Embedder>>>,
+    downloading: AtomicBool,
+    files: RwLock<HashMap<PathBuf, Embedded>>,
+    /// One update at a time; another request searches what is there.
+    updating: Mutex<()>,
+}
+
+impl Default for SemanticIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SemanticIndex {
+    pub fn new() -> Self {
+        Self {
+            model_dir: OnceLock::new(),
+            embedder: Mutex::new(None),
+            downloading: AtomicBool::new(false),
+            files: RwLock::new(HashMap::new()),
+            updating: Mutex::new(()),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_embedder(embedder: Arc<dyn Embedder>) -> Self {
+        let index = Self::new();
+        *index.embedder.lock().unwrap() = Some(embedder);
+        index
+    }
+
+    pub fn set_model_dir(&self, dir: PathBuf) {
+        let _ = self.model_dir.set(dir);
+    }
+
+    fn model_dir(&self) -> Result<&Path, String> {
+        self.model_dir
+            .get()
+            .map(PathBuf::as_path)
+            .ok_or_else(|| "model_unavailable: no cache directory for the model".to_string())
+    }
+
+    pub fn model_status(&self) -> ModelStatus {
+        let loaded = self
+            .embedder
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some();
+        ModelStatus {
+            ready: loaded || self.model_dir().is_ok_and(model::is_present),
+            downloading: self.downloading.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Fail fast, before any walking, while the model is not downloaded.
+    pub fn check_model(&self) -> Result<(), String> {
+        match self.model_status().ready {
+            true => Ok(()),
+            false => Err(model::NOT_DOWNLOADED.to_string()),
+        }
+    }
+
+    /// Download the model with `client` (the app's route, see `network`).
+    pub async fn download_model(&self, client: &reqwest::Client) -> Result<(), String> {
+        let dir = self.model_dir()?.to_path_buf();
+        if self.downloading.swap(true, Ordering::AcqRel) {
+            return Err("model_download_in_progress: the model is already downloading".into());
+        }
+        let result = model::download(client, &dir).await;
+        self.downloading.store(false, Ordering::Release);
+        result
+    }
+
+    fn embedder(&self) -> Result<Arc<dyn Embedder>, String> {
+        let mut slot = self.embedder.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(embedder) = slot.as_ref() {
+            return Ok(embedder.clone());
+        }
+        let dir = self.model_dir()?;
+        let embedder: Arc<dyn Embedder> = Arc::new(model::LocalModel::load(dir)?);
+        *slot = Some(embedder.clone());
+        Ok(embedder)
+    }
+
+    /// Bring the vectors under `scope` in step with `files` (the
+    /// `FileIndex` files under it): embed the documents that are new or
+    /// changed, as far as `budget` allows, and forget the ones that are gone.
+    /// Documents outside `scope` are neither read nor forgotten: they belong
+    /// to other pairings' requests. A listing with nothing under `scope` says
+    /// the index has not walked it yet, not that every document went away, so
+    /// it forgets nothing.
+    pub fn update(
+        &self,
+        files: &[FileMeta],
+        scope: &[String],
+        budget: &Budget,
+    ) -> Result<Progress, String> {
+        let documents: Vec<&FileMeta> = files
+            .iter()
+            .filter(|f| chunk::is_document(&f.path) && file_index::in_scope(&f.path, scope))
+            .collect();
+        let _updating = match self.updating.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => return Ok(self.progress(&documents)),
+        };
+
+        let stale: Vec<&FileMeta> = {
+            let mut embedded = self.files.write().unwrap_or_else(|e| e.into_inner());
+            let listed = files.iter().any(|f| file_index::in_scope(&f.path, scope));
+            if listed {
+                let current: HashSet<&Path> = documents.iter().map(|f| f.path.as_path()).collect();
+                embedded.retain(|path, _| {
+                    !file_index::in_scope(path, scope) || current.contains(path.as_path())
+                });
+            }
+            documents
+                .iter()
+                .filter(|f| {
+                    embedded
+                        .get(&f.path)
+                        .is_none_or(|e| e.modified != f.modified)
+                })
+                .copied()
+                .collect()
+        };
+        if stale.is_empty() {
+            return Ok(self.progress(&documents));
+        }
+
+        let embedder = self.embedder()?;
+        for file in stale {
+            if !budget.visit() {
+                break;
+            }
+            let passages = match read_document(&file.path, budget) {
+                Some(text) => embed_passages(&text, embedder.as_ref())?,
+                // Unreadable or too big: remembered as empty until it changes.
+                None if budget.exhausted().is_none() => Vec::new(),
+                None => break,
+            };
+            self.files
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(
+                    file.path.clone(),
+                    Embedded {
+                        modified: file.modified,
+                        passages,
+                    },
+                );
+        }
+        Ok(self.progress(&documents))
+    }
+
+    fn progress(&self, documents: &[&FileMeta]) -> Progress {
+        let embedded = self.files.read().unwrap_or_else(|e| e.into_inner());
+        let indexed = documents
+            .iter()
+            .filter(|f| {
+                embedded
+                    .get(&f.path)
+                    .is_some_and(|e| e.modified == f.modified)
+            })
+            .count();
+        Progress {
+            indexed,
+            pending: documents.len() - indexed,
+        }
+    }
+
+    /// The `candidates` closest to `query`, best first, at most `limit`.
+    /// `reads` is a file's frecency count.
+    pub fn search(
+        &self,
+        query: &str,
+        candidates: &[FileMeta],
+        reads: impl Fn(&Path) -> u32,
+        limit: usize,
+    ) -> Result<Vec<Hit>, String> {
+        let embedder = self.embedder()?;
+        let query = embedder
+            .embed(&[query.to_string()])?
+            .pop()
+            .map(unit)
+            .ok_or("embed_failed: no vector for the query")?;
+        let now = SystemTime::now();
+        let embedded = self.files.read().unwrap_or_else(|e| e.into_inner());
+
+        let mut hits: Vec<Hit> = Vec::new();
+        for file in candidates {
+            let Some(entry) = embedded.get(&file.path) else {
+                continue;
+            };
+            let best = entry
+                .passages
+                .iter()
+                .map(|p| (dot(&query, &p.vector), p))
+                .max_by(|a, b| a.0.total_cmp(&b.0));
+            let Some((similarity, passage)) = best else {
+                continue;
+            };
+            if similarity < MIN_SIMILARITY {
+                continue;
+            }
+            hits.push(Hit {
+                path: file.path.clone(),
+                name: file.name.clone(),
+                score: score(similarity, reads(&file.path), file.modified, now),
+                line: passage.line,
+                preview: passage.preview.clone(),
+            });
+        }
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.name.cmp(&b.name)));
+        hits.truncate(limit);
+        Ok(hits)
+    }
+}
+
+/// A file's rank: its best passage's similarity, plus up to
+/// [`FRECENCY_WEIGHT`] for being read often and up to [`RECENCY_WEIGHT`] for
+/// having changed lately.
+pub fn score(similarity: f32, reads: u32, modified: Option<SystemTime>, now: SystemTime) -> f32 {
+    let frecency = ((1.0 + reads as f32).ln() / (1.0 + FRECENCY_SATURATION).ln()).min(1.0);
+    let recency = modified
+        .and_then(|m| now.duration_since(m).ok())
+        .map(|age| 0.5f32.powf(age.as_secs_f32() / 86_400.0 / RECENCY_HALF_LIFE_DAYS))
+        .unwrap_or(0.0);
+    similarity + FRECENCY_WEIGHT * frecency + RECENCY_WEIGHT * recency
+}
+
+/// The document's text, if it is small enough and the budget allows reading
+/// it. Not UTF-8 is read lossily.
+fn read_document(path: &Path, budget: &Budget) -> Option<String> {
+    let size = std::fs::metadata(path).ok()?.len();
+    if size > chunk::MAX_FILE_BYTES || !budget.read(size) {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn embed_passages(text: &str, embedder: &dyn Embedder) -> Result<Vec<Passage>, String> {
+    let pieces = chunk::split(text);
+    if pieces.is_empty() {
+        return Ok(Vec::new());
+    }
+    let texts: Vec<String> = pieces.iter().map(|p| p.text.clone()).collect();
+    let vectors = embedder.embed(&texts)?;
+    if vectors.len() != pieces.len() {
+        return Err("embed_failed: vector count does not match".to_string());
+    }
+    Ok(pieces
+        .into_iter()
+        .zip(vectors)
+        .map(|(piece, vector)| Passage {
+            line: piece.line,
+            preview: preview(&piece.text),
+            vector: unit(vector),
+        })
+        .collect())
+}
+
+/// The first line of a passage, cut to 200 chars.
+fn preview(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    match line.char_indices().nth(200) {
+        Some((at, _)) => format!("{}…", &line[..at]),
+        None => line.to_string(),
+    }
+}
+
+fn unit(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+pub mod testing {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::Embedder;
+
+    /// Bag of words over a few concepts, with synonyms: enough to tell
+    /// meaning from spelling. Counts the texts it embeds.
+    #[derive(Default)]
+    pub struct Concepts {
+        pub embedded: AtomicUsize,
+    }
+
+    const CONCEPTS: &[&[&str]] = &[
+        &["transfection", "lipofection", "transfect"],
+        &["efficiency", "yield", "efficient"],
+        &["buffer", "pbs", "tris"],
+        &["budget", "invoice", "grant"],
+    ];
+
+    impl Embedder for Concepts {
+        fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+            self.embedded.fetch_add(texts.len(), Ordering::Relaxed);
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let mut v = vec![0.0; CONCEPTS.len() + 1];
+                    v[CONCEPTS.len()] = 0.1; // no text is the zero vector
+                    for word in text.to_lowercase().split(|c: char| !c.is_alphanumeric()) {
+                        if let Some(i) = CONCEPTS.iter().position(|c| c.contains(&word)) {
+                            v[i] += 1.0;
+                        }
+                    }
+                    v
+                })
+                .collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::Concepts;
+    use super::*;
+    use std::time::Duration;
+
+    use crate::tools::budget::Limits;
+
+    fn budget() -> Budget {
+        Budget::new(Limits {
+            deadline: Duration::from_secs(60),
+            max_files: usize::MAX,
+            max_bytes: u64::MAX,
+        })
+    }
+
+    fn meta(dir: &Path, name: &str) -> FileMeta {
+        let path = dir.join(name);
+        FileMeta {
+            modified: std::fs::metadata(&path).and_then(|m| m.modified()).ok(),
+            name: name.to_string(),
+            path,
+        }
+    }
+
+    #[test]
+    fn finds_documents_by_meaning_and_embeds_only_what_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, text: &str| std::fs::write(dir.path().join(name), text).unwrap();
+        write(
+            "protocol.md",
+            "# Day 3\n\nBetter lipofection yield with less reagent.",
+        );
+        write("grant.txt", "Invoice for the grant budget.");
+        write("plot.png", "not a document");
+        let concepts = Arc::new(Concepts::default());
+        let index = SemanticIndex::with_embedder(concepts.clone());
+        let scope = vec![dir.path().display().to_string()];
+        let files = || {
+            ["protocol.md", "grant.txt", "plot.png"]
+                .iter()
+                .map(|n| meta(dir.path(), n))
+                .collect::<Vec<_>>()
+        };
+
+        let progress = index.update(&files(), &scope, &budget()).unwrap();
+        assert_eq!(
+            progress,
+            Progress {
+                indexed: 2,
+                pending: 0
+            }
+        );
+        let hits = index
+            .search("transfection efficiency", &files(), |_| 0, 10)
+            .unwrap();
+        let names: Vec<&str> = hits.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, ["protocol.md"]);
+        assert_eq!(hits[0].line, 1);
+        assert_eq!(hits[0].preview, "# Day 3");
+
+        // Nothing changed: nothing is embedded again.
+        let before = concepts.embedded.load(Ordering::Relaxed);
+        index.update(&files(), &scope, &budget()).unwrap();
+        assert_eq!(concepts.embedded.load(Ordering::Relaxed), before);
+
+        // A removed document is forgotten; a budget spent leaves the rest pending.
+        std::fs::remove_file(dir.path().join("grant.txt")).unwrap();
+        write("buffers.md", "PBS and Tris buffer recipes.");
+        let files = vec![
+            meta(dir.path(), "protocol.md"),
+            meta(dir.path(), "buffers.md"),
+        ];
+        let spent = Budget::new(Limits {
+            deadline: Duration::from_secs(60),
+            max_files: 0,
+            max_bytes: u64::MAX,
+        });
+        let progress = index.update(&files, &scope, &spent).unwrap();
+        assert_eq!(
+            progress,
+            Progress {
+                indexed: 1,
+                pending: 1
+            }
+        );
+        assert_eq!(index.files.read().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn an_empty_listing_forgets_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("protocol.md"), "Lipofection protocol.").unwrap();
+        std::fs::write(dir.path().join("plot.png"), "not a document").unwrap();
+        let index = SemanticIndex::with_embedder(Arc::new(Concepts::default()));
+        let scope = vec![dir.path().display().to_string()];
+        let files = vec![
+            meta(dir.path(), "protocol.md"),
+            meta(dir.path(), "plot.png"),
+        ];
+        index.update(&files, &scope, &budget()).unwrap();
+        assert_eq!(index.files.read().unwrap().len(), 1);
+
+        // An index that has not walked the folder yet lists nothing under it.
+        index.update(&[], &scope, &budget()).unwrap();
+        assert_eq!(index.files.read().unwrap().len(), 1);
+
+        // A listing with files but no documents left does forget it.
+        index.update(&files[1..], &scope, &budget()).unwrap();
+        assert!(index.files.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn without_the_model_downloaded_requests_fail_at_once() {
+        let index = SemanticIndex::new();
+        assert!(index
+            .check_model()
+            .unwrap_err()
+            .starts_with("model_unavailable:"));
+        let dir = tempfile::tempdir().unwrap();
+        index.set_model_dir(dir.path().to_path_buf());
+        assert!(!index.model_status().ready);
+        assert!(index
+            .check_model()
+            .unwrap_err()
+            .starts_with("model_unavailable:"));
+        assert!(SemanticIndex::with_embedder(Arc::new(Concepts::default()))
+            .check_model()
+            .is_ok());
+    }
+
+    #[test]
+    fn frecency_and_recency_only_break_near_ties() {
+        let now = SystemTime::now();
+        let fresh = Some(now);
+        let old = Some(now - Duration::from_secs(365 * 86_400));
+        // Read often and just changed beats an untouched, equally close file…
+        assert!(score(0.60, 20, fresh, now) > score(0.60, 0, old, now));
+        assert!(score(0.60, 1, old, now) > score(0.60, 0, old, now));
+        // …but not a clearly closer one.
+        assert!(score(0.60, 1_000, fresh, now) < score(0.75, 0, None, now));
+    }
+}
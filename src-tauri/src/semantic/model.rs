@@ -0,0 +1,157 @@
+//! The on-device embedding model: all-MiniLM-L6-v2 (384 dimensions), run on
+//! the CPU by ONNX Runtime through `fastembed`.
+//!
+//! The weights (about 90 MB) are not bundled. The user downloads them from
+//! Settings, over the same proxy and trusted CAs as every other connection
+//! (see `network`), into the app's cache directory; `fastembed`'s own
+//! downloader is compiled out. Until the files are there, the tool answers
+//! `model_unavailable` at once. Embedding itself never leaves the machine.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use fastembed::{
+    InitOptionsUserDefined, Pooling, TextEmbedding, TokenizerFiles, UserDefinedEmbeddingModel,
+};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+
+use super::Embedder;
+
+/// The ONNX export `fastembed` itself uses for this model, pinned to one
+/// commit so the files cannot change under us.
+const SOURCE: &str = "https://huggingface.co/Qdrant/all-MiniLM-L6-v2-onnx/resolve";
+// TODO(pin): fill in the commit and the SHA-256 of each file at it; until then
+// every download fails its checksum and nothing is installed.
+const REVISION: &str = "0000000000000000000000000000000000000000";
+const MODEL_FILE: &str = "model.onnx";
+const TOKENIZER_FILES: [&str; 4] = [
+    "tokenizer.json",
+    "config.json",
+    "special_tokens_map.json",
+    "tokenizer_config.json",
+];
+/// The SHA-256 of each file at `REVISION`, checked before it is installed.
+const CHECKSUMS: [(&str, &str); 5] = [
+    (
+        "tokenizer.json",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+    ),
+    (
+        "config.json",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+    ),
+    (
+        "special_tokens_map.json",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+    ),
+    (
+        "tokenizer_config.json",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+    ),
+    (
+        MODEL_FILE,
+        "0000000000000000000000000000000000000000000000000000000000000000",
+    ),
+];
+
+pub const NOT_DOWNLOADED: &str = "model_unavailable: the document search model is not \
+     downloaded; download it in Settings → Document search";
+
+/// Passages embedded per model run.
+const BATCH: usize = 16;
+
+/// Whether every model file is in `dir`.
+pub fn is_present(dir: &Path) -> bool {
+    std::iter::once(MODEL_FILE)
+        .chain(TOKENIZER_FILES)
+        .all(|name| dir.join(name).is_file())
+}
+
+/// Fetch the model files missing from `dir` with `client`. Each file is
+/// written under a `.part` name and renamed once complete and matching its
+/// checksum, so an interrupted or altered download is never taken for the
+/// model.
+pub async fn download(client: &reqwest::Client, dir: &Path) -> Result<(), String> {
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| format!("model_download_failed: {}: {e}", dir.display()))?;
+    for (name, checksum) in CHECKSUMS {
+        let target = dir.join(name);
+        if target.is_file() {
+            continue;
+        }
+        let failed = |e: &dyn std::fmt::Display| format!("model_download_failed: {name}: {e}");
+        let mut response = client
+            .get(format!("{SOURCE}/{REVISION}/{name}"))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| failed(&e))?;
+        let part = dir.join(format!("{name}.part"));
+        let mut file = tokio::fs::File::create(&part)
+            .await
+            .map_err(|e| failed(&e))?;
+        let mut hasher = Sha256::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| failed(&e))? {
+            hasher.update(&chunk);
+            file.write_all(&chunk).await.map_err(|e| failed(&e))?;
+        }
+        file.flush().await.map_err(|e| failed(&e))?;
+        if hex(&hasher.finalize()) != checksum {
+            drop(file);
+            let _ = tokio::fs::remove_file(&part).await;
+            return Err(failed(&"checksum mismatch"));
+        }
+        tokio::fs::rename(&part, &target)
+            .await
+            .map_err(|e| failed(&e))?;
+    }
+    log::info!("Embedding model downloaded to {}", dir.display());
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub struct LocalModel {
+    model: Mutex<TextEmbedding>,
+}
+
+impl LocalModel {
+    /// Load the model from `dir`, without touching the network.
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        if !is_present(dir) {
+            return Err(NOT_DOWNLOADED.to_string());
+        }
+        let read = |name: &str| {
+            std::fs::read(dir.join(name))
+                .map_err(|e| format!("model_unavailable: could not read {name}: {e}"))
+        };
+        let tokenizer_files = TokenizerFiles {
+            tokenizer_file: read("tokenizer.json")?,
+            config_file: read("config.json")?,
+            special_tokens_map_file: read("special_tokens_map.json")?,
+            tokenizer_config_file: read("tokenizer_config.json")?,
+        };
+        let model = UserDefinedEmbeddingModel::new(read(MODEL_FILE)?, tokenizer_files)
+            .with_pooling(Pooling::Mean);
+        let model = TextEmbedding::try_new_from_user_defined(model, InitOptionsUserDefined::new())
+            .map_err(|e| format!("model_unavailable: could not load the model: {e}"))?;
+        log::info!("Embedding model loaded from {}", dir.display());
+        Ok(Self {
+            model: Mutex::new(model),
+        })
+    }
+}
+
+impl Embedder for LocalModel {
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        self.model
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .embed(texts.to_vec(), Some(BATCH))
+            .map_err(|e| format!("embed_failed: {e}"))
+    }
+}
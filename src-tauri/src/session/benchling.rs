@@ -9,9 +9,9 @@
 //!      `tools::benchling`).
 //!   2. Probes `GET https://benchling.com/1/api/users/me` with that cookie to
 //!      confirm the session is valid and read the user's handle.
-//!   3. Registers this device's free Benchling connector with the Beakr backend
-//!      (`POST {api_base}/v1/connectors/benchling-desktop/connect`) so the agent's
-//!      tools can RPC back here.
+//!   3. Registers this device's free Benchling connector with each paired Beakr
+//!      backend (`POST {api_base}/v1/connectors/benchling-desktop/connect`) so the
+//!      agent's tools can RPC back here.
 //!   4. Emits the `session:connected` frontend event so the UI shows "Connected".
 //!
 //! Session-capture choice (cookie vs in-webview eval) is documented in
@@ -23,6 +23,7 @@ use std::time::Duration;
 use tauri::{AppHandle, Emitter, Manager};
 use url::Url;
 
+use crate::environment::Profile;
 use crate::state::{AppState, BenchlingSession};
 
 /// How long to keep polling the webview for a logged-in session before giving up.
@@ -38,19 +39,8 @@ const BENCHLING_ORIGIN: &str = "https://benchling.com";
 const TENANT_HOST: &str = "benchling.com";
 const RESTORE_WINDOW_LABEL: &str = "session-benchling-restore";
 
-/// Derive the Beakr backend API base from the WS URL (mirrors `claim_pairing_code`).
-fn api_base() -> String {
-    crate::ws_url()
-        .replace("ws://", "http://")
-        .replace("wss://", "https://")
-        .replace("/v1/desktop-agent/ws", "")
-}
-
-fn http_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .user_agent(format!("BeakrDesktop/{}", env!("CARGO_PKG_VERSION")))
-        .build()
-        .expect("failed to build HTTP client")
+fn http_client(state: &AppState) -> reqwest::Client {
+    state.network.current().http_client()
 }
 
 /// Reads the benchling.com `session` cookie from the open webview's cookie store.
@@ -73,8 +63,11 @@ fn read_session_cookie(app: &AppHandle, window_label: &str) -> Option<String> {
 ///
 /// Returns `Ok(Some(handle))` when logged in, `Ok(None)` when the session is not
 /// (yet) valid (401 / idle), or `Err` on a transport/other-status failure.
-async fn probe_users_me(session_cookie: &str) -> Result<Option<String>, String> {
-    let resp = http_client()
+pub(crate) async fn probe_users_me(
+    state: &AppState,
+    session_cookie: &str,
+) -> Result<Option<String>, String> {
+    let resp = http_client(state)
         .get(format!("{BENCHLING_ORIGIN}/1/api/users/me"))
         .header("Cookie", format!("session={session_cookie}"))
         .header("X-Requested-With", "XMLHttpRequest")
@@ -113,14 +106,19 @@ async fn probe_users_me(session_cookie: &str) -> Result<Option<String>, String>
 ///
 /// `POST {api_base}/v1/connectors/benchling-desktop/connect` with the device token
 /// authorizes this device so the agent's Benchling tools can be RPC'd here.
-async fn register_connector(token: &str, user_handle: &str) -> Result<(), String> {
-    let url = format!("{}/v1/connectors/benchling-desktop/connect", api_base());
+async fn register_connector(
+    state: &AppState,
+    api_base: &str,
+    token: &str,
+    user_handle: &str,
+) -> Result<(), String> {
+    let url = format!("{api_base}/v1/connectors/benchling-desktop/connect");
     let body = serde_json::json!({
         "tenant_host": TENANT_HOST,
         "user_handle": user_handle,
     });
 
-    let resp = http_client()
+    let resp = http_client(state)
         .post(&url)
         .header("Authorization", format!("Bearer {token}"))
         .header("Content-Type", "application/json")
@@ -137,32 +135,32 @@ async fn register_connector(token: &str, user_handle: &str) -> Result<(), String
     Ok(())
 }
 
-/// Register the currently captured Benchling session with Beakr, if possible.
+/// Register the currently captured Benchling session with every paired Beakr
+/// backend, if possible.
 ///
 /// This is intentionally best-effort: the local Benchling tools can still work
 /// with a captured cookie even if the backend registration is temporarily down.
 pub async fn register_current_session_with_backend(app: &AppHandle, state: &AppState) {
-    let session = { state.benchling_session.read().await.clone() };
-    let token = { state.auth_token.read().await.clone() };
-
-    let (Some(session), Some(token)) = (session, token) else {
+    let Some(session) = state.benchling_session.read().await.clone() else {
         return;
     };
-    if token.is_empty() {
-        return;
-    }
 
-    if let Err(e) = register_connector(&token, &session.user_handle).await {
-        log::warn!("Benchling connector registration failed: {e}");
-        let _ = app.emit(
-            "session:error",
-            serde_json::json!({
-                "provider": "benchling",
-                "message": format!(
-                    "Connected to Benchling, but registering with Beakr failed: {e}"
-                ),
-            }),
-        );
+    for (profile, token) in state.links.paired().await {
+        let registered =
+            register_connector(state, &profile.api_base(), &token, &session.user_handle).await;
+        if let Err(e) = registered {
+            log::warn!("Benchling connector registration with {} failed: {e}", profile.name);
+            let _ = app.emit(
+                "session:error",
+                serde_json::json!({
+                    "provider": "benchling",
+                    "message": format!(
+                        "Connected to Benchling, but registering with Beakr ({}) failed: {e}",
+                        profile.name
+                    ),
+                }),
+            );
+        }
     }
 }
 
@@ -177,7 +175,7 @@ async fn capture_valid_session(
         return Ok(None);
     };
 
-    let Some(handle) = probe_users_me(&cookie).await? else {
+    let Some(handle) = probe_users_me(state, &cookie).await? else {
         return Ok(None);
     };
 
@@ -191,13 +189,7 @@ async fn capture_valid_session(
     // Register the connector + this device with the backend so the agent's tools
     // can RPC here. A missing device token is a non-fatal warning: the session is
     // still captured locally, and registration is retried when a token is set.
-    if state
-        .auth_token
-        .read()
-        .await
-        .as_deref()
-        .is_some_and(|token| !token.is_empty())
-    {
+    if !state.links.paired().await.is_empty() {
         register_current_session_with_backend(app, state).await;
     } else {
         log::warn!("Benchling connected but no device token — pair this device with Beakr first");
@@ -332,13 +324,18 @@ const LIVENESS_INTERVAL: Duration = Duration::from_secs(60);
 
 /// Reports to the backend that the live Benchling session has ended, so the web
 /// connector card reflects the lost session (best-effort).
-async fn report_disconnect(token: &str) {
-    let url = format!("{}/v1/connectors/benchling-desktop/disconnect", api_base());
-    let _ = http_client()
-        .post(&url)
-        .header("Authorization", format!("Bearer {token}"))
-        .send()
-        .await;
+async fn report_disconnect(state: &AppState, paired: &[(Profile, String)]) {
+    for (profile, token) in paired {
+        let url = format!(
+            "{}/v1/connectors/benchling-desktop/disconnect",
+            profile.api_base()
+        );
+        let _ = http_client(state)
+            .post(&url)
+            .header("Authorization", format!("Bearer {token}"))
+            .send()
+            .await;
+    }
 }
 
 /// Long-lived task that keeps the UI and backend honest about the live Benchling
@@ -359,20 +356,21 @@ pub async fn watch_session_liveness(app: AppHandle, state: AppState) {
         let session = { state.benchling_session.read().await.clone() };
         match session {
             None => {
-                // No live session. Report once; retry next tick if the device
-                // token has not been loaded into state yet.
+                // No live session. Report once; retry next tick if no pairing's
+                // token has been loaded into state yet.
                 if needs_report {
-                    if let Some(token) = state.auth_token.read().await.clone() {
+                    let paired = state.links.paired().await;
+                    if !paired.is_empty() {
                         let _ = app.emit(
                             "session:disconnected",
                             serde_json::json!({ "provider": "benchling" }),
                         );
-                        report_disconnect(&token).await;
+                        report_disconnect(&state, &paired).await;
                         needs_report = false;
                     }
                 }
             }
-            Some(sess) => match probe_users_me(&sess.session_cookie).await {
+            Some(sess) => match probe_users_me(&state, &sess.session_cookie).await {
                 // Live — re-arm so a later death/quit is reported again.
                 Ok(Some(_)) => needs_report = true,
                 Ok(None) => {
@@ -383,9 +381,7 @@ pub async fn watch_session_liveness(app: AppHandle, state: AppState) {
                             "session:disconnected",
                             serde_json::json!({ "provider": "benchling" }),
                         );
-                        if let Some(token) = state.auth_token.read().await.clone() {
-                            report_disconnect(&token).await;
-                        }
+                        report_disconnect(&state, &state.links.paired().await).await;
                         needs_report = false;
                     }
                 }
@@ -1,4 +1,3 @@
-use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -78,33 +77,23 @@ pub struct ActiveCodingRun {
 /// Shared application state, accessible from commands and the WS client.
 #[derive(Clone)]
 pub struct AppState {
-    pub auth_token: Arc<RwLock<Option<String>>>,
-    pub ws_status: Arc<RwLock<ConnectionStatus>>,
     pub scoped_folders: Arc<RwLock<Vec<String>>>,
     pub device_name: Arc<RwLock<String>>,
-    pub device_id: Arc<RwLock<Option<String>>>,
-    pub ws_shutdown: Arc<tokio::sync::Notify>,
-    /// Set to true when the user explicitly disconnects, so the WS run loop stops
-    /// reconnecting. A lone `ws_shutdown` notify permit can be consumed by the
-    /// message loop before the run loop checks it, so this flag — not the notify —
-    /// is the durable source of truth for "the user asked to stay disconnected."
-    /// Cleared on the next connect. Distinguishes a deliberate disconnect (stay
-    /// down) from a dropped socket (auto-reconnect).
-    pub shutdown_requested: Arc<AtomicBool>,
-    /// Notifies the WS client when scoped_folders are changed via the UI, so
-    /// it pushes the new list to the backend. The WS client must be the only
-    /// waiter on this channel — see `notify_folders_changed`.
-    pub folders_changed: Arc<tokio::sync::Notify>,
-    /// The same scoped-folders-changed signal for the filesystem watcher
-    /// (file_watch.rs), which must rebind to the new roots. A separate channel
-    /// because `Notify::notify_one` wakes a single waiter: with the watcher and
-    /// the WS client parked on one Notify, a folder change woke only one of
-    /// them and the other silently missed it — the backend kept a stale
-    /// scoped_folders list (ENG-1624).
+    /// One per pairing, each with its own connection (see `ws::link`).
+    pub links: Arc<crate::ws::link::Links>,
+    /// The scoped-folders-changed signal for the filesystem watcher
+    /// (file_watch.rs), which must rebind to the new roots. Separate from each
+    /// link's `folders_changed` because `Notify::notify_one` wakes a single
+    /// waiter: with the watcher and the WS client parked on one Notify, a
+    /// folder change woke only one of them and the other silently missed it —
+    /// the backend kept a stale scoped_folders list (ENG-1624).
     pub watch_folders_changed: Arc<tokio::sync::Notify>,
     /// In-memory metadata cache over the scoped folders, so repeat filename
     /// searches answer without re-walking disk (ENG-1150).
     pub file_index: Arc<crate::file_index::FileIndex>,
+    /// Embeddings of the documents in the scoped folders, kept in step with
+    /// `file_index` by `semantic_search_files`.
+    pub semantic_index: Arc<crate::semantic::SemanticIndex>,
     /// The captured Benchling browser session, set once the user connects and
     /// logs in. `None` until a successful connect; the live `benchling_*` tools
     /// return a reconnect error while it is `None` or after the session expires.
@@ -119,13 +108,14 @@ pub struct AppState {
     /// run visibility). std RwLock on purpose: the tray "Stop run" handler is
     /// synchronous.
     pub active_coding_run: Arc<std::sync::RwLock<Option<ActiveCodingRun>>>,
-    /// Sender into the live WS connection's outbound queue, set while
-    /// connected (ENG-1536). Lets settings changes push a readiness_update
-    /// without waiting for a reconnect. None when disconnected — pushes are
-    /// simply skipped; the next register carries fresh state anyway.
-    pub ws_outbound: Arc<
-        std::sync::RwLock<Option<tokio::sync::mpsc::Sender<crate::ws::protocol::OutgoingMessage>>>,
-    >,
+    /// The route outbound connections take: proxy and extra trusted CAs
+    /// (see `network`).
+    pub network: Arc<crate::network::Network>,
+    /// The optional local MCP server over the same tools (see `mcp`).
+    /// Stopped unless enabled in Settings.
+    pub mcp: Arc<crate::mcp::McpServer>,
+    /// Tool timings and reconnect counts since launch (see `metrics`).
+    pub metrics: Arc<crate::metrics::Metrics>,
 }
 
 /// Signal cancellation of the active coding run, from the tray or the app
@@ -202,30 +192,31 @@ impl AppState {
             .unwrap_or_else(|| "My Computer".to_string());
 
         Self {
-            auth_token: Arc::new(RwLock::new(None)),
-            ws_status: Arc::new(RwLock::new(ConnectionStatus::Disconnected)),
             scoped_folders: Arc::new(RwLock::new(Vec::new())),
             device_name: Arc::new(RwLock::new(device_name)),
-            device_id: Arc::new(RwLock::new(None)),
-            ws_shutdown: Arc::new(tokio::sync::Notify::new()),
-            shutdown_requested: Arc::new(AtomicBool::new(false)),
-            folders_changed: Arc::new(tokio::sync::Notify::new()),
+            links: Arc::new(crate::ws::link::Links::default()),
             watch_folders_changed: Arc::new(tokio::sync::Notify::new()),
             file_index: Arc::new(crate::file_index::FileIndex::new()),
+            semantic_index: Arc::new(crate::semantic::SemanticIndex::new()),
             benchling_session: Arc::new(RwLock::new(None)),
             inflight: Arc::new(crate::ws::inflight::InflightRegistry::new()),
             processes: Arc::new(crate::process_group::ProcessRegistry::new()),
             active_coding_run: Arc::new(std::sync::RwLock::new(None)),
-            ws_outbound: Arc::new(std::sync::RwLock::new(None)),
+            network: Arc::new(crate::network::Network::new()),
+            mcp: Arc::new(crate::mcp::McpServer::new()),
+            metrics: Arc::new(crate::metrics::Metrics::default()),
         }
     }
 
-    /// Signal every consumer of a scoped-folders change: the WS client (which
-    /// pushes the new list to the backend) and the filesystem watcher (which
-    /// rebinds to the new roots). Always use this instead of notifying one
-    /// channel directly, so a new consumer can't be starved by an existing one.
+    /// Signal every consumer of a scoped-folders change: each link's WS client
+    /// (which pushes its new list to the backend) and the filesystem watcher
+    /// (which rebinds to the new roots). Always use this instead of notifying
+    /// one channel directly, so a new consumer can't be starved by an existing
+    /// one.
     pub fn notify_folders_changed(&self) {
-        self.folders_changed.notify_one();
+        for link in self.links.all() {
+            link.folders_changed.notify_one();
+        }
         self.watch_folders_changed.notify_one();
     }
 }
@@ -242,9 +233,19 @@ mod tests {
     #[tokio::test]
     async fn folder_change_signals_both_ws_and_watcher() {
         let state = AppState::new();
+        let link = Arc::new(crate::ws::link::Link::new(
+            crate::pairing::Pairing {
+                id: "lab".into(),
+                name: "Lab".into(),
+                environment: crate::environment::default_id().into(),
+                folders: None,
+            },
+            crate::environment::default_profile(),
+        ));
+        state.links.insert(link.clone());
         state.notify_folders_changed();
 
-        tokio::time::timeout(Duration::from_secs(1), state.folders_changed.notified())
+        tokio::time::timeout(Duration::from_secs(1), link.folders_changed.notified())
             .await
             .expect("WS channel missed the folder change");
         tokio::time::timeout(Duration::from_secs(1), state.watch_folders_changed.notified())
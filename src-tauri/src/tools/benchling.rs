@@ -16,7 +16,7 @@
 //! When the user Connects Benchling and logs in, `session::commands` captures the
 //! HttpOnly `session` cookie from the benchling.com webview's cookie store into
 //! [`crate::state::BenchlingSession`]. These tools then call `/1/api/*` directly
-//! from Rust with `http_client()` and a `Cookie: session=<value>` header.
+//! from Rust with `http_client(state)` and a `Cookie: session=<value>` header.
 //!
 //! We chose the cookie approach over evaluating `fetch` inside the open webview
 //! because:
@@ -134,6 +134,32 @@ fn classify_id(id: &str) -> ItemKind {
     }
 }
 
+/// Every tool [`dispatch`] answers, as `register` advertises them.
+pub const TOOLS: &[&str] = &[
+    "benchling_list_projects",
+    "benchling_get_project",
+    "benchling_list_entries",
+    "benchling_get_entry",
+    "benchling_list_dna_sequences",
+    "benchling_get_dna_sequence",
+    "benchling_list_custom_entities",
+    "benchling_get_custom_entity",
+    "benchling_search",
+    "benchling_search_projects",
+    "benchling_search_entries",
+    "benchling_search_dna_sequences",
+    "benchling_search_custom_entities",
+    "benchling_list_protocols",
+    "benchling_get_protocol",
+    "benchling_search_protocols",
+    "benchling_list_aa_sequences",
+    "benchling_get_aa_sequence",
+    "benchling_search_aa_sequences",
+    "benchling_list_files",
+    "benchling_get_file",
+    "benchling_search_files",
+];
+
 /// Dispatch a `benchling_*` tool to its handler. Returns `Ok((data, None))` (these
 /// tools do not report file-style byte counts) or `Err(message)`.
 pub async fn dispatch(
@@ -177,11 +203,8 @@ pub fn handles(tool: &str) -> bool {
 
 // ---- HTTP helpers ----------------------------------------------------------
 
-fn http_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .user_agent(format!("BeakrDesktop/{}", env!("CARGO_PKG_VERSION")))
-        .build()
-        .expect("failed to build HTTP client")
+fn http_client(state: &AppState) -> reqwest::Client {
+    state.network.current().http_client()
 }
 
 /// Reads the captured session or returns the reconnect error.
@@ -200,7 +223,7 @@ async fn session(state: &AppState) -> Result<BenchlingSession, String> {
 /// is cleared and [`RECONNECT_ERROR`] is returned so the agent prompts a reconnect.
 async fn api_get(state: &AppState, sess: &BenchlingSession, path: &str) -> Result<Value, String> {
     let url = format!("{}{path}", api_base(sess));
-    let resp = http_client()
+    let resp = http_client(state)
         .get(&url)
         .header("Cookie", format!("session={}", sess.session_cookie))
         .header("X-Requested-With", "XMLHttpRequest")
@@ -271,7 +294,7 @@ fn absolutize_urls(value: &mut Value, host: &str) {
 /// it is to read an authenticated HTML page. The token is bound to the session
 /// cookie, so we fetch this session's tenant home and extract it. Read GETs don't
 /// need CSRF; only the POST /search path does.
-async fn fetch_csrf_token(sess: &BenchlingSession) -> Result<String, String> {
+async fn fetch_csrf_token(state: &AppState, sess: &BenchlingSession) -> Result<String, String> {
     // The tenant home for a path-handle (free) tenant is /{handle}; any authenticated
     // page carries the meta, so fall back to the tenant root if the handle page fails.
     let candidates = [
@@ -280,7 +303,7 @@ async fn fetch_csrf_token(sess: &BenchlingSession) -> Result<String, String> {
     ];
     let mut last_err = String::from("no CSRF page candidates");
     for url in candidates {
-        match http_client()
+        match http_client(state)
             .get(&url)
             .header("Cookie", format!("session={}", sess.session_cookie))
             .header("Accept", "text/html")
@@ -364,7 +387,7 @@ async fn api_post(
     body: Value,
 ) -> Result<Value, String> {
     let url = format!("{}{path}", api_base(sess));
-    let resp = http_client()
+    let resp = http_client(state)
         .post(&url)
         .headers(csrf_post_headers(sess, csrf))
         .json(&body)
@@ -411,7 +434,7 @@ async fn ranked_search(
     types: &[&str],
     limit: u64,
 ) -> Result<Value, String> {
-    let csrf = fetch_csrf_token(sess).await?;
+    let csrf = fetch_csrf_token(state, sess).await?;
     let body = json!({
         "filters": [
             { "key": "TYPES", "operator": "IS_ONE_OF", "value": types },
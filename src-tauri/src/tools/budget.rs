@@ -0,0 +1,205 @@
+//! Deadlines and resource budgets for the read-only tools that walk a tree.
+//!
+//! Only coding runs used to have a ceiling (`run_timeout`); a content search
+//! over a network mount or a recursive listing of a home folder could run as
+//! long as the tree lasted. Each walking tool now gets a [`Budget`]: a
+//! deadline, a number of entries it may visit and a number of bytes it may
+//! read. The walkers check it per entry and stop once any part is spent,
+//! answering with what they found so far, `truncated: true` and the
+//! [`Reason`].
+//!
+//! The engine may tighten a tool's limits per request (`timeout_seconds`,
+//! `max_files_visited`, `max_bytes_read`), never loosen them. A cancel
+//! spends the budget too (see `streaming`), which is how it reaches the
+//! walker threads.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// What a tool may spend on one request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub deadline: Duration,
+    /// Directory entries the walk may visit, directories included.
+    pub max_files: usize,
+    pub max_bytes: u64,
+}
+
+/// The ceilings per tool. A listing reads no file bodies, so bytes don't
+/// bound it.
+fn defaults(tool: &str) -> Limits {
+    match tool {
+        "search_files" => Limits {
+            deadline: Duration::from_secs(30),
+            max_files: 100_000,
+            max_bytes: 512 * 1024 * 1024,
+        },
+        "list_files" => Limits {
+            deadline: Duration::from_secs(20),
+            max_files: 200_000,
+            max_bytes: u64::MAX,
+        },
+        // Embedding is the cost here: a few documents a second on a laptop
+        // CPU. What is left is embedded by the next request.
+        "semantic_search_files" => Limits {
+            deadline: Duration::from_secs(20),
+            max_files: 2_000,
+            max_bytes: 64 * 1024 * 1024,
+        },
+        _ => Limits {
+            deadline: Duration::from_secs(30),
+            max_files: 100_000,
+            max_bytes: 256 * 1024 * 1024,
+        },
+    }
+}
+
+/// `tool`'s limits for a request: its defaults, lowered by whatever the
+/// params ask for.
+pub fn limits(tool: &str, params: &Value) -> Limits {
+    let ceiling = defaults(tool);
+    let requested = |key: &str| params.get(key).and_then(|v| v.as_u64());
+    Limits {
+        deadline: requested("timeout_seconds")
+            .map(|s| Duration::from_secs(s.max(1)).min(ceiling.deadline))
+            .unwrap_or(ceiling.deadline),
+        max_files: requested("max_files_visited")
+            .map(|n| (n.max(1) as usize).min(ceiling.max_files))
+            .unwrap_or(ceiling.max_files),
+        max_bytes: requested("max_bytes_read")
+            .map(|n| n.max(1).min(ceiling.max_bytes))
+            .unwrap_or(ceiling.max_bytes),
+    }
+}
+
+/// Which budget ran out, as the response's `reason`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    Deadline,
+    MaxFilesVisited,
+    MaxBytesRead,
+    /// Never in a response: a cancelled request answers with an error.
+    Cancelled,
+}
+
+/// One request's spending against its [`Limits`], shared by the walker's
+/// threads.
+pub struct Budget {
+    limits: Limits,
+    started: Instant,
+    files: AtomicUsize,
+    bytes: AtomicU64,
+    /// The first budget to run out; it stays out.
+    exhausted: OnceLock<Reason>,
+}
+
+impl Budget {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            started: Instant::now(),
+            files: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+            exhausted: OnceLock::new(),
+        }
+    }
+
+    /// Count one visited entry. False once anything is spent: stop walking.
+    pub fn visit(&self) -> bool {
+        if self.files.fetch_add(1, Ordering::Relaxed) >= self.limits.max_files {
+            return self.exhaust(Reason::MaxFilesVisited);
+        }
+        self.in_time()
+    }
+
+    /// Count `bytes` read. False once anything is spent: stop reading.
+    pub fn read(&self, bytes: u64) -> bool {
+        let before = self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        if before.saturating_add(bytes) > self.limits.max_bytes {
+            return self.exhaust(Reason::MaxBytesRead);
+        }
+        self.in_time()
+    }
+
+    fn in_time(&self) -> bool {
+        if self.exhausted.get().is_some() {
+            return false;
+        }
+        if self.started.elapsed() >= self.limits.deadline {
+            return self.exhaust(Reason::Deadline);
+        }
+        true
+    }
+
+    fn exhaust(&self, reason: Reason) -> bool {
+        let _ = self.exhausted.set(reason);
+        false
+    }
+
+    /// When the deadline passes.
+    pub fn deadline(&self) -> Instant {
+        self.started + self.limits.deadline
+    }
+
+    /// Spend the budget from outside the walk: the walkers stop at their
+    /// next entry.
+    pub fn stop(&self, reason: Reason) {
+        self.exhaust(reason);
+    }
+
+    /// Why the walk stopped early, if it did.
+    pub fn exhausted(&self) -> Option<Reason> {
+        self.exhausted.get().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn requests_can_tighten_the_limits_but_not_loosen_them() {
+        let ceiling = defaults("search_files");
+        assert_eq!(limits("search_files", &json!({})), ceiling);
+        let tightened = limits(
+            "search_files",
+            &json!({ "timeout_seconds": 5, "max_files_visited": 10, "max_bytes_read": 0 }),
+        );
+        assert_eq!(tightened.deadline, Duration::from_secs(5));
+        assert_eq!(tightened.max_files, 10);
+        assert_eq!(tightened.max_bytes, 1);
+        let loosened = limits(
+            "search_files",
+            &json!({ "timeout_seconds": 86_400, "max_files_visited": u64::MAX }),
+        );
+        assert_eq!(loosened, ceiling);
+    }
+
+    #[test]
+    fn the_first_budget_spent_is_the_reason_and_stays_spent() {
+        let budget = Budget::new(Limits {
+            deadline: Duration::from_secs(60),
+            max_files: 2,
+            max_bytes: 100,
+        });
+        assert!(budget.visit() && budget.visit());
+        assert!(budget.read(60));
+        assert_eq!(budget.exhausted(), None);
+        assert!(!budget.read(60));
+        assert!(!budget.visit());
+        assert_eq!(budget.exhausted(), Some(Reason::MaxBytesRead));
+
+        let late = Budget::new(Limits {
+            deadline: Duration::ZERO,
+            ..defaults("list_files")
+        });
+        assert!(!late.visit());
+        assert_eq!(late.exhausted(), Some(Reason::Deadline));
+    }
+}
@@ -4,40 +4,131 @@
 //! `claude -p <prompt> --output-format stream-json --verbose
 //!  --include-partial-messages` — `--verbose` is REQUIRED with stream-json or
 //! nothing streams until the end; partial messages give token-level deltas.
+//! An interactive session drops the prompt argument for `--input-format
+//! stream-json`: turns arrive as `user` messages on stdin, each answered by
+//! its own `result`, and the CLI exits once stdin closes. Relaying
+//! permissions uses the same input plus `--permission-prompt-tool stdio`:
+//! a tool call outside the profile becomes a `control_request` on stdout
+//! that blocks until a `control_response` arrives on stdin.
 //!
 //! Guardrails (DESIGN.md decision 7, DESIGN-REVIEW.md §3-4):
-//! - Tool surface: Read/Glob/Grep/Edit/Write under `acceptEdits`. Bash is
-//!   DENIED pending Max's ruling; WebFetch/WebSearch denied (Claude Code has
-//!   no default network sandbox — an allowed fetch is an exfil channel).
+//! - Tool surface: compiled from the run's permission profile (see
+//!   `permissions.rs`). The default profile is Read/Glob/Grep/Edit/Write
+//!   under `acceptEdits` with Bash DENIED; a profile may allow-list specific
+//!   commands as `Bash(<prefix>:*)` rules. WebFetch/WebSearch are denied in
+//!   every profile (Claude Code has no default network sandbox — an allowed
+//!   fetch is an exfil channel). With permissions relayed, Bash is no
+//!   longer denied outright: commands outside the allow-list are asked about.
 //! - Protected paths: deny rules stop the CLI from editing its own security
 //!   config inside the workspace (`.claude/`, `.git/`, `.vscode/`) — the
 //!   self-escalation mechanism of the Copilot RCE (CVE-2025-53773). Deny
 //!   rules outrank every permission mode, so this holds even if the mode
 //!   changes later.
+//!
+//! Desktop tools: when the run has an MCP endpoint, `--mcp-config` adds it
+//! as the `beakr` server and `mcp__beakr` is allow-listed — its tools are
+//! read-only and run the desktop's own scope and deny-list checks.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use tokio::process::Command;
 
-use super::runner::{Chunk, LocalCodingRunner, ParsedLine, RunResult, RunSpec};
+use super::permissions::PermissionProfile;
+use super::runner::{
+    Chunk, LocalCodingRunner, McpEndpoint, ParsedLine, PermissionDecision, PermissionRequest,
+    RunResult, RunSpec, MCP_SERVER_NAME, MCP_TOKEN_ENV,
+};
+use super::usage::Usage;
 
 pub struct ClaudeRunner;
 
-/// Session-scoped settings passed via `--settings`: deny rules protecting the
-/// agent's own config surface + secret files, regardless of permission mode.
-/// Relative patterns resolve against the run's cwd.
-const PROTECTED_PATH_SETTINGS: &str = r#"{"permissions":{"deny":[
-"Edit(./.claude/**)","Write(./.claude/**)",
-"Edit(./.git/**)","Write(./.git/**)",
-"Edit(./.vscode/**)","Write(./.vscode/**)",
-"Edit(./.codex/**)","Write(./.codex/**)",
-"Read(./.env)","Read(./.env.*)","Read(./**/.env)","Read(./**/.env.*)"
-]}}"#;
-
-const ALLOWED_TOOLS: &str = "Read,Glob,Grep,Edit,Write";
-/// Bash denied pending Max's write-scope ruling (DESIGN.md decision 7);
-/// WebFetch/WebSearch denied because CC has no default network sandbox.
-const DISALLOWED_TOOLS: &str = "Bash,WebFetch,WebSearch";
+/// Deny rules passed via `--settings` on every run: they protect the
+/// agent's own config surface + secret files, regardless of permission mode
+/// or profile. Relative patterns resolve against the run's cwd.
+const PROTECTED_PATH_DENY: &[&str] = &[
+    "Edit(./.claude/**)",
+    "Write(./.claude/**)",
+    "Edit(./.git/**)",
+    "Write(./.git/**)",
+    "Edit(./.vscode/**)",
+    "Write(./.vscode/**)",
+    "Edit(./.codex/**)",
+    "Write(./.codex/**)",
+    "Read(./.env)",
+    "Read(./.env.*)",
+    "Read(./**/.env)",
+    "Read(./**/.env.*)",
+];
+
+/// Denied in every profile: CC has no default network sandbox.
+const NETWORK_TOOLS: &[&str] = &["WebFetch", "WebSearch"];
+/// Everything that writes through the file tools. Each one a profile does
+/// not grant is denied outright — `acceptEdits` would otherwise auto-approve
+/// it although it is not named in `--allowedTools`.
+const WRITE_TOOLS: &[&str] = &["Edit", "Write", "MultiEdit", "NotebookEdit"];
+
+/// The write tools `profile` does not grant.
+fn ungranted_write_tools(profile: &PermissionProfile) -> impl Iterator<Item = &'static str> + '_ {
+    WRITE_TOOLS
+        .iter()
+        .copied()
+        .filter(|tool| !profile.tools.iter().any(|t| t == tool))
+}
+
+/// `--allowedTools`: the profile's file tools plus one rule per allowed
+/// command. A plain entry is a prefix (`pytest` → `Bash(pytest:*)`); an
+/// entry with `*` is passed through as a glob. The desktop tools' server,
+/// when the run has one, is allowed whole.
+fn allowed_tools(profile: &PermissionProfile, desktop_tools: bool) -> String {
+    let bash = profile.bash_allow.iter().map(|p| {
+        if p.contains('*') {
+            format!("Bash({p})")
+        } else {
+            format!("Bash({p}:*)")
+        }
+    });
+    let mcp = desktop_tools.then(|| format!("mcp__{MCP_SERVER_NAME}"));
+    profile.tools.iter().cloned().chain(bash).chain(mcp).collect::<Vec<_>>().join(",")
+}
+
+/// `--disallowedTools`: Bash unless the profile allow-lists commands (a bare
+/// `Bash` deny would outrank the allow rules) or the run relays permissions
+/// (a deny is never asked about), the network tools always, and the write
+/// tools the profile does not grant.
+fn disallowed_tools(profile: &PermissionProfile, relay_permissions: bool) -> String {
+    let mut denied: Vec<&str> = Vec::new();
+    if !profile.allows_bash() && !relay_permissions {
+        denied.push("Bash");
+    }
+    denied.extend(NETWORK_TOOLS);
+    denied.extend(ungranted_write_tools(profile));
+    denied.join(",")
+}
+
+/// Session-scoped `--settings`: the protected-path deny rules, plus the
+/// ungranted write tools again — deny rules outrank every permission mode,
+/// so this holds even if the flags above drift.
+fn session_settings(profile: &PermissionProfile) -> String {
+    let mut deny: Vec<&str> = PROTECTED_PATH_DENY.to_vec();
+    deny.extend(ungranted_write_tools(profile));
+    serde_json::json!({ "permissions": { "deny": deny } }).to_string()
+}
+
+/// `--mcp-config`: the run's desktop-tools endpoint as an HTTP server. The
+/// header names `MCP_TOKEN_ENV`, which the CLI expands, rather than the
+/// token: argv is readable by every process of the user.
+fn mcp_config(endpoint: &McpEndpoint) -> String {
+    serde_json::json!({
+        "mcpServers": {
+            MCP_SERVER_NAME: {
+                "type": "http",
+                "url": endpoint.url,
+                "headers": { "Authorization": format!("Bearer ${{{MCP_TOKEN_ENV}}}") },
+            }
+        }
+    })
+    .to_string()
+}
 
 /// Normalize one tool_use into chunks: a "tool" activity marker carrying the
 /// target path when the tool has one, plus a "file_changed" for write-capable
@@ -83,6 +174,27 @@ fn basename(path: &str) -> &str {
     path.rsplit('/').next().unwrap_or(path)
 }
 
+/// An Anthropic API `usage` object, normalized. Absent fields count as 0.
+fn api_usage(u: &serde_json::Value) -> Usage {
+    let count = |key: &str| u[key].as_u64().unwrap_or(0);
+    Usage {
+        input_tokens: count("input_tokens"),
+        output_tokens: count("output_tokens"),
+        cache_read_tokens: count("cache_read_input_tokens"),
+        cache_write_tokens: count("cache_creation_input_tokens"),
+    }
+}
+
+fn usage_chunk(usage: Usage) -> ParsedLine {
+    if usage.is_empty() {
+        return ParsedLine::Ignore;
+    }
+    ParsedLine::Chunk(Chunk {
+        usage: Some(usage),
+        ..Chunk::bare("usage")
+    })
+}
+
 impl LocalCodingRunner for ClaudeRunner {
     fn name(&self) -> &'static str {
         "claude"
@@ -90,16 +202,37 @@ impl LocalCodingRunner for ClaudeRunner {
 
     fn build_command(&self, binary: &Path, spec: &RunSpec) -> Command {
         let mut cmd = Command::new(binary);
-        cmd.current_dir(&spec.working_dir)
-            .arg("-p")
-            .arg(&spec.prompt)
-            .args(["--output-format", "stream-json"])
+        cmd.current_dir(&spec.working_dir).arg("-p");
+        if spec.interactive || spec.relay_permissions {
+            cmd.args(["--input-format", "stream-json"]);
+        } else {
+            cmd.arg(&spec.prompt);
+        }
+        if spec.relay_permissions {
+            cmd.args(["--permission-prompt-tool", "stdio"]);
+        }
+        cmd.args(["--output-format", "stream-json"])
             .arg("--verbose")
             .arg("--include-partial-messages")
-            .args(["--permission-mode", "acceptEdits"])
-            .args(["--allowedTools", ALLOWED_TOOLS])
-            .args(["--disallowedTools", DISALLOWED_TOOLS])
-            .args(["--settings", &PROTECTED_PATH_SETTINGS.replace('\n', "")]);
+            // A read-only profile has nothing to auto-accept; under the
+            // default mode anything unlisted is refused in `-p`.
+            .args([
+                "--permission-mode",
+                if spec.permissions.can_write() { "acceptEdits" } else { "default" },
+            ])
+            .args([
+                "--allowedTools",
+                &allowed_tools(&spec.permissions, spec.desktop_tools.is_some()),
+            ])
+            .args([
+                "--disallowedTools",
+                &disallowed_tools(&spec.permissions, spec.relay_permissions),
+            ])
+            .args(["--settings", &session_settings(&spec.permissions)]);
+        if let Some(endpoint) = &spec.desktop_tools {
+            cmd.args(["--mcp-config", &mcp_config(endpoint)])
+                .env(MCP_TOKEN_ENV, &endpoint.token);
+        }
 
         if let Some(session) = &spec.session_id {
             cmd.args(["--resume", session]);
@@ -144,19 +277,37 @@ impl LocalCodingRunner for ClaudeRunner {
                 }),
                 _ => ParsedLine::Ignore,
             },
-            // Token-level deltas (--include-partial-messages).
+            // Raw API stream events (--include-partial-messages).
             Some("stream_event") => {
-                let delta = &v["event"]["delta"];
-                if delta["type"].as_str() == Some("text_delta") {
-                    match delta["text"].as_str() {
-                        Some(t) if !t.is_empty() => ParsedLine::Chunk(Chunk {
-                            text: Some(t.to_string()),
-                            ..Chunk::bare("text")
-                        }),
-                        _ => ParsedLine::Ignore,
+                let event = &v["event"];
+                match event["type"].as_str() {
+                    // Live usage, one API call at a time: input and cache on
+                    // message_start, the message's whole output on its single
+                    // message_delta (message_start's output count is a
+                    // placeholder the delta already includes).
+                    Some("message_start") => usage_chunk(Usage {
+                        output_tokens: 0,
+                        ..api_usage(&event["message"]["usage"])
+                    }),
+                    Some("message_delta") => usage_chunk(Usage {
+                        output_tokens: event["usage"]["output_tokens"].as_u64().unwrap_or(0),
+                        ..Usage::default()
+                    }),
+                    // Token-level text deltas.
+                    _ => {
+                        let delta = &event["delta"];
+                        if delta["type"].as_str() == Some("text_delta") {
+                            match delta["text"].as_str() {
+                                Some(t) if !t.is_empty() => ParsedLine::Chunk(Chunk {
+                                    text: Some(t.to_string()),
+                                    ..Chunk::bare("text")
+                                }),
+                                _ => ParsedLine::Ignore,
+                            }
+                        } else {
+                            ParsedLine::Ignore
+                        }
                     }
-                } else {
-                    ParsedLine::Ignore
                 }
             }
             // Whole assistant messages: forward each tool_use as a structured
@@ -180,10 +331,28 @@ impl LocalCodingRunner for ClaudeRunner {
                     _ => ParsedLine::Chunks(chunks),
                 }
             }
+            // A permission prompt (relay mode): the CLI waits on stdin for
+            // the matching control_response. Other control requests are
+            // not ours to answer.
+            Some("control_request") => {
+                let request = &v["request"];
+                match (v["request_id"].as_str(), request["subtype"].as_str()) {
+                    (Some(id), Some("can_use_tool")) => {
+                        ParsedLine::PermissionRequest(PermissionRequest {
+                            id: id.to_string(),
+                            tool: request["tool_name"].as_str().unwrap_or("unknown").to_string(),
+                            input: request["input"].clone(),
+                        })
+                    }
+                    _ => ParsedLine::Ignore,
+                }
+            }
             Some("result") => ParsedLine::Final(RunResult {
                 session_id: v["session_id"].as_str().map(String::from),
                 result: v["result"].as_str().map(String::from),
                 total_cost_usd: v["total_cost_usd"].as_f64(),
+                // The session's own total — authoritative over the live sum.
+                usage: Some(api_usage(&v["usage"])).filter(|u| !u.is_empty()),
                 is_error: v["is_error"].as_bool().unwrap_or(false),
             }),
             // Additive schema: unknown types are skipped, never errors.
@@ -215,6 +384,59 @@ impl LocalCodingRunner for ClaudeRunner {
             exit_code.map_or("signal".to_string(), |c| c.to_string())
         )
     }
+
+    fn state_paths(&self, home: &Path) -> Vec<PathBuf> {
+        // ~/.claude.json is rewritten on every run (project history, tips);
+        // the cache dir holds its debug and MCP logs.
+        vec![
+            home.join(".claude"),
+            home.join(".claude.json"),
+            home.join(".cache/claude-cli-nodejs"),
+        ]
+    }
+
+    fn api_hosts(&self) -> &'static [&'static str] {
+        // API, OAuth token refresh for subscription logins, feature flags.
+        &["api.anthropic.com", "console.anthropic.com", "statsig.anthropic.com"]
+    }
+
+    fn encode_turn(&self, prompt: &str) -> Option<String> {
+        let message = serde_json::json!({
+            "type": "user",
+            "message": {
+                "role": "user",
+                "content": [{"type": "text", "text": prompt}],
+            },
+        });
+        Some(message.to_string())
+    }
+
+    fn relays_permissions(&self) -> bool {
+        true
+    }
+
+    fn encode_permission_decision(
+        &self,
+        request: &PermissionRequest,
+        decision: &PermissionDecision,
+    ) -> Option<String> {
+        // An allow must hand the input back; the CLI runs what it is given.
+        let verdict = if decision.allow {
+            serde_json::json!({ "behavior": "allow", "updatedInput": request.input })
+        } else {
+            let message = decision.message.as_deref().unwrap_or("The user denied this tool call.");
+            serde_json::json!({ "behavior": "deny", "message": message })
+        };
+        let response = serde_json::json!({
+            "type": "control_response",
+            "response": {
+                "subtype": "success",
+                "request_id": request.id,
+                "response": verdict,
+            },
+        });
+        Some(response.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -325,11 +547,48 @@ mod tests {
                 session_id: Some("abc-123".into()),
                 result: Some("Done — 3 tests fixed.".into()),
                 total_cost_usd: Some(0.1234),
+                usage: None,
                 is_error: false,
             })
         );
     }
 
+    #[test]
+    fn usage_streams_per_api_call_and_the_result_carries_the_total() {
+        let start = r#"{"type":"stream_event","event":{"type":"message_start","message":{"id":"msg_1","usage":{"input_tokens":920,"cache_creation_input_tokens":64,"cache_read_input_tokens":6144,"output_tokens":1}}}}"#;
+        assert_eq!(
+            parse(start),
+            ParsedLine::Chunk(Chunk {
+                usage: Some(Usage {
+                    input_tokens: 920,
+                    output_tokens: 0,
+                    cache_read_tokens: 6144,
+                    cache_write_tokens: 64,
+                }),
+                ..Chunk::bare("usage")
+            })
+        );
+        let delta = r#"{"type":"stream_event","event":{"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":150}}}"#;
+        let ParsedLine::Chunk(chunk) = parse(delta) else {
+            panic!("expected a usage chunk");
+        };
+        assert_eq!(chunk.usage.map(|u| u.output_tokens), Some(150));
+
+        let result = r#"{"type":"result","subtype":"success","is_error":false,"result":"ok","session_id":"s","usage":{"input_tokens":1840,"cache_creation_input_tokens":0,"cache_read_input_tokens":12288,"output_tokens":312}}"#;
+        let ParsedLine::Final(final_result) = parse(result) else {
+            panic!("expected Final");
+        };
+        assert_eq!(
+            final_result.usage,
+            Some(Usage {
+                input_tokens: 1840,
+                output_tokens: 312,
+                cache_read_tokens: 12288,
+                cache_write_tokens: 0,
+            })
+        );
+    }
+
     #[test]
     fn api_retry_yields_status_and_unknowns_are_ignored() {
         assert!(matches!(
@@ -349,6 +608,10 @@ mod tests {
             working_dir: std::env::temp_dir(),
             session_id: Some("sess-9".into()),
             api_key: Some("sk-test".into()),
+            permissions: PermissionProfile::default_profile(),
+            interactive: false,
+            relay_permissions: false,
+            desktop_tools: None,
         };
         let cmd = ClaudeRunner.build_command(Path::new("/usr/local/bin/claude"), &spec);
         let std_cmd = cmd.as_std();
@@ -401,6 +664,212 @@ mod tests {
             .any(|(k, v)| k == "ANTHROPIC_API_KEY" && v.as_deref() == Some("sk-test")));
     }
 
+    fn args_for(permissions: PermissionProfile) -> Vec<String> {
+        let spec = RunSpec {
+            prompt: "p".into(),
+            working_dir: std::env::temp_dir(),
+            session_id: None,
+            api_key: None,
+            permissions,
+            interactive: false,
+            relay_permissions: false,
+            desktop_tools: None,
+        };
+        ClaudeRunner
+            .build_command(Path::new("claude"), &spec)
+            .as_std()
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    fn flag<'a>(args: &'a [String], name: &str) -> &'a str {
+        let i = args.iter().position(|a| a == name).expect("flag present");
+        &args[i + 1]
+    }
+
+    #[test]
+    fn bash_profile_compiles_to_allow_rules_without_a_blanket_deny() {
+        let args = args_for(PermissionProfile {
+            name: "tests".into(),
+            tools: vec!["Read".into(), "Edit".into()],
+            bash_allow: vec!["pytest".into(), "python scripts/*".into()],
+        });
+        assert_eq!(
+            flag(&args, "--allowedTools"),
+            "Read,Edit,Bash(pytest:*),Bash(python scripts/*)"
+        );
+        // A bare Bash deny would outrank the allow rules.
+        assert_eq!(
+            flag(&args, "--disallowedTools"),
+            "WebFetch,WebSearch,Write,MultiEdit,NotebookEdit"
+        );
+        assert_eq!(flag(&args, "--permission-mode"), "acceptEdits");
+    }
+
+    #[test]
+    fn a_partial_write_grant_denies_the_other_write_tools() {
+        // `acceptEdits` would approve any write tool not denied.
+        let args = args_for(PermissionProfile {
+            name: "edits".into(),
+            tools: vec!["Read".into(), "Edit".into()],
+            bash_allow: vec![],
+        });
+        assert_eq!(flag(&args, "--permission-mode"), "acceptEdits");
+        let denied: Vec<&str> = flag(&args, "--disallowedTools").split(',').collect();
+        let settings: serde_json::Value =
+            serde_json::from_str(flag(&args, "--settings")).unwrap();
+        let denies = settings["permissions"]["deny"].as_array().unwrap();
+        for tool in ["Write", "MultiEdit", "NotebookEdit"] {
+            assert!(denied.contains(&tool), "{tool} in --disallowedTools");
+            assert!(denies.iter().any(|d| d == tool), "{tool} in the deny rules");
+        }
+        assert!(!denied.contains(&"Edit"));
+        assert!(!denies.iter().any(|d| d == "Edit"));
+    }
+
+    #[test]
+    fn read_only_profile_denies_every_write_tool() {
+        let args = args_for(PermissionProfile::analysis());
+        assert_eq!(flag(&args, "--allowedTools"), "Read,Glob,Grep");
+        assert_eq!(
+            flag(&args, "--disallowedTools"),
+            "Bash,WebFetch,WebSearch,Edit,Write,MultiEdit,NotebookEdit"
+        );
+        assert_eq!(flag(&args, "--permission-mode"), "default");
+        let settings: serde_json::Value =
+            serde_json::from_str(flag(&args, "--settings")).unwrap();
+        let denies = settings["permissions"]["deny"].as_array().unwrap();
+        assert!(denies.iter().any(|d| d == "Write(./.git/**)"), "protected paths always ride");
+        assert!(denies.iter().any(|d| d == "MultiEdit"));
+    }
+
+    #[test]
+    fn desktop_tools_ride_an_mcp_config_and_are_allowed() {
+        assert!(!args_for(PermissionProfile::analysis()).iter().any(|a| a == "--mcp-config"));
+        let spec = RunSpec {
+            prompt: "p".into(),
+            working_dir: std::env::temp_dir(),
+            session_id: None,
+            api_key: None,
+            permissions: PermissionProfile::analysis(),
+            interactive: false,
+            relay_permissions: false,
+            desktop_tools: Some(McpEndpoint {
+                url: "http://127.0.0.1:40123/mcp".into(),
+                token: "t0k".into(),
+            }),
+        };
+        let cmd = ClaudeRunner.build_command(Path::new("claude"), &spec);
+        let std_cmd = cmd.as_std();
+        let args: Vec<String> = std_cmd
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(flag(&args, "--allowedTools"), "Read,Glob,Grep,mcp__beakr");
+        let config: serde_json::Value = serde_json::from_str(flag(&args, "--mcp-config")).unwrap();
+        let server = &config["mcpServers"]["beakr"];
+        assert_eq!(server["type"], "http");
+        assert_eq!(server["url"], "http://127.0.0.1:40123/mcp");
+        assert_eq!(server["headers"]["Authorization"], "Bearer ${BEAKR_MCP_TOKEN}");
+        assert!(!args.iter().any(|a| a.contains("t0k")), "the token stays out of argv");
+        assert!(std_cmd.get_envs().any(|(k, v)| {
+            k == "BEAKR_MCP_TOKEN" && v.is_some_and(|v| v == "t0k")
+        }));
+    }
+
+    #[test]
+    fn interactive_session_takes_turns_on_stdin() {
+        let spec = RunSpec {
+            prompt: "first turn".into(),
+            working_dir: std::env::temp_dir(),
+            session_id: None,
+            api_key: None,
+            permissions: PermissionProfile::default_profile(),
+            interactive: true,
+            relay_permissions: false,
+            desktop_tools: None,
+        };
+        let args: Vec<String> = ClaudeRunner
+            .build_command(Path::new("claude"), &spec)
+            .as_std()
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        // The prompt goes over stdin, not argv.
+        assert!(!args.iter().any(|a| a == "first turn"));
+        assert_eq!(flag(&args, "--input-format"), "stream-json");
+        assert_eq!(flag(&args, "--output-format"), "stream-json");
+
+        let line = ClaudeRunner.encode_turn("now the docs").unwrap();
+        assert!(!line.contains('\n'), "one turn is one NDJSON line");
+        let v: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(v["type"], "user");
+        assert_eq!(v["message"]["content"][0]["text"], "now the docs");
+    }
+
+    #[test]
+    fn relayed_permission_prompts_parse_and_answer_on_stdin() {
+        let plain = args_for(PermissionProfile::default_profile());
+        assert!(!plain.iter().any(|a| a == "--permission-prompt-tool"));
+        let spec = RunSpec {
+            prompt: "run the tests".into(),
+            working_dir: std::env::temp_dir(),
+            session_id: None,
+            api_key: None,
+            permissions: PermissionProfile::default_profile(),
+            interactive: false,
+            relay_permissions: true,
+            desktop_tools: None,
+        };
+        let args: Vec<String> = ClaudeRunner
+            .build_command(Path::new("claude"), &spec)
+            .as_std()
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(flag(&args, "--permission-prompt-tool"), "stdio");
+        assert_eq!(flag(&args, "--input-format"), "stream-json");
+        assert!(!args.iter().any(|a| a == "run the tests"), "prompt goes over stdin");
+        // Bash is asked about, not denied; the network tools stay denied,
+        // and so do the write tools `acceptEdits` would approve unasked.
+        assert_eq!(
+            flag(&args, "--disallowedTools"),
+            "WebFetch,WebSearch,MultiEdit,NotebookEdit"
+        );
+
+        let line = r#"{"type":"control_request","request_id":"perm-1","request":{"subtype":"can_use_tool","tool_name":"Bash","input":{"command":"pytest -q"}}}"#;
+        let request = match parse(line) {
+            ParsedLine::PermissionRequest(r) => r,
+            other => panic!("expected a permission request, got {other:?}"),
+        };
+        assert_eq!(request.id, "perm-1");
+        assert_eq!(request.tool, "Bash");
+        assert_eq!(request.input["command"], "pytest -q");
+        assert_eq!(
+            parse(r#"{"type":"control_request","request_id":"x","request":{"subtype":"interrupt"}}"#),
+            ParsedLine::Ignore
+        );
+
+        let allow = PermissionDecision { allow: true, message: None };
+        let v: serde_json::Value = serde_json::from_str(
+            &ClaudeRunner.encode_permission_decision(&request, &allow).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(v["type"], "control_response");
+        assert_eq!(v["response"]["request_id"], "perm-1");
+        assert_eq!(v["response"]["response"]["behavior"], "allow");
+        assert_eq!(v["response"]["response"]["updatedInput"]["command"], "pytest -q");
+
+        let deny = PermissionDecision { allow: false, message: Some("not on main".into()) };
+        let v: serde_json::Value = serde_json::from_str(
+            &ClaudeRunner.encode_permission_decision(&request, &deny).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(v["response"]["response"]["behavior"], "deny");
+        assert_eq!(v["response"]["response"]["message"], "not on main");
+    }
+
     #[test]
     fn failure_classification_is_typed() {
         let r = ClaudeRunner;
@@ -3,7 +3,10 @@
 //! Drives the user's local `codex` headlessly: `codex exec --json` (JSONL on
 //! stdout), resuming via `codex exec resume <thread_id>`. Auth is the user's
 //! own `codex login` (~/.codex/auth.json) — Beakr handles no credential, same
-//! posture as the Claude adapter (DESIGN.md decision 5).
+//! posture as the Claude adapter (DESIGN.md decision 5). `exec` takes exactly
+//! one prompt, so there are no interactive sessions: a follow-up is a new
+//! process resuming the thread. Nor does it ask for approvals (see below),
+//! so there are no permission prompts to relay either.
 //!
 //! Guardrails (DESIGN.md decisions 1/7, DESIGN-REVIEW.md §7): the sandbox IS
 //! the control. `--sandbox workspace-write` confines writes to the working
@@ -12,20 +15,27 @@
 //! hanging on an approval that can never come. Never `danger-full-access`;
 //! never `--ephemeral` (it breaks resume).
 //!
+//! Desktop tools: a run's MCP endpoint goes in as `-c mcp_servers.beakr.*`
+//! config overrides, the token through an env var rather than argv.
+//!
 //! Schema discipline: Codex documents its JSONL as additive and
 //! version-dependent — match known `type`/`item_type` values, Ignore all
 //! unknowns, never error on them.
 //!
-//! Cost: Codex reports token usage on `turn.completed`, NOT dollars. No cost
-//! chunk is emitted — converting tokens to dollars would assume API pricing,
-//! which is wrong for the ChatGPT-plan users this auth model targets. The
-//! run card simply shows no cost for Codex runs (David, 2026-07-17).
+//! Cost: Codex reports token usage on `turn.completed`, NOT dollars. That
+//! becomes a `usage` chunk; no cost chunk is emitted — converting tokens to
+//! dollars would assume API pricing, which is wrong for the ChatGPT-plan
+//! users this auth model targets. The run card simply shows no cost for
+//! Codex runs (David, 2026-07-17).
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use tokio::process::Command;
 
-use super::runner::{Chunk, LocalCodingRunner, ParsedLine, RunResult, RunSpec};
+use super::runner::{
+    Chunk, LocalCodingRunner, ParsedLine, RunResult, RunSpec, MCP_SERVER_NAME, MCP_TOKEN_ENV,
+};
+use super::usage::Usage;
 
 pub struct CodexRunner;
 
@@ -104,10 +114,22 @@ impl LocalCodingRunner for CodexRunner {
         cmd.current_dir(&spec.working_dir);
         cmd.arg("exec")
             .arg("--json")
-            .args(["--sandbox", "workspace-write"])
+            // Codex has no per-tool allow-list; a read-only permission
+            // profile maps onto its read-only sandbox instead.
+            .args([
+                "--sandbox",
+                if spec.permissions.can_write() { "workspace-write" } else { "read-only" },
+            ])
             .arg("--skip-git-repo-check")
             .arg("--cd")
             .arg(&spec.working_dir);
+        if let Some(endpoint) = &spec.desktop_tools {
+            // TOML values: the strings are quoted.
+            let server = format!("mcp_servers.{MCP_SERVER_NAME}");
+            cmd.args(["-c", &format!("{server}.url=\"{}\"", endpoint.url)])
+                .args(["-c", &format!("{server}.bearer_token_env_var=\"{MCP_TOKEN_ENV}\"")])
+                .env(MCP_TOKEN_ENV, &endpoint.token);
+        }
         if let Some(session) = &spec.session_id {
             cmd.args(["resume", session]);
         }
@@ -152,6 +174,14 @@ impl LocalCodingRunner for CodexRunner {
                         }),
                         None => ParsedLine::Ignore,
                     },
+                    // An MCP call, e.g. one of the desktop tools.
+                    Some("mcp_tool_call") => match item["tool"].as_str() {
+                        Some(tool) => ParsedLine::Chunk(Chunk {
+                            text: Some(tool.to_string()),
+                            ..Chunk::bare("tool")
+                        }),
+                        None => ParsedLine::Ignore,
+                    },
                     _ => ParsedLine::Ignore,
                 }
             }
@@ -170,6 +200,7 @@ impl LocalCodingRunner for CodexRunner {
                             session_id: None,
                             result: Some(text.to_string()),
                             total_cost_usd: None,
+                            usage: None,
                             is_error: false,
                         }),
                         None => ParsedLine::Ignore,
@@ -240,12 +271,35 @@ impl LocalCodingRunner for CodexRunner {
                     session_id: None,
                     result: Some(msg.to_string()),
                     total_cost_usd: None,
+                    usage: None,
                     is_error: true,
                 })
             }
 
-            // turn.started / turn.completed (token usage only — no dollars,
-            // see module docs) and unknown event types: skip.
+            // Per-turn token usage (no dollars — see module docs). Cached
+            // input is a subset of `input_tokens` here; split it out to match
+            // the normalized counts.
+            Some("turn.completed") => {
+                let u = &v["usage"];
+                let count = |key: &str| u[key].as_u64().unwrap_or(0);
+                let cached = count("cached_input_tokens");
+                let usage = Usage {
+                    input_tokens: count("input_tokens").saturating_sub(cached),
+                    output_tokens: count("output_tokens"),
+                    cache_read_tokens: cached,
+                    cache_write_tokens: 0,
+                };
+                if usage.is_empty() {
+                    ParsedLine::Ignore
+                } else {
+                    ParsedLine::Chunk(Chunk {
+                        usage: Some(usage),
+                        ..Chunk::bare("usage")
+                    })
+                }
+            }
+
+            // turn.started and unknown event types: skip.
             _ => ParsedLine::Ignore,
         }
     }
@@ -277,11 +331,32 @@ impl LocalCodingRunner for CodexRunner {
             exit_code.map_or("signal".to_string(), |c| c.to_string())
         )
     }
+
+    fn state_paths(&self, home: &Path) -> Vec<PathBuf> {
+        // Login, sessions and config; CODEX_HOME relocates all of it.
+        match std::env::var_os("CODEX_HOME") {
+            Some(dir) => vec![PathBuf::from(dir)],
+            None => vec![home.join(".codex")],
+        }
+    }
+
+    fn api_hosts(&self) -> &'static [&'static str] {
+        // API-key usage, the ChatGPT-plan backend, and its token refresh.
+        &["api.openai.com", "chatgpt.com", "auth.openai.com"]
+    }
+
+    fn enforces_bash_allow(&self) -> bool {
+        // `workspace-write` and `read-only` gate what a command may touch,
+        // not which commands run.
+        false
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tools::coding_agent::permissions::PermissionProfile;
+    use crate::tools::coding_agent::runner::McpEndpoint;
 
     fn parse(line: &str) -> ParsedLine {
         CodexRunner.parse_line(line)
@@ -419,11 +494,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn turn_completed_yields_usage_with_cache_hits_split_out() {
+        let line = r#"{"type":"turn.completed","usage":{"input_tokens":5120,"cached_input_tokens":3072,"output_tokens":188}}"#;
+        assert_eq!(
+            parse(line),
+            ParsedLine::Chunk(Chunk {
+                usage: Some(Usage {
+                    input_tokens: 2048,
+                    output_tokens: 188,
+                    cache_read_tokens: 3072,
+                    cache_write_tokens: 0,
+                }),
+                ..Chunk::bare("usage")
+            })
+        );
+    }
+
     #[test]
     fn unknown_event_and_item_types_are_ignored_not_errors() {
         // Codex documents its schema as additive/version-dependent.
         for line in [
-            r#"{"type":"turn.completed","usage":{"input_tokens":100,"output_tokens":50}}"#,
+            r#"{"type":"turn.started"}"#,
             r#"{"type":"item.completed","item":{"type":"reasoning","text":"thinking"}}"#,
             r#"{"type":"item.completed","item":{"type":"web_search","query":"docs"}}"#,
             r#"{"type":"some.future.event","payload":{}}"#,
@@ -454,6 +546,10 @@ mod tests {
             working_dir: "/tmp".into(),
             session_id: Some("0199-abc".into()),
             api_key: Some("sk-ant-should-not-appear".into()),
+            permissions: PermissionProfile::default_profile(),
+            interactive: false,
+            relay_permissions: false,
+            desktop_tools: None,
         };
         let cmd = CodexRunner.build_command(Path::new("/usr/local/bin/codex"), &spec);
         let std_cmd = cmd.as_std();
@@ -477,4 +573,75 @@ mod tests {
             .get_envs()
             .any(|(k, _)| k.to_string_lossy() == "ANTHROPIC_API_KEY"));
     }
+
+    #[test]
+    fn read_only_profile_selects_the_read_only_sandbox() {
+        let spec = RunSpec {
+            prompt: "look around".into(),
+            working_dir: "/tmp".into(),
+            session_id: None,
+            api_key: None,
+            permissions: PermissionProfile::analysis(),
+            interactive: false,
+            relay_permissions: false,
+            desktop_tools: None,
+        };
+        let cmd = CodexRunner.build_command(Path::new("codex"), &spec);
+        let args: Vec<String> = cmd
+            .as_std()
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        let i = args.iter().position(|a| a == "--sandbox").unwrap();
+        assert_eq!(args[i + 1], "read-only");
+    }
+
+    #[test]
+    fn desktop_tools_go_in_as_config_overrides_with_the_token_in_env() {
+        let spec = RunSpec {
+            prompt: "pull the plasmid".into(),
+            working_dir: "/tmp".into(),
+            session_id: Some("0199-abc".into()),
+            api_key: None,
+            permissions: PermissionProfile::default_profile(),
+            interactive: false,
+            relay_permissions: false,
+            desktop_tools: Some(McpEndpoint {
+                url: "http://127.0.0.1:40123/mcp".into(),
+                token: "t0k".into(),
+            }),
+        };
+        let cmd = CodexRunner.build_command(Path::new("codex"), &spec);
+        let std_cmd = cmd.as_std();
+        let args: Vec<String> = std_cmd
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        let overrides: Vec<&str> = args
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i > 0 && args[i - 1] == "-c")
+            .map(|(_, a)| a.as_str())
+            .collect();
+        assert_eq!(
+            overrides,
+            [
+                r#"mcp_servers.beakr.url="http://127.0.0.1:40123/mcp""#,
+                r#"mcp_servers.beakr.bearer_token_env_var="BEAKR_MCP_TOKEN""#,
+            ]
+        );
+        let resume_pos = args.iter().position(|a| a == "resume").unwrap();
+        assert!(args.iter().position(|a| a == "-c").unwrap() < resume_pos);
+        assert!(!args.iter().any(|a| a.contains("t0k")), "the token stays out of argv");
+        assert!(std_cmd.get_envs().any(|(k, v)| {
+            k == "BEAKR_MCP_TOKEN" && v.is_some_and(|v| v == "t0k")
+        }));
+
+        let started = r#"{"type":"item.started","item":{"id":"item_3","type":"mcp_tool_call","server":"beakr","tool":"benchling_get_dna_sequence","status":"in_progress"}}"#;
+        assert!(matches!(
+            CodexRunner.parse_line(started),
+            ParsedLine::Chunk(Chunk { kind: "tool", text: Some(ref t), .. })
+                if t == "benchling_get_dna_sequence"
+        ));
+    }
 }
@@ -0,0 +1,558 @@
+//! End-to-end coverage for the orchestrator's process paths — spawn, stream,
+//! cancel, timeout, reap — against the scriptable mock CLI. The adapters are
+//! the real ones and the mock replays recorded NDJSON, so each test runs the
+//! same parse → chunk → settle path a live run takes. Timings are shrunk so
+//! the escalation paths finish in a couple of seconds.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+
+use super::claude::ClaudeRunner;
+use super::codex::CodexRunner;
+use super::mock_cli::{MockCli, OnInterrupt};
+use super::*;
+use crate::ws::inflight::{InflightRegistry, RunInput};
+use crate::ws::protocol::OutgoingMessage;
+
+const CLAUDE_SUCCESS: &str = include_str!("fixtures/claude_success.ndjson");
+const CLAUDE_NOT_LOGGED_IN: &str = include_str!("fixtures/claude_not_logged_in.ndjson");
+const CODEX_SUCCESS: &str = include_str!("fixtures/codex_success.ndjson");
+
+const REQUEST_ID: &str = "req-e2e";
+/// Upper bound for any single scenario; every escalation path must finish
+/// well inside it or the orchestrator is leaking a wait.
+const SCENARIO_CEILING: Duration = Duration::from_secs(8);
+
+fn fast(run: Duration) -> PumpConfig {
+    PumpConfig {
+        timings: RunTimings {
+            run,
+            cancel_grace: Duration::from_millis(500),
+            exit_wait: Duration::from_secs(1),
+            session_idle: Duration::from_secs(5),
+            permission_wait: Duration::from_secs(5),
+        },
+        sandbox: SandboxReport::unconfined("e2e harness"),
+        token_allowance: None,
+        session: None,
+    }
+}
+
+struct Outcome {
+    result: Result<(serde_json::Value, Option<u64>), String>,
+    chunks: Vec<serde_json::Value>,
+    /// What the orchestrator wrote to the CLI's stdin, line by line.
+    stdin: Vec<String>,
+    stopping_fired: bool,
+    /// The group was reaped after the pump returned — no orphaned CLI.
+    reaped: bool,
+    elapsed: Duration,
+}
+
+impl Outcome {
+    fn data(&self) -> &serde_json::Value {
+        &self.result.as_ref().expect("run should settle Ok").0
+    }
+
+    fn error(&self) -> &str {
+        self.result.as_ref().expect_err("run should fail")
+    }
+
+    fn kinds(&self) -> Vec<&str> {
+        self.chunks.iter().filter_map(|c| c["kind"].as_str()).collect()
+    }
+}
+
+/// Spawn the mock exactly the way `handle_streaming` spawns a real CLI, pump
+/// it, settle it, and collect everything that went out on the wire.
+async fn drive(
+    runner: &dyn LocalCodingRunner,
+    mock: MockCli,
+    config: PumpConfig,
+    cancel_after: Option<Duration>,
+) -> Outcome {
+    let dir = tempfile::tempdir().unwrap();
+    let binary = mock.install(dir.path());
+    let spec = RunSpec {
+        prompt: "fix the failing test".into(),
+        working_dir: dir.path().to_path_buf(),
+        session_id: None,
+        api_key: None,
+        permissions: super::permissions::PermissionProfile::default_profile(),
+        interactive: config.session.as_ref().is_some_and(|s| s.takes_turns),
+        relay_permissions: false,
+        desktop_tools: None,
+    };
+    let mut cmd = runner.build_command(&binary, &spec);
+    cmd.stdin(if config.session.is_some() {
+        std::process::Stdio::piped()
+    } else {
+        std::process::Stdio::null()
+    })
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    let mut child = spawn(&mut cmd, &binary);
+
+    let (tx, mut rx) = mpsc::channel(256);
+    let stream = ToolStream::new(REQUEST_ID.into(), tx);
+    let inflight = Arc::new(InflightRegistry::new());
+    let mut cancel = inflight.register(REQUEST_ID);
+    if let Some(after) = cancel_after {
+        let inflight = inflight.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(after).await;
+            inflight.cancel(REQUEST_ID);
+        });
+    }
+
+    let started = Instant::now();
+    let mut stopping_fired = false;
+    let pumped = pump_run(
+        runner,
+        &mut child,
+        &stream,
+        &mut cancel,
+        config,
+        &mut None,
+        || stopping_fired = true,
+    )
+    .await;
+    let result = pumped.and_then(|run| settle_run(runner, run));
+    let elapsed = started.elapsed();
+    // A second wait() on an exited child returns its cached status; one that
+    // is still running (timeout path: SIGTERMed, not yet reaped) gets a
+    // moment to die.
+    let reaped = tokio::time::timeout(Duration::from_secs(3), child.wait())
+        .await
+        .is_ok();
+
+    drop(stream);
+    let mut chunks = Vec::new();
+    while let Some(msg) = rx.recv().await {
+        if let OutgoingMessage::ResponseChunk { data, .. } = msg {
+            chunks.push(data);
+        }
+    }
+    Outcome {
+        result,
+        chunks,
+        stdin: MockCli::stdin_lines(&binary),
+        stopping_fired,
+        reaped,
+        elapsed,
+    }
+}
+
+/// `GroupChild::spawn`, retrying ETXTBSY: a parallel test forking between our
+/// write and close of the script can briefly hold it open for writing.
+fn spawn(cmd: &mut tokio::process::Command, binary: &Path) -> GroupChild {
+    for _ in 0..50 {
+        match GroupChild::spawn(cmd) {
+            Ok(child) => return child,
+            Err(e) if e.raw_os_error() == Some(libc::ETXTBSY) => {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            Err(e) => panic!("spawn {}: {e}", binary.display()),
+        }
+    }
+    panic!("spawn {}: text file stayed busy", binary.display());
+}
+
+/// A terminal `result` the mock prints from its SIGINT trap — what claude
+/// emits when interrupted cleanly.
+fn interrupted_result(session_id: &str) -> String {
+    serde_json::json!({
+        "type": "result",
+        "subtype": "error_during_execution",
+        "is_error": true,
+        "session_id": session_id,
+        "total_cost_usd": 0.013,
+    })
+    .to_string()
+}
+
+#[tokio::test]
+async fn claude_success_streams_chunks_and_settles_with_audit() {
+    let mock = MockCli::new().replay(CLAUDE_SUCCESS, Duration::from_millis(10));
+    let out = drive(&ClaudeRunner, mock, fast(Duration::from_secs(5)), None).await;
+
+    let data = out.data();
+    assert_eq!(data["session_id"], "5f2c7a10-1b7e-4c1a-9f55-0d6c2e8a4b21");
+    assert_eq!(data["result"], "Fixed the off-by-one in seed_sweep.py.");
+    assert_eq!(data["total_cost_usd"], 0.0421);
+    assert_eq!(data["cli"], "claude");
+    assert_eq!(data["cancelled"], false);
+    assert_eq!(data["files_changed"], serde_json::json!(["/repo/seed_sweep.py"]));
+
+    let kinds = out.kinds();
+    assert_eq!(kinds.first(), Some(&"session"));
+    assert_eq!(out.chunks[0]["sandbox"]["kind"], "none", "session chunk reports the sandbox");
+    assert!(kinds.contains(&"file_changed"));
+    assert!(kinds.ends_with(&["cost", "usage"]), "{kinds:?}");
+    assert!(!out.stopping_fired);
+    assert!(out.reaped);
+
+    // Live usage chunks carry the running total; the result's own total
+    // closes the stream and lands in the terminal payload.
+    let usage: Vec<&serde_json::Value> = out
+        .chunks
+        .iter()
+        .filter(|c| c["kind"] == "usage")
+        .map(|c| &c["usage"])
+        .collect();
+    assert_eq!(usage[0]["input_tokens"], 920);
+    assert_eq!(usage[1]["output_tokens"], 150);
+    assert_eq!(usage[2]["input_tokens"], 1840);
+    let total = serde_json::json!({
+        "input_tokens": 1840,
+        "output_tokens": 312,
+        "cache_read_tokens": 12288,
+        "cache_write_tokens": 0,
+    });
+    assert_eq!(*usage.last().unwrap(), &total);
+    assert_eq!(data["usage"], total);
+}
+
+#[tokio::test]
+async fn codex_success_records_files_and_commands() {
+    let mock = MockCli::new().replay(CODEX_SUCCESS, Duration::from_millis(10));
+    let out = drive(&CodexRunner, mock, fast(Duration::from_secs(5)), None).await;
+
+    let data = out.data();
+    assert_eq!(data["result"], "Created hello.txt containing exactly hi.");
+    assert_eq!(data["cli"], "codex");
+    assert_eq!(data["files_changed"], serde_json::json!(["/repo/hello.txt"]));
+    assert_eq!(
+        data["commands"],
+        serde_json::json!(["/bin/zsh -lc 'cat hello.txt'"])
+    );
+    // Codex's resume id rides the session chunk, not the terminal payload.
+    let session = &out.chunks[0];
+    assert_eq!(session["kind"], "session");
+    assert_eq!(session["session_id"], "0199a3c1-7e55-7d10-b1c4-2f8e3a9d6c01");
+    assert_eq!(data["usage"]["input_tokens"], 2048);
+    assert_eq!(data["usage"]["cache_read_tokens"], 3072);
+    assert!(out.reaped);
+}
+
+#[tokio::test]
+async fn cancel_within_grace_keeps_the_session_id() {
+    let mock = MockCli::new()
+        .line(CLAUDE_SUCCESS.lines().next().unwrap())
+        .sleep(Duration::from_secs(30))
+        .on_interrupt(OnInterrupt::Graceful {
+            lines: vec![interrupted_result("sess-graceful")],
+            exit_code: 130,
+        });
+    let out = drive(
+        &ClaudeRunner,
+        mock,
+        fast(Duration::from_secs(10)),
+        Some(Duration::from_millis(300)),
+    )
+    .await;
+
+    let data = out.data();
+    assert_eq!(data["cancelled"], true);
+    assert_eq!(data["session_id"], "sess-graceful");
+    assert!(out.stopping_fired);
+    // The trap's result line arrived inside the grace and was forwarded.
+    assert!(out.kinds().contains(&"cost"));
+    assert!(out.reaped);
+    assert!(out.elapsed < SCENARIO_CEILING, "took {:?}", out.elapsed);
+}
+
+#[tokio::test]
+async fn cancel_escalates_to_sigterm_when_sigint_is_ignored() {
+    let mock = MockCli::new()
+        .line(CLAUDE_SUCCESS.lines().next().unwrap())
+        .sleep(Duration::from_secs(30))
+        .on_interrupt(OnInterrupt::Ignore);
+    let out = drive(
+        &ClaudeRunner,
+        mock,
+        fast(Duration::from_secs(10)),
+        Some(Duration::from_millis(200)),
+    )
+    .await;
+
+    let data = out.data();
+    assert_eq!(data["cancelled"], true);
+    assert_eq!(data["session_id"], serde_json::Value::Null);
+    assert!(out.stopping_fired);
+    assert!(out.reaped);
+    // Waited out the full grace before escalating, but no longer than needed.
+    assert!(out.elapsed >= Duration::from_millis(700), "took {:?}", out.elapsed);
+    assert!(out.elapsed < SCENARIO_CEILING, "took {:?}", out.elapsed);
+}
+
+#[tokio::test]
+async fn run_past_its_deadline_is_stopped_with_run_timeout() {
+    let mock = MockCli::new()
+        .line(CLAUDE_SUCCESS.lines().next().unwrap())
+        .sleep(Duration::from_secs(30));
+    let out = drive(&ClaudeRunner, mock, fast(Duration::from_secs(1)), None).await;
+
+    assert!(out.error().starts_with("run_timeout:"), "{}", out.error());
+    assert_eq!(out.kinds(), ["session"]);
+    assert!(!out.stopping_fired);
+    assert!(out.reaped, "SIGTERMed group should exit");
+    assert!(out.elapsed < SCENARIO_CEILING, "took {:?}", out.elapsed);
+}
+
+/// A config with stdin input the test feeds: an interactive session, or
+/// (`takes_turns` false) a single-shot run that relays permission prompts.
+fn session(run: Duration, takes_turns: bool) -> (PumpConfig, mpsc::UnboundedSender<RunInput>) {
+    let (tx, input) = mpsc::unbounded_channel();
+    let config = PumpConfig {
+        session: Some(SessionInput {
+            first_turn: ClaudeRunner.encode_turn("fix the failing test").unwrap(),
+            input,
+            takes_turns,
+        }),
+        ..fast(run)
+    };
+    (config, tx)
+}
+
+#[tokio::test]
+async fn interactive_session_answers_follow_ups_until_ended() {
+    let second = serde_json::json!({
+        "type": "result",
+        "subtype": "success",
+        "is_error": false,
+        "result": "Added a regression test.",
+        "session_id": "5f2c7a10-1b7e-4c1a-9f55-0d6c2e8a4b21",
+        "usage": {"input_tokens": 100, "output_tokens": 20},
+    });
+    let mock = MockCli::new()
+        .read_line()
+        .replay(CLAUDE_SUCCESS, Duration::from_millis(10))
+        .read_line()
+        .line(&second.to_string())
+        .drain_stdin();
+    let (config, input) = session(Duration::from_secs(5), true);
+    input.send(RunInput::Prompt("now add a test".into())).unwrap();
+    input.send(RunInput::End).unwrap();
+    let out = drive(&ClaudeRunner, mock, config, None).await;
+
+    // The request settles with the last turn's answer once input closes.
+    let data = out.data();
+    assert_eq!(data["result"], "Added a regression test.");
+    let kinds = out.kinds();
+    assert_eq!(kinds.iter().filter(|k| **k == "turn").count(), 1);
+    assert_eq!(kinds.iter().filter(|k| **k == "turn_end").count(), 2);
+    assert_eq!(kinds.last(), Some(&"turn_end"));
+    // Each result's usage covers its own turn; the run total spans both.
+    assert_eq!(data["usage"]["input_tokens"], 1840 + 100);
+    assert_eq!(data["usage"]["output_tokens"], 312 + 20);
+    assert!(!out.stopping_fired);
+    assert!(out.reaped);
+}
+
+#[tokio::test]
+async fn idle_session_closes_its_input_and_settles() {
+    let mock = MockCli::new()
+        .read_line()
+        .replay(CLAUDE_SUCCESS, Duration::ZERO)
+        .drain_stdin();
+    let (mut config, _input) = session(Duration::from_secs(10), true);
+    config.timings.session_idle = Duration::from_millis(300);
+    let out = drive(&ClaudeRunner, mock, config, None).await;
+
+    assert_eq!(out.data()["result"], "Fixed the off-by-one in seed_sweep.py.");
+    assert_eq!(out.kinds().last(), Some(&"turn_end"));
+    assert!(out.reaped);
+    assert!(out.elapsed < SCENARIO_CEILING, "took {:?}", out.elapsed);
+}
+
+#[tokio::test]
+async fn turn_sent_before_the_cli_spawns_waits_for_it() {
+    // `handle_streaming` opens the queue when it accepts the request; the
+    // engine's next turn may land while preflight and the sandbox still run.
+    let inflight = InflightRegistry::new();
+    let input = inflight.open_input(REQUEST_ID);
+    assert!(inflight.send_input(REQUEST_ID, RunInput::Prompt("now add a test".into())));
+    assert!(inflight.send_input(REQUEST_ID, RunInput::End));
+
+    let mock = MockCli::new()
+        .read_line()
+        .replay(CLAUDE_SUCCESS, Duration::from_millis(10))
+        .read_line()
+        .drain_stdin();
+    let config = PumpConfig {
+        session: Some(SessionInput {
+            first_turn: ClaudeRunner.encode_turn("fix the failing test").unwrap(),
+            input,
+            takes_turns: true,
+        }),
+        ..fast(Duration::from_secs(5))
+    };
+    let out = drive(&ClaudeRunner, mock, config, None).await;
+
+    assert_eq!(out.stdin.len(), 2, "{:?}", out.stdin);
+    assert!(out.stdin[1].contains("now add a test"));
+    assert_eq!(out.kinds().iter().filter(|k| **k == "turn").count(), 1);
+    assert!(out.reaped);
+}
+
+/// A `can_use_tool` prompt for a Bash command, as claude emits it.
+fn bash_prompt(permission_id: &str) -> String {
+    serde_json::json!({
+        "type": "control_request",
+        "request_id": permission_id,
+        "request": {
+            "subtype": "can_use_tool",
+            "tool_name": "Bash",
+            "input": {"command": "pytest -q"},
+        },
+    })
+    .to_string()
+}
+
+#[tokio::test]
+async fn relayed_prompt_waits_for_the_decision_and_writes_it_back() {
+    let mock = MockCli::new()
+        .read_line()
+        .line(&bash_prompt("perm-1"))
+        .read_line()
+        .replay(CLAUDE_SUCCESS, Duration::ZERO)
+        .drain_stdin();
+    let (config, input) = session(Duration::from_secs(5), false);
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let _ = input.send(RunInput::Decision {
+            permission_id: "perm-1".into(),
+            allow: true,
+            message: None,
+        });
+        // Held until the run is done, like the registry entry.
+        tokio::time::sleep(SCENARIO_CEILING).await;
+    });
+    let out = drive(&ClaudeRunner, mock, config, None).await;
+
+    assert_eq!(out.data()["result"], "Fixed the off-by-one in seed_sweep.py.");
+    let kinds = out.kinds();
+    assert_eq!(kinds[..2], ["permission_request", "permission_decision"]);
+    assert_eq!(out.chunks[0]["permission"]["input"]["command"], "pytest -q");
+    assert_eq!(out.chunks[1]["decision"]["allow"], true);
+    // The prompt, then the answer; stdin closed after the single answer.
+    assert_eq!(out.stdin.len(), 2, "{:?}", out.stdin);
+    let answer: serde_json::Value = serde_json::from_str(&out.stdin[1]).unwrap();
+    assert_eq!(answer["response"]["request_id"], "perm-1");
+    assert_eq!(answer["response"]["response"]["behavior"], "allow");
+    assert!(out.reaped);
+}
+
+#[tokio::test]
+async fn unanswered_prompt_is_denied_when_the_wait_runs_out() {
+    let mock = MockCli::new()
+        .read_line()
+        .line(&bash_prompt("perm-1"))
+        .read_line()
+        .replay(CLAUDE_SUCCESS, Duration::ZERO)
+        .drain_stdin();
+    let (mut config, _input) = session(Duration::from_secs(5), false);
+    config.timings.permission_wait = Duration::from_millis(300);
+    let out = drive(&ClaudeRunner, mock, config, None).await;
+
+    assert!(out.result.is_ok(), "{:?}", out.result);
+    assert_eq!(out.chunks[1]["kind"], "permission_decision");
+    assert_eq!(out.chunks[1]["decision"]["allow"], false);
+    let answer: serde_json::Value = serde_json::from_str(&out.stdin[1]).unwrap();
+    assert_eq!(answer["response"]["response"]["behavior"], "deny");
+    assert!(out.elapsed < SCENARIO_CEILING, "took {:?}", out.elapsed);
+}
+
+#[tokio::test]
+async fn crossing_the_token_allowance_stops_the_run() {
+    // The first API call alone (920 input tokens) spends the allowance; the
+    // CLI would otherwise keep working for half a minute.
+    let mut lines = CLAUDE_SUCCESS.lines();
+    let mock = MockCli::new()
+        .line(lines.next().unwrap())
+        .line(lines.next().unwrap())
+        .sleep(Duration::from_secs(30));
+    let config = PumpConfig {
+        token_allowance: Some(500),
+        ..fast(Duration::from_secs(10))
+    };
+    let out = drive(&ClaudeRunner, mock, config, None).await;
+
+    assert!(out.error().starts_with("budget_exceeded:"), "{}", out.error());
+    assert!(out.stopping_fired);
+    assert!(out.reaped);
+    assert!(out.elapsed < SCENARIO_CEILING, "took {:?}", out.elapsed);
+}
+
+#[tokio::test]
+async fn allowance_spent_by_the_final_answer_keeps_the_answer() {
+    // Codex reports usage after its answer; a finished run is not stopped,
+    // the next one is refused up front instead.
+    let mock = MockCli::new().replay(CODEX_SUCCESS, Duration::ZERO);
+    let config = PumpConfig {
+        token_allowance: Some(100),
+        ..fast(Duration::from_secs(5))
+    };
+    let out = drive(&CodexRunner, mock, config, None).await;
+
+    assert_eq!(out.data()["result"], "Created hello.txt containing exactly hi.");
+    assert!(!out.stopping_fired);
+    assert!(out.reaped);
+}
+
+#[tokio::test]
+async fn wedged_process_after_stdout_eof_is_killed() {
+    // Emits a full successful stream, closes stdout, then hangs — the
+    // status is never collected, so even a "success" result is a failure.
+    let mock = MockCli::new()
+        .replay(CLAUDE_SUCCESS, Duration::ZERO)
+        .close_stdout()
+        .sleep(Duration::from_secs(30));
+    let out = drive(&ClaudeRunner, mock, fast(Duration::from_secs(10)), None).await;
+
+    let error = out.error();
+    assert!(error.starts_with("run_failed: claude exited with signal"), "{error}");
+    assert!(out.reaped, "SIGKILLed group should exit");
+    assert!(out.elapsed < SCENARIO_CEILING, "took {:?}", out.elapsed);
+}
+
+#[tokio::test]
+async fn claude_not_logged_in_is_auth_failed() {
+    let mock = MockCli::new()
+        .replay(CLAUDE_NOT_LOGGED_IN, Duration::ZERO)
+        .exit_code(1);
+    let out = drive(&ClaudeRunner, mock, fast(Duration::from_secs(5)), None).await;
+
+    assert!(out.error().starts_with("auth_failed:"), "{}", out.error());
+    assert!(out.reaped);
+}
+
+#[tokio::test]
+async fn codex_login_error_on_stderr_is_auth_failed() {
+    let mock = MockCli::new()
+        .stderr("Error: Not logged in. Run `codex login` first.")
+        .exit_code(1);
+    let out = drive(&CodexRunner, mock, fast(Duration::from_secs(5)), None).await;
+
+    assert!(out.error().starts_with("auth_failed:"), "{}", out.error());
+    assert!(out.chunks.is_empty());
+}
+
+#[tokio::test]
+async fn nonzero_exit_after_a_result_is_run_failed_with_the_code() {
+    let mock = MockCli::new()
+        .replay(CLAUDE_SUCCESS, Duration::ZERO)
+        .stderr("panic: something broke")
+        .exit_code(3);
+    let out = drive(&ClaudeRunner, mock, fast(Duration::from_secs(5)), None).await;
+
+    assert!(
+        out.error().starts_with("run_failed: claude exited with 3"),
+        "{}",
+        out.error()
+    );
+}
@@ -0,0 +1,238 @@
+//! Run history: one record per coding run, next to its log.
+//!
+//! `<app-log-dir>/coding-runs/<request_id>.run.json` says who ran what,
+//! where, and how it ended — enough for the app window to list and search
+//! past runs, open a run's log, and resume its CLI session. Written once,
+//! when the run ends; a crash mid-run leaves only the log behind.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+const RECORD_SUFFIX: &str = ".run.json";
+/// How much of a run's log `open` returns. Logs of long runs grow to
+/// megabytes; the tail is the part that says how it ended.
+const LOG_TAIL_BYTES: u64 = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub request_id: String,
+    pub cli: String,
+    pub working_dir: String,
+    pub prompt: String,
+    /// The CLI session to resume, when the run got far enough to have one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub started_at_ms: u64,
+    pub ended_at_ms: u64,
+    pub outcome: RunOutcome,
+    /// The typed error of a failed run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub files_changed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_cost_usd: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_path: Option<String>,
+}
+
+impl RunRecord {
+    /// Stamp the end of the run.
+    pub fn ended(mut self, outcome: RunOutcome, error: Option<&str>) -> Self {
+        self.ended_at_ms = now_ms();
+        self.outcome = outcome;
+        self.error = error.map(String::from);
+        self
+    }
+}
+
+/// Unix epoch millis — the unit `ActiveCodingRun` and the UI already use.
+pub fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// A past run as the app window opens it: the record plus its log's tail.
+#[derive(Debug, Serialize)]
+pub struct OpenedRun {
+    #[serde(flatten)]
+    pub record: RunRecord,
+    pub log: Option<String>,
+    /// True when `log` is only the end of a longer file.
+    pub log_truncated: bool,
+}
+
+/// Request ids arrive from the engine and the webview; as file names they
+/// must stay inside the runs dir.
+pub(super) fn valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !request_id.starts_with('.')
+}
+
+fn record_path(dir: &Path, request_id: &str) -> Result<PathBuf, String> {
+    if !valid_request_id(request_id) {
+        return Err(format!("bad_params: invalid run id '{request_id}'"));
+    }
+    Ok(dir.join(format!("{request_id}{RECORD_SUFFIX}")))
+}
+
+pub fn record(dir: &Path, run: &RunRecord) -> Result<(), String> {
+    let path = record_path(dir, &run.request_id)?;
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let json = serde_json::to_vec_pretty(run).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+pub fn load(dir: &Path, request_id: &str) -> Result<RunRecord, String> {
+    let path = record_path(dir, request_id)?;
+    let bytes = std::fs::read(&path)
+        .map_err(|_| format!("not_found: no coding run '{request_id}' in the history"))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("run record {request_id} is corrupt: {e}"))
+}
+
+/// Every readable record, newest first.
+pub fn list(dir: &Path) -> Vec<RunRecord> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut runs: Vec<RunRecord> = entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().ends_with(RECORD_SUFFIX))
+        .filter_map(|e| std::fs::read(e.path()).ok())
+        .filter_map(|bytes| serde_json::from_slice(&bytes).ok())
+        .collect();
+    runs.sort_by_key(|r| std::cmp::Reverse(r.started_at_ms));
+    runs
+}
+
+/// Case-insensitive match on every word of `query` against the prompt,
+/// folder, CLI, session id, error and changed files. An empty query
+/// matches everything.
+pub fn matches(run: &RunRecord, query: &str) -> bool {
+    let haystack = [
+        run.prompt.as_str(),
+        run.working_dir.as_str(),
+        run.cli.as_str(),
+        run.session_id.as_deref().unwrap_or_default(),
+        run.error.as_deref().unwrap_or_default(),
+    ]
+    .into_iter()
+    .chain(run.files_changed.iter().map(String::as_str))
+    .collect::<Vec<_>>()
+    .join("\n")
+    .to_lowercase();
+    query
+        .to_lowercase()
+        .split_whitespace()
+        .all(|word| haystack.contains(word))
+}
+
+/// A record plus the tail of its log, when the log still exists.
+pub fn open(dir: &Path, request_id: &str) -> Result<OpenedRun, String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let record = load(dir, request_id)?;
+    let mut log = None;
+    let mut log_truncated = false;
+    if let Some(path) = &record.log_path {
+        if let Ok(mut file) = std::fs::File::open(path) {
+            let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+            if len > LOG_TAIL_BYTES {
+                log_truncated = file.seek(SeekFrom::Start(len - LOG_TAIL_BYTES)).is_ok();
+            }
+            let mut bytes = Vec::new();
+            if file.read_to_end(&mut bytes).is_ok() {
+                log = Some(String::from_utf8_lossy(&bytes).into_owned());
+            }
+        }
+    }
+    Ok(OpenedRun {
+        record,
+        log,
+        log_truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(request_id: &str, started_at_ms: u64, prompt: &str) -> RunRecord {
+        RunRecord {
+            request_id: request_id.into(),
+            cli: "claude".into(),
+            working_dir: "/Users/d/seed-sweep".into(),
+            prompt: prompt.into(),
+            session_id: Some(format!("sess-{request_id}")),
+            started_at_ms,
+            ended_at_ms: started_at_ms + 1000,
+            outcome: RunOutcome::Succeeded,
+            error: None,
+            files_changed: vec!["/Users/d/seed-sweep/sweep.py".into()],
+            total_cost_usd: Some(0.04),
+            log_path: None,
+        }
+    }
+
+    #[test]
+    fn records_list_newest_first_and_search_by_words() {
+        let dir = tempfile::tempdir().unwrap();
+        record(dir.path(), &run("req-1", 100, "Fix the failing seed test")).unwrap();
+        record(dir.path(), &run("req-2", 200, "Add a plotting script")).unwrap();
+        std::fs::write(dir.path().join("req-3.run.json"), "{").unwrap();
+
+        let runs = list(dir.path());
+        let ids: Vec<&str> = runs.iter().map(|r| r.request_id.as_str()).collect();
+        assert_eq!(ids, ["req-2", "req-1"]);
+        assert!(matches(&runs[1], "SEED failing"));
+        assert!(matches(&runs[1], "sweep.py"));
+        assert!(!matches(&runs[1], "seed plotting"));
+        assert!(matches(&runs[0], ""));
+    }
+
+    #[test]
+    fn run_ids_cannot_escape_the_runs_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        for id in ["../settings", "a/b", "", ".hidden"] {
+            assert!(
+                load(dir.path(), id).unwrap_err().starts_with("bad_params:"),
+                "{id}"
+            );
+        }
+        assert!(load(dir.path(), "req-missing")
+            .unwrap_err()
+            .starts_with("not_found:"));
+    }
+
+    #[test]
+    fn open_returns_the_tail_of_a_long_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join("req-1.log");
+        let mut body = "x".repeat(LOG_TAIL_BYTES as usize);
+        body.push_str("\n== run finished ==\n");
+        std::fs::write(&log_path, &body).unwrap();
+        let mut r = run("req-1", 100, "p");
+        r.log_path = Some(log_path.display().to_string());
+        record(dir.path(), &r).unwrap();
+
+        let opened = open(dir.path(), "req-1").unwrap();
+        let log = opened.log.unwrap();
+        assert!(opened.log_truncated);
+        assert_eq!(log.len(), LOG_TAIL_BYTES as usize);
+        assert!(log.ends_with("== run finished ==\n"));
+    }
+}
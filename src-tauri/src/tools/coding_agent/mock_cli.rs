@@ -0,0 +1,224 @@
+//! Scriptable stand-in for a coding CLI (test-only).
+//!
+//! Writes a small POSIX `sh` script that replays a recorded NDJSON stream on
+//! stdout with configurable delays, stderr output, exit code and SIGINT
+//! behavior. The orchestrator then spawns it exactly like the real `claude` /
+//! `codex` binary — same process group, same pipes, same signals — so the
+//! spawn/cancel/timeout/reap paths run against a real child instead of pure
+//! adapter calls. A script rather than a compiled helper because a lib test
+//! has no way to ship a second binary, and every unix runner has `/bin/sh`.
+//!
+//! The script ignores its arguments: adapters still build their real command
+//! lines, which their own unit tests pin. Whatever it reads on stdin is
+//! appended to `<script>.stdin`, so a test can check what the orchestrator
+//! wrote (see [`MockCli::stdin_lines`]).
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Appends the line just read (`$_in`) to the stdin record.
+const RECORD_STDIN: &str = r#"printf '%s\n' "$_in" >> "$0.stdin""#;
+
+/// What the mock does when the orchestrator SIGINTs its process group.
+#[derive(Default)]
+pub enum OnInterrupt {
+    /// Default disposition: SIGINT kills the script (and its `sleep`).
+    #[default]
+    Die,
+    /// Trap SIGINT, print these stdout lines (typically a terminal `result`
+    /// carrying the session id), then exit with `exit_code` — what both CLIs
+    /// do when interrupted cleanly.
+    Graceful { lines: Vec<String>, exit_code: i32 },
+    /// Ignore SIGINT entirely, so only the SIGTERM escalation stops it.
+    Ignore,
+}
+
+enum Step {
+    Stdout(String),
+    Stderr(String),
+    Sleep(Duration),
+    /// Close stdout while the process keeps running — a wedged CLI.
+    CloseStdout,
+    /// Block until one line arrives on stdin (or it closes).
+    ReadLine,
+    /// Block until stdin closes, recording whatever arrives.
+    DrainStdin,
+}
+
+#[derive(Default)]
+pub struct MockCli {
+    steps: Vec<Step>,
+    exit_code: i32,
+    on_interrupt: OnInterrupt,
+}
+
+impl MockCli {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replay a recorded NDJSON stream, one line per event, pausing `delay`
+    /// between events the way a live CLI trickles them out.
+    pub fn replay(mut self, ndjson: &str, delay: Duration) -> Self {
+        for line in ndjson.lines().filter(|l| !l.trim().is_empty()) {
+            if !self.steps.is_empty() && !delay.is_zero() {
+                self.steps.push(Step::Sleep(delay));
+            }
+            self.steps.push(Step::Stdout(line.to_string()));
+        }
+        self
+    }
+
+    pub fn line(mut self, line: &str) -> Self {
+        self.steps.push(Step::Stdout(line.to_string()));
+        self
+    }
+
+    pub fn stderr(mut self, line: &str) -> Self {
+        self.steps.push(Step::Stderr(line.to_string()));
+        self
+    }
+
+    pub fn sleep(mut self, duration: Duration) -> Self {
+        self.steps.push(Step::Sleep(duration));
+        self
+    }
+
+    pub fn close_stdout(mut self) -> Self {
+        self.steps.push(Step::CloseStdout);
+        self
+    }
+
+    /// Wait for the next line on stdin: a turn, or a permission decision.
+    pub fn read_line(mut self) -> Self {
+        self.steps.push(Step::ReadLine);
+        self
+    }
+
+    /// Wait for the session's input to close — how a CLI in streaming-input
+    /// mode knows it is done.
+    pub fn drain_stdin(mut self) -> Self {
+        self.steps.push(Step::DrainStdin);
+        self
+    }
+
+    pub fn exit_code(mut self, code: i32) -> Self {
+        self.exit_code = code;
+        self
+    }
+
+    pub fn on_interrupt(mut self, behavior: OnInterrupt) -> Self {
+        self.on_interrupt = behavior;
+        self
+    }
+
+    /// The generated script. Pure, so the quoting is unit-testable.
+    pub fn script(&self) -> String {
+        let mut s = String::from("#!/bin/sh\n");
+        match &self.on_interrupt {
+            OnInterrupt::Die => {}
+            OnInterrupt::Graceful { lines, exit_code } => {
+                s.push_str("on_int() {\n");
+                for line in lines {
+                    let _ = writeln!(s, "  printf '%s\\n' {}", quote(line));
+                }
+                let _ = writeln!(s, "  exit {exit_code}\n}}\ntrap on_int INT");
+            }
+            // Inherited by the script's `sleep` children too, so the whole
+            // group survives SIGINT.
+            OnInterrupt::Ignore => s.push_str("trap '' INT\n"),
+        }
+        for step in &self.steps {
+            match step {
+                Step::Stdout(line) => {
+                    let _ = writeln!(s, "printf '%s\\n' {}", quote(line));
+                }
+                Step::Stderr(line) => {
+                    let _ = writeln!(s, "printf '%s\\n' {} >&2", quote(line));
+                }
+                Step::Sleep(d) => {
+                    let _ = writeln!(s, "sleep {:.3}", d.as_secs_f64());
+                }
+                Step::CloseStdout => s.push_str("exec 1>&-\n"),
+                Step::ReadLine => {
+                    let _ = writeln!(s, "read -r _in && {RECORD_STDIN}");
+                }
+                Step::DrainStdin => {
+                    let _ = writeln!(s, "while read -r _in; do {RECORD_STDIN}; done");
+                }
+            }
+        }
+        let _ = writeln!(s, "exit {}", self.exit_code);
+        s
+    }
+
+    /// The lines the installed script at `binary` has read from stdin.
+    pub fn stdin_lines(binary: &Path) -> Vec<String> {
+        let mut path = binary.as_os_str().to_owned();
+        path.push(".stdin");
+        std::fs::read_to_string(path)
+            .map(|s| s.lines().map(String::from).collect())
+            .unwrap_or_default()
+    }
+
+    /// Write the script into `dir` as an executable and return its path —
+    /// hand it to `LocalCodingRunner::build_command` as the binary.
+    pub fn install(&self, dir: &Path) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("mock-cli");
+        std::fs::write(&path, self.script()).expect("write mock cli");
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod mock cli");
+        path
+    }
+}
+
+/// Single-quote for sh, escaping embedded single quotes.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_quotes_lines_and_orders_steps() {
+        let script = MockCli::new()
+            .line(r#"{"text":"it's here"}"#)
+            .sleep(Duration::from_millis(250))
+            .stderr("Not logged in")
+            .exit_code(1)
+            .script();
+        assert_eq!(
+            script,
+            "#!/bin/sh\n\
+             printf '%s\\n' '{\"text\":\"it'\\''s here\"}'\n\
+             sleep 0.250\n\
+             printf '%s\\n' 'Not logged in' >&2\n\
+             exit 1\n"
+        );
+    }
+
+    #[test]
+    fn replay_interleaves_delays_between_events_only() {
+        let script = MockCli::new()
+            .replay("{\"a\":1}\n\n{\"b\":2}\n", Duration::from_millis(10))
+            .script();
+        assert_eq!(script.matches("printf").count(), 2);
+        assert_eq!(script.matches("sleep").count(), 1, "no delay before the first event");
+    }
+
+    #[test]
+    fn graceful_interrupt_installs_a_trap() {
+        let script = MockCli::new()
+            .on_interrupt(OnInterrupt::Graceful {
+                lines: vec![r#"{"type":"result"}"#.into()],
+                exit_code: 130,
+            })
+            .script();
+        assert!(script.contains("trap on_int INT"));
+        assert!(script.contains("  exit 130\n}"));
+    }
+}
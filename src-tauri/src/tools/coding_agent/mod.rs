@@ -4,16 +4,31 @@
 //! `codex.rs` in ENG-1529). Rides the ENG-1527 rails: streams
 //! `response_chunk`s, honors the cancel signal (SIGINT to the child's process
 //! group; the terminal response still goes out), holds the one-coding-run
-//! slot, and registers the child for reap-on-quit.
+//! slot, and registers the child for reap-on-quit. An interactive run keeps
+//! the CLI alive and feeds it follow-up turns (WS `turn`) on stdin until the
+//! engine ends the session or it sits idle. A run that relays permissions
+//! forwards the CLI's tool prompts as `permission_request` chunks and
+//! writes back the web user's `permission_decision`, or a deny on timeout.
+//! Each run also gets the desktop's own tools (scoped folders, Benchling)
+//! over a per-run MCP endpoint, unless the user or the engine opts out.
 
 mod binary;
 mod claude;
 mod codex;
+#[cfg(all(test, unix))]
+mod e2e_tests;
+pub mod history;
+#[cfg(all(test, unix))]
+mod mock_cli;
+pub mod permissions;
+pub mod preflight;
 pub mod readiness;
 mod run_log;
 pub mod runner;
+mod session;
+pub mod usage;
 
-pub use run_log::open_log_in_terminal;
+pub use run_log::{backfill as backfill_run_chunks, open_log_in_terminal, runs_dir};
 
 use std::time::Duration;
 
@@ -22,10 +37,16 @@ use tauri::{AppHandle, Emitter};
 use tokio::io::{AsyncBufReadExt, BufReader};
 
 use crate::process_group::GroupChild;
+use crate::sandbox::SandboxReport;
 use crate::state::{ActiveCodingRun, AppState, CodingRunStatus};
-use crate::ws::inflight::CancelSignal;
+use crate::ws::inflight::{CancelSignal, InputReceiver, RunInput};
 use crate::ws::ToolStream;
-use runner::{Chunk, LocalCodingRunner, ParsedLine, RunResult, RunSpec};
+use history::RunOutcome;
+use runner::{
+    Chunk, LocalCodingRunner, McpEndpoint, ParsedLine, PermissionDecision, RunResult, RunSpec,
+};
+use session::{SessionEvent, StdinSession};
+use usage::Usage;
 
 /// Default ceiling on a single run. Long enough for a real coding task, short
 /// enough that a wedged CLI can't hold the coding slot forever. The engine's
@@ -36,6 +57,15 @@ const MAX_RUN_TIMEOUT_MINUTES: u64 = 360;
 /// After a cancel SIGINT, how long the CLI gets to exit cleanly (saving its
 /// session for resume) before the whole group is SIGTERMed.
 const CANCEL_GRACE: Duration = Duration::from_secs(5);
+/// After stdout EOF, how long the process gets to exit before it is treated
+/// as wedged and the whole group is SIGKILLed.
+const EXIT_WAIT: Duration = Duration::from_secs(10);
+/// How long an interactive session waits for its next turn before closing
+/// the CLI's input and settling. The run ceiling still bounds the session.
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// How long a relayed permission prompt waits for the web user before the
+/// CLI is told no. The CLI sits blocked meanwhile, so this is short.
+const PERMISSION_WAIT: Duration = Duration::from_secs(5 * 60);
 
 #[derive(Debug, Deserialize)]
 struct Params {
@@ -51,6 +81,26 @@ struct Params {
     /// 15-minute default. Sent by the engine's detached (wait=false) mode.
     #[serde(default)]
     timeout_minutes: Option<u64>,
+    /// Named permission profile for this run. Capped by the user's local
+    /// ceiling (Settings), so it can only narrow the tool surface; absent
+    /// runs at the ceiling.
+    #[serde(default)]
+    permission_profile: Option<String>,
+    /// How strict the working-directory pre-flight is; absent = defaults.
+    #[serde(default)]
+    preflight: preflight::PreflightOptions,
+    /// Keep the CLI running for follow-up turns (`IncomingMessage::Turn`)
+    /// instead of settling after the first answer.
+    #[serde(default)]
+    interactive: bool,
+    /// Relay the CLI's permission prompts to the engine instead of refusing
+    /// whatever the profile leaves out. Needs the local opt-in (Settings).
+    #[serde(default)]
+    relay_permissions: bool,
+    /// Serve the desktop tools to the CLI over MCP. Absent = on, unless
+    /// the user turned it off in Settings; `false` opts this run out.
+    #[serde(default)]
+    desktop_tools: Option<bool>,
 }
 
 /// The effective run ceiling for a request — never trusts the wire value
@@ -105,6 +155,44 @@ pub async fn handle_streaming(
         }
     };
 
+    // A relayed prompt can allow what the permission ceiling leaves out, so
+    // the engine may only ask for it where the user opted in.
+    if params.relay_permissions {
+        if settings.permission_relay != Some(true) {
+            return Err("permission_relay_disabled: relaying permission prompts is off on \
+                        this device (Beakr Desktop settings)"
+                .into());
+        }
+        if !runner.relays_permissions() {
+            return Err(format!(
+                "bad_params: {} cannot relay permission prompts",
+                runner.name()
+            ));
+        }
+    }
+
+    // The first turn of an interactive session goes over stdin, in the
+    // CLI's own streaming-input format — which not every CLI has. A run
+    // that relays permissions answers on stdin, so its prompt goes there too.
+    // Its input queue opens now, not at spawn: a turn or decision the engine
+    // sends during preflight and setup waits there for the pump instead of
+    // being dropped. Every way out of this request closes it again.
+    let session = if params.interactive || params.relay_permissions {
+        let first_turn = runner.encode_turn(&params.prompt).ok_or_else(|| {
+            format!(
+                "bad_params: {} has no interactive sessions — send follow-ups as resumed runs",
+                runner.name()
+            )
+        })?;
+        Some(SessionInput {
+            first_turn,
+            input: state.inflight.open_input(stream.request_id()),
+            takes_turns: params.interactive,
+        })
+    } else {
+        None
+    };
+
     // The CLI's cwd must be inside a user-granted folder, same rule as every
     // other desktop tool. canonicalize + prefix-match via the shared validator.
     let working_dir = crate::security::validate_path(&params.working_dir, scoped_folders)
@@ -116,12 +204,39 @@ pub async fn handle_streaming(
         ));
     }
 
+    // Local usage budget: refuse outright once a cap is spent; otherwise the
+    // pump stops the run when it crosses what is left.
+    let token_allowance = settings
+        .usage_budget
+        .clone()
+        .unwrap_or_default()
+        .check(&usage::current_summary(app))?;
+
     // One coding run at a time per device (ENG-1527 slot). Refuse, don't queue.
     let _slot = state
         .inflight
         .try_begin_coding_run()
         .ok_or("coding_run_busy: a coding run is already in progress on this device")?;
 
+    // Pre-flight: is this a sensible folder to let an agent loose in? The
+    // report goes out either way; a blocking check ends the run here.
+    let home = std::env::var_os("HOME").map(std::path::PathBuf::from);
+    let report =
+        preflight::run(&working_dir, scoped_folders, home.as_deref(), &params.preflight).await;
+    stream
+        .chunk(
+            serde_json::to_value(Chunk {
+                preflight: Some(Box::new(report.clone())),
+                ..Chunk::bare("preflight")
+            })
+            .unwrap_or_default(),
+        )
+        .await;
+    if let Some(error) = report.error() {
+        log::info!("Coding run {} refused: {error}", stream.request_id());
+        return Err(error);
+    }
+
     // Auth is agnostic (DESIGN.md decision 5): most Beakr users have a Claude
     // subscription, not an API key. We inject ANTHROPIC_API_KEY only if the
     // user explicitly set one; otherwise the CLI uses whatever login already
@@ -129,7 +244,8 @@ pub async fn handle_streaming(
     // is present, the run fails fast with "Not logged in" and we classify it
     // as auth_failed with a "log into Claude Code" hint — no server-side
     // pre-flight guess needed. Beakr never handles the subscription credential.
-    let api_key = settings.anthropic_api_key.clone().filter(|k| !k.is_empty());
+    let api_key = crate::secrets::get(app, crate::secrets::ANTHROPIC_API_KEY)?
+        .filter(|k| !k.is_empty());
 
     // The settings override is per-CLI: claude_binary_path must never route a
     // codex run to the claude binary. A codex_binary_path setting can land
@@ -141,19 +257,109 @@ pub async fn handle_streaming(
     };
     let binary = binary::resolve(runner.name(), binary_override)?;
 
+    let permissions = permissions::resolve(
+        settings.permission_profile.as_deref(),
+        params.permission_profile.as_deref(),
+        settings.permission_profiles.as_deref().unwrap_or_default(),
+    )?;
+    log::info!(
+        "Coding run {} uses permission profile '{}' (tools: {}; bash: {})",
+        stream.request_id(),
+        permissions.name,
+        permissions.tools.join(","),
+        if permissions.allows_bash() { permissions.bash_allow.join(", ") } else { "denied".into() },
+    );
+
     let working_dir_display = working_dir.to_string_lossy().into_owned();
+    let mut history_entry = history::RunRecord {
+        request_id: stream.request_id().to_string(),
+        cli: runner.name().to_string(),
+        working_dir: working_dir_display.clone(),
+        prompt: params.prompt.clone(),
+        session_id: params.session_id.clone(),
+        started_at_ms: history::now_ms(),
+        ended_at_ms: 0,
+        outcome: RunOutcome::Failed,
+        error: None,
+        files_changed: Vec::new(),
+        total_cost_usd: None,
+        log_path: None,
+    };
+    // The desktop's own tools for the CLI, on an endpoint that lives as
+    // long as this run. Best-effort: without them the run still works in
+    // its cwd.
+    let desktop_tools = if params.desktop_tools != Some(false)
+        && settings.coding_desktop_tools != Some(false)
+    {
+        match crate::mcp::RunEndpoint::start(
+            state.clone(),
+            stream.request_id(),
+            scoped_folders.to_vec(),
+        )
+        .await
+        {
+            Ok(endpoint) => Some(endpoint),
+            Err(e) => {
+                log::warn!("Coding run {} runs without desktop tools: {e}", stream.request_id());
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let local_ports: Vec<u16> = desktop_tools.iter().map(crate::mcp::RunEndpoint::port).collect();
+
     let spec = RunSpec {
         prompt: params.prompt,
         working_dir,
         session_id: params.session_id,
         api_key,
+        permissions,
+        interactive: params.interactive,
+        relay_permissions: params.relay_permissions,
+        desktop_tools: desktop_tools.as_ref().map(|endpoint| McpEndpoint {
+            url: endpoint.url(),
+            token: endpoint.token().to_string(),
+        }),
     };
 
     let mut cmd = runner.build_command(&binary, &spec);
-    cmd.stdin(std::process::Stdio::null())
+    cmd.stdin(if session.is_some() {
+        std::process::Stdio::piped()
+    } else {
+        std::process::Stdio::null()
+    })
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped());
 
+    // OS-level confinement (Linux). Held until the run ends: it owns the
+    // egress proxy the CLI talks through.
+    let confinement = if settings.coding_sandbox == Some(false) {
+        crate::sandbox::Confinement::unconfined("disabled in Settings")
+    } else {
+        let home = std::path::PathBuf::from(std::env::var("HOME").unwrap_or_default());
+        crate::sandbox::confine(
+            &mut cmd,
+            &crate::sandbox::Policy {
+                working_dir: &spec.working_dir,
+                state_paths: runner.state_paths(&home),
+                allowed_hosts: runner.api_hosts(),
+                local_ports: &local_ports,
+                egress: state.network.current(),
+            },
+        )
+        .await?
+    };
+    let sandbox = sandbox_report(runner, &confinement.report);
+    log::info!(
+        "Coding run {} sandbox: {} (filesystem {}, network {}){}",
+        stream.request_id(),
+        sandbox.kind,
+        sandbox.filesystem,
+        sandbox.network,
+        sandbox.note.as_deref().map(|n| format!(" — {n}")).unwrap_or_default(),
+    );
+
     let mut child = GroupChild::spawn(&mut cmd)
         .map_err(|e| format!("spawn_failed: could not start {}: {e}", runner.name()))?;
     state.processes.register(stream.request_id(), &child);
@@ -165,6 +371,7 @@ pub async fn handle_streaming(
         runner.name(),
         &working_dir_display,
     );
+    history_entry.log_path = run_log.as_ref().map(|l| l.path.display().to_string());
     set_run_ui(
         app,
         state,
@@ -172,16 +379,198 @@ pub async fn handle_streaming(
             request_id: stream.request_id().to_string(),
             working_dir: working_dir_display,
             cli: runner.name().to_string(),
-            started_at_ms: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_millis() as u64)
-                .unwrap_or(0),
+            started_at_ms: history_entry.started_at_ms,
             status: CodingRunStatus::Running,
-            log_path: run_log.as_ref().map(|l| l.path.display().to_string()),
+            log_path: history_entry.log_path.clone(),
         }),
     )
     .await;
 
+    let pumped = pump_run(
+        runner,
+        &mut child,
+        stream,
+        &mut cancel,
+        PumpConfig {
+            timings: RunTimings::for_run(timeout),
+            sandbox,
+            token_allowance,
+            session,
+        },
+        &mut run_log,
+        // Truth-telling (ENG-1552): the child has been signalled but is NOT
+        // confirmed dead. The tray/window show "Stopping…" until cleanup()
+        // clears the run after the process is reaped.
+        || mark_run_stopping(app, state),
+    )
+    .await;
+    cleanup(app, state, stream.request_id()).await;
+
+    let run = match pumped {
+        Ok(run) => run,
+        Err(message) => {
+            if let Some(log) = run_log.as_mut() {
+                log.finish_error(&message);
+            }
+            record_history(app, history_entry.ended(RunOutcome::Failed, Some(&message)));
+            return Err(message);
+        }
+    };
+
+    record_usage(app, runner, stream.request_id(), &run);
+    let usage = run.usage;
+    let cancelled = run.cancelled;
+    history_entry.session_id = run
+        .final_result
+        .as_ref()
+        .and_then(|r| r.session_id.clone())
+        .or_else(|| run.session_id.clone())
+        .or(history_entry.session_id);
+    history_entry.files_changed = run.files_changed.clone();
+    history_entry.total_cost_usd = run.final_result.as_ref().and_then(|r| r.total_cost_usd);
+
+    let outcome = settle_run(runner, run);
+    match &outcome {
+        Ok(_) if cancelled => {
+            if let Some(log) = run_log.as_mut() {
+                log.finish(None, None, None, true);
+            }
+            record_history(app, history_entry.ended(RunOutcome::Cancelled, None));
+            return outcome;
+        }
+        Ok((data, _)) => {
+            if let Some(log) = run_log.as_mut() {
+                log.finish(
+                    data.get("result").and_then(|v| v.as_str()),
+                    data.get("total_cost_usd").and_then(|v| v.as_f64()),
+                    Some(&usage),
+                    false,
+                );
+            }
+            record_history(app, history_entry.ended(RunOutcome::Succeeded, None));
+        }
+        Err(error) => {
+            if let Some(log) = run_log.as_mut() {
+                log.finish_error(error);
+            }
+            record_history(app, history_entry.ended(RunOutcome::Failed, Some(error)));
+        }
+    }
+
+    // Feed the zero-cost readiness signal (ENG-1536): a successful run is
+    // proof of login; an auth_failed one is proof of its absence. Other
+    // failures say nothing about auth and leave the cache alone.
+    match &outcome {
+        Ok(_) => crate::config::record_cli_auth(app, runner.name(), true),
+        Err(e) if e.starts_with("auth_failed:") => {
+            crate::config::record_cli_auth(app, runner.name(), false)
+        }
+        Err(_) => {}
+    }
+    outcome
+}
+
+/// Wall-clock limits for one run. Production values come from
+/// [`RunTimings::for_run`]; the e2e harness shrinks them so the cancel,
+/// timeout and wedged-process paths finish in seconds.
+#[derive(Clone, Copy, Debug)]
+struct RunTimings {
+    /// Ceiling on the whole run (see `run_timeout`).
+    run: Duration,
+    /// After a cancel SIGINT, how long the CLI gets before SIGTERM.
+    cancel_grace: Duration,
+    /// After stdout EOF, how long the process gets to exit before the group
+    /// is SIGKILLed as wedged.
+    exit_wait: Duration,
+    /// How long an interactive session waits for its next turn.
+    session_idle: Duration,
+    /// How long a relayed permission prompt waits for a decision.
+    permission_wait: Duration,
+}
+
+impl RunTimings {
+    fn for_run(run: Duration) -> Self {
+        Self {
+            run,
+            cancel_grace: CANCEL_GRACE,
+            exit_wait: EXIT_WAIT,
+            session_idle: SESSION_IDLE_TIMEOUT,
+            permission_wait: PERMISSION_WAIT,
+        }
+    }
+}
+
+/// Fixed inputs to [`pump_run`] for one run.
+struct PumpConfig {
+    timings: RunTimings,
+    /// Attached to the adapter's `session` chunk, so the engine sees how the
+    /// run is confined.
+    sandbox: SandboxReport,
+    /// Budget tokens (see `Usage::budget_tokens`) the run may spend before
+    /// it is stopped; None when the user set no budget.
+    token_allowance: Option<u64>,
+    /// Set for a run that reads stdin: an interactive session, or a run
+    /// that relays permission prompts.
+    session: Option<SessionInput>,
+}
+
+/// The input side of a run that reads stdin.
+struct SessionInput {
+    /// The first turn, already encoded by the adapter.
+    first_turn: String,
+    input: InputReceiver,
+    /// Interactive: stdin stays open for more turns after the first.
+    takes_turns: bool,
+}
+
+/// Everything the pump learned about a run that reached stdout EOF —
+/// naturally or after a cancel. Turned into the terminal response by
+/// [`settle_run`].
+struct PumpedRun {
+    final_result: Option<RunResult>,
+    cancelled: bool,
+    /// Typed error when the pump itself stopped the run (`run_timeout:`,
+    /// `budget_exceeded:`).
+    aborted: Option<String>,
+    files_changed: Vec<String>,
+    commands: Vec<String>,
+    usage: Usage,
+    model: Option<String>,
+    session_id: Option<String>,
+    /// None when the exit status could not be collected (wait failed, or the
+    /// process wedged after closing stdout and was killed).
+    status: Option<std::process::ExitStatus>,
+    stderr_tail: String,
+}
+
+/// Drive a spawned CLI to completion: stdout lines → normalized chunks →
+/// `response_chunk` frames, cancel or a spent budget → SIGINT + grace +
+/// SIGTERM, deadline → SIGTERM. A run with a session gets its first turn on
+/// stdin, then each queued follow-up and each answer to a relayed prompt,
+/// until nothing more is owed (stdin closes and the CLI exits on its own).
+/// The only error is `spawn_failed:` when the command was built without
+/// piped stdout (or stdin, for a session); a deadline comes back as an
+/// `aborted` run whose group has been SIGTERMed but not reaped.
+/// `on_stopping` fires once, when the run is first told to stop.
+///
+/// Independent of the app handle on purpose: everything UI-facing stays in
+/// [`handle_streaming`], so the e2e harness drives this exact code path
+/// against the mock CLI.
+async fn pump_run(
+    runner: &dyn LocalCodingRunner,
+    child: &mut GroupChild,
+    stream: &ToolStream,
+    cancel: &mut CancelSignal,
+    config: PumpConfig,
+    run_log: &mut Option<run_log::RunLog>,
+    mut on_stopping: impl FnMut(),
+) -> Result<PumpedRun, String> {
+    let PumpConfig {
+        timings,
+        sandbox,
+        token_allowance,
+        session,
+    } = config;
     let stdout = child
         .stdout_take()
         .ok_or("spawn_failed: no stdout from the CLI")?;
@@ -206,49 +595,80 @@ pub async fn handle_streaming(
     // Accumulated audit metadata rides the terminal response too, so a
     // detached consumer that never saw the chunk stream still gets it.
     let mut lines = BufReader::new(stdout).lines();
-    let mut final_result: Option<RunResult> = None;
+    let mut acc = RunAccumulator::default();
     let mut cancelled = false;
-    let mut files_changed: Vec<String> = Vec::new();
-    let mut commands: Vec<String> = Vec::new();
-    let deadline = tokio::time::Instant::now() + timeout;
+    let mut aborted: Option<String> = None;
+    let mut stopping = false;
+    let deadline = tokio::time::Instant::now() + timings.run;
+
+    // Input side: closing stdin is how a run that reads it ends. Turns not
+    // yet answered keep the run "working" for the budget and hold off the
+    // idle timer; pending prompts keep the input open.
+    let mut session = match session {
+        Some(SessionInput {
+            first_turn,
+            input,
+            takes_turns,
+        }) => {
+            let stdin = child
+                .stdin_take()
+                .ok_or("spawn_failed: no stdin for the CLI's input")?;
+            acc.interactive = takes_turns;
+            let session = StdinSession::start(
+                stdin,
+                &first_turn,
+                input,
+                takes_turns,
+                timings.session_idle,
+                timings.permission_wait,
+            )
+            .await
+            .map_err(|e| format!("spawn_failed: could not send the first turn: {e}"))?;
+            Some(session)
+        }
+        None => None,
+    };
 
     loop {
-        tokio::select! {
+        let stop = tokio::select! {
             line = lines.next_line() => {
                 match line {
-                    Ok(Some(line)) => match runner.parse_line(&line) {
-                        ParsedLine::Chunk(chunk) => {
-                            record_chunk(&chunk, &mut files_changed, &mut commands, &mut run_log);
-                            let data = serde_json::to_value(&chunk).unwrap_or_default();
-                            stream.chunk(data).await;
-                        }
-                        ParsedLine::Chunks(chunks) => {
-                            for chunk in &chunks {
-                                record_chunk(chunk, &mut files_changed, &mut commands, &mut run_log);
-                                let data = serde_json::to_value(chunk).unwrap_or_default();
-                                stream.chunk(data).await;
+                    Ok(Some(line)) => {
+                        let answered = acc.turns_answered;
+                        let prompt =
+                            forward_line(runner, &line, stream, &mut acc, run_log, &sandbox).await;
+                        if let Some(session) = session.as_mut() {
+                            if acc.turns_answered > answered {
+                                session.answered();
+                            }
+                            if let Some(request) = prompt {
+                                session.permission_requested(request);
                             }
                         }
-                        ParsedLine::Final(result) => {
-                            // Surface the run's cost through the normal chunk
-                            // stream too (ENG-1552): the engine's accumulator
-                            // folds it into every subsequent card emit. The CLI
-                            // only reports cost here, on its terminal event —
-                            // there are no mid-run ticks to forward.
-                            if let Some(cost) = result.total_cost_usd {
-                                let data = serde_json::to_value(Chunk {
-                                    total_cost_usd: Some(cost),
-                                    ..Chunk::bare("cost")
-                                })
-                                .unwrap_or_default();
-                                stream.chunk(data).await;
+                        // Only a run still working toward an answer is
+                        // stopped. Codex reports usage after its answer, so
+                        // its runs meet the budget at the start only.
+                        let working = !stopping
+                            && session
+                                .as_ref()
+                                .map_or(acc.turns_answered == 0, StdinSession::working);
+                        match token_allowance {
+                            Some(allowance) if working && acc.usage.budget_tokens() > allowance => {
+                                log::info!(
+                                    "Coding run {} crossed its token allowance ({allowance}) — stopping",
+                                    stream.request_id()
+                                );
+                                aborted = Some(format!(
+                                    "budget_exceeded: the run used {} tokens, past the {allowance} \
+                                     left in the local usage budget, and was stopped; the session \
+                                     may be resumable",
+                                    acc.usage.budget_tokens()
+                                ));
+                                true
                             }
-                            final_result = Some(result);
-                            // Keep draining until EOF: the process exit code
-                            // and any trailing events still matter.
+                            _ => false,
                         }
-                        ParsedLine::Ignore => {}
-                    },
+                    }
                     Ok(None) => break, // EOF: child closed stdout.
                     Err(e) => {
                         log::warn!("stdout read error from {}: {e}", runner.name());
@@ -256,37 +676,120 @@ pub async fn handle_streaming(
                     }
                 }
             }
-            _ = cancel.cancelled(), if !cancelled => {
+            event = next_event(&mut session),
+                if session.as_ref().is_some_and(StdinSession::is_open) && !stopping =>
+            {
+                let session = session.as_mut().expect("guarded above");
+                match event {
+                    SessionEvent::Input(RunInput::Prompt(prompt)) if session.takes_turns() => {
+                        let line = runner.encode_turn(&prompt).unwrap_or_default();
+                        match session.send_turn(&line).await {
+                            Ok(()) => {
+                                let chunk = Chunk {
+                                    text: Some(prompt),
+                                    ..Chunk::bare("turn")
+                                };
+                                send_chunk(chunk, stream, &mut acc, run_log).await;
+                            }
+                            Err(e) => {
+                                log::warn!("Could not send a turn to {}: {e}", runner.name());
+                                session.close();
+                            }
+                        }
+                    }
+                    SessionEvent::Input(RunInput::Prompt(_)) => {
+                        log::debug!(
+                            "Turn for {} dropped — it takes no more turns",
+                            stream.request_id()
+                        );
+                    }
+                    SessionEvent::Input(RunInput::End) => session.end_turns(),
+                    SessionEvent::Input(RunInput::Decision {
+                        permission_id,
+                        allow,
+                        message,
+                    }) => {
+                        let decision = PermissionDecision { allow, message };
+                        match session.decide(runner, &permission_id, decision).await {
+                            Some(chunk) => send_chunk(chunk, stream, &mut acc, run_log).await,
+                            None => log::debug!(
+                                "Decision for {} on a prompt no longer pending ({permission_id})",
+                                stream.request_id()
+                            ),
+                        }
+                    }
+                    SessionEvent::Expired(permission_id) => {
+                        let wait = session.permission_wait().as_secs();
+                        log::info!(
+                            "Permission prompt {permission_id} of {} unanswered for {wait}s \
+                             — denying",
+                            stream.request_id()
+                        );
+                        let decision = PermissionDecision {
+                            allow: false,
+                            message: Some(format!(
+                                "No answer from the user within {wait} seconds; not allowed."
+                            )),
+                        };
+                        let chunk = session.decide(runner, &permission_id, decision).await;
+                        if let Some(chunk) = chunk {
+                            send_chunk(chunk, stream, &mut acc, run_log).await;
+                        }
+                    }
+                    SessionEvent::Idle => {
+                        log::info!(
+                            "Coding session {} idle for {}s — closing its input",
+                            stream.request_id(),
+                            timings.session_idle.as_secs()
+                        );
+                        session.close();
+                    }
+                    // The request is finishing.
+                    SessionEvent::Disconnected => session.close(),
+                }
+                false
+            }
+            _ = cancel.cancelled(), if !stopping => {
                 cancelled = true;
                 log::info!("Cancel received for {} — SIGINT to the group", stream.request_id());
-                // Truth-telling (ENG-1552): the child has been signalled but
-                // is NOT confirmed dead. The tray/window show "Stopping…"
-                // until cleanup() clears the run after the process is reaped.
-                mark_run_stopping(app, state).await;
-                child.interrupt();
-                // Give the CLI CANCEL_GRACE to exit cleanly (session stays
-                // resumable), then escalate. The loop keeps draining stdout
-                // meanwhile so a final `result` event can still be captured.
-                let _ = tokio::time::timeout(CANCEL_GRACE, lines.next_line()).await;
-                child.terminate();
+                true
             }
             _ = tokio::time::sleep_until(deadline) => {
                 child.terminate();
-                cleanup(app, state, stream.request_id()).await;
-                let message = format!(
-                    "run_timeout: coding run exceeded {} minutes and was stopped; \
-                     the session may be resumable",
-                    timeout.as_secs() / 60
-                );
-                if let Some(log) = run_log.as_mut() {
-                    log.finish_error(&message);
-                }
-                return Err(message);
+                return Ok(PumpedRun {
+                    aborted: Some(format!(
+                        "run_timeout: coding run exceeded {} minutes and was stopped; \
+                         the session may be resumable",
+                        timings.run.as_secs() / 60
+                    )),
+                    status: None,
+                    stderr_tail: String::new(),
+                    ..acc.into_run(cancelled)
+                });
+            }
+        };
+        if stop {
+            stopping = true;
+            if let Some(session) = session.as_mut() {
+                session.close();
             }
+            on_stopping();
+            child.interrupt();
+            // Give the CLI the cancel grace to exit cleanly (session stays
+            // resumable), then escalate. Lines that arrive inside the grace
+            // are forwarded like any other, so a final `result` event
+            // carrying the session id is not lost.
+            let grace_end = tokio::time::Instant::now() + timings.cancel_grace;
+            while let Ok(Ok(Some(line))) =
+                tokio::time::timeout_at(grace_end, lines.next_line()).await
+            {
+                forward_line(runner, &line, stream, &mut acc, run_log, &sandbox).await;
+            }
+            child.terminate();
         }
     }
 
-    let status = match tokio::time::timeout(Duration::from_secs(10), child.wait()).await {
+    let status = match tokio::time::timeout(timings.exit_wait, child.wait()).await {
         Ok(Ok(status)) => Some(status),
         Ok(Err(e)) => {
             log::warn!("wait() failed for {}: {e}", runner.name());
@@ -299,71 +802,219 @@ pub async fn handle_streaming(
         }
     };
     let stderr_tail = stderr_task.await.unwrap_or_default();
-    cleanup(app, state, stream.request_id()).await;
 
-    if cancelled {
+    Ok(PumpedRun {
+        aborted,
+        status,
+        stderr_tail,
+        ..acc.into_run(cancelled)
+    })
+}
+
+/// The run's audit accumulators plus the newest terminal payload.
+#[derive(Default)]
+struct RunAccumulator {
+    final_result: Option<RunResult>,
+    files_changed: Vec<String>,
+    commands: Vec<String>,
+    /// Running total; what `usage` chunks carry on the wire.
+    usage: Usage,
+    model: Option<String>,
+    /// From the adapter's `session` chunk — Codex's only resume id.
+    session_id: Option<String>,
+    /// Interactive session: each Final answers one turn.
+    interactive: bool,
+    turns_answered: u32,
+    /// Usage of the turns already answered. A CLI-reported total covers
+    /// one turn, so it replaces only the current turn's live sum.
+    answered_usage: Usage,
+}
+
+impl RunAccumulator {
+    fn into_run(self, cancelled: bool) -> PumpedRun {
+        PumpedRun {
+            final_result: self.final_result,
+            cancelled,
+            aborted: None,
+            files_changed: self.files_changed,
+            commands: self.commands,
+            usage: self.usage,
+            model: self.model,
+            session_id: self.session_id,
+            status: None,
+            stderr_tail: String::new(),
+        }
+    }
+}
+
+/// Parse one stdout line and forward whatever it yields to the engine.
+/// Returns the permission prompt the CLI is now blocked on, if any.
+async fn forward_line(
+    runner: &dyn LocalCodingRunner,
+    line: &str,
+    stream: &ToolStream,
+    acc: &mut RunAccumulator,
+    run_log: &mut Option<run_log::RunLog>,
+    sandbox: &SandboxReport,
+) -> Option<runner::PermissionRequest> {
+    let mut prompt = None;
+    let chunks = match runner.parse_line(line) {
+        ParsedLine::Chunk(chunk) => vec![running_total(chunk, &mut acc.usage)],
+        ParsedLine::Chunks(chunks) => chunks
+            .into_iter()
+            .map(|chunk| running_total(chunk, &mut acc.usage))
+            .collect(),
+        ParsedLine::Final(result) => {
+            let mut chunks = Vec::new();
+            // Surface the run's cost through the normal chunk stream too
+            // (ENG-1552): the engine's accumulator folds it into every
+            // subsequent card emit. The CLI only reports cost here, on its
+            // terminal event — there are no mid-run ticks to forward.
+            if let Some(cost) = result.total_cost_usd {
+                chunks.push(Chunk {
+                    total_cost_usd: Some(cost),
+                    ..Chunk::bare("cost")
+                });
+            }
+            // A CLI-reported total supersedes the live sum (which can miss
+            // calls the stream didn't surface).
+            if let Some(total) = result.usage {
+                acc.usage = acc.answered_usage;
+                acc.usage.add(&total);
+                chunks.push(Chunk {
+                    usage: Some(acc.usage),
+                    ..Chunk::bare("usage")
+                });
+            }
+            acc.answered_usage = acc.usage;
+            acc.turns_answered += 1;
+            if acc.interactive {
+                chunks.push(Chunk {
+                    text: result.result.clone(),
+                    session_id: result.session_id.clone(),
+                    ..Chunk::bare("turn_end")
+                });
+            }
+            // Keep draining until EOF: the process exit code and any
+            // trailing events still matter. The newest Final wins.
+            acc.final_result = Some(result);
+            chunks
+        }
+        ParsedLine::PermissionRequest(request) => {
+            let chunk = Chunk {
+                permission: Some(Box::new(request.clone())),
+                ..Chunk::bare("permission_request")
+            };
+            prompt = Some(request);
+            vec![chunk]
+        }
+        ParsedLine::Ignore => return None,
+    };
+    for mut chunk in chunks {
+        attach_sandbox(&mut chunk, sandbox);
+        if chunk.kind == "session" {
+            acc.model = chunk.model.clone();
+            acc.session_id = chunk.session_id.clone();
+        }
+        send_chunk(chunk, stream, acc, run_log).await;
+    }
+    prompt
+}
+
+/// Record one chunk the orchestrator produced and forward it.
+async fn send_chunk(
+    chunk: Chunk,
+    stream: &ToolStream,
+    acc: &mut RunAccumulator,
+    run_log: &mut Option<run_log::RunLog>,
+) {
+    record_chunk(&chunk, &mut acc.files_changed, &mut acc.commands, run_log);
+    let data = serde_json::to_value(&chunk).unwrap_or_default();
+    stream.chunk(data).await;
+}
+
+/// The session's next event; never resolves for a run without one.
+async fn next_event(session: &mut Option<StdinSession>) -> SessionEvent {
+    match session {
+        Some(session) => session.next_event().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Fold an adapter's usage increment into the run total and put the total
+/// on the chunk in its place.
+fn running_total(mut chunk: Chunk, total: &mut Usage) -> Chunk {
+    if let Some(increment) = chunk.usage {
+        total.add(&increment);
+        chunk.usage = Some(*total);
+    }
+    chunk
+}
+
+/// The run's sandbox as the `session` chunk reports it: the OS-level
+/// confinement, plus any profile limit the CLI itself does not enforce.
+fn sandbox_report(runner: &dyn LocalCodingRunner, confinement: &SandboxReport) -> SandboxReport {
+    let mut report = confinement.clone();
+    if !runner.enforces_bash_allow() {
+        report.add_note(&format!(
+            "{} does not enforce the permission profile's bash limits: it runs any command \
+             its own sandbox allows",
+            runner.name()
+        ));
+    }
+    report
+}
+
+fn attach_sandbox(chunk: &mut Chunk, sandbox: &SandboxReport) {
+    if chunk.kind == "session" {
+        chunk.sandbox = Some(Box::new(sandbox.clone()));
+    }
+}
+
+/// Turn a pumped run into the terminal response: the cancelled shape, the
+/// success payload, or a typed failure from the adapter's classifier.
+fn settle_run(
+    runner: &dyn LocalCodingRunner,
+    run: PumpedRun,
+) -> Result<(serde_json::Value, Option<u64>), String> {
+    if run.cancelled {
         // Terminal response still goes out (LSP cancel semantics); carry the
         // session id so the next turn can resume where the user stopped it.
-        if let Some(log) = run_log.as_mut() {
-            log.finish(None, None, true);
-        }
-        let session_id = final_result.and_then(|r| r.session_id);
+        let session_id = run.final_result.and_then(|r| r.session_id);
         return Ok((
             serde_json::json!({
                 "cancelled": true,
                 "session_id": session_id,
                 "cli": runner.name(),
-                "files_changed": files_changed,
-                "commands": commands,
+                "files_changed": run.files_changed,
+                "commands": run.commands,
+                "usage": run.usage,
             }),
             None,
         ));
     }
 
-    let exited_ok = status.map(|s| s.success()).unwrap_or(false);
-    let outcome = match final_result {
+    let exited_ok = run.status.map(|s| s.success()).unwrap_or(false);
+    let exit_code = run.status.and_then(|s| s.code());
+    if let Some(error) = run.aborted {
+        return Err(error);
+    }
+    match run.final_result {
         Some(result) if exited_ok && !result.is_error => {
             let mut data = serde_json::to_value(&result).unwrap_or_default();
             data["cli"] = serde_json::Value::String(runner.name().to_string());
             data["cancelled"] = serde_json::Value::Bool(false);
-            data["files_changed"] = serde_json::to_value(&files_changed).unwrap_or_default();
-            data["commands"] = serde_json::to_value(&commands).unwrap_or_default();
+            data["files_changed"] = serde_json::to_value(&run.files_changed).unwrap_or_default();
+            data["commands"] = serde_json::to_value(&run.commands).unwrap_or_default();
+            data["usage"] = serde_json::to_value(run.usage).unwrap_or_default();
             Ok((data, None))
         }
         Some(result) => Err(runner.classify_failure(
-            status.and_then(|s| s.code()),
-            result.result.as_deref().unwrap_or(&stderr_tail),
+            exit_code,
+            result.result.as_deref().unwrap_or(&run.stderr_tail),
         )),
-        None => Err(runner.classify_failure(status.and_then(|s| s.code()), &stderr_tail)),
-    };
-    match &outcome {
-        Ok((data, _)) => {
-            if let Some(log) = run_log.as_mut() {
-                log.finish(
-                    data.get("result").and_then(|v| v.as_str()),
-                    data.get("total_cost_usd").and_then(|v| v.as_f64()),
-                    false,
-                );
-            }
-        }
-        Err(error) => {
-            if let Some(log) = run_log.as_mut() {
-                log.finish_error(error);
-            }
-        }
-    }
-
-    // Feed the zero-cost readiness signal (ENG-1536): a successful run is
-    // proof of login; an auth_failed one is proof of its absence. Other
-    // failures say nothing about auth and leave the cache alone.
-    match &outcome {
-        Ok(_) => crate::config::record_cli_auth(app, runner.name(), true),
-        Err(e) if e.starts_with("auth_failed:") => {
-            crate::config::record_cli_auth(app, runner.name(), false)
-        }
-        Err(_) => {}
+        None => Err(runner.classify_failure(exit_code, &run.stderr_tail)),
     }
-    outcome
 }
 
 /// Fold one outbound chunk into the run's audit accumulators and its live
@@ -389,6 +1040,74 @@ fn record_chunk(
     }
 }
 
+/// Persist what the run spent next to its log, whatever its outcome — a
+/// failed or stopped run still used tokens. Best-effort like the log itself.
+fn record_usage(
+    app: &AppHandle,
+    runner: &dyn LocalCodingRunner,
+    request_id: &str,
+    run: &PumpedRun,
+) {
+    let total_cost_usd = run.final_result.as_ref().and_then(|r| r.total_cost_usd);
+    if run.usage.is_empty() && total_cost_usd.is_none() {
+        return;
+    }
+    let record = usage::RunUsage {
+        request_id: request_id.to_string(),
+        cli: runner.name().to_string(),
+        model: run.model.clone(),
+        finished_at: chrono::Local::now().to_rfc3339(),
+        usage: run.usage,
+        total_cost_usd,
+    };
+    let written = run_log::runs_dir(app)
+        .and_then(|dir| usage::record(&dir, &record).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        log::warn!("Could not record usage for coding run {request_id}: {e}");
+    }
+}
+
+/// Best-effort, like the log: a history write never changes the outcome.
+fn record_history(app: &AppHandle, entry: history::RunRecord) {
+    let written = run_log::runs_dir(app).and_then(|dir| history::record(&dir, &entry));
+    if let Err(e) = written {
+        log::warn!("Could not record coding run {} in the history: {e}", entry.request_id);
+    }
+}
+
+/// Start a run from the app window instead of the engine — resuming a past
+/// session from the history. Same path as an engine run (slot, sandbox,
+/// budget, log, history); the chunks just have no engine to go to. The
+/// outcome is announced as `coding_run:finished`. Returns the new run's id.
+pub fn start_local_run(app: AppHandle, state: AppState, params: serde_json::Value) -> String {
+    let request_id = format!("local-{:016x}", rand::random::<u64>());
+    let id = request_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let cancel = state.inflight.register(&id);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+        let drain = tokio::spawn(async move { while rx.recv().await.is_some() {} });
+        let stream = ToolStream::new(id.clone(), tx);
+        let scoped_folders = state.scoped_folders.read().await.clone();
+        let outcome =
+            handle_streaming(&app, &state, params, &scoped_folders, &stream, cancel).await;
+        state.inflight.finish(&id);
+        drop(stream);
+        let _ = drain.await;
+        if let Err(e) = &outcome {
+            log::info!("Local coding run {id} failed: {e}");
+        }
+        let _ = app.emit(
+            "coding_run:finished",
+            serde_json::json!({
+                "request_id": id,
+                "ok": outcome.is_ok(),
+                "error": outcome.err(),
+            }),
+        );
+    });
+    request_id
+}
+
 /// Reflect run start/stop in the tray + frontend, and keep the active run
 /// where the tray "Stop run" handler and the app window can reach it.
 async fn set_run_ui(app: &AppHandle, state: &AppState, run: Option<ActiveCodingRun>) {
@@ -407,7 +1126,7 @@ async fn set_run_ui(app: &AppHandle, state: &AppState, run: Option<ActiveCodingR
 
 /// Move the active run to Stopping without touching its identity fields.
 /// No-op if the run has already been cleared (cancel racing a natural exit).
-async fn mark_run_stopping(app: &AppHandle, state: &AppState) {
+fn mark_run_stopping(app: &AppHandle, state: &AppState) {
     let stopping = {
         let mut guard = state
             .active_coding_run
@@ -450,6 +1169,16 @@ mod tests {
         assert_eq!(run_timeout(Some(0)), Duration::from_secs(60));
     }
 
+    #[test]
+    fn a_cli_that_ignores_bash_limits_says_so_in_its_sandbox_report() {
+        let confinement = SandboxReport::unconfined("disabled in Settings");
+        let claude = sandbox_report(&claude::ClaudeRunner, &confinement);
+        assert_eq!(claude, confinement);
+        let codex = sandbox_report(&codex::CodexRunner, &confinement);
+        let note = codex.note.unwrap();
+        assert!(note.starts_with("disabled in Settings; codex does not enforce"), "{note}");
+    }
+
     #[test]
     fn record_chunk_accumulates_files_and_commands() {
         let mut files = Vec::new();
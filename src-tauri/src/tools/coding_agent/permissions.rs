@@ -0,0 +1,214 @@
+//! Permission profiles — named tool surfaces for coding runs.
+//!
+//! A profile says which file tools a run may use and which shell commands,
+//! if any, it may execute. The user picks a profile in Settings; that pick is
+//! the local CEILING. The engine may request a profile per run, and the run
+//! gets the intersection: a request can narrow what the user allowed (an
+//! "analysis" pass on a machine set to "default"), never widen it.
+//!
+//! What no profile can change: the protected-path deny rules and the
+//! WebFetch/WebSearch denial (no network sandbox — an allowed fetch is an
+//! exfil channel). Those ride every run; see `claude.rs`.
+
+use serde::{Deserialize, Serialize};
+
+/// Today's surface: read + edit, no shell. The ceiling when Settings names
+/// none, so existing installs keep their pre-profile behavior.
+pub const DEFAULT_PROFILE: &str = "default";
+/// Read-only: the agent can look but not touch.
+pub const ANALYSIS_PROFILE: &str = "analysis";
+
+/// File tools a profile may grant. Bash is granted only through
+/// `bash_allow` patterns; network tools are never grantable.
+const GRANTABLE_TOOLS: &[&str] = &[
+    "Read",
+    "Glob",
+    "Grep",
+    "Edit",
+    "Write",
+    "MultiEdit",
+    "NotebookEdit",
+];
+const WRITE_TOOLS: &[&str] = &["Edit", "Write", "MultiEdit", "NotebookEdit"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PermissionProfile {
+    pub name: String,
+    /// Granted file tools, a subset of `GRANTABLE_TOOLS`.
+    pub tools: Vec<String>,
+    /// Shell commands the run may execute. A plain entry is a command prefix
+    /// (`pytest` allows `pytest -x tests/`); an entry with `*` is a glob
+    /// (`python scripts/*`). Empty = Bash denied outright.
+    #[serde(default)]
+    pub bash_allow: Vec<String>,
+}
+
+impl PermissionProfile {
+    fn new(name: &str, tools: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            tools: tools.iter().map(|t| t.to_string()).collect(),
+            bash_allow: Vec::new(),
+        }
+    }
+
+    pub fn default_profile() -> Self {
+        Self::new(DEFAULT_PROFILE, &["Read", "Glob", "Grep", "Edit", "Write"])
+    }
+
+    pub fn analysis() -> Self {
+        Self::new(ANALYSIS_PROFILE, &["Read", "Glob", "Grep"])
+    }
+
+    pub fn can_write(&self) -> bool {
+        self.tools.iter().any(|t| WRITE_TOOLS.contains(&t.as_str()))
+    }
+
+    pub fn allows_bash(&self) -> bool {
+        !self.bash_allow.is_empty()
+    }
+
+    /// Narrow this profile to what `ceiling` also grants. Keeps this
+    /// profile's name so logs say what was asked for; bash patterns must
+    /// match a ceiling entry exactly (no glob-subsumption reasoning).
+    pub fn capped_by(&self, ceiling: &PermissionProfile) -> PermissionProfile {
+        PermissionProfile {
+            name: self.name.clone(),
+            tools: self
+                .tools
+                .iter()
+                .filter(|t| ceiling.tools.contains(t))
+                .cloned()
+                .collect(),
+            bash_allow: self
+                .bash_allow
+                .iter()
+                .filter(|p| ceiling.bash_allow.contains(p))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+pub fn builtin_profiles() -> Vec<PermissionProfile> {
+    vec![PermissionProfile::default_profile(), PermissionProfile::analysis()]
+}
+
+/// Reject a user-defined profile the CLIs couldn't express safely.
+pub fn validate(profile: &PermissionProfile) -> Result<(), String> {
+    let name = profile.name.trim();
+    if name.is_empty() {
+        return Err("permission profile name cannot be empty".into());
+    }
+    if builtin_profiles().iter().any(|b| b.name == name) {
+        return Err(format!("'{name}' is a built-in permission profile"));
+    }
+    for tool in &profile.tools {
+        if !GRANTABLE_TOOLS.contains(&tool.as_str()) {
+            return Err(format!(
+                "profile '{name}': '{tool}' is not a grantable tool (allowed: {})",
+                GRANTABLE_TOOLS.join(", ")
+            ));
+        }
+    }
+    for pattern in &profile.bash_allow {
+        // Parens and commas would break out of the `Bash(...)` rule in the
+        // comma-separated --allowedTools list.
+        if pattern.trim().is_empty()
+            || pattern.chars().any(|c| matches!(c, '(' | ')' | ',') || c.is_control())
+        {
+            return Err(format!("profile '{name}': invalid bash pattern '{pattern}'"));
+        }
+    }
+    Ok(())
+}
+
+/// Look a profile up by name: built-ins first, then the user's own.
+pub fn find(name: &str, custom: &[PermissionProfile]) -> Option<PermissionProfile> {
+    builtin_profiles()
+        .into_iter()
+        .chain(custom.iter().cloned())
+        .find(|p| p.name == name)
+}
+
+/// The effective profile for one run: the engine's request (if any) capped
+/// by the user's local ceiling. Unknown names are `bad_params` — a typo must
+/// not silently fall back to a wider surface.
+pub fn resolve(
+    ceiling: Option<&str>,
+    requested: Option<&str>,
+    custom: &[PermissionProfile],
+) -> Result<PermissionProfile, String> {
+    let ceiling_name = ceiling.unwrap_or(DEFAULT_PROFILE);
+    let ceiling = find(ceiling_name, custom).ok_or_else(|| {
+        format!("bad_params: permission profile '{ceiling_name}' in Settings no longer exists")
+    })?;
+    match requested {
+        None => Ok(ceiling),
+        Some(name) => {
+            let requested = find(name, custom)
+                .ok_or_else(|| format!("bad_params: unknown permission profile '{name}'"))?;
+            Ok(requested.capped_by(&ceiling))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pytest_profile() -> PermissionProfile {
+        PermissionProfile {
+            name: "tests".into(),
+            tools: vec!["Read".into(), "Glob".into(), "Grep".into(), "Edit".into()],
+            bash_allow: vec!["pytest".into(), "python scripts/*".into()],
+        }
+    }
+
+    #[test]
+    fn no_ceiling_and_no_request_is_todays_surface() {
+        let p = resolve(None, None, &[]).unwrap();
+        assert_eq!(p, PermissionProfile::default_profile());
+        assert!(p.can_write());
+        assert!(!p.allows_bash());
+    }
+
+    #[test]
+    fn request_is_capped_by_the_local_ceiling() {
+        let custom = [pytest_profile()];
+        // Narrowing is honored as asked.
+        let p = resolve(Some("tests"), Some(ANALYSIS_PROFILE), &custom).unwrap();
+        assert_eq!(p.tools, ["Read", "Glob", "Grep"]);
+        assert!(!p.allows_bash());
+        // Widening is not: a read-only ceiling strips edits and bash.
+        let p = resolve(Some(ANALYSIS_PROFILE), Some("tests"), &custom).unwrap();
+        assert!(!p.can_write());
+        assert!(!p.allows_bash());
+        // Bash patterns survive only where the ceiling grants them too.
+        let p = resolve(Some("tests"), Some("tests"), &custom).unwrap();
+        assert_eq!(p.bash_allow, ["pytest", "python scripts/*"]);
+        let p = resolve(Some(DEFAULT_PROFILE), Some("tests"), &custom).unwrap();
+        assert!(p.bash_allow.is_empty());
+        assert!(!p.tools.contains(&"Write".to_string()));
+    }
+
+    #[test]
+    fn unknown_names_are_bad_params() {
+        assert!(resolve(None, Some("yolo"), &[]).unwrap_err().starts_with("bad_params:"));
+        assert!(resolve(Some("gone"), None, &[]).unwrap_err().starts_with("bad_params:"));
+    }
+
+    #[test]
+    fn validate_rejects_ungrantable_tools_and_unsafe_patterns() {
+        assert!(validate(&pytest_profile()).is_ok());
+        let mut p = pytest_profile();
+        p.tools.push("WebFetch".into());
+        assert!(validate(&p).is_err());
+        let mut p = pytest_profile();
+        p.bash_allow = vec!["pytest),Bash(rm".into()];
+        assert!(validate(&p).is_err());
+        let mut p = pytest_profile();
+        p.name = DEFAULT_PROFILE.into();
+        assert!(validate(&p).is_err(), "built-ins can't be shadowed");
+    }
+}
@@ -0,0 +1,502 @@
+//! Pre-flight checks on a coding run's working directory.
+//!
+//! Scope validation only says the agent *may* work in a folder. These checks
+//! say whether it is a sensible place to let an agent loose: not a whole home
+//! folder or a broad scoped root, writable, not so large the CLI will crawl
+//! it for minutes, with room left on disk, and — where the engine asks for
+//! it — with no uncommitted work the run could tangle with. The report rides
+//! a `preflight` chunk so the engine can show it; any blocking check fails
+//! the run with `preflight_failed:` before the CLI starts.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// A tree this large gets a warning: the CLI indexes and greps it.
+const LARGE_TREE_FILES: usize = 20_000;
+/// Past this, the folder is almost certainly not one project. Counting stops
+/// here too, so the check stays bounded on huge trees.
+const HUGE_TREE_FILES: usize = 100_000;
+/// Free space under which a run is refused / warned about. Builds and
+/// dependency installs the agent triggers need headroom.
+const MIN_FREE_BYTES: u64 = 100 * 1024 * 1024;
+const LOW_FREE_BYTES: u64 = 1024 * 1024 * 1024;
+/// Sensitive files named in the report, at most.
+const SENSITIVE_EXAMPLES: usize = 3;
+const GIT_STATUS_TIMEOUT: Duration = Duration::from_secs(10);
+/// Preflight runs git outside the sandbox, in a folder nobody has vetted, and
+/// a repository's own config can name programs for git to run. These keep
+/// `git status` from starting any: no fsmonitor hook, no hooks, no optional
+/// index writes. Filter drivers are blanked by name (see `check_git`).
+const SAFE_GIT_CONFIG: &[&str] = &[
+    "core.fsmonitor=false",
+    "core.hooksPath=/dev/null",
+    "core.untrackedCache=false",
+];
+
+/// What the engine may ask of the checks, per run.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PreflightOptions {
+    /// Refuse to start on a git tree with uncommitted changes, instead of
+    /// only warning — so the run's diff is exactly the agent's work.
+    #[serde(default)]
+    pub require_clean_tree: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Ok,
+    Warn,
+    Block,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Check {
+    /// "broad_dir" | "scoped_root" | "writable" | "git" | "tree_size" |
+    /// "disk_space" | "sensitive_files"
+    pub name: &'static str,
+    pub severity: Severity,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PreflightReport {
+    pub checks: Vec<Check>,
+}
+
+impl PreflightReport {
+    fn push(&mut self, name: &'static str, severity: Severity, detail: impl Into<String>) {
+        self.checks.push(Check {
+            name,
+            severity,
+            detail: detail.into(),
+        });
+    }
+
+    pub fn blocked(&self) -> bool {
+        self.checks.iter().any(|c| c.severity == Severity::Block)
+    }
+
+    /// The typed error for a blocked run, naming every blocking check.
+    pub fn error(&self) -> Option<String> {
+        let reasons: Vec<&str> = self
+            .checks
+            .iter()
+            .filter(|c| c.severity == Severity::Block)
+            .map(|c| c.detail.as_str())
+            .collect();
+        (!reasons.is_empty()).then(|| format!("preflight_failed: {}", reasons.join("; ")))
+    }
+}
+
+/// Run every check on `working_dir` (already canonical and in scope).
+pub async fn run(
+    working_dir: &Path,
+    scoped_folders: &[String],
+    home: Option<&Path>,
+    options: &PreflightOptions,
+) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    check_location(&mut report, working_dir, scoped_folders, home);
+    check_writable(&mut report, working_dir);
+    check_git(&mut report, working_dir, options).await;
+    let dir = working_dir.to_path_buf();
+    let tree = tokio::task::spawn_blocking(move || walk_tree(&dir))
+        .await
+        .unwrap_or_default();
+    check_tree(&mut report, &tree);
+    check_disk(&mut report, working_dir);
+    report
+}
+
+fn check_location(
+    report: &mut PreflightReport,
+    working_dir: &Path,
+    scoped_folders: &[String],
+    home: Option<&Path>,
+) {
+    let home = home.and_then(|h| h.canonicalize().ok());
+    let broad = working_dir.parent().is_none()
+        || home.as_deref().is_some_and(|h| h.starts_with(working_dir));
+    if broad {
+        report.push(
+            "broad_dir",
+            Severity::Block,
+            format!(
+                "{} is a home folder or above it — pick the project folder to work in",
+                working_dir.display()
+            ),
+        );
+    }
+
+    let is_root = scoped_folders
+        .iter()
+        .filter_map(|f| PathBuf::from(f).canonicalize().ok())
+        .any(|root| root == working_dir);
+    // A repository granted directly is the common, fine case; a granted
+    // folder of many projects is not a place to run an agent at the top of.
+    if is_root && !broad && !working_dir.join(".git").exists() {
+        report.push(
+            "scoped_root",
+            Severity::Block,
+            format!(
+                "{} is a whole scoped folder, not a project inside it — pick a subfolder",
+                working_dir.display()
+            ),
+        );
+    }
+}
+
+fn check_writable(report: &mut PreflightReport, working_dir: &Path) {
+    if !is_writable(working_dir) {
+        report.push(
+            "writable",
+            Severity::Block,
+            format!("{} is not writable by Beakr", working_dir.display()),
+        );
+    }
+}
+
+#[cfg(unix)]
+fn is_writable(dir: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: `path` is a valid NUL-terminated string for the call's duration.
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_writable(dir: &Path) -> bool {
+    std::fs::metadata(dir)
+        .map(|m| !m.permissions().readonly())
+        .unwrap_or(false)
+}
+
+/// `git` in `working_dir` with `SAFE_GIT_CONFIG`, and no system config or
+/// prompts.
+fn git(working_dir: &Path) -> tokio::process::Command {
+    let mut command = tokio::process::Command::new("git");
+    for setting in SAFE_GIT_CONFIG {
+        command.args(["-c", setting]);
+    }
+    command
+        .current_dir(working_dir)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+    command
+}
+
+/// `git status` with every filter driver the repository configures blanked:
+/// git skips a filter whose command is empty, and re-hashing a touched file
+/// would otherwise run its `clean` command. Submodules are not entered.
+async fn safe_git_status(working_dir: &Path) -> std::io::Result<std::process::Output> {
+    let config = git(working_dir)
+        .args([
+            "config",
+            "--name-only",
+            "--get-regexp",
+            r"^filter\..*\.(clean|smudge|process)$",
+        ])
+        .output()
+        .await?;
+    let mut status = git(working_dir);
+    for key in String::from_utf8_lossy(&config.stdout).lines() {
+        status.args(["-c", &format!("{key}=")]);
+    }
+    status
+        .args([
+            "status",
+            "--porcelain",
+            "--untracked-files=normal",
+            "--ignore-submodules=all",
+        ])
+        .output()
+        .await
+}
+
+async fn check_git(report: &mut PreflightReport, working_dir: &Path, options: &PreflightOptions) {
+    let status = safe_git_status(working_dir);
+    let output = match tokio::time::timeout(GIT_STATUS_TIMEOUT, status).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(_)) => {
+            report.push(
+                "git",
+                Severity::Warn,
+                "git is not installed — the run's changes can't be reviewed or undone with git",
+            );
+            return;
+        }
+        Err(_) => {
+            report.push("git", Severity::Warn, "git status did not finish in time");
+            return;
+        }
+    };
+    if !output.status.success() {
+        report.push(
+            "git",
+            Severity::Warn,
+            "not a git repository — the run's changes can't be reviewed or undone with git",
+        );
+        return;
+    }
+    let changed = String::from_utf8_lossy(&output.stdout).lines().count();
+    if changed == 0 {
+        report.push("git", Severity::Ok, "working tree is clean");
+        return;
+    }
+    let severity = if options.require_clean_tree {
+        Severity::Block
+    } else {
+        Severity::Warn
+    };
+    report.push(
+        "git",
+        severity,
+        format!(
+            "{changed} uncommitted change{} — commit or stash them so the run's diff is only the agent's",
+            if changed == 1 { "" } else { "s" }
+        ),
+    );
+}
+
+#[derive(Debug, Default)]
+struct TreeScan {
+    files: usize,
+    /// True when counting stopped at HUGE_TREE_FILES.
+    capped: bool,
+    sensitive: usize,
+    sensitive_examples: Vec<String>,
+}
+
+/// Count files the way the CLIs see the tree (gitignore respected, hidden
+/// files included, `.git` skipped), noting sensitive ones on the way.
+fn walk_tree(dir: &Path) -> TreeScan {
+    let mut scan = TreeScan::default();
+    let walker = ignore::WalkBuilder::new(dir)
+        .hidden(false)
+        .filter_entry(|e| e.file_name() != ".git")
+        .build();
+    for entry in walker.flatten() {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        scan.files += 1;
+        if crate::security::is_denied(Path::new(entry.file_name())) {
+            scan.sensitive += 1;
+            if scan.sensitive_examples.len() < SENSITIVE_EXAMPLES {
+                let rel = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+                scan.sensitive_examples.push(rel.display().to_string());
+            }
+        }
+        if scan.files >= HUGE_TREE_FILES {
+            scan.capped = true;
+            break;
+        }
+    }
+    scan
+}
+
+fn check_tree(report: &mut PreflightReport, tree: &TreeScan) {
+    if tree.capped {
+        report.push(
+            "tree_size",
+            Severity::Block,
+            format!("more than {HUGE_TREE_FILES} files — this looks like many projects, not one"),
+        );
+    } else if tree.files > LARGE_TREE_FILES {
+        report.push(
+            "tree_size",
+            Severity::Warn,
+            format!(
+                "{} files — the agent may be slow to find its way around",
+                tree.files
+            ),
+        );
+    } else {
+        report.push("tree_size", Severity::Ok, format!("{} files", tree.files));
+    }
+    if tree.sensitive > 0 {
+        report.push(
+            "sensitive_files",
+            Severity::Warn,
+            format!(
+                "{} sensitive file{} in the tree (e.g. {}) — the agent can read them",
+                tree.sensitive,
+                if tree.sensitive == 1 { "" } else { "s" },
+                tree.sensitive_examples.join(", ")
+            ),
+        );
+    }
+}
+
+fn check_disk(report: &mut PreflightReport, working_dir: &Path) {
+    let Some(free) = free_bytes(working_dir) else {
+        return;
+    };
+    let mib = free / (1024 * 1024);
+    if free < MIN_FREE_BYTES {
+        report.push(
+            "disk_space",
+            Severity::Block,
+            format!("only {mib} MiB free on this disk"),
+        );
+    } else if free < LOW_FREE_BYTES {
+        report.push(
+            "disk_space",
+            Severity::Warn,
+            format!("{mib} MiB free on this disk"),
+        );
+    }
+}
+
+#[cfg(unix)]
+fn free_bytes(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stat` is a valid out-pointer.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)] // the field widths differ by platform
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn free_bytes(_dir: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let ok = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false);
+        assert!(ok, "git {args:?}");
+    }
+
+    fn severity(report: &PreflightReport, name: &str) -> Option<Severity> {
+        report
+            .checks
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| c.severity)
+    }
+
+    #[tokio::test]
+    async fn home_and_bare_scoped_roots_are_refused() {
+        let home = tempfile::tempdir().unwrap();
+        let home = home.path().canonicalize().unwrap();
+        let scoped = vec![home.display().to_string()];
+
+        let report = run(&home, &scoped, Some(&home), &PreflightOptions::default()).await;
+        assert_eq!(severity(&report, "broad_dir"), Some(Severity::Block));
+        assert!(report.error().unwrap().starts_with("preflight_failed:"));
+
+        let projects = home.join("projects");
+        std::fs::create_dir(&projects).unwrap();
+        let scoped = vec![projects.display().to_string()];
+        let report = run(
+            &projects,
+            &scoped,
+            Some(&home),
+            &PreflightOptions::default(),
+        )
+        .await;
+        assert_eq!(severity(&report, "broad_dir"), None);
+        assert_eq!(severity(&report, "scoped_root"), Some(Severity::Block));
+    }
+
+    #[tokio::test]
+    async fn dirty_tree_warns_unless_a_clean_one_is_required() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().canonicalize().unwrap();
+        git(&repo, &["init", "-q"]);
+        std::fs::write(repo.join("sweep.py"), "print(1)\n").unwrap();
+        std::fs::write(repo.join(".env"), "TOKEN=x\n").unwrap();
+        // A repo granted directly is a fine place to run.
+        let scoped = vec![repo.display().to_string()];
+
+        let report = run(&repo, &scoped, None, &PreflightOptions::default()).await;
+        assert_eq!(severity(&report, "scoped_root"), None);
+        assert_eq!(severity(&report, "git"), Some(Severity::Warn));
+        assert_eq!(severity(&report, "sensitive_files"), Some(Severity::Warn));
+        assert!(!report.blocked(), "{report:?}");
+
+        let strict = PreflightOptions {
+            require_clean_tree: true,
+        };
+        let report = run(&repo, &scoped, None, &strict).await;
+        let error = report.error().unwrap();
+        assert!(
+            error.starts_with("preflight_failed: 2 uncommitted changes"),
+            "{error}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn git_status_runs_none_of_the_repositorys_programs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "-q"]);
+        git(&repo, &["config", "user.email", "lab@example.org"]);
+        git(&repo, &["config", "user.name", "Lab"]);
+        std::fs::write(repo.join("plate.csv"), "A1,0.42\n").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-qm", "plate"]);
+
+        // A cloned repository can carry an fsmonitor hook and, with an
+        // attributes file, a clean filter; status on a touched file runs both.
+        let ran = dir.path().join("ran");
+        let hook = dir.path().join("hook");
+        let touch = format!("touch {}", ran.display());
+        std::fs::write(&hook, format!("#!/bin/sh\n{touch}\n")).unwrap();
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+        git(
+            &repo,
+            &["config", "core.fsmonitor", &hook.display().to_string()],
+        );
+        git(
+            &repo,
+            &["config", "filter.lab.clean", &format!("{touch}; cat")],
+        );
+        std::fs::write(repo.join(".gitattributes"), "* filter=lab\n").unwrap();
+        std::fs::write(repo.join("plate.csv"), "A1,0.43\n").unwrap();
+
+        let mut report = PreflightReport::default();
+        check_git(&mut report, &repo, &PreflightOptions::default()).await;
+        assert_eq!(severity(&report, "git"), Some(Severity::Warn));
+        assert!(!ran.exists());
+    }
+
+    #[test]
+    fn tree_scan_stops_counting_at_the_cap() {
+        let mut report = PreflightReport::default();
+        check_tree(
+            &mut report,
+            &TreeScan {
+                files: HUGE_TREE_FILES,
+                capped: true,
+                ..TreeScan::default()
+            },
+        );
+        assert_eq!(severity(&report, "tree_size"), Some(Severity::Block));
+    }
+}
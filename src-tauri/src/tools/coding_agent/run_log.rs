@@ -1,21 +1,42 @@
-//! Human-readable per-run log powering the local terminal live view.
+//! Human-readable per-run log powering the local live views.
 //!
 //! The CLI runs headlessly (stream-json on stdout), so there is no terminal a
 //! user could watch. This mirrors the parsed chunk stream — the same
 //! normalized vocabulary both adapters emit — into a plain-text log under the
 //! app's log dir, and "Watch in Terminal" opens Terminal.app tailing it.
 //!
+//! The app window watches the same stream without a terminal: every chunk is
+//! also emitted as a `coding_run:chunk` event and appended to
+//! `<request_id>.chunks.ndjson`, which a window opened mid-run reads to
+//! backfill what it missed. Each entry carries a sequence number so the
+//! window can stitch backfill and live events without gaps or repeats.
+//!
 //! Logging is strictly best-effort: any I/O failure disables the log and never
 //! disturbs the run. Content mirrors what already streams to the engine
 //! (assistant narration, tool activity, changed files) — never CLI internals
 //! or file contents beyond what the run card shows.
 
-use std::io::Write;
+use std::io::{BufRead, Write};
 use std::path::{Path, PathBuf};
 
-use tauri::{AppHandle, Manager};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
 
 use super::runner::Chunk;
+use super::usage::Usage;
+
+const CHUNKS_SUFFIX: &str = ".chunks.ndjson";
+/// Backfill returns at most this many of a run's newest chunks. Text arrives
+/// in small fragments, so a long run can log tens of thousands.
+const BACKFILL_MAX_CHUNKS: usize = 5000;
+
+/// `<app-log-dir>/coding-runs` — run logs and their usage records.
+pub fn runs_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_log_dir()
+        .map(|base| base.join("coding-runs"))
+        .map_err(|e| format!("no app log dir: {e}"))
+}
 
 pub struct RunLog {
     file: std::fs::File,
@@ -23,6 +44,21 @@ pub struct RunLog {
     /// True while the last write was a text fragment without a trailing
     /// newline; structural markers then break the line first.
     mid_line: bool,
+    request_id: String,
+    /// Where `coding_run:chunk` events go; None in tests.
+    app: Option<AppHandle>,
+    /// The structured sidecar backfill reads; None once a write failed.
+    chunks: Option<std::fs::File>,
+    seq: u64,
+}
+
+/// One chunk as the app window sees it, live (`coding_run:chunk`) and in
+/// backfill alike.
+#[derive(Serialize)]
+struct LiveChunk<'a> {
+    request_id: &'a str,
+    seq: u64,
+    chunk: &'a Chunk,
 }
 
 impl RunLog {
@@ -34,10 +70,10 @@ impl RunLog {
         cli: &str,
         working_dir: &str,
     ) -> Option<RunLog> {
-        let dir = match app.path().app_log_dir() {
-            Ok(base) => base.join("coding-runs"),
+        let dir = match runs_dir(app) {
+            Ok(dir) => dir,
             Err(e) => {
-                log::warn!("run log disabled: no app log dir: {e}");
+                log::warn!("run log disabled: {e}");
                 return None;
             }
         };
@@ -53,10 +89,18 @@ impl RunLog {
                 return None;
             }
         };
+        let chunks_path = dir.join(format!("{request_id}{CHUNKS_SUFFIX}"));
+        let chunks = std::fs::File::create(&chunks_path)
+            .map_err(|e| log::warn!("run backfill disabled: {}: {e}", chunks_path.display()))
+            .ok();
         let mut this = RunLog {
             file,
             path,
             mid_line: false,
+            request_id: request_id.to_string(),
+            app: Some(app.clone()),
+            chunks,
+            seq: 0,
         };
         let started = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
         this.write_str(&format!(
@@ -65,8 +109,10 @@ impl RunLog {
         Some(this)
     }
 
-    /// Mirror one normalized chunk into the log, tail-friendly (flushed).
+    /// Mirror one normalized chunk into the log, tail-friendly (flushed),
+    /// and on to the app window.
     pub fn chunk(&mut self, chunk: &Chunk) {
+        self.stream(chunk);
         match chunk.kind {
             "text" => {
                 if let Some(text) = &chunk.text {
@@ -102,14 +148,53 @@ impl RunLog {
                     self.write_str(&format!("[session started: {cli} · {model}]\n"));
                 }
             }
-            // "command" duplicates the "tool" activity label for Codex, and
-            // "cost" is covered by the footer.
+            "turn" => {
+                if let Some(prompt) = &chunk.text {
+                    self.break_line();
+                    self.write_str(&format!("\n> {prompt}\n\n"));
+                }
+            }
+            "turn_end" => {
+                self.break_line();
+                self.write_str("[turn finished]\n");
+            }
+            "permission_request" => {
+                if let Some(request) = &chunk.permission {
+                    // A Bash prompt reads best as its command line.
+                    let what = match request.input["command"].as_str() {
+                        Some(command) => command.to_string(),
+                        None => request.input.to_string(),
+                    };
+                    self.break_line();
+                    self.write_str(&format!("[asks to use {}: {what}]\n", request.tool));
+                }
+            }
+            "permission_decision" => {
+                if let Some(decision) = &chunk.decision {
+                    self.break_line();
+                    match (&decision.message, decision.allow) {
+                        (_, true) => self.write_str("[allowed]\n"),
+                        (Some(message), false) => {
+                            self.write_str(&format!("[denied: {message}]\n"))
+                        }
+                        (None, false) => self.write_str("[denied]\n"),
+                    }
+                }
+            }
+            // "command" duplicates the "tool" activity label for Codex;
+            // "cost" and "usage" are covered by the footer.
             _ => {}
         }
     }
 
     /// Terminal footer for a successful (or device-cancelled) run.
-    pub fn finish(&mut self, answer: Option<&str>, cost_usd: Option<f64>, cancelled: bool) {
+    pub fn finish(
+        &mut self,
+        answer: Option<&str>,
+        cost_usd: Option<f64>,
+        usage: Option<&Usage>,
+        cancelled: bool,
+    ) {
         self.break_line();
         if cancelled {
             self.write_str("\n== run stopped by the user ==\n");
@@ -125,6 +210,15 @@ impl RunLog {
         if let Some(cost) = cost_usd {
             self.write_str(&format!("cost: ${cost:.4}\n"));
         }
+        if let Some(usage) = usage.filter(|u| !u.is_empty()) {
+            self.write_str(&format!(
+                "tokens: {} in · {} out · {} cache read · {} cache write\n",
+                usage.input_tokens,
+                usage.output_tokens,
+                usage.cache_read_tokens,
+                usage.cache_write_tokens
+            ));
+        }
     }
 
     /// Terminal footer for a failed run (typed error string).
@@ -133,6 +227,31 @@ impl RunLog {
         self.write_str(&format!("\n== run failed ==\n{error}\n"));
     }
 
+    /// Every chunk kind goes to the window — it renders cost and usage live,
+    /// which the text log leaves to its footer.
+    fn stream(&mut self, chunk: &Chunk) {
+        self.seq += 1;
+        let entry = LiveChunk {
+            request_id: &self.request_id,
+            seq: self.seq,
+            chunk,
+        };
+        if let Some(file) = self.chunks.as_mut() {
+            let mut line = serde_json::to_vec(&entry).unwrap_or_default();
+            line.push(b'\n');
+            if file.write_all(&line).and_then(|()| file.flush()).is_err() {
+                log::warn!(
+                    "run backfill disabled for {}: write failed",
+                    self.request_id
+                );
+                self.chunks = None;
+            }
+        }
+        if let Some(app) = &self.app {
+            let _ = app.emit("coding_run:chunk", &entry);
+        }
+    }
+
     fn write_text_fragment(&mut self, text: &str) {
         self.mid_line = !text.ends_with('\n');
         let _ = self.file.write_all(text.as_bytes());
@@ -153,6 +272,34 @@ impl RunLog {
     }
 }
 
+/// The chunks a run has streamed so far (or in total, once it ended), as
+/// `coding_run:chunk` payloads in order — the newest `BACKFILL_MAX_CHUNKS`
+/// of them. A run that logged nothing backfills as empty.
+pub fn backfill(dir: &Path, request_id: &str) -> Result<Vec<serde_json::Value>, String> {
+    if !super::history::valid_request_id(request_id) {
+        return Err(format!("bad_params: invalid run id '{request_id}'"));
+    }
+    let path = dir.join(format!("{request_id}{CHUNKS_SUFFIX}"));
+    let file = match std::fs::File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("could not read {}: {e}", path.display())),
+    };
+    // The run may be mid-write: a torn last line just fails to parse and is
+    // picked up live instead.
+    let mut entries: std::collections::VecDeque<serde_json::Value> = Default::default();
+    for line in std::io::BufReader::new(file).lines() {
+        let Ok(line) = line else { break };
+        if let Ok(entry) = serde_json::from_str(&line) {
+            if entries.len() == BACKFILL_MAX_CHUNKS {
+                entries.pop_front();
+            }
+            entries.push_back(entry);
+        }
+    }
+    Ok(entries.into())
+}
+
 /// Open Terminal.app tailing `log_path`, via a generated `.command` file —
 /// the standard macOS way to hand Terminal a command without scripting
 /// permissions. The wrapper lives next to the log and is overwritten per use.
@@ -208,6 +355,10 @@ mod tests {
             file: std::fs::File::create(dir.join("t.log")).unwrap(),
             path: dir.join("t.log"),
             mid_line: false,
+            request_id: "t".into(),
+            app: None,
+            chunks: std::fs::File::create(dir.join("t.chunks.ndjson")).ok(),
+            seq: 0,
         }
     }
 
@@ -246,12 +397,24 @@ mod tests {
     fn finish_writes_answer_and_cost() {
         let dir = tempfile::tempdir().unwrap();
         let mut log = log_in(dir.path());
-        log.finish(Some("Done - 3 tests fixed."), Some(0.1234), false);
+        let usage = Usage {
+            input_tokens: 1840,
+            output_tokens: 312,
+            cache_read_tokens: 12288,
+            cache_write_tokens: 0,
+        };
+        log.finish(
+            Some("Done - 3 tests fixed."),
+            Some(0.1234),
+            Some(&usage),
+            false,
+        );
         let content = read(dir.path());
         assert!(content.contains("== run finished =="));
         // The answer gets its own line — cost must not concatenate onto it
         // (live-verified nit: "...agent.cost: $" without this).
-        assert!(content.contains("Done - 3 tests fixed.\ncost: $0.1234"));
+        assert!(content.contains("Done - 3 tests fixed.\ncost: $0.1234\n"));
+        assert!(content.contains("tokens: 1840 in · 312 out · 12288 cache read · 0 cache write\n"));
     }
 
     #[test]
@@ -259,9 +422,43 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let mut log = log_in(dir.path());
         log.finish_error("auth_failed: not logged in");
-        log.finish(None, None, true);
+        log.finish(None, None, None, true);
         let content = read(dir.path());
         assert!(content.contains("== run failed ==\nauth_failed: not logged in"));
         assert!(content.contains("== run stopped by the user =="));
     }
+
+    #[test]
+    fn chunks_backfill_in_order_with_sequence_numbers() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = log_in(dir.path());
+        log.chunk(&Chunk {
+            text: Some("Reading".into()),
+            ..chunk("text")
+        });
+        log.chunk(&Chunk {
+            total_cost_usd: Some(0.02),
+            ..chunk("cost")
+        });
+        // A torn trailing write (the run is still going) is skipped.
+        let mut sidecar = std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("t.chunks.ndjson"))
+            .unwrap();
+        sidecar.write_all(b"{\"request_id\":\"t\",\"se").unwrap();
+
+        let entries = backfill(dir.path(), "t").unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["seq"], 1);
+        assert_eq!(entries[0]["chunk"]["text"], "Reading");
+        // Kinds the text log leaves out still reach the window.
+        assert_eq!(entries[1]["chunk"]["kind"], "cost");
+        assert_eq!(
+            backfill(dir.path(), "never-ran").unwrap(),
+            Vec::<serde_json::Value>::new()
+        );
+        assert!(backfill(dir.path(), "../t")
+            .unwrap_err()
+            .starts_with("bad_params:"));
+    }
 }
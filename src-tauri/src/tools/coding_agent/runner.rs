@@ -19,13 +19,67 @@ pub struct RunSpec {
     /// User's API key for the CLI's backend, when that CLI needs one
     /// (Claude Code under the v1 auth decision; Codex uses its own login).
     pub api_key: Option<String>,
+    /// Effective tool surface, already capped by the user's local ceiling
+    /// (see `permissions::resolve`).
+    pub permissions: super::permissions::PermissionProfile,
+    /// Keep the CLI alive for follow-up turns fed on stdin. `prompt` is then
+    /// the first turn, which the orchestrator writes (see `encode_turn`)
+    /// rather than the command line carrying it.
+    pub interactive: bool,
+    /// Ask before using a tool outside the profile instead of refusing it:
+    /// the CLI's permission prompts come out as `PermissionRequest`s and the
+    /// orchestrator writes the web user's answer back on stdin. Like
+    /// `interactive`, the prompt then goes over stdin.
+    pub relay_permissions: bool,
+    /// The desktop's own tools (files in the scoped folders, Benchling),
+    /// served to this run over MCP. None = the CLI sees only its cwd.
+    pub desktop_tools: Option<McpEndpoint>,
+}
+
+/// A per-run MCP server the CLI is pointed at (see `mcp::RunEndpoint`).
+#[derive(Debug, Clone)]
+pub struct McpEndpoint {
+    /// Streamable-HTTP URL on loopback.
+    pub url: String,
+    /// Bearer token; valid for this run only.
+    pub token: String,
+}
+
+/// The name the desktop tools are registered under in the CLI, so Claude
+/// sees them as `mcp__beakr__read_file` and so on.
+pub const MCP_SERVER_NAME: &str = "beakr";
+
+/// Env var carrying the desktop-tools bearer token: the CLI's MCP config
+/// names it instead of holding the token, so the token stays out of argv.
+pub const MCP_TOKEN_ENV: &str = "BEAKR_MCP_TOKEN";
+
+/// A CLI asking to use a tool the run's profile does not already allow.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PermissionRequest {
+    /// The CLI's own id for the prompt; the decision must echo it.
+    pub id: String,
+    pub tool: String,
+    /// The tool call as the CLI would make it (e.g. `{"command": "..."}`).
+    pub input: serde_json::Value,
+}
+
+/// The answer to a `PermissionRequest` — the web user's, or a deny when
+/// nobody answered in time.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PermissionDecision {
+    pub allow: bool,
+    /// Why, for a deny; the CLI shows it to the model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 /// One normalized streaming chunk, forwarded to the engine as
 /// `response_chunk.data`. Kept deliberately small and additive.
 #[derive(Debug, Serialize, PartialEq)]
 pub struct Chunk {
-    /// "session" | "text" | "tool" | "file_changed" | "cost" | "status"
+    /// "session" | "text" | "tool" | "file_changed" | "cost" | "usage" |
+    /// "status" | "preflight" | "turn" | "turn_end" | "permission_request" |
+    /// "permission_decision"
     pub kind: &'static str,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<String>,
@@ -48,6 +102,11 @@ pub struct Chunk {
     /// ChatGPT-plan users the auth decision targets (DESIGN.md decision 5).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total_cost_usd: Option<f64>,
+    /// For "usage": token counts. Adapters emit the increment their CLI just
+    /// reported; the orchestrator replaces it with the run's running total
+    /// before forwarding, so on the wire it reads like "cost".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<super::usage::Usage>,
     /// For "command": the shell command a sandboxed Codex run executed.
     /// The engine's audit row collects these (its accumulator already reads
     /// `command`); Claude never emits it while Bash stays denied.
@@ -61,6 +120,23 @@ pub struct Chunk {
     pub cli: Option<&'static str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
+    /// For "session": the OS-level sandbox the run is confined by (Linux),
+    /// or why there is none. Attached by the orchestrator, not the adapter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<Box<crate::sandbox::SandboxReport>>,
+    /// For "preflight": the working-directory checks, sent before the CLI
+    /// starts (and before a `preflight_failed` error, so the engine can
+    /// show which checks blocked).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preflight: Option<Box<super::preflight::PreflightReport>>,
+    /// For "permission_request": what the CLI wants to run; the engine
+    /// answers with a `permission_decision` naming its `id`. Also on the
+    /// matching "permission_decision" chunk, so each reads on its own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permission: Option<Box<PermissionRequest>>,
+    /// For "permission_decision": what the CLI was told.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decision: Option<PermissionDecision>,
 }
 
 impl Chunk {
@@ -74,9 +150,14 @@ impl Chunk {
             path: None,
             change: None,
             total_cost_usd: None,
+            usage: None,
             command: None,
             cli: None,
             model: None,
+            sandbox: None,
+            preflight: None,
+            permission: None,
+            decision: None,
         }
     }
 }
@@ -91,6 +172,10 @@ pub struct RunResult {
     pub result: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total_cost_usd: Option<f64>,
+    /// The CLI's own total for the run, when its terminal event carries one
+    /// (Claude). Replaces the orchestrator's running sum.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<super::usage::Usage>,
     pub is_error: bool,
 }
 
@@ -105,6 +190,8 @@ pub enum ParsedLine {
     /// The run's terminal payload (the CLI's own "result" event). The process
     /// may still take a moment to exit; the orchestrator keeps draining.
     Final(RunResult),
+    /// The CLI is waiting for a yes/no on a tool call (relay mode only).
+    PermissionRequest(PermissionRequest),
     /// Line understood but not worth forwarding (or unknown type — both CLIs
     /// document their stream schemas as additive, so unknowns are skipped,
     /// never errors).
@@ -125,4 +212,41 @@ pub trait LocalCodingRunner: Send + Sync {
     /// Map a nonzero-exit / stderr tail to a typed, user-facing error.
     /// Stable `code:` prefixes — the engine matches on them.
     fn classify_failure(&self, exit_code: Option<i32>, stderr_tail: &str) -> String;
+
+    /// Where the CLI keeps its own login, sessions and caches under `home` —
+    /// the only writable places outside the working dir in a sandboxed run.
+    fn state_paths(&self, home: &std::path::Path) -> Vec<std::path::PathBuf>;
+
+    /// The model API hosts a sandboxed run may reach (443 only).
+    fn api_hosts(&self) -> &'static [&'static str];
+
+    /// One user turn as a line of the CLI's streaming-input format, for an
+    /// interactive session. None when the CLI has no such input — follow-ups
+    /// then go through a fresh process resuming the session.
+    fn encode_turn(&self, _prompt: &str) -> Option<String> {
+        None
+    }
+
+    /// Whether the CLI honors a profile's `bash_allow`. One that does not
+    /// runs any command its own sandbox permits, and the run's sandbox
+    /// report says so.
+    fn enforces_bash_allow(&self) -> bool {
+        true
+    }
+
+    /// Whether the CLI can hand its permission prompts to the orchestrator
+    /// (see `RunSpec::relay_permissions`).
+    fn relays_permissions(&self) -> bool {
+        false
+    }
+
+    /// The answer to a relayed permission prompt, as a line of the CLI's
+    /// streaming-input format.
+    fn encode_permission_decision(
+        &self,
+        _request: &PermissionRequest,
+        _decision: &PermissionDecision,
+    ) -> Option<String> {
+        None
+    }
 }
@@ -0,0 +1,203 @@
+//! The CLI's stdin during a run that reads it: the turns of an interactive
+//! session and the answers to relayed permission prompts.
+//!
+//! Closing stdin is how such a run ends, so the pipe stays open while the
+//! CLI is still owed something — an answer to a turn, a decision on a
+//! prompt — or more turns may come. The pump owns the stdout side; this
+//! only decides what goes in and when the input closes.
+
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::process::ChildStdin;
+use tokio::time::Instant;
+
+use super::runner::{Chunk, LocalCodingRunner, PermissionDecision, PermissionRequest};
+use crate::ws::inflight::{InputReceiver, RunInput};
+
+/// What the session is waiting on next, as seen by the pump.
+pub(super) enum SessionEvent {
+    /// Input from the engine (a turn, an end, a decision).
+    Input(RunInput),
+    /// The input queue closed — the request is finishing.
+    Disconnected,
+    /// No turn arrived within the idle timeout.
+    Idle,
+    /// Nobody answered the oldest pending prompt in time.
+    Expired(String),
+}
+
+pub(super) struct StdinSession {
+    pipe: Option<ChildStdin>,
+    input: Option<InputReceiver>,
+    /// More turns may still come: an interactive session the engine has
+    /// not ended.
+    takes_turns: bool,
+    /// Turns written but not yet answered with a result.
+    unanswered: u32,
+    idle_timeout: Duration,
+    idle_deadline: Option<Instant>,
+    permission_wait: Duration,
+    /// Prompts relayed to the engine, oldest first, with their deadlines.
+    pending: Vec<(PermissionRequest, Instant)>,
+}
+
+impl StdinSession {
+    /// Write the first turn and start taking input.
+    pub async fn start(
+        mut pipe: ChildStdin,
+        first_turn: &str,
+        input: InputReceiver,
+        takes_turns: bool,
+        idle_timeout: Duration,
+        permission_wait: Duration,
+    ) -> std::io::Result<Self> {
+        write_line(&mut pipe, first_turn).await?;
+        Ok(Self {
+            pipe: Some(pipe),
+            input: Some(input),
+            takes_turns,
+            unanswered: 1,
+            idle_timeout,
+            idle_deadline: None,
+            permission_wait,
+            pending: Vec::new(),
+        })
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.pipe.is_some()
+    }
+
+    /// Whether the CLI is working toward an answer (for the budget).
+    pub fn working(&self) -> bool {
+        self.unanswered > 0
+    }
+
+    /// Whether more turns may come (ignored input otherwise).
+    pub fn takes_turns(&self) -> bool {
+        self.takes_turns
+    }
+
+    /// The next thing to act on. Never resolves once the input is closed.
+    pub async fn next_event(&mut self) -> SessionEvent {
+        let expiry = self.pending.first().map(|(_, deadline)| *deadline);
+        tokio::select! {
+            input = recv(&mut self.input) => match input {
+                Some(input) => SessionEvent::Input(input),
+                None => SessionEvent::Disconnected,
+            },
+            _ = sleep_until(self.idle_deadline) => SessionEvent::Idle,
+            _ = sleep_until(expiry) => {
+                SessionEvent::Expired(self.pending[0].0.id.clone())
+            }
+        }
+    }
+
+    /// Another user turn, already encoded by the adapter.
+    pub async fn send_turn(&mut self, line: &str) -> std::io::Result<()> {
+        let pipe = self.pipe.as_mut().ok_or(std::io::ErrorKind::BrokenPipe)?;
+        write_line(pipe, line).await?;
+        self.unanswered += 1;
+        self.idle_deadline = None;
+        Ok(())
+    }
+
+    /// A turn was answered (the CLI's result event).
+    pub fn answered(&mut self) {
+        self.unanswered = self.unanswered.saturating_sub(1);
+        self.arm_idle();
+        self.close_if_done();
+    }
+
+    /// The engine ended the session: close once nothing is owed.
+    pub fn end_turns(&mut self) {
+        self.takes_turns = false;
+        self.idle_deadline = None;
+        self.close_if_done();
+    }
+
+    /// A prompt went out to the engine; it must be answered within the
+    /// permission wait.
+    pub fn permission_requested(&mut self, request: PermissionRequest) {
+        self.idle_deadline = None;
+        let deadline = Instant::now() + self.permission_wait;
+        self.pending.push((request, deadline));
+    }
+
+    /// Answer a pending prompt. Returns the `permission_decision` chunk to
+    /// forward, or None when `permission_id` is not pending (answered,
+    /// expired, or never asked).
+    pub async fn decide(
+        &mut self,
+        runner: &dyn LocalCodingRunner,
+        permission_id: &str,
+        decision: PermissionDecision,
+    ) -> Option<Chunk> {
+        let index = self.pending.iter().position(|(r, _)| r.id == permission_id)?;
+        let (request, _) = self.pending.remove(index);
+        let line = runner
+            .encode_permission_decision(&request, &decision)
+            .unwrap_or_default();
+        if let Some(pipe) = self.pipe.as_mut() {
+            if let Err(e) = write_line(pipe, &line).await {
+                log::warn!("Could not answer a permission prompt of {}: {e}", runner.name());
+                self.close();
+            }
+        }
+        self.arm_idle();
+        self.close_if_done();
+        Some(Chunk {
+            permission: Some(Box::new(request)),
+            decision: Some(decision),
+            ..Chunk::bare("permission_decision")
+        })
+    }
+
+    /// How long an unanswered prompt waits, for the timeout deny message.
+    pub fn permission_wait(&self) -> Duration {
+        self.permission_wait
+    }
+
+    /// Close the CLI's input now; it finishes what it has and exits.
+    pub fn close(&mut self) {
+        self.pipe = None;
+        self.input = None;
+        self.takes_turns = false;
+        self.idle_deadline = None;
+        self.pending.clear();
+    }
+
+    fn arm_idle(&mut self) {
+        if self.takes_turns && self.unanswered == 0 && self.pending.is_empty() {
+            self.idle_deadline = Some(Instant::now() + self.idle_timeout);
+        }
+    }
+
+    fn close_if_done(&mut self) {
+        if !self.takes_turns && self.unanswered == 0 && self.pending.is_empty() {
+            self.close();
+        }
+    }
+}
+
+/// One encoded line onto the CLI's stdin.
+async fn write_line(stdin: &mut ChildStdin, line: &str) -> std::io::Result<()> {
+    stdin.write_all(line.trim_end().as_bytes()).await?;
+    stdin.write_all(b"\n").await?;
+    stdin.flush().await
+}
+
+async fn recv(input: &mut Option<InputReceiver>) -> Option<RunInput> {
+    match input {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
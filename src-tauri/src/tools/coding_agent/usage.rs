@@ -0,0 +1,308 @@
+//! Token usage accounting across coding runs.
+//!
+//! Both CLIs report token counts in their streams: Claude per API call (the
+//! `message_start`/`message_delta` stream events) plus an authoritative total
+//! on its `result`, Codex per turn on `turn.completed`. The adapters turn
+//! those into `usage` chunks; the orchestrator keeps the run's running total,
+//! persists it next to the run log as `<request_id>.usage.json`, and checks
+//! it against the user's local budget.
+//!
+//! Tokens, not dollars, are the common unit: only Claude reports cost, and
+//! only its API-key users actually pay per token (see `codex.rs`).
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Datelike, Local};
+use serde::{Deserialize, Serialize};
+
+const RECORD_SUFFIX: &str = ".usage.json";
+
+/// Token counts for one run (or one increment of it). Normalized across the
+/// CLIs: `input_tokens` is uncached input only — Codex counts cache hits
+/// inside its input total, Claude reports them separately.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_write_tokens: u64,
+}
+
+impl Usage {
+    pub fn add(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Usage::default()
+    }
+
+    /// What a budget counts. Cache reads are left out: they are billed at a
+    /// fraction of input and dominate long sessions, so counting them would
+    /// make a cap trip on re-reading context rather than on new work.
+    pub fn budget_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_write_tokens
+    }
+}
+
+/// The persisted usage of one finished run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunUsage {
+    pub request_id: String,
+    pub cli: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// RFC 3339, local offset — periods are the user's calendar days.
+    pub finished_at: String,
+    pub usage: Usage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_cost_usd: Option<f64>,
+}
+
+/// One CLI's usage over a period.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageTotals {
+    pub runs: u32,
+    #[serde(flatten)]
+    pub usage: Usage,
+    /// Sum of the costs the CLI reported (Claude only). None when no run in
+    /// the period reported one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_cost_usd: Option<f64>,
+}
+
+/// Usage for the current day and month, keyed by CLI. Rides `register` and
+/// backs the Settings usage panel.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageSummary {
+    /// "YYYY-MM-DD" / "YYYY-MM" in local time.
+    pub day: String,
+    pub month: String,
+    pub today: BTreeMap<String, UsageTotals>,
+    pub this_month: BTreeMap<String, UsageTotals>,
+}
+
+impl UsageSummary {
+    fn budget_tokens(period: &BTreeMap<String, UsageTotals>) -> u64 {
+        period.values().map(|t| t.usage.budget_tokens()).sum()
+    }
+}
+
+/// Local caps on token usage across all CLIs. Unset = uncapped.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageBudget {
+    #[serde(default)]
+    pub daily_tokens: Option<u64>,
+    #[serde(default)]
+    pub monthly_tokens: Option<u64>,
+}
+
+impl UsageBudget {
+    /// Tokens left before the tighter cap trips; None when uncapped.
+    pub fn remaining(&self, summary: &UsageSummary) -> Option<u64> {
+        let daily = self
+            .daily_tokens
+            .map(|cap| cap.saturating_sub(UsageSummary::budget_tokens(&summary.today)));
+        let monthly = self
+            .monthly_tokens
+            .map(|cap| cap.saturating_sub(UsageSummary::budget_tokens(&summary.this_month)));
+        match (daily, monthly) {
+            (Some(d), Some(m)) => Some(d.min(m)),
+            (d, m) => d.or(m),
+        }
+    }
+
+    /// Refuse to start a run once a cap is used up. Returns the allowance
+    /// the run may spend before it is stopped.
+    pub fn check(&self, summary: &UsageSummary) -> Result<Option<u64>, String> {
+        match self.remaining(summary) {
+            Some(0) => Err(format!(
+                "budget_exceeded: the local usage budget is used up ({}); \
+                 raise it in Settings or wait for the next period",
+                self.describe()
+            )),
+            remaining => Ok(remaining),
+        }
+    }
+
+    fn describe(&self) -> String {
+        let mut caps = Vec::new();
+        if let Some(daily) = self.daily_tokens {
+            caps.push(format!("{daily} tokens/day"));
+        }
+        if let Some(monthly) = self.monthly_tokens {
+            caps.push(format!("{monthly} tokens/month"));
+        }
+        caps.join(", ")
+    }
+}
+
+/// Persist one run's usage into `dir` (the run-log directory).
+pub fn record(dir: &Path, run: &RunUsage) -> std::io::Result<()> {
+    let path = record_path(dir, &run.request_id)?;
+    std::fs::create_dir_all(dir)?;
+    let json = serde_json::to_vec_pretty(run).map_err(std::io::Error::other)?;
+    std::fs::write(path, json)
+}
+
+/// The engine names the run; as a file name it must stay inside `dir`.
+fn record_path(dir: &Path, request_id: &str) -> std::io::Result<PathBuf> {
+    if !super::history::valid_request_id(request_id) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("bad_params: invalid run id '{request_id}'"),
+        ));
+    }
+    Ok(dir.join(format!("{request_id}{RECORD_SUFFIX}")))
+}
+
+/// Every readable usage record in `dir`. Unreadable ones are skipped — a
+/// corrupt file must not take the summary (or `register`) down with it.
+pub fn load_all(dir: &Path) -> Vec<RunUsage> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().ends_with(RECORD_SUFFIX))
+        .filter_map(|e| std::fs::read(e.path()).ok())
+        .filter_map(|bytes| serde_json::from_slice(&bytes).ok())
+        .collect()
+}
+
+/// Fold run records into today's and this month's totals per CLI.
+pub fn summarize(runs: &[RunUsage], now: DateTime<Local>) -> UsageSummary {
+    let mut summary = UsageSummary {
+        day: now.format("%Y-%m-%d").to_string(),
+        month: now.format("%Y-%m").to_string(),
+        ..UsageSummary::default()
+    };
+    for run in runs {
+        let Ok(finished) = DateTime::parse_from_rfc3339(&run.finished_at) else {
+            continue;
+        };
+        let finished = finished.with_timezone(&Local);
+        if finished.year() != now.year() || finished.month() != now.month() {
+            continue;
+        }
+        add_run(summary.this_month.entry(run.cli.clone()).or_default(), run);
+        if finished.day() == now.day() {
+            add_run(summary.today.entry(run.cli.clone()).or_default(), run);
+        }
+    }
+    summary
+}
+
+fn add_run(totals: &mut UsageTotals, run: &RunUsage) {
+    totals.runs += 1;
+    totals.usage.add(&run.usage);
+    if let Some(cost) = run.total_cost_usd {
+        *totals.total_cost_usd.get_or_insert(0.0) += cost;
+    }
+}
+
+/// Today's and this month's usage from the records on disk.
+pub fn current_summary(app: &tauri::AppHandle) -> UsageSummary {
+    let runs = match super::run_log::runs_dir(app) {
+        Ok(dir) => load_all(&dir),
+        Err(_) => Vec::new(),
+    };
+    summarize(&runs, Local::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(cli: &str, finished_at: &str, input: u64, cost: Option<f64>) -> RunUsage {
+        RunUsage {
+            request_id: format!("req-{}", finished_at.replace([':', '+'], "-")),
+            cli: cli.into(),
+            model: None,
+            finished_at: finished_at.into(),
+            usage: Usage {
+                input_tokens: input,
+                output_tokens: 10,
+                cache_read_tokens: 1000,
+                cache_write_tokens: 0,
+            },
+            total_cost_usd: cost,
+        }
+    }
+
+    fn at(rfc3339: &str) -> DateTime<Local> {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Local)
+    }
+
+    #[test]
+    fn summary_splits_day_and_month_per_cli() {
+        let now = at("2026-03-15T12:00:00+00:00");
+        let runs = [
+            run("claude", "2026-03-15T09:00:00+00:00", 100, Some(0.5)),
+            run("claude", "2026-03-02T09:00:00+00:00", 200, Some(0.25)),
+            run("codex", "2026-03-15T11:00:00+00:00", 300, None),
+            run("codex", "2026-02-15T11:00:00+00:00", 400, None),
+            run("codex", "not a timestamp", 500, None),
+        ];
+        let summary = summarize(&runs, now);
+        let today_claude = &summary.today["claude"];
+        assert_eq!(today_claude.runs, 1);
+        assert_eq!(today_claude.usage.input_tokens, 100);
+        let month_claude = &summary.this_month["claude"];
+        assert_eq!(month_claude.runs, 2);
+        assert_eq!(month_claude.total_cost_usd, Some(0.75));
+        // Codex reports no cost, and last month's run is out of both periods.
+        let month_codex = &summary.this_month["codex"];
+        assert_eq!(month_codex.runs, 1);
+        assert_eq!(month_codex.total_cost_usd, None);
+    }
+
+    #[test]
+    fn budget_counts_new_tokens_and_the_tighter_cap_wins() {
+        let now = at("2026-03-15T12:00:00+00:00");
+        // 110 budget tokens each; the 1000 cache reads don't count.
+        let runs = [
+            run("claude", "2026-03-15T09:00:00+00:00", 100, None),
+            run("codex", "2026-03-01T09:00:00+00:00", 100, None),
+        ];
+        let summary = summarize(&runs, now);
+        assert_eq!(UsageBudget::default().remaining(&summary), None);
+        let budget = UsageBudget {
+            daily_tokens: Some(1000),
+            monthly_tokens: Some(300),
+        };
+        assert_eq!(budget.remaining(&summary), Some(80));
+        assert_eq!(budget.check(&summary), Ok(Some(80)));
+        let spent = UsageBudget {
+            daily_tokens: Some(110),
+            monthly_tokens: None,
+        };
+        assert!(spent
+            .check(&summary)
+            .unwrap_err()
+            .starts_with("budget_exceeded:"));
+    }
+
+    #[test]
+    fn records_round_trip_and_junk_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let r = run("claude", "2026-03-15T09:00:00+01:00", 100, Some(0.1));
+        record(dir.path(), &r).unwrap();
+        std::fs::write(dir.path().join("broken.usage.json"), "{").unwrap();
+        std::fs::write(dir.path().join("req-1.log"), "not a record").unwrap();
+        assert_eq!(load_all(dir.path()), vec![r]);
+
+        let mut escaping = run("claude", "2026-03-15T09:00:00+01:00", 1, None);
+        escaping.request_id = "../../escaped".into();
+        let nested = dir.path().join("runs");
+        assert!(record(&nested, &escaping).is_err());
+        assert!(!dir.path().join("escaped.usage.json").exists());
+        assert!(!nested.exists());
+    }
+}
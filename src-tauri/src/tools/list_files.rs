@@ -1,10 +1,15 @@
-use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use ignore::{WalkBuilder, WalkState};
 use serde_json::{json, Value};
 
 use crate::security;
+use crate::tools::budget::{self, Budget};
+use crate::tools::streaming::{self, Batches, Found};
 use crate::unicode;
+use crate::ws::inflight::CancelSignal;
+use crate::ws::ToolStream;
 
 /// Listing order. `Name` preserves the original stable alphabetical contract;
 /// `Modified`/`Size` exist so "most recent file" / "biggest file" questions
@@ -17,6 +22,16 @@ enum SortBy {
     Size,
 }
 
+/// A parsed `list_files` request.
+struct Listing {
+    root: PathBuf,
+    recursive: bool,
+    glob_pattern: Option<glob::Pattern>,
+    sort_by: SortBy,
+    descending: bool,
+    max_results: Option<usize>,
+}
+
 /// Handle a `list_files` request.
 ///
 /// Params:
@@ -26,10 +41,57 @@ enum SortBy {
 /// - `sort_by` (string, optional): "name" (default) | "modified" | "size"
 /// - `order` (string, optional): "asc" | "desc" (default: asc for name, desc otherwise)
 /// - `max_results` (number, optional): Cap the listing, applied AFTER sorting
+/// - `timeout_seconds`, `max_files_visited` (number, optional): Tighten the
+///   walk's budget (see `budget`)
 pub async fn handle(
     params: Value,
     scoped_folders: &[String],
 ) -> Result<(Value, Option<u64>), String> {
+    let listing = parse(&params, scoped_folders)?;
+    let budget = Budget::new(budget::limits("list_files", &params));
+    let found = Found::default();
+    walk(&listing, &budget, &found, None);
+    Ok((answer(&listing, found.take(), &budget), None))
+}
+
+/// Handle a `list_files` request from the engine: cancellable, and with
+/// `stream: true` the entries go out as `response_chunk`s (`{"files":
+/// [...]}`) in the order the walk finds them, `max_results` capping how
+/// many. Sorting needs the whole listing, so `sort_by` can't stream.
+pub async fn handle_streaming(
+    params: Value,
+    scoped_folders: &[String],
+    stream: &ToolStream,
+    cancel: CancelSignal,
+) -> Result<(Value, Option<u64>), String> {
+    let streamed = streaming::requested(&params);
+    if streamed && params.get("sort_by").is_some() {
+        return Err(
+            "sort_by can't be combined with stream: entries are sent as they are found".into(),
+        );
+    }
+    let listing = Arc::new(parse(&params, scoped_folders)?);
+    let budget = Arc::new(Budget::new(budget::limits("list_files", &params)));
+    let found = Arc::new(Found::default());
+    let cap = listing.max_results.filter(|_| streamed);
+    let walker = {
+        let (listing, budget, found) = (listing.clone(), budget.clone(), found.clone());
+        move || walk(&listing, &budget, &found, cap)
+    };
+    let batches = streamed.then_some(Batches {
+        stream,
+        key: "files",
+    });
+    streaming::drive(walker, &found, &budget, cancel, batches).await?;
+    let files = found.take();
+    if streamed {
+        let capped = cap.is_some_and(|cap| files.len() >= cap);
+        return Ok((streaming::summary(files.len(), &budget, capped), None));
+    }
+    Ok((answer(&listing, files, &budget), None))
+}
+
+fn parse(params: &Value, scoped_folders: &[String]) -> Result<Listing, String> {
     let path = params
         .get("path")
         .and_then(|v| v.as_str())
@@ -62,7 +124,7 @@ pub async fn handle(
         .map(|n| n as usize);
 
     // Validate path is within scoped folders.
-    let canonical = security::validate_path(path, scoped_folders).map_err(|e| e.to_string())?;
+    let root = security::validate_path(path, scoped_folders).map_err(|e| e.to_string())?;
 
     // Compile glob pattern if provided.
     let glob_pattern = match pattern {
@@ -70,27 +132,36 @@ pub async fn handle(
         None => None,
     };
 
+    Ok(Listing {
+        root,
+        recursive,
+        glob_pattern,
+        sort_by,
+        descending,
+        max_results,
+    })
+}
+
+/// Walk the listing's tree into `found`, until `cap` entries or the budget
+/// runs out.
+fn walk(listing: &Listing, budget: &Budget, found: &Found, cap: Option<usize>) {
     // Parallel walk (ripgrep's `ignore` crate). Standard ignore-file/hidden
     // filtering is disabled to preserve the previous walkdir semantics; the win
     // is multi-core traversal plus pruning denied directories (node_modules,
     // .git, …) at the directory level instead of descending and discarding.
-    let mut builder = WalkBuilder::new(&canonical);
+    let mut builder = WalkBuilder::new(&listing.root);
     builder
         .standard_filters(false)
         .hidden(false)
         .parents(false)
         .follow_links(false)
-        .max_depth(if recursive { None } else { Some(1) });
-
-    let root = Arc::new(canonical.clone());
-    let glob_pattern = Arc::new(glob_pattern);
-    let files = Arc::new(Mutex::new(Vec::<Value>::new()));
+        .max_depth(if listing.recursive { None } else { Some(1) });
 
     builder.build_parallel().run(|| {
-        let root = Arc::clone(&root);
-        let glob_pattern = Arc::clone(&glob_pattern);
-        let files = Arc::clone(&files);
         Box::new(move |entry| {
+            if !budget.visit() {
+                return WalkState::Quit;
+            }
             let entry = match entry {
                 Ok(e) => e,
                 Err(_) => return WalkState::Continue,
@@ -98,7 +169,7 @@ pub async fn handle(
             let entry_path = entry.path();
 
             // Skip the root directory itself.
-            if entry_path == root.as_path() {
+            if entry_path == listing.root.as_path() {
                 return WalkState::Continue;
             }
 
@@ -120,7 +191,7 @@ pub async fn handle(
 
             // Apply glob filter to output (non-matching directories are still
             // descended so their matching children are listed).
-            if let Some(pat) = glob_pattern.as_ref() {
+            if let Some(pat) = listing.glob_pattern.as_ref() {
                 if !pat.matches(&file_name) {
                     return WalkState::Continue;
                 }
@@ -144,28 +215,34 @@ pub async fn handle(
                 .ok()
                 .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339());
 
-            files.lock().unwrap().push(json!({
+            let listed = json!({
                 "name": unicode::normalize_whitespace(&file_name),
                 "path": unicode::normalize_whitespace(&entry_path.display().to_string()),
                 "size": metadata.len(),
                 "type": file_type,
                 "modified_at": modified_at,
-            }));
+            });
+            if !found.push(listed, cap) {
+                return WalkState::Quit;
+            }
             WalkState::Continue
         })
     });
+}
 
+/// The whole listing as one response: sorted, capped, and marked truncated
+/// when either the cap or the budget cut it short.
+fn answer(listing: &Listing, mut out: Vec<Value>, budget: &Budget) -> Value {
     // The parallel walk yields in nondeterministic order; sort for a stable
     // listing. RFC3339 UTC timestamps compare correctly as strings; entries
     // without a timestamp sort as oldest. Path is the tiebreak so equal keys
     // stay deterministic.
-    let mut out = std::mem::take(&mut *files.lock().unwrap());
     out.sort_by(|a, b| {
         let path_cmp = a
             .get("path")
             .and_then(|v| v.as_str())
             .cmp(&b.get("path").and_then(|v| v.as_str()));
-        let key_cmp = match sort_by {
+        let key_cmp = match listing.sort_by {
             SortBy::Name => path_cmp,
             SortBy::Modified => a
                 .get("modified_at")
@@ -176,7 +253,7 @@ pub async fn handle(
                 .and_then(|v| v.as_u64())
                 .cmp(&b.get("size").and_then(|v| v.as_u64())),
         };
-        let ordered = if descending { key_cmp.reverse() } else { key_cmp };
+        let ordered = if listing.descending { key_cmp.reverse() } else { key_cmp };
         ordered.then(path_cmp)
     });
 
@@ -186,11 +263,24 @@ pub async fn handle(
     // unbounded payloads and, once anything truncated downstream, the newest
     // files vanishing alphabetically.
     let total = out.len();
-    if let Some(cap) = max_results {
+    if let Some(cap) = listing.max_results {
         out.truncate(cap);
     }
-    let truncated = out.len() < total;
-    Ok((json!({ "files": out, "total_found": total, "truncated": truncated }), None))
+    // A spent budget means the walk itself stopped short: `total_found` is
+    // only what it saw.
+    let mut value = json!({ "files": out, "total_found": total });
+    match budget.exhausted() {
+        Some(reason) => {
+            value["truncated"] = json!(true);
+            value["reason"] = json!(reason);
+        }
+        None if out.len() < total => {
+            value["truncated"] = json!(true);
+            value["reason"] = json!("max_results");
+        }
+        None => value["truncated"] = json!(false),
+    }
+    value
 }
 
 #[cfg(test)]
@@ -346,6 +436,27 @@ mod tests {
 
         assert_eq!(names(&value), vec!["a.txt".to_string(), "b.txt".to_string()]);
         assert_eq!(value.get("truncated").unwrap(), true);
+        assert_eq!(value.get("reason").unwrap(), "max_results");
+    }
+
+    #[tokio::test]
+    async fn a_spent_budget_returns_what_the_walk_saw() {
+        let tree = TempTree::new("budget");
+        for i in 0..5 {
+            tree.write(&format!("d{i}/f.txt"), "x");
+        }
+
+        let (value, _) = handle(
+            json!({ "path": tree.path_str(), "recursive": true, "max_files_visited": 4 }),
+            &tree.scoped(),
+        )
+        .await
+        .unwrap();
+
+        // The root counts as a visit too.
+        assert!(names(&value).len() <= 3, "{value}");
+        assert_eq!(value["truncated"], true);
+        assert_eq!(value["reason"], "max_files_visited");
     }
 
     #[tokio::test]
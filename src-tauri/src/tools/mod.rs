@@ -1,4 +1,5 @@
 mod benchling;
+mod budget;
 pub mod coding_agent;
 mod file_info;
 mod list_files;
@@ -6,11 +7,42 @@ mod open_terminal;
 mod read_file;
 mod reveal_file;
 mod search_files;
+mod semantic_search_files;
+mod streaming;
 
 use serde_json::Value;
 
 use crate::state::AppState;
 
+/// The tools `dispatch_request` answers besides the `benchling_*` ones.
+const LOCAL: &[&str] = &[
+    "list_files",
+    "search_files",
+    "semantic_search_files",
+    "read_file",
+    "file_info",
+    "reveal_file",
+    "open_terminal",
+];
+
+/// The tools `dispatch_streaming` answers.
+const CODING: &[&str] = &["run_coding_agent"];
+
+/// The tools that can send `response_chunk`s ahead of their response: the
+/// coding runs, and the walks that send what they find as they go (see
+/// `streaming`).
+pub const STREAMING: &[&str] = &["run_coding_agent", "list_files", "search_files"];
+
+/// Every tool a `request` may name — what `register` advertises.
+pub fn names() -> Vec<&'static str> {
+    LOCAL
+        .iter()
+        .chain(benchling::TOOLS)
+        .chain(CODING)
+        .copied()
+        .collect()
+}
+
 /// Dispatch a tool request to the appropriate handler.
 ///
 /// Returns `Ok((data, optional_bytes_transferred))` or `Err(error_message)`.
@@ -32,6 +64,9 @@ pub async fn dispatch_request(
         "search_files" => {
             search_files::handle(params, scoped_folders, &state.file_index).await
         }
+        "semantic_search_files" => {
+            semantic_search_files::handle(params, scoped_folders, state).await
+        }
         "read_file" => read_file::handle(params, scoped_folders, &state.file_index).await,
         "file_info" => file_info::handle(params, scoped_folders).await,
         "reveal_file" => reveal_file::handle(params, scoped_folders).await,
@@ -44,7 +79,37 @@ pub async fn dispatch_request(
 /// the cancel signal (their children require cleanup on cancel, so the
 /// select-drop pattern used for read-only tools is not safe for them).
 pub fn is_streaming(tool: &str) -> bool {
-    coding_agent::handles(tool)
+    STREAMING.contains(&tool)
+}
+
+/// The streaming tools that need no more than the state: the walks.
+/// The rest go through `Host::dispatch_streaming`.
+pub fn streams_locally(tool: &str) -> bool {
+    matches!(tool, "list_files" | "search_files")
+}
+
+pub async fn dispatch_local_streaming(
+    tool: &str,
+    params: Value,
+    scoped_folders: &[String],
+    state: &AppState,
+    stream: &crate::ws::ToolStream,
+    cancel: crate::ws::inflight::CancelSignal,
+) -> Result<(Value, Option<u64>), String> {
+    match tool {
+        "list_files" => list_files::handle_streaming(params, scoped_folders, stream, cancel).await,
+        "search_files" => {
+            search_files::handle_streaming(
+                params,
+                scoped_folders,
+                &state.file_index,
+                stream,
+                cancel,
+            )
+            .await
+        }
+        other => Err(format!("Unknown streaming tool: {other}")),
+    }
 }
 
 pub async fn dispatch_streaming(
@@ -62,3 +127,27 @@ pub async fn dispatch_streaming(
     }
     Err(format!("Unknown streaming tool: {tool}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advertised_names_match_the_dispatchers() {
+        let names = names();
+        let unique: std::collections::BTreeSet<_> = names.iter().collect();
+        assert_eq!(unique.len(), names.len(), "a tool is listed twice");
+        for tool in benchling::TOOLS {
+            assert!(benchling::handles(tool), "{tool} not routed to Benchling");
+        }
+        for tool in STREAMING {
+            assert!(is_streaming(tool), "{tool} not routed as streaming");
+            assert!(names.contains(tool), "{tool} not advertised");
+            assert_eq!(
+                streams_locally(tool),
+                !coding_agent::handles(tool),
+                "{tool} routed to neither or both"
+            );
+        }
+    }
+}
@@ -1,14 +1,26 @@
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 use ignore::{WalkBuilder, WalkState};
 use serde_json::{json, Value};
 
 use crate::file_index::FileIndex;
 use crate::security;
+use crate::tools::budget::{self, Budget};
+use crate::tools::streaming::{self, Batches, Found};
 use crate::unicode;
+use crate::ws::inflight::CancelSignal;
+use crate::ws::ToolStream;
+
+/// A parsed `search_files` request.
+struct Search {
+    query: String,
+    search_content: bool,
+    limit: usize,
+    file_types: Option<Vec<String>>,
+    path: Option<String>,
+}
 
 /// Handle a `search_files` request.
 ///
@@ -18,11 +30,76 @@ use crate::unicode;
 /// - `search_content` (bool, optional): Search inside file contents
 /// - `file_types` (array of strings, optional): Filter by extension
 /// - `limit` (integer, optional): Max results (default 20)
+/// - `timeout_seconds`, `max_files_visited`, `max_bytes_read` (integer,
+///   optional): Tighten a content search's budget (see `budget`)
 pub async fn handle(
     params: Value,
     scoped_folders: &[String],
     index: &FileIndex,
 ) -> Result<(Value, Option<u64>), String> {
+    let search = parse(&params, scoped_folders)?;
+    if !search.search_content {
+        let results = search_names(&search, scoped_folders, index);
+        return Ok((json!({ "results": results }), None));
+    }
+    let roots = search.roots(scoped_folders);
+    if roots.is_empty() {
+        return Ok((json!({ "results": [] }), None));
+    }
+    let budget = Budget::new(budget::limits("search_files", &params));
+    let found = Found::default();
+    walk_content(&search, &roots, &budget, &found);
+    Ok((answer(found.take(), &budget), None))
+}
+
+/// Handle a `search_files` request from the engine: cancellable, and with
+/// `stream: true` the hits go out as `response_chunk`s (`{"results":
+/// [...]}`) as the walk finds them. A filename search answers from the
+/// index at once, so it comes as a single chunk.
+pub async fn handle_streaming(
+    params: Value,
+    scoped_folders: &[String],
+    index: &FileIndex,
+    stream: &ToolStream,
+    cancel: CancelSignal,
+) -> Result<(Value, Option<u64>), String> {
+    let streamed = streaming::requested(&params);
+    let search = Arc::new(parse(&params, scoped_folders)?);
+    let budget = Arc::new(Budget::new(budget::limits("search_files", &params)));
+    let found = Arc::new(Found::default());
+    if !search.search_content {
+        let results = search_names(&search, scoped_folders, index);
+        if !streamed {
+            return Ok((json!({ "results": results }), None));
+        }
+        let total = results.len();
+        if total > 0 {
+            stream.chunk(json!({ "results": results })).await;
+        }
+        return Ok((streaming::summary(total, &budget, false), None));
+    }
+    let roots = search.roots(scoped_folders);
+    let walker = {
+        let (search, budget, found) = (search.clone(), budget.clone(), found.clone());
+        move || {
+            if !roots.is_empty() {
+                walk_content(&search, &roots, &budget, &found);
+            }
+        }
+    };
+    let batches = streamed.then_some(Batches {
+        stream,
+        key: "results",
+    });
+    streaming::drive(walker, &found, &budget, cancel, batches).await?;
+    let results = found.take();
+    if streamed {
+        return Ok((streaming::summary(results.len(), &budget, false), None));
+    }
+    Ok((answer(results, &budget), None))
+}
+
+fn parse(params: &Value, scoped_folders: &[String]) -> Result<Search, String> {
     let query = params
         .get("query")
         .and_then(|v| v.as_str())
@@ -41,52 +118,65 @@ pub async fn handle(
 
     // An explicit `path` restricts the search to a subtree; validate it against
     // scope up front (applies to both filename and content search).
-    let path_param = params.get("path").and_then(|v| v.as_str());
-    if let Some(path) = path_param {
+    let path = params.get("path").and_then(|v| v.as_str());
+    if let Some(path) = path {
         security::validate_path(path, scoped_folders).map_err(|e| e.to_string())?;
     }
 
-    // Filename/path search is served from the in-memory index — no disk walk on
-    // repeat queries. The index prunes denied dirs/files at build time and is
-    // refreshed incrementally (unchanged directories are reused).
-    if !search_content {
-        index.ensure_fresh(scoped_folders);
-        let root_filter = path_param.map(PathBuf::from);
-        let hits = index.search_names(
-            query,
-            root_filter.as_deref(),
-            file_types.as_deref(),
-            limit,
-        );
-        let results: Vec<Value> = hits
-            .iter()
-            .map(|f| {
-                json!({
-                    "path": unicode::normalize_whitespace(&f.path.display().to_string()),
-                    "name": unicode::normalize_whitespace(&f.name),
-                })
-            })
-            .collect();
-        return Ok((json!({ "results": results }), None));
-    }
+    Ok(Search {
+        query: query.to_string(),
+        search_content,
+        limit,
+        file_types,
+        path: path.map(str::to_string),
+    })
+}
 
-    // Content search must read file bodies, so it still walks the tree.
-    let search_roots: Vec<String> = match path_param {
-        Some(path) => vec![path.to_string()],
-        None => scoped_folders.to_vec(),
-    };
-    if search_roots.is_empty() {
-        return Ok((json!({ "results": [] }), None));
+impl Search {
+    /// Content search must read file bodies, so it still walks the tree:
+    /// these roots.
+    fn roots(&self, scoped_folders: &[String]) -> Vec<String> {
+        match &self.path {
+            Some(path) => vec![path.clone()],
+            None => scoped_folders.to_vec(),
+        }
     }
+}
+
+/// Filename/path search is served from the in-memory index — no disk walk on
+/// repeat queries. The index prunes denied dirs/files at build time and is
+/// refreshed incrementally (unchanged directories are reused).
+fn search_names(search: &Search, scoped_folders: &[String], index: &FileIndex) -> Vec<Value> {
+    index.ensure_fresh(scoped_folders);
+    let root_filter = search.path.as_ref().map(PathBuf::from);
+    let hits = index.search_names(
+        &search.query,
+        scoped_folders,
+        root_filter.as_deref(),
+        search.file_types.as_deref(),
+        search.limit,
+    );
+    hits.iter()
+        .map(|f| {
+            json!({
+                "path": unicode::normalize_whitespace(&f.path.display().to_string()),
+                "name": unicode::normalize_whitespace(&f.name),
+            })
+        })
+        .collect()
+}
 
+/// Walk `roots` for files whose content matches, into `found`, until
+/// `limit` hits or the budget runs out.
+fn walk_content(search: &Search, roots: &[String], budget: &Budget, found: &Found) {
     // Walk all roots in parallel (ripgrep's `ignore` crate). Standard
     // ignore-file/hidden filtering is disabled so results match the previous
     // single-threaded walkdir semantics exactly; the speedup is twofold:
     // multi-core traversal, and pruning denied directories (node_modules,
     // .git, .venv, …) at the directory level so we never descend into them —
     // the old code walked every file inside them only to discard each one.
-    let mut builder = WalkBuilder::new(&search_roots[0]);
-    for root in &search_roots[1..] {
+    let mut builder = WalkBuilder::new(&roots[0]);
+    for root in &roots[1..] {
         builder.add(root);
     }
     builder
@@ -95,19 +185,17 @@ pub async fn handle(
         .parents(false)
         .follow_links(false);
 
-    let query_lower = Arc::new(query.to_lowercase());
-    let file_types = Arc::new(file_types);
-    let results = Arc::new(Mutex::new(Vec::<Value>::new()));
-    let count = Arc::new(AtomicUsize::new(0));
+    let query_lower = search.query.to_lowercase();
+    let limit = search.limit;
 
     builder.build_parallel().run(|| {
-        let query_lower = Arc::clone(&query_lower);
-        let file_types = Arc::clone(&file_types);
-        let results = Arc::clone(&results);
-        let count = Arc::clone(&count);
+        let query_lower = query_lower.as_str();
         Box::new(move |entry| {
             // Enough already found — stop dispatching new work.
-            if count.load(Ordering::Relaxed) >= limit {
+            if found.len() >= limit {
+                return WalkState::Quit;
+            }
+            if !budget.visit() {
                 return WalkState::Quit;
             }
             let entry = match entry {
@@ -136,7 +224,7 @@ pub async fn handle(
             let file_name = entry.file_name().to_string_lossy().to_string();
 
             // Filter by file type if specified.
-            if let Some(types) = file_types.as_ref() {
+            if let Some(types) = search.file_types.as_ref() {
                 let ext = entry_path
                     .extension()
                     .map(|e| e.to_string_lossy().to_lowercase())
@@ -147,8 +235,8 @@ pub async fn handle(
             }
 
             // Only content search reaches the walker; filename/path search is
-            // served from the index above.
-            let hit = search_file_content(entry_path, query_lower.as_str()).map(|context| {
+            // served from the index.
+            let hit = search_file_content(entry_path, query_lower, budget).map(|context| {
                 json!({
                     "path": unicode::normalize_whitespace(&entry_path.display().to_string()),
                     "name": unicode::normalize_whitespace(&file_name),
@@ -157,25 +245,26 @@ pub async fn handle(
             });
 
             if let Some(hit) = hit {
-                let mut guard = results.lock().unwrap();
-                if guard.len() < limit {
-                    guard.push(hit);
-                    if count.fetch_add(1, Ordering::Relaxed) + 1 >= limit {
-                        return WalkState::Quit;
-                    }
+                if !found.push(hit, Some(limit)) {
+                    return WalkState::Quit;
                 }
             }
             WalkState::Continue
         })
     });
+}
 
-    let mut out = std::mem::take(&mut *results.lock().unwrap());
-    out.truncate(limit);
-    Ok((json!({ "results": out }), None))
+/// A content search's hits as one response.
+fn answer(out: Vec<Value>, budget: &Budget) -> Value {
+    match budget.exhausted() {
+        Some(reason) => json!({ "results": out, "truncated": true, "reason": reason }),
+        None => json!({ "results": out, "truncated": false }),
+    }
 }
 
 /// Search a file's content for the query string. Returns the first matching line as context.
-fn search_file_content(path: &Path, query: &str) -> Option<String> {
+/// Lines read count against `budget`; once it is spent the file is given up.
+fn search_file_content(path: &Path, query: &str, budget: &Budget) -> Option<String> {
     let file = std::fs::File::open(path).ok()?;
     let metadata = file.metadata().ok()?;
 
@@ -191,6 +280,9 @@ fn search_file_content(path: &Path, query: &str) -> Option<String> {
             Ok(l) => l,
             Err(_) => return None, // Likely binary
         };
+        if !budget.read(line.len() as u64 + 1) {
+            return None;
+        }
 
         if line.to_lowercase().contains(query) {
             let context = if line.len() > 200 {
@@ -371,6 +463,38 @@ mod tests {
         assert_eq!(found, vec!["app.js".to_string()], "got {found:?}");
     }
 
+    #[tokio::test]
+    async fn content_search_stops_at_its_byte_budget() {
+        let tree = TempTree::new("budget");
+        for i in 0..4 {
+            tree.write(
+                &format!("f{i}.txt"),
+                &format!("{}\nneedle\n", "x".repeat(100)),
+            );
+        }
+
+        let (value, _) = handle(
+            json!({ "query": "needle", "search_content": true, "max_bytes_read": 150 }),
+            &tree.scoped(),
+            &FileIndex::new(),
+        )
+        .await
+        .unwrap();
+        assert!(results(&value).len() <= 1, "{value}");
+        assert_eq!(value["truncated"], true);
+        assert_eq!(value["reason"], "max_bytes_read");
+
+        let (value, _) = handle(
+            json!({ "query": "needle", "search_content": true }),
+            &tree.scoped(),
+            &FileIndex::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(results(&value).len(), 4);
+        assert_eq!(value["truncated"], false);
+    }
+
     #[tokio::test]
     async fn limit_caps_result_count() {
         let tree = TempTree::new("limit");
@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use crate::security;
+use crate::state::AppState;
+use crate::tools::budget::{self, Budget};
+use crate::unicode;
+
+/// Handle a `semantic_search_files` request: documents ranked by meaning
+/// rather than by the words they share with the query (see `semantic`).
+///
+/// Params:
+/// - `query` (string, required): What the documents are about
+/// - `path` (string, optional): Limit search to this directory
+/// - `file_types` (array of strings, optional): Filter by extension
+/// - `limit` (integer, optional): Max results (default 10, max 50)
+/// - `timeout_seconds`, `max_files_visited`, `max_bytes_read` (integer,
+///   optional): Tighten the budget for embedding new documents first (see
+///   `budget`)
+///
+/// `pending` counts documents not embedded yet, so not searched; a repeat
+/// request picks up where this one stopped.
+pub async fn handle(
+    params: Value,
+    scoped_folders: &[String],
+    state: &AppState,
+) -> Result<(Value, Option<u64>), String> {
+    let query = params
+        .get("query")
+        .and_then(|v| v.as_str())
+        .filter(|q| !q.trim().is_empty())
+        .ok_or("semantic_search_files requires 'query' parameter")?
+        .to_string();
+    let limit = params
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(10)
+        .clamp(1, 50) as usize;
+    let file_types: Option<Vec<String>> = params
+        .get("file_types")
+        .and_then(|v| serde_json::from_value(v.clone()).ok());
+    let path = params.get("path").and_then(|v| v.as_str());
+    if let Some(path) = path {
+        security::validate_path(path, scoped_folders).map_err(|e| e.to_string())?;
+    }
+    let root_filter = path.map(PathBuf::from);
+    state.semantic_index.check_model()?;
+
+    let budget = Budget::new(budget::limits("semantic_search_files", &params));
+    let scoped = scoped_folders.to_vec();
+    let index = state.file_index.clone();
+    let semantic = state.semantic_index.clone();
+    // Reading and embedding are CPU- and disk-bound.
+    let (hits, progress, budget) = tokio::task::spawn_blocking(move || {
+        index.ensure_fresh(&scoped);
+        let files = index.files(&scoped);
+        let progress = semantic.update(&files, &scoped, &budget)?;
+        let candidates: Vec<_> = files
+            .into_iter()
+            .filter(|f| {
+                root_filter
+                    .as_ref()
+                    .is_none_or(|root| f.path.starts_with(root))
+            })
+            .filter(|f| {
+                file_types.as_ref().is_none_or(|types| {
+                    let ext = f
+                        .path
+                        .extension()
+                        .map(|e| e.to_string_lossy().to_lowercase())
+                        .unwrap_or_default();
+                    types.iter().any(|t| t.to_lowercase() == ext)
+                })
+            })
+            .collect();
+        let hits = semantic.search(&query, &candidates, |p| index.access_count(p), limit)?;
+        Ok::<_, String>((hits, progress, budget))
+    })
+    .await
+    .map_err(|e| format!("semantic search failed: {e}"))??;
+
+    // The index walks only `scoped_folders`; this is the same check every
+    // other file tool makes, so an excerpt never comes from elsewhere.
+    let results: Vec<Value> = hits
+        .iter()
+        .filter(|hit| security::validate_path(&hit.path.to_string_lossy(), scoped_folders).is_ok())
+        .map(|hit| {
+            json!({
+                "path": unicode::normalize_whitespace(&hit.path.display().to_string()),
+                "name": unicode::normalize_whitespace(&hit.name),
+                "score": (hit.score * 1000.0).round() / 1000.0,
+                "match_context": format!("L{}: {}", hit.line, unicode::normalize_whitespace(&hit.preview)),
+            })
+        })
+        .collect();
+    let mut value = json!({
+        "results": results,
+        "indexed": progress.indexed,
+        "pending": progress.pending,
+        "truncated": progress.pending > 0,
+    });
+    if progress.pending > 0 {
+        value["reason"] = match budget.exhausted() {
+            Some(reason) => json!(reason),
+            // Another request is embedding them.
+            None => json!("indexing"),
+        };
+    }
+    Ok((value, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    use crate::semantic::testing::Concepts;
+    use crate::semantic::SemanticIndex;
+
+    fn names(value: &Value) -> Vec<&str> {
+        value["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn documents_outside_the_callers_scope_are_not_read_or_returned() {
+        let lab = tempfile::tempdir().unwrap();
+        let home = tempfile::tempdir().unwrap();
+        std::fs::write(lab.path().join("protocol.md"), "Better lipofection yield.").unwrap();
+        std::fs::write(home.path().join("diary.md"), "Transfection was efficient.").unwrap();
+        let lab_only = vec![lab.path().display().to_string()];
+        let both = vec![lab_only[0].clone(), home.path().display().to_string()];
+        let concepts = Arc::new(Concepts::default());
+        let mut state = AppState::new();
+        state.semantic_index = Arc::new(SemanticIndex::with_embedder(concepts.clone()));
+        let query = json!({ "query": "transfection efficiency" });
+
+        // The lab pairing's search reads only the lab folder: one passage,
+        // and the query.
+        let (value, _) = handle(query.clone(), &lab_only, &state).await.unwrap();
+        assert_eq!(names(&value), ["protocol.md"]);
+        assert_eq!(value["indexed"], 1);
+        assert_eq!(concepts.embedded.load(Ordering::Relaxed), 2);
+
+        // Another pairing indexes both; the lab pairing still sees only its own.
+        let (value, _) = handle(query.clone(), &both, &state).await.unwrap();
+        assert_eq!(names(&value).len(), 2);
+        let (value, _) = handle(query, &lab_only, &state).await.unwrap();
+        assert_eq!(names(&value), ["protocol.md"]);
+    }
+}
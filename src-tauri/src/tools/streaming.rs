@@ -0,0 +1,267 @@
+//! Walks that answer as they go: `list_files` and `search_files` over WS.
+//!
+//! The parallel walk discovers hits progressively, but the handlers used to
+//! answer only once it was over. Here the walk runs on a blocking thread and
+//! pushes what it finds into [`Found`]; meanwhile the request task sends
+//! whatever was added since the last look as one `response_chunk` — every
+//! [`BATCH_INTERVAL`], or as soon as [`BATCH_MAX`] are waiting — when the
+//! request asked for `stream: true`. The terminal response then only sums up
+//! (see [`summary`]).
+//!
+//! Either way the request task, not the walk, decides when to answer: a
+//! cancel from the engine stops the walkers through their [`Budget`] and
+//! settles the request at once, and a walker stuck in a slow filesystem call
+//! (a network mount) is not waited for past the deadline.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::sync::Notify;
+
+use crate::tools::budget::{Budget, Reason};
+use crate::ws::inflight::CancelSignal;
+use crate::ws::ToolStream;
+
+/// How often what was found is sent while the walk goes on.
+pub const BATCH_INTERVAL: Duration = Duration::from_millis(250);
+/// A batch this big goes out without waiting for the interval.
+pub const BATCH_MAX: usize = 200;
+/// How long past its deadline a walk may take to notice it.
+const DEADLINE_GRACE: Duration = Duration::from_secs(1);
+
+/// What the walker threads have found so far, in the order they found it.
+#[derive(Default)]
+pub struct Found {
+    items: Mutex<Vec<Value>>,
+    ready: Notify,
+}
+
+impl Found {
+    /// Add `item` unless `cap` are already here. False once full: stop
+    /// walking.
+    pub fn push(&self, item: Value, cap: Option<usize>) -> bool {
+        let mut items = self.items.lock().unwrap();
+        if cap.is_some_and(|cap| items.len() >= cap) {
+            return false;
+        }
+        items.push(item);
+        if items.len().is_multiple_of(BATCH_MAX) {
+            self.ready.notify_one();
+        }
+        cap.is_none_or(|cap| items.len() < cap)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Everything found; later pushes start a new list.
+    pub fn take(&self) -> Vec<Value> {
+        std::mem::take(&mut *self.items.lock().unwrap())
+    }
+
+    fn since(&self, from: usize) -> Vec<Value> {
+        self.items.lock().unwrap()[from..].to_vec()
+    }
+}
+
+/// Where batches go: the request's stream, each batch under `key`
+/// (`{"files": [...]}`, `{"results": [...]}`).
+pub struct Batches<'a> {
+    pub stream: &'a ToolStream,
+    pub key: &'static str,
+}
+
+impl Batches<'_> {
+    /// Send what `found` holds past `sent`; returns the new `sent`.
+    async fn flush(&self, found: &Found, sent: usize) -> usize {
+        let batch = found.since(sent);
+        if batch.is_empty() {
+            return sent;
+        }
+        let sent = sent + batch.len();
+        self.stream.chunk(json!({ self.key: batch })).await;
+        sent
+    }
+}
+
+/// Run `walk` on a blocking thread until it returns, the request is
+/// cancelled or the deadline is past. With `batches`, what it finds goes
+/// out as it is found. Errors are `cancelled by server`, as for every
+/// cancelled request.
+pub async fn drive(
+    walk: impl FnOnce() + Send + 'static,
+    found: &Found,
+    budget: &Budget,
+    mut cancel: CancelSignal,
+    batches: Option<Batches<'_>>,
+) -> Result<(), String> {
+    let mut walk = tokio::task::spawn_blocking(walk);
+    let give_up = tokio::time::sleep_until((budget.deadline() + DEADLINE_GRACE).into());
+    tokio::pin!(give_up);
+    let mut tick = tokio::time::interval(BATCH_INTERVAL);
+    tick.tick().await; // consume the first immediate tick
+
+    // Until the registry drops the signal (a duplicate request id), when
+    // `cancelled` resolves without a cancel.
+    let mut cancellable = true;
+    let mut sent = 0;
+    loop {
+        tokio::select! {
+            done = &mut walk => {
+                if let Err(e) = done {
+                    log::warn!("Walk panicked: {e}");
+                }
+                break;
+            }
+            _ = cancel.cancelled(), if cancellable => {
+                if cancel.is_cancelled() {
+                    budget.stop(Reason::Cancelled);
+                    return Err("cancelled by server".to_string());
+                }
+                cancellable = false;
+            }
+            _ = &mut give_up => {
+                // The walkers will stop at their next entry; don't wait for
+                // one stuck in a filesystem call.
+                budget.stop(Reason::Deadline);
+                break;
+            }
+            _ = tick.tick() => {}
+            _ = found.ready.notified() => {}
+        }
+        if let Some(batches) = &batches {
+            sent = batches.flush(found, sent).await;
+        }
+    }
+    if let Some(batches) = &batches {
+        batches.flush(found, sent).await;
+    }
+    Ok(())
+}
+
+/// The terminal response of a streamed walk: how much was sent, and
+/// whether (and why) the walk stopped short. `capped` is a result cap
+/// reached.
+pub fn summary(total: usize, budget: &Budget, capped: bool) -> Value {
+    let mut value = json!({ "streamed": true, "total_found": total });
+    match budget.exhausted() {
+        Some(reason) => {
+            value["truncated"] = json!(true);
+            value["reason"] = json!(reason);
+        }
+        None if capped => {
+            value["truncated"] = json!(true);
+            value["reason"] = json!("max_results");
+        }
+        None => value["truncated"] = json!(false),
+    }
+    value
+}
+
+/// Whether a request asked for its results as they are found.
+pub fn requested(params: &Value) -> bool {
+    params
+        .get("stream")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::tools::budget::Limits;
+    use crate::ws::inflight::InflightRegistry;
+    use crate::ws::protocol::OutgoingMessage;
+
+    fn budget(deadline: Duration) -> Arc<Budget> {
+        Arc::new(Budget::new(Limits {
+            deadline,
+            max_files: usize::MAX,
+            max_bytes: u64::MAX,
+        }))
+    }
+
+    /// The `files` of each chunk sent so far.
+    fn batches_sent(rx: &mut tokio::sync::mpsc::Receiver<OutgoingMessage>) -> Vec<Vec<Value>> {
+        let mut out = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            let chunk = serde_json::to_value(msg).unwrap();
+            out.push(chunk["data"]["files"].as_array().unwrap().clone());
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn found_items_go_out_in_batches_while_the_walk_runs() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+        let stream = ToolStream::new("req-1".into(), tx);
+        let found = Arc::new(Found::default());
+        let budget = budget(Duration::from_secs(30));
+        let registry = InflightRegistry::new();
+        let walk = {
+            let found = found.clone();
+            move || {
+                for n in 0..BATCH_MAX + 3 {
+                    found.push(json!(n), None);
+                }
+                std::thread::sleep(BATCH_INTERVAL * 2);
+                found.push(json!("last"), None);
+            }
+        };
+        let batches = Batches {
+            stream: &stream,
+            key: "files",
+        };
+        drive(
+            walk,
+            &found,
+            &budget,
+            registry.register("req-1"),
+            Some(batches),
+        )
+        .await
+        .unwrap();
+
+        let batches = batches_sent(&mut rx);
+        assert!(batches.len() >= 2, "{} batches", batches.len());
+        let sent = batches.concat();
+        assert_eq!(sent.len(), BATCH_MAX + 4);
+        assert_eq!(sent.last().unwrap(), "last");
+        assert_eq!(found.take().len(), BATCH_MAX + 4);
+        assert_eq!(summary(sent.len(), &budget, false)["truncated"], false);
+    }
+
+    #[tokio::test]
+    async fn a_cancel_or_the_deadline_settles_without_waiting_for_the_walk() {
+        let registry = InflightRegistry::new();
+        let found = Arc::new(Found::default());
+        let stuck = || std::thread::sleep(Duration::from_secs(2));
+
+        let budget_a = budget(Duration::from_secs(30));
+        let cancel = registry.register("req-1");
+        let canceller = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            registry.cancel("req-1");
+        };
+        let started = std::time::Instant::now();
+        let (result, _) = tokio::join!(drive(stuck, &found, &budget_a, cancel, None), canceller);
+        assert_eq!(result.unwrap_err(), "cancelled by server");
+        assert_eq!(budget_a.exhausted(), Some(Reason::Cancelled));
+
+        let budget_b = budget(Duration::ZERO);
+        drive(stuck, &found, &budget_b, registry.register("req-2"), None)
+            .await
+            .unwrap();
+        assert_eq!(budget_b.exhausted(), Some(Reason::Deadline));
+        assert!(started.elapsed() < Duration::from_millis(1800));
+        assert_eq!(summary(0, &budget_b, false)["reason"], "deadline");
+    }
+}
@@ -1,17 +1,22 @@
+use std::sync::Mutex;
+
 use tauri::{
-    menu::{MenuBuilder, MenuItem, MenuItemBuilder},
+    menu::{Menu, MenuBuilder, MenuItem, MenuItemBuilder},
     tray::TrayIconBuilder,
     AppHandle, Manager,
 };
 
-use crate::state::{ActiveCodingRun, CodingRunStatus, ConnectionStatus};
+use crate::state::{ActiveCodingRun, AppState, CodingRunStatus, ConnectionStatus};
 
 const WINDOW_LABEL: &str = "settings";
 const WINDOW_TITLE: &str = "Beakr Desktop";
 
 /// Holds the tray menu items whose text we update at runtime.
 pub struct TrayState {
-    pub status_item: MenuItem<tauri::Wry>,
+    pub menu: Menu<tauri::Wry>,
+    /// The status lines at the top of the menu: one per pairing, or a single
+    /// "Status:" line while there is at most one.
+    pub status_items: Mutex<Vec<MenuItem<tauri::Wry>>>,
     /// "Stop coding run" — enabled only while a local coding run is active
     /// (ENG-1528). Clicking cancels the active run via the inflight registry,
     /// which SIGINTs the CLI's process group.
@@ -32,8 +37,8 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
         .enabled(false)
         .build(app)?;
 
-    // Default to the unpaired label; lib.rs updates it from the stored token at
-    // startup, and claim_pairing_code/clear_token update it as pairing changes.
+    // Default to the unpaired label; update_tray_status keeps it in step with
+    // the pairings from startup on.
     let settings_item = MenuItemBuilder::with_id("settings", "Pair device").build(app)?;
 
     let stop_run_item = MenuItemBuilder::with_id("stop_run", "Stop coding run")
@@ -44,14 +49,6 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
         .enabled(false)
         .build(app)?;
 
-    // Store the menu item handles so we can update them at runtime.
-    app.manage(TrayState {
-        status_item: status_item.clone(),
-        stop_run_item: stop_run_item.clone(),
-        watch_run_item: watch_run_item.clone(),
-        settings_item: settings_item.clone(),
-    });
-
     let quit_item = MenuItemBuilder::with_id("quit", "Quit Beakr").build(app)?;
 
     let menu = MenuBuilder::new(app)
@@ -64,6 +61,15 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
         .item(&quit_item)
         .build()?;
 
+    // Store the menu and item handles so we can update them at runtime.
+    app.manage(TrayState {
+        menu: menu.clone(),
+        status_items: Mutex::new(vec![status_item]),
+        stop_run_item: stop_run_item.clone(),
+        watch_run_item: watch_run_item.clone(),
+        settings_item: settings_item.clone(),
+    });
+
     let _tray = TrayIconBuilder::new()
         .icon(app.default_window_icon().cloned().unwrap())
         .menu(&menu)
@@ -152,25 +158,63 @@ pub fn show_settings_window(app: &AppHandle) {
     }
 }
 
-/// Update the tray menu status text.
-pub fn update_tray_status(app: &AppHandle, status: &ConnectionStatus) {
-    let text = format!("Status: {status}");
-    if let Some(tray_state) = app.try_state::<TrayState>() {
-        let _ = tray_state.status_item.set_text(&text);
+/// The status lines for the pairings' (name, status). One pairing reads as
+/// it always did; several get a line each, named.
+fn status_lines(links: &[(String, ConnectionStatus)]) -> Vec<String> {
+    match links {
+        [] => vec![format!("Status: {}", ConnectionStatus::Disconnected)],
+        [(_, status)] => vec![format!("Status: {status}")],
+        _ => links
+            .iter()
+            .map(|(name, status)| format!("{name}: {status}"))
+            .collect(),
     }
 }
 
-/// Update the menu item label to reflect whether a device is paired.
-/// Unpaired -> "Pair device" (the window opens on the pairing screen); paired ->
-/// "Open Beakr". The click action is unchanged; only the label adapts.
-pub fn update_tray_pairing(app: &AppHandle, is_paired: bool) {
-    let label = if is_paired {
-        "Open Beakr"
-    } else {
+/// Reflect the pairings in the tray: a status line each, and the window item
+/// labelled for whether any exist. Unpaired -> "Pair device" (the window opens
+/// on the pairing screen); paired -> "Open Beakr". The click action is
+/// unchanged; only the label adapts.
+pub fn update_tray_status(app: &AppHandle, state: &AppState) {
+    let Some(tray_state) = app.try_state::<TrayState>() else {
+        return;
+    };
+    let links: Vec<_> = state
+        .links
+        .all()
+        .iter()
+        .map(|l| (l.pairing().name, l.status()))
+        .collect();
+    let label = if links.is_empty() {
         "Pair device"
+    } else {
+        "Open Beakr"
     };
-    if let Some(tray_state) = app.try_state::<TrayState>() {
-        let _ = tray_state.settings_item.set_text(label);
+    let _ = tray_state.settings_item.set_text(label);
+
+    let lines = status_lines(&links);
+    let mut items = tray_state.status_items.lock().expect("tray lock poisoned");
+    if items.len() == lines.len() {
+        for (item, line) in items.iter().zip(&lines) {
+            let _ = item.set_text(line);
+        }
+        return;
+    }
+    // The number of pairings changed: rebuild the lines in place.
+    for item in items.drain(..) {
+        let _ = tray_state.menu.remove(&item);
+    }
+    for (position, line) in lines.iter().enumerate() {
+        match MenuItemBuilder::with_id(format!("status-{position}"), line)
+            .enabled(false)
+            .build(app)
+        {
+            Ok(item) => {
+                let _ = tray_state.menu.insert(&item, position);
+                items.push(item);
+            }
+            Err(e) => log::warn!("Could not add a tray status line: {e}"),
+        }
     }
 }
 
@@ -224,6 +268,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn one_pairing_keeps_the_plain_status_line() {
+        assert_eq!(status_lines(&[]), ["Status: Disconnected"]);
+        assert_eq!(
+            status_lines(&[("Lab".into(), ConnectionStatus::Connected)]),
+            ["Status: Connected"]
+        );
+    }
+
+    #[test]
+    fn several_pairings_get_a_named_line_each() {
+        assert_eq!(
+            status_lines(&[
+                ("Lab".into(), ConnectionStatus::Connected),
+                ("Personal".into(), ConnectionStatus::Reconnecting),
+            ]),
+            ["Lab: Connected", "Personal: Reconnecting…"]
+        );
+    }
+
     #[test]
     fn idle_item_is_generic_and_disabled() {
         assert_eq!(
@@ -1,16 +1,25 @@
 use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use futures_util::{SinkExt, StreamExt};
 use rand::Rng;
-use tauri::{AppHandle, Emitter};
 use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
 use tokio_tungstenite::tungstenite::{client::IntoClientRequest, http::HeaderValue, Message};
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 
 use crate::state::{AppState, ConnectionStatus};
 use crate::tools;
-use crate::ws::protocol::{IncomingMessage, OutgoingMessage, ResponseStatus};
+use crate::ws::codec;
+use crate::ws::host::Host;
+use crate::ws::inflight::RunInput;
+use crate::ws::journal::Frame;
+use crate::ws::link::Link;
+use crate::ws::protocol::{
+    feature, Capabilities, IncomingMessage, Negotiated, OutgoingMessage, ResponseStatus,
+    PROTOCOL_VERSION,
+};
 
 /// App-level heartbeat that refreshes the engine's Redis online key.
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(45);
@@ -26,10 +35,19 @@ const PING_INTERVAL: Duration = Duration::from_secs(20);
 const LIVENESS_TIMEOUT: Duration = Duration::from_secs(30);
 /// How often we re-check the liveness deadline.
 const LIVENESS_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Reconnect backoff doubles from this, up to 16x.
+const BACKOFF_UNIT: Duration = Duration::from_secs(1);
 const MAX_BACKOFF: Duration = Duration::from_secs(30);
-/// Outbound frame buffer between spawned request tasks and the socket loop.
-/// Sized for bursts of streamed chunks; senders await (backpressure) when full.
+/// How long the engine gets to answer `register`.
+const REGISTER_TIMEOUT: Duration = Duration::from_secs(10);
+/// Pause before reconnecting, so a refreshed token can arrive.
+const TOKEN_WAIT: Duration = Duration::from_millis(500);
+/// Outbound frame buffer for readiness pushes, and between a streaming tool
+/// and its request task. Senders await (backpressure) when full.
 const OUTBOUND_BUFFER: usize = 256;
+/// Journal frames written per wake, so a long replay cannot starve the
+/// liveness arms of the select loop.
+const REPLAY_BATCH: usize = 32;
 
 /// WebSocket close codes from the server.
 const CLOSE_REVOKED: u16 = 4010;
@@ -47,20 +65,65 @@ const REVOKE_403_THRESHOLD: u32 = 5;
 const HANDSHAKE_403_MARKER: &str = "handshake_403_forbidden";
 const CLOSE_SESSION_EXPIRED: u16 = 4011;
 
-pub struct WsClient {
-    app: AppHandle,
+/// Every wait in the connection loop. Production values are the constants
+/// above; the protocol tests shrink them so backoff and liveness play out in
+/// milliseconds.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct LinkTimings {
+    pub heartbeat: Duration,
+    pub ping: Duration,
+    pub liveness_timeout: Duration,
+    pub liveness_check: Duration,
+    pub backoff_unit: Duration,
+    pub max_backoff: Duration,
+    pub register_timeout: Duration,
+    pub token_wait: Duration,
+}
+
+impl LinkTimings {
+    const PRODUCTION: Self = Self {
+        heartbeat: HEARTBEAT_INTERVAL,
+        ping: PING_INTERVAL,
+        liveness_timeout: LIVENESS_TIMEOUT,
+        liveness_check: LIVENESS_CHECK_INTERVAL,
+        backoff_unit: BACKOFF_UNIT,
+        max_backoff: MAX_BACKOFF,
+        register_timeout: REGISTER_TIMEOUT,
+        token_wait: TOKEN_WAIT,
+    };
+}
+
+pub struct WsClient<H> {
+    host: H,
     state: AppState,
-    ws_url: String,
+    /// The pairing this client connects, to whichever environment it names
+    /// at each attempt.
+    link: Arc<Link>,
+    timings: LinkTimings,
 }
 
-impl WsClient {
-    pub fn new(app: AppHandle, state: AppState, ws_url: String) -> Self {
-        Self { app, state, ws_url }
+impl<H: Host> WsClient<H> {
+    pub fn new(host: H, state: AppState, link: Arc<Link>) -> Self {
+        Self {
+            host,
+            state,
+            link,
+            timings: LinkTimings::PRODUCTION,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_timings(self, timings: LinkTimings) -> Self {
+        Self { timings, ..self }
     }
 
     /// Main entry point: connect and run, with automatic reconnection.
     pub async fn run(&self) {
-        log::info!("WsClient starting, url={}", self.ws_url);
+        log::info!(
+            "WsClient starting for {}, url={}",
+            self.link.id,
+            self.link.environment().ws_url
+        );
         let mut attempt = 0u32;
         // Consecutive handshake-403 count (see REVOKE_403_THRESHOLD).
         let mut consecutive_403s = 0u32;
@@ -81,20 +144,22 @@ impl WsClient {
                         // the window opens on demand), so we can't rely on the
                         // frontend's token_invalid listener to clear the token +
                         // flip the tray.
-                        if let Err(e) =
-                            crate::commands::clear_device_token(&self.app, &self.state).await
-                        {
+                        if let Err(e) = self.host.unlink(&self.state, &self.link.id).await {
                             log::error!("Failed to clear device token after revocation: {e}");
                         }
                         // Also notify the frontend (when a window is open) so it
                         // routes to the PairingScreen instead of the paired view.
-                        let _ = self.app.emit("token_invalid", ());
+                        self.host.emit_event(
+                            "token_invalid",
+                            serde_json::json!({ "pairing": &self.link.id }),
+                        );
                         return;
                     }
                     if close_code == Some(CLOSE_SESSION_EXPIRED) {
                         log::info!("Session expired, reconnecting immediately");
-                        let _ = self.app.emit("token_refresh_needed", ());
-                        tokio::time::sleep(Duration::from_millis(500)).await;
+                        self.host
+                            .emit_event("token_refresh_needed", serde_json::Value::Null);
+                        tokio::time::sleep(self.timings.token_wait).await;
                         // Fall through to reconnect with attempt = 0 (no backoff)
                     }
                     // Normal disconnect — reconnect
@@ -116,18 +181,17 @@ impl WsClient {
             }
 
             // Stop reconnecting if the user explicitly disconnected.
-            if self.state.shutdown_requested.load(Ordering::SeqCst) {
+            if self.link.shutdown_requested.load(Ordering::SeqCst) {
                 self.set_status(ConnectionStatus::Disconnected).await;
                 return;
             }
 
             self.set_status(ConnectionStatus::Reconnecting).await;
+            self.state.metrics.record_reconnect(&self.link.id);
 
             // Exponential backoff with jitter
-            let base = Duration::from_secs(1 << attempt.min(4));
-            let backoff = base.min(MAX_BACKOFF);
             let jitter_factor = rand::thread_rng().gen_range(0.8..1.2);
-            let wait = backoff.mul_f64(jitter_factor);
+            let wait = backoff(attempt, &self.timings).mul_f64(jitter_factor);
 
             log::info!(
                 "Reconnecting in {:.1}s (attempt {attempt})",
@@ -135,21 +199,23 @@ impl WsClient {
             );
 
             // Request a fresh token from the frontend before reconnecting
-            let _ = self.app.emit("token_refresh_needed", ());
+            self.host
+                .emit_event("token_refresh_needed", serde_json::Value::Null);
 
             tokio::select! {
                 _ = tokio::time::sleep(wait) => {}
-                _ = self.state.ws_shutdown.notified() => {}
+                _ = self.link.ws_shutdown.notified() => {}
+                _ = self.link.ws_reconnect.notified() => {}
             }
 
             // Re-check after waking: a disconnect during backoff should stop here.
-            if self.state.shutdown_requested.load(Ordering::SeqCst) {
+            if self.link.shutdown_requested.load(Ordering::SeqCst) {
                 self.set_status(ConnectionStatus::Disconnected).await;
                 return;
             }
 
             // Brief wait for token to arrive
-            tokio::time::sleep(Duration::from_millis(500)).await;
+            tokio::time::sleep(self.timings.token_wait).await;
         }
     }
 
@@ -160,36 +226,31 @@ impl WsClient {
         &self,
         consecutive_403s: u32,
     ) -> Result<Option<u16>, Box<dyn std::error::Error + Send + Sync>> {
-        let token = self.state.auth_token.read().await.clone();
+        let token = self.link.auth_token.read().await.clone();
+        let ws_url = self.link.environment().ws_url;
 
         // Build request — use subprotocol auth in production, query params in dev
-        let user_agent = format!("BeakrDesktop/{}", env!("CARGO_PKG_VERSION"));
+        let user_agent = crate::network::user_agent();
 
+        // Proxy and extra CAs from Settings (see `network`).
+        let network = self.state.network.current();
         let connect_result = if let Some(token) = token {
-            let mut request = self.ws_url.as_str().into_client_request()?;
-            let subprotocol = format!("beakr-v1, bearer.{token}");
-            request.headers_mut().insert(
-                "Sec-WebSocket-Protocol",
-                HeaderValue::from_str(&subprotocol)?,
-            );
-            request
-                .headers_mut()
-                .insert("User-Agent", HeaderValue::from_str(&user_agent)?);
-            tokio_tungstenite::connect_async(request).await
+            network
+                .connect_ws(handshake_request(&ws_url, &token)?)
+                .await
         } else if cfg!(debug_assertions) && std::env::var("BEAKR_DEV_IDENTITY").is_ok() {
             // Dev identity is OPT-IN (ENG-1582 bug 2): auto-connecting as a
             // hardcoded dev_local identity pre-pairing created a decoy device
             // row that confused the device picker and online-status gate.
             // Set BEAKR_DEV_IDENTITY=1 to use it deliberately.
             let dev_url = format!(
-                "{}?identity_id=dev_local&email=dev@localhost&identity_name=dev&display_name=Dev+User",
-                self.ws_url
+                "{ws_url}?identity_id=dev_local&email=dev@localhost&identity_name=dev&display_name=Dev+User"
             );
             let mut request = dev_url.as_str().into_client_request()?;
             request
                 .headers_mut()
                 .insert("User-Agent", HeaderValue::from_str(&user_agent)?);
-            tokio_tungstenite::connect_async(request).await
+            network.connect_ws(request).await
         } else {
             return Err("No auth token available".into());
         };
@@ -222,28 +283,10 @@ impl WsClient {
 
         // Send register message
         let device_name = self.state.device_name.read().await.clone();
-        let scoped_folders = self.state.scoped_folders.read().await.clone();
-
-        // Per-CLI readiness rides registration (ENG-1536): free signals only,
-        // no version spawn here — keep the connect handshake snappy.
-        let settings = crate::config::load_settings(&self.app);
-        let (coding_agents, coding_agent_default) = {
-            use crate::tools::coding_agent::readiness::{detect, effective_default};
-            let claude = detect(
-                "claude",
-                settings.claude_binary_path.as_deref(),
-                settings.claude_auth_ok,
-                false,
-            );
-            let codex = detect("codex", None, settings.codex_auth_ok, false);
-            let (claude, codex) = tokio::join!(claude, codex);
-            let agents = vec![claude, codex];
-            // Report the EFFECTIVE default (explicit setting, else the CLI
-            // the user actually has) so the web's "via <CLI>" never names a
-            // CLI this machine wouldn't run.
-            let default = effective_default(settings.default_cli.as_deref(), &agents);
-            (Some(agents), Some(default.to_string()))
-        };
+        let scoped_folders = self.scoped_folders().await;
+
+        // Per-CLI readiness rides registration (ENG-1536).
+        let coding = self.host.coding_registration().await;
 
         let register = OutgoingMessage::Register {
             device_name,
@@ -251,15 +294,23 @@ impl WsClient {
             scoped_folders,
             platform_version: Some(os_version()),
             app_version: Some(env!("CARGO_PKG_VERSION").to_string()),
-            coding_agents,
-            coding_agent_default,
+            coding_agents: coding.agents,
+            coding_agent_default: coding.default_cli,
+            coding_usage: coding.usage,
+            resume: if self.link.engine_resumes.load(Ordering::Relaxed) {
+                self.link.journal.resume()
+            } else {
+                Vec::new()
+            },
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: Box::new(Capabilities::current()),
         };
 
         let register_json = serde_json::to_string(&register)?;
         write.send(Message::Text(register_json)).await?;
 
         // Wait for registered response
-        let registered_msg = tokio::time::timeout(Duration::from_secs(10), read.next())
+        let registered_msg = tokio::time::timeout(self.timings.register_timeout, read.next())
             .await?
             .ok_or("Connection closed before registration")??;
 
@@ -280,17 +331,31 @@ impl WsClient {
             format!("Failed to parse registration response: {e} (raw: {registered_text:?})")
         })?;
 
-        let device_id = match incoming {
-            IncomingMessage::Registered { device_id } => device_id,
+        let (device_id, negotiated) = match incoming {
+            IncomingMessage::Registered {
+                device_id,
+                protocol_version,
+                capabilities,
+            } => (
+                device_id,
+                Negotiated::from_registered(protocol_version, capabilities),
+            ),
             _ => return Err("Expected 'registered' message".into()),
         };
 
-        *self.state.device_id.write().await = Some(device_id.clone());
+        self.link
+            .engine_resumes
+            .store(negotiated.supports(feature::RESUME), Ordering::Relaxed);
+        *self.link.device_id.write().await = Some(device_id.clone());
         self.set_status(ConnectionStatus::Connected).await;
-        log::info!("Connected and registered as device {device_id}");
+        log::info!(
+            "Connected and registered as device {device_id} (protocol v{}, features: {})",
+            negotiated.protocol_version,
+            negotiated.features.join(", ")
+        );
 
         // Run message loop
-        let close_code = self.message_loop(&mut write, &mut read).await;
+        let close_code = self.message_loop(&mut write, &mut read, &negotiated).await;
 
         self.set_status(ConnectionStatus::Disconnected).await;
         Ok(close_code)
@@ -304,22 +369,29 @@ impl WsClient {
             Message,
         >,
         read: &mut futures_util::stream::SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+        negotiated: &Negotiated,
     ) -> Option<u16> {
-        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        let timings = self.timings;
+        let mut heartbeat = tokio::time::interval(timings.heartbeat);
         heartbeat.tick().await; // consume the first immediate tick
 
-        let mut ping = tokio::time::interval(PING_INTERVAL);
+        let mut ping = tokio::time::interval(timings.ping);
         ping.tick().await; // consume the first immediate tick
 
-        let mut liveness = tokio::time::interval(LIVENESS_CHECK_INTERVAL);
+        let mut liveness = tokio::time::interval(timings.liveness_check);
         liveness.tick().await; // consume the first immediate tick
 
-        // Outbound channel for spawned request tasks (ENG-1527). Handlers run
-        // as their own tasks so a minutes-long tool call can't stall this
-        // select loop — stalling it would starve the Ping/Pong liveness cycle
-        // and force a spurious reconnect mid-run (the ENG-1261 detector), and
-        // would serialize every other tool behind the slow one. Tasks send
-        // their frames here; only this loop touches the socket.
+        // Spawned request tasks (ENG-1527) write into the journal, not the
+        // socket. Handlers run as their own tasks so a minutes-long tool call
+        // can't stall this select loop — stalling it would starve the
+        // Ping/Pong liveness cycle and force a spurious reconnect mid-run (the
+        // ENG-1261 detector), and would serialize every other tool behind the
+        // slow one. Only this loop touches the socket; whatever the last
+        // connection left unacknowledged goes out first, to an engine that
+        // takes replays.
+        let journal = self.link.journal.clone();
+        journal.attach(negotiated.supports(feature::RESUME));
+
         let (out_tx, mut out_rx) =
             tokio::sync::mpsc::channel::<OutgoingMessage>(OUTBOUND_BUFFER);
 
@@ -328,11 +400,14 @@ impl WsClient {
         // path below via the guard — a stale sender would silently drop
         // pushes into a dead channel.
         *self
-            .state
+            .link
             .ws_outbound
             .write()
             .expect("ws_outbound lock poisoned") = Some(out_tx.clone());
-        let _outbound_guard = OutboundGuard(self.state.ws_outbound.clone());
+        let _outbound_guard = OutboundGuard {
+            sender: self.link.ws_outbound.clone(),
+            journal: journal.clone(),
+        };
 
         // Time of the last inbound frame of ANY kind. A live link refreshes this
         // on every Ping->Pong round-trip; a half-open socket lets it go stale,
@@ -340,11 +415,20 @@ impl WsClient {
         // arm could block forever on a silently-dead socket while the tray still
         // showed "Connected" and the engine's online key expired (ENG-1261).
         let mut last_inbound = Instant::now();
+        // When the Ping its Pong answers went out, for the round-trip.
+        let mut ping_sent: Option<Instant> = None;
+        let heartbeat_rtt = &self.link.heartbeat_rtt;
+        *heartbeat_rtt.write().expect("rtt lock poisoned") = None;
 
         loop {
             tokio::select! {
                 _ = heartbeat.tick() => {
-                    let msg = serde_json::to_string(&OutgoingMessage::Heartbeat)
+                    let metrics = (negotiated.supports(feature::METRICS)
+                        && self.state.metrics.sharing())
+                    .then(|| {
+                        Box::new(crate::metrics::pairing_snapshot(&self.state, &self.link.id))
+                    });
+                    let msg = serde_json::to_string(&OutgoingMessage::Heartbeat { metrics })
                         .unwrap_or_default();
                     if write.send(Message::Text(msg)).await.is_err() {
                         return None;
@@ -356,21 +440,44 @@ impl WsClient {
                     // back, so `last_inbound` goes stale and the liveness branch
                     // below forces a reconnect. (A `write.send` that errors here —
                     // e.g. once the OS finally times out the socket — also exits.)
-                    if write.send(Message::Ping(Vec::new())).await.is_err() {
+                    // The payload is the journal watermark; its Pong acks.
+                    let watermark = journal.watermark().to_be_bytes().to_vec();
+                    if write.send(Message::Ping(watermark)).await.is_err() {
                         return None;
                     }
+                    ping_sent = Some(Instant::now());
                 }
 
                 _ = liveness.tick() => {
-                    if link_is_dead(last_inbound.elapsed(), LIVENESS_TIMEOUT) {
+                    if link_is_dead(last_inbound.elapsed(), timings.liveness_timeout) {
                         log::warn!(
-                            "No inbound frame for {:?} (> {LIVENESS_TIMEOUT:?}) — link is dead, reconnecting",
+                            "No inbound frame for {:?} (> {:?}) — link is dead, reconnecting",
                             last_inbound.elapsed(),
+                            timings.liveness_timeout,
                         );
                         return None;
                     }
                 }
 
+                _ = journal.ready() => {
+                    for _ in 0..REPLAY_BATCH {
+                        let Some(frame) = journal.next_unsent() else {
+                            break;
+                        };
+                        let id = frame.id;
+                        if let Some(message) = encode_for_peer(frame, negotiated) {
+                            if let Err(e) = write.send(message).await {
+                                log::error!("Failed to send outgoing frame: {e}");
+                                return None;
+                            }
+                        }
+                        journal.mark_sent(id);
+                    }
+                    if journal.next_unsent().is_some() {
+                        journal.rewake();
+                    }
+                }
+
                 outgoing = out_rx.recv() => {
                     // recv() can't yield None here: `out_tx` lives in this
                     // scope for the whole loop, so the channel never closes.
@@ -395,14 +502,30 @@ impl WsClient {
                             last_inbound = Instant::now();
                             match message {
                                 Message::Text(text) => {
-                                    self.handle_text_message(&text, &out_tx);
+                                    self.handle_text_message(&text);
+                                }
+                                Message::Binary(bytes) => {
+                                    let compression = negotiated.compression.as_deref();
+                                    match codec::decode(&bytes, compression) {
+                                        Ok(text) => self.handle_text_message(&text),
+                                        Err(e) => log::warn!("Dropped a binary frame: {e}"),
+                                    }
                                 }
                                 Message::Close(frame) => {
                                     let code = frame.as_ref().map(|f| f.code.into());
                                     log::info!("WebSocket closed with code: {code:?}");
                                     return code;
                                 }
-                                // Pong / Ping / Binary: liveness already recorded.
+                                Message::Pong(payload) => {
+                                    if let Some(sent) = ping_sent.take() {
+                                        *heartbeat_rtt.write().expect("rtt lock poisoned") =
+                                            Some(sent.elapsed());
+                                    }
+                                    if let Ok(mark) = <[u8; 8]>::try_from(payload.as_slice()) {
+                                        journal.ack_through(u64::from_be_bytes(mark));
+                                    }
+                                }
+                                // Ping: liveness already recorded.
                                 _ => {}
                             }
                         }
@@ -417,8 +540,8 @@ impl WsClient {
                     }
                 }
 
-                _ = self.state.folders_changed.notified() => {
-                    let folders = self.state.scoped_folders.read().await.clone();
+                _ = self.link.folders_changed.notified() => {
+                    let folders = self.scoped_folders().await;
                     let msg = serde_json::to_string(&OutgoingMessage::UpdateFolders {
                         scoped_folders: folders,
                     }).unwrap_or_default();
@@ -428,15 +551,21 @@ impl WsClient {
                     log::info!("Sent folder update to server");
                 }
 
-                _ = self.state.ws_shutdown.notified() => {
+                _ = self.link.ws_shutdown.notified() => {
                     // Only tear down on a real, user-requested disconnect. A stray
                     // notify permit left over from a previous disconnect must not
                     // close a freshly re-established connection.
-                    if self.state.shutdown_requested.load(Ordering::SeqCst) {
+                    if self.link.shutdown_requested.load(Ordering::SeqCst) {
                         let _ = write.send(Message::Close(None)).await;
                         return None;
                     }
                 }
+
+                _ = self.link.ws_reconnect.notified() => {
+                    log::info!("Reconnecting on request (environment changed)");
+                    let _ = write.send(Message::Close(None)).await;
+                    return None;
+                }
             }
         }
     }
@@ -444,11 +573,7 @@ impl WsClient {
     /// Handle an incoming text message. Requests are dispatched as their own
     /// tasks (never awaited here — see the outbound-channel note in
     /// `message_loop`); cancels resolve against the in-flight registry.
-    fn handle_text_message(
-        &self,
-        text: &str,
-        out_tx: &tokio::sync::mpsc::Sender<OutgoingMessage>,
-    ) {
+    fn handle_text_message(&self, text: &str) {
         let incoming: IncomingMessage = match serde_json::from_str(text) {
             Ok(m) => m,
             Err(e) => {
@@ -463,10 +588,10 @@ impl WsClient {
                 tool,
                 params,
             } => {
-                let app = self.app.clone();
+                let host = self.host.clone();
                 let state = self.state.clone();
-                let out_tx = out_tx.clone();
-                tokio::spawn(run_request(app, state, request_id, tool, params, out_tx));
+                let link = self.link.clone();
+                tokio::spawn(run_request(host, state, link, request_id, tool, params));
             }
             IncomingMessage::Cancel { request_id } => {
                 if self.state.inflight.cancel(&request_id) {
@@ -477,6 +602,39 @@ impl WsClient {
                     log::debug!("Cancel for unknown/finished request {request_id}");
                 }
             }
+            IncomingMessage::Turn {
+                request_id,
+                prompt,
+                end_session,
+            } => {
+                let inflight = &self.state.inflight;
+                let inputs = prompt
+                    .map(RunInput::Prompt)
+                    .into_iter()
+                    .chain(end_session.then_some(RunInput::End));
+                let mut delivered = true;
+                for input in inputs {
+                    delivered &= inflight.send_input(&request_id, input);
+                }
+                if !delivered {
+                    log::debug!("Turn for unknown/finished/single-shot request {request_id}");
+                }
+            }
+            IncomingMessage::PermissionDecision {
+                request_id,
+                permission_id,
+                allow,
+                message,
+            } => {
+                let decision = RunInput::Decision {
+                    permission_id,
+                    allow,
+                    message,
+                };
+                if !self.state.inflight.send_input(&request_id, decision) {
+                    log::debug!("Permission decision for unknown/finished request {request_id}");
+                }
+            }
             IncomingMessage::Registered { .. } => {
                 log::warn!("Unexpected 'registered' message during message loop");
             }
@@ -484,29 +642,37 @@ impl WsClient {
     }
 
     async fn set_status(&self, status: ConnectionStatus) {
-        crate::tray::update_tray_status(&self.app, &status);
-        let status_str = serde_json::to_value(&status).ok();
-        *self.state.ws_status.write().await = status;
-        let _ = self.app.emit("ws:status_changed", status_str);
+        *self.link.ws_status.write().expect("status lock poisoned") = status.clone();
+        self.host.status_changed(&self.state, &self.link.id, &status);
+    }
+
+    /// The scoped folders this pairing reaches.
+    async fn scoped_folders(&self) -> Vec<String> {
+        let scoped = self.state.scoped_folders.read().await.clone();
+        self.link.pairing().scope(&scoped)
     }
 }
 
 /// Body of one spawned request task (ENG-1527): register for cancellation,
 /// run the tool, always emit exactly one terminal `Response` — success, error,
 /// or "cancelled" — so the engine side never hangs on a cancelled request.
-async fn run_request(
-    app: AppHandle,
+/// Frames go into the journal, so a connection lost mid-request delays them
+/// rather than dropping them.
+async fn run_request<H: Host>(
+    host: H,
     state: AppState,
+    link: Arc<Link>,
     request_id: String,
     tool: String,
     params: serde_json::Value,
-    out_tx: tokio::sync::mpsc::Sender<OutgoingMessage>,
 ) {
-    let scoped_folders = state.scoped_folders.read().await.clone();
+    let scoped = state.scoped_folders.read().await.clone();
+    let scoped_folders = link.pairing().scope(&scoped);
     let cancel = state.inflight.register(&request_id);
+    link.journal.open(&request_id);
 
     // Notify frontend that a tool request started
-    let _ = app.emit(
+    host.emit_event(
         "tool:request_started",
         serde_json::json!({
             "request_id": &request_id,
@@ -518,11 +684,30 @@ async fn run_request(
     // Read-only tools are safely droppable mid-flight, so a plain select is
     // enough. Streaming tools (the coding runs of ENG-1528) must clean up
     // their children on cancel, so they receive the signal itself and manage
-    // their own cancellation instead of being dropped by this outer race.
+    // their own cancellation instead of being dropped by this outer race;
+    // so do the walks, whose threads a dropped future would leave running.
+    let started = Instant::now();
+    let cancel_seen = cancel.clone();
     let response = if tools::is_streaming(&tool) {
-        let stream = crate::ws::ToolStream::new(request_id.clone(), out_tx.clone());
-        tools::dispatch_streaming(&app, &state, &tool, params, &scoped_folders, &stream, cancel)
-            .await
+        let (chunk_tx, mut chunk_rx) = tokio::sync::mpsc::channel(OUTBOUND_BUFFER);
+        let journal = link.journal.clone();
+        let forward = tokio::spawn(async move {
+            while let Some(chunk) = chunk_rx.recv().await {
+                journal.push(&chunk).await;
+            }
+        });
+        let stream = crate::ws::ToolStream::new(request_id.clone(), chunk_tx);
+        let response = if tools::streams_locally(&tool) {
+            tools::dispatch_local_streaming(&tool, params, &scoped_folders, &state, &stream, cancel)
+                .await
+        } else {
+            host.dispatch_streaming(&state, &tool, params, &scoped_folders, &stream, cancel)
+                .await
+        };
+        // Every chunk is journaled before the terminal response.
+        drop(stream);
+        let _ = forward.await;
+        response
     } else {
         let mut cancel = cancel;
         tokio::select! {
@@ -532,6 +717,13 @@ async fn run_request(
     };
 
     state.inflight.finish(&request_id);
+    state.metrics.record_call(
+        &tool,
+        Some(&link.id),
+        &response,
+        cancel_seen.is_cancelled(),
+        started.elapsed(),
+    );
 
     let (outgoing, result_status) = match response {
         Ok((data, bytes)) => (
@@ -541,6 +733,7 @@ async fn run_request(
                 data: Some(data),
                 error: None,
                 bytes_transferred: bytes,
+                bytes_saved: None,
             },
             "success",
         ),
@@ -551,13 +744,14 @@ async fn run_request(
                 data: None,
                 error: Some(e),
                 bytes_transferred: None,
+                bytes_saved: None,
             },
             "error",
         ),
     };
 
     // Notify frontend that the request completed
-    let _ = app.emit(
+    host.emit_event(
         "tool:request_completed",
         serde_json::json!({
             "request_id": &request_id,
@@ -565,12 +759,27 @@ async fn run_request(
         }),
     );
 
-    // A send failure means the connection this request arrived on is gone;
-    // the engine's request future died with it, so dropping the response
-    // mirrors the pre-ENG-1527 behavior.
-    if out_tx.send(outgoing).await.is_err() {
-        log::warn!("Connection closed before response for {request_id} could be sent");
-    }
+    link.journal.push(&outgoing).await;
+}
+
+/// The upgrade request for `ws_url` carrying the device token. The token
+/// rides the subprotocol header, never the URL, so it stays out of proxy and
+/// server access logs.
+pub(crate) fn handshake_request(
+    ws_url: &str,
+    token: &str,
+) -> Result<Request, Box<dyn std::error::Error + Send + Sync>> {
+    let mut request = ws_url.into_client_request()?;
+    let subprotocol = format!("beakr-v1, bearer.{token}");
+    request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        HeaderValue::from_str(&subprotocol)?,
+    );
+    request.headers_mut().insert(
+        "User-Agent",
+        HeaderValue::from_str(&crate::network::user_agent())?,
+    );
+    Ok(request)
 }
 
 /// True when a connect error is a 403 Forbidden returned at the HTTP
@@ -589,6 +798,55 @@ fn handshake_403_is_terminal(consecutive_403s: u32) -> bool {
     consecutive_403s >= REVOKE_403_THRESHOLD
 }
 
+/// Reconnect delay before jitter: doubles per failed attempt from
+/// `backoff_unit`, stops doubling at 16x, never above `max_backoff`.
+fn backoff(attempt: u32, timings: &LinkTimings) -> Duration {
+    (timings.backoff_unit * (1u32 << attempt.min(4))).min(timings.max_backoff)
+}
+
+/// The message to write for a journaled frame, shaped to what the engine
+/// accepted at registration. Large frames are compressed when that was
+/// negotiated, and a compressed terminal response reports the saving in its
+/// `bytes_saved`. `None` drops the frame: chunks to an engine without
+/// `response_chunk`, and chunks over its frame limit. An oversized terminal
+/// response becomes an error response so the request still settles.
+fn encode_for_peer(frame: Frame, negotiated: &Negotiated) -> Option<Message> {
+    if !frame.terminal && !negotiated.supports(feature::RESPONSE_CHUNK) {
+        return None;
+    }
+    let compression = negotiated.compression.as_deref();
+    let message = if frame.terminal {
+        codec::encode_reporting_saving(frame.text, compression)
+    } else {
+        codec::encode(frame.text, compression)
+    };
+    let wire = message.len() as u64;
+    match negotiated.max_frame_bytes {
+        Some(max) if wire > max && !frame.terminal => {
+            log::warn!(
+                "Dropped a {wire}-byte chunk of {}: the engine accepts {max}",
+                frame.request_id
+            );
+            None
+        }
+        Some(max) if wire > max => {
+            let error = OutgoingMessage::Response {
+                request_id: frame.request_id,
+                status: ResponseStatus::Error,
+                data: None,
+                error: Some(format!(
+                    "frame_too_large: response is {wire} bytes, the engine accepts {max}"
+                )),
+                bytes_transferred: None,
+                bytes_saved: None,
+            };
+            let text = serde_json::to_string(&error).ok()?;
+            Some(codec::encode(text, compression))
+        }
+        _ => Some(message),
+    }
+}
+
 /// Whether the WebSocket link should be considered dead, given how long it has
 /// been since the last inbound frame. Extracted as a pure function so the
 /// liveness invariant is unit-testable without a live socket.
@@ -701,6 +959,62 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn backoff_doubles_then_holds() {
+        let t = LinkTimings::PRODUCTION;
+        let secs: Vec<u64> = (0..7).map(|a| backoff(a, &t).as_secs()).collect();
+        assert_eq!(secs, [1, 2, 4, 8, 16, 16, 16]);
+        let capped = LinkTimings {
+            max_backoff: Duration::from_secs(5),
+            ..t
+        };
+        assert_eq!(backoff(9, &capped), Duration::from_secs(5));
+        // A saturated attempt counter must not overflow the shift.
+        assert_eq!(backoff(u32::MAX, &t), Duration::from_secs(16));
+    }
+
+    #[test]
+    fn frames_are_shaped_to_the_negotiated_peer() {
+        let frame = |terminal: bool, text: String| Frame {
+            id: 1,
+            request_id: "req-1".into(),
+            terminal,
+            text,
+        };
+        let text_of = |sent: Option<Message>| sent.map(|m| m.into_text().unwrap());
+        let legacy = Negotiated::from_registered(None, None);
+        let sent = encode_for_peer(frame(false, "{}".into()), &legacy);
+        assert_eq!(text_of(sent).as_deref(), Some("{}"));
+
+        let no_chunks = Negotiated {
+            features: vec![],
+            max_frame_bytes: Some(8),
+            ..legacy.clone()
+        };
+        assert!(encode_for_peer(frame(false, "{}".into()), &no_chunks).is_none());
+        let sent = encode_for_peer(frame(true, "{}".into()), &no_chunks);
+        assert_eq!(text_of(sent).as_deref(), Some("{}"));
+        let sent = encode_for_peer(frame(true, "x".repeat(9)), &no_chunks);
+        let replaced: serde_json::Value = serde_json::from_str(&text_of(sent).unwrap()).unwrap();
+        assert_eq!(replaced["status"], "error");
+        assert!(replaced["error"].as_str().unwrap().starts_with("frame_too_large:"));
+
+        // Compressed, a frame is measured by what goes on the wire, and the
+        // response says what that saved.
+        let deflate = Negotiated {
+            max_frame_bytes: Some(1024),
+            compression: Some(codec::DEFLATE.into()),
+            ..legacy
+        };
+        let big = format!("{{\"content\":\"{}\"}}", "a".repeat(64 * 1024));
+        let sent = encode_for_peer(frame(true, big.clone()), &deflate).unwrap();
+        assert!(sent.is_binary() && sent.len() <= 1024);
+        let text = codec::decode(&sent.into_data(), Some(codec::DEFLATE)).unwrap();
+        let response: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let saved = response["bytes_saved"].as_u64().unwrap();
+        assert!(saved > 63 * 1024 && saved < big.len() as u64, "{saved}");
+    }
+
     #[test]
     fn liveness_constants_are_ordered_for_recovery() {
         // The Ping must fire before the liveness deadline so a healthy link
@@ -712,16 +1026,19 @@ mod tests {
     }
 }
 
-/// Clears `AppState::ws_outbound` when the connection loop exits by any path
-/// (error, close, shutdown), so no one pushes into a dead channel.
-struct OutboundGuard(
-    std::sync::Arc<
+/// Clears `Link::ws_outbound` and detaches the journal when the
+/// connection loop exits by any path (error, close, shutdown), so no one
+/// pushes into a dead channel or waits on a dead socket.
+struct OutboundGuard {
+    sender: std::sync::Arc<
         std::sync::RwLock<Option<tokio::sync::mpsc::Sender<OutgoingMessage>>>,
     >,
-);
+    journal: std::sync::Arc<crate::ws::journal::Journal>,
+}
 
 impl Drop for OutboundGuard {
     fn drop(&mut self) {
-        *self.0.write().expect("ws_outbound lock poisoned") = None;
+        *self.sender.write().expect("ws_outbound lock poisoned") = None;
+        self.journal.detach();
     }
 }
@@ -0,0 +1,146 @@
+//! Frame compression, negotiated at `register`.
+//!
+//! Tool responses are verbose JSON — `list_files` over big trees, base64
+//! binary reads, `search_files` with content — and many users sit on hotel or
+//! field-station Wi-Fi. tungstenite 0.24 has no permessage-deflate, so the
+//! compression lives in the protocol instead: once the engine accepts
+//! `deflate`, a frame of at least `COMPRESS_MIN` bytes goes out as a Binary
+//! message holding the raw-deflated JSON a Text message would have carried.
+//! The opcode is the flag (text frames are never compressed), and the engine
+//! may send its own frames the same way.
+
+use std::io::{Read, Write};
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::ws::protocol::MAX_FRAME_BYTES;
+
+pub const DEFLATE: &str = "deflate";
+/// Encodings this client reads and writes, best first.
+pub const SUPPORTED: &[&str] = &[DEFLATE];
+/// Smaller frames go out as text: the bytes saved would not pay for the CPU.
+const COMPRESS_MIN: usize = 4096;
+
+/// The message carrying a frame's JSON under the negotiated encoding. Falls
+/// back to text when compressing would not shrink it.
+pub fn encode(text: String, compression: Option<&str>) -> Message {
+    if compression != Some(DEFLATE) || text.len() < COMPRESS_MIN {
+        return Message::Text(text);
+    }
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+    match encoder
+        .write_all(text.as_bytes())
+        .and_then(|_| encoder.finish())
+    {
+        Ok(bytes) if bytes.len() < text.len() => Message::Binary(bytes),
+        _ => Message::Text(text),
+    }
+}
+
+/// Like [`encode`] for a JSON object, which goes out compressed with a
+/// `bytes_saved` field appended: how much smaller the object went on the
+/// wire, the field's own few bytes not counted. One pass: the object is
+/// deflated up to its closing brace and flushed, the saving read off the
+/// output so far, and the field and brace deflated after it.
+pub fn encode_reporting_saving(text: String, compression: Option<&str>) -> Message {
+    let Some(body) = text.strip_suffix('}') else {
+        return encode(text, compression);
+    };
+    if compression != Some(DEFLATE) || text.len() < COMPRESS_MIN {
+        return Message::Text(text);
+    }
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+    let compressed = encoder
+        .write_all(body.as_bytes())
+        .and_then(|_| encoder.flush())
+        .and_then(|_| {
+            let saved = text.len().saturating_sub(encoder.get_ref().len());
+            encoder.write_all(format!(",\"bytes_saved\":{saved}}}").as_bytes())
+        })
+        .and_then(|_| encoder.finish());
+    match compressed {
+        Ok(bytes) if bytes.len() < text.len() => Message::Binary(bytes),
+        _ => Message::Text(text),
+    }
+}
+
+/// The JSON inside a Binary message from the engine. Inflation stops past
+/// `MAX_FRAME_BYTES`, so a small frame cannot expand without bound.
+pub fn decode(bytes: &[u8], compression: Option<&str>) -> Result<String, String> {
+    if compression != Some(DEFLATE) {
+        return Err("binary frame, but no compression was negotiated".into());
+    }
+    let mut text = String::new();
+    DeflateDecoder::new(bytes)
+        .take(MAX_FRAME_BYTES + 1)
+        .read_to_string(&mut text)
+        .map_err(|e| format!("undecodable deflate frame: {e}"))?;
+    if text.len() as u64 > MAX_FRAME_BYTES {
+        return Err(format!("frame inflates past {MAX_FRAME_BYTES} bytes"));
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_frames_round_trip_compressed_and_small_ones_stay_text() {
+        let listing = serde_json::json!({
+            "files": (0..500).map(|i| format!("/data/run-{i}/reads.fastq")).collect::<Vec<_>>(),
+        })
+        .to_string();
+        let Message::Binary(bytes) = encode(listing.clone(), Some(DEFLATE)) else {
+            panic!("a repetitive listing should compress");
+        };
+        assert!(bytes.len() * 4 < listing.len());
+        assert_eq!(decode(&bytes, Some(DEFLATE)).unwrap(), listing);
+
+        assert!(matches!(encode(listing, None), Message::Text(_)));
+        assert!(matches!(
+            encode("{}".into(), Some(DEFLATE)),
+            Message::Text(_)
+        ));
+        assert!(decode(&bytes, None).is_err());
+    }
+
+    #[test]
+    fn a_compressed_object_reports_its_saving_inside() {
+        let response = serde_json::json!({
+            "request_id": "req-1",
+            "data": { "content": "A1,0.42,ok\n".repeat(1024) },
+        });
+        let text = response.to_string();
+        let Message::Binary(bytes) = encode_reporting_saving(text.clone(), Some(DEFLATE)) else {
+            panic!("a repetitive response should compress");
+        };
+        let sent: serde_json::Value =
+            serde_json::from_str(&decode(&bytes, Some(DEFLATE)).unwrap()).unwrap();
+        assert_eq!(sent["data"], response["data"]);
+        let saved = sent["bytes_saved"].as_u64().unwrap() as usize;
+        assert!(
+            saved < text.len() && saved + bytes.len() >= text.len(),
+            "{saved}"
+        );
+
+        assert_eq!(
+            encode_reporting_saving(text.clone(), None),
+            Message::Text(text)
+        );
+    }
+
+    #[test]
+    fn inflation_is_bounded() {
+        let bomb = "0".repeat(MAX_FRAME_BYTES as usize + 1);
+        let Message::Binary(bytes) = encode(bomb, Some(DEFLATE)) else {
+            panic!("zeros compress");
+        };
+        assert!(decode(&bytes, Some(DEFLATE))
+            .unwrap_err()
+            .contains("inflates past"));
+    }
+}
@@ -0,0 +1,649 @@
+//! End-to-end coverage for `WsClient` against the mock engine: registration,
+//! requests and streamed chunks, cancel, replay across reconnects, the close
+//! codes, handshake 403s, reconnect backoff, liveness, folder updates and
+//! pairings side by side. The client is the real
+//! one over a real socket; only the app around it is a recording host, and
+//! the timings are shrunk so backoff and liveness play out in milliseconds.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use super::client::{LinkTimings, WsClient};
+use super::host::{CodingRegistration, Host};
+use super::inflight::CancelSignal;
+use super::link::Link;
+use super::mock_engine::{Attempt, EngineConnection, MockEngine, DEVICE_ID};
+use super::ToolStream;
+use crate::pairing::Pairing;
+use crate::state::{AppState, ConnectionStatus};
+
+const TOKEN: &str = "device-token-e2e";
+/// Backoff steps are multiples of this; big enough that one doubling stands
+/// clear of scheduling noise.
+const BACKOFF_UNIT: Duration = Duration::from_millis(25);
+
+fn fast() -> LinkTimings {
+    LinkTimings {
+        // Out of the way: `recv` skips heartbeats anyway.
+        heartbeat: Duration::from_secs(60),
+        ping: Duration::from_millis(50),
+        liveness_timeout: Duration::from_millis(300),
+        liveness_check: Duration::from_millis(25),
+        backoff_unit: BACKOFF_UNIT,
+        max_backoff: Duration::from_secs(30),
+        register_timeout: Duration::from_secs(2),
+        token_wait: Duration::from_millis(5),
+    }
+}
+
+/// Records what the client asks of the app. Streaming tools stand in for a
+/// coding run: two chunks, then hold until cancelled.
+#[derive(Clone, Default)]
+struct RecordingHost {
+    log: Arc<Mutex<HostLog>>,
+}
+
+#[derive(Default)]
+struct HostLog {
+    statuses: Vec<ConnectionStatus>,
+    events: Vec<String>,
+    unlinked: bool,
+}
+
+impl RecordingHost {
+    fn statuses(&self) -> Vec<ConnectionStatus> {
+        self.log.lock().unwrap().statuses.clone()
+    }
+
+    fn events(&self) -> Vec<String> {
+        self.log.lock().unwrap().events.clone()
+    }
+
+    fn unlinked(&self) -> bool {
+        self.log.lock().unwrap().unlinked
+    }
+}
+
+impl Host for RecordingHost {
+    fn status_changed(&self, _state: &AppState, _pairing: &str, status: &ConnectionStatus) {
+        self.log.lock().unwrap().statuses.push(status.clone());
+    }
+
+    fn emit_event(&self, event: &str, _payload: Value) {
+        self.log.lock().unwrap().events.push(event.to_string());
+    }
+
+    async fn coding_registration(&self) -> CodingRegistration {
+        CodingRegistration::default()
+    }
+
+    async fn unlink(&self, state: &AppState, pairing: &str) -> Result<(), String> {
+        if let Some(link) = state.links.get(pairing) {
+            *link.auth_token.write().await = None;
+        }
+        self.log.lock().unwrap().unlinked = true;
+        Ok(())
+    }
+
+    async fn dispatch_streaming(
+        &self,
+        _state: &AppState,
+        _tool: &str,
+        _params: Value,
+        _scoped_folders: &[String],
+        stream: &ToolStream,
+        mut cancel: CancelSignal,
+    ) -> Result<(Value, Option<u64>), String> {
+        stream
+            .chunk(json!({ "kind": "text", "text": "working" }))
+            .await;
+        stream
+            .chunk(json!({ "kind": "text", "text": "still working" }))
+            .await;
+        cancel.cancelled().await;
+        Err("cancelled by server".to_string())
+    }
+}
+
+struct Harness {
+    engine: MockEngine,
+    host: RecordingHost,
+    state: AppState,
+    link: Arc<Link>,
+    run: tokio::task::JoinHandle<()>,
+}
+
+/// A paired link to `engine`, reaching `folders` of the scoped ones.
+async fn link_to(
+    state: &AppState,
+    id: &str,
+    engine: &MockEngine,
+    folders: Option<Vec<String>>,
+) -> Arc<Link> {
+    let mut profile = crate::environment::default_profile();
+    profile.ws_url = engine.url();
+    let pairing = Pairing {
+        id: id.into(),
+        name: id.into(),
+        environment: profile.id.clone(),
+        folders,
+    };
+    let link = Arc::new(Link::new(pairing, profile));
+    *link.auth_token.write().await = Some(TOKEN.to_string());
+    state.links.insert(link.clone());
+    link
+}
+
+impl Harness {
+    /// A paired client running against a fresh mock engine. `script` fails
+    /// the first handshakes with those statuses.
+    async fn start(script: &[u16]) -> Self {
+        Self::start_in(Vec::new(), script).await
+    }
+
+    async fn start_in(scoped_folders: Vec<String>, script: &[u16]) -> Self {
+        let engine = MockEngine::start().await;
+        engine.reject_handshakes(script.iter().copied());
+        let host = RecordingHost::default();
+        let state = AppState::new();
+        *state.scoped_folders.write().await = scoped_folders;
+        let link = link_to(&state, "lab", &engine, None).await;
+        let client =
+            WsClient::new(host.clone(), state.clone(), link.clone()).with_timings(fast());
+        let run = tokio::spawn(async move { client.run().await });
+        Self {
+            engine,
+            host,
+            state,
+            link,
+            run,
+        }
+    }
+
+    /// The next registered connection, once the client has seen
+    /// `registered` too.
+    async fn connection(&mut self) -> EngineConnection {
+        let conn = self.engine.next_connection().await;
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while self.status().await != ConnectionStatus::Connected {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("client never reached Connected");
+        conn
+    }
+
+    /// Wait for `run()` to return on its own (revocation, user disconnect).
+    async fn stopped(&mut self) {
+        tokio::time::timeout(Duration::from_secs(10), &mut self.run)
+            .await
+            .expect("client kept running")
+            .unwrap();
+    }
+
+    async fn status(&self) -> ConnectionStatus {
+        self.link.status()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        self.run.abort();
+    }
+}
+
+fn gaps(attempts: &[Attempt]) -> Vec<Duration> {
+    attempts.windows(2).map(|w| w[1].at - w[0].at).collect()
+}
+
+#[tokio::test]
+async fn registers_and_answers_requests() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("notes.txt");
+    std::fs::write(&file, "hello").unwrap();
+    let folder = dir.path().to_string_lossy().to_string();
+    let mut h = Harness::start_in(vec![folder.clone()], &[]).await;
+
+    let mut conn = h.connection().await;
+    assert_eq!(h.engine.attempts()[0].token.as_deref(), Some(TOKEN));
+    assert_eq!(conn.register["scoped_folders"], json!([folder]));
+    assert!(conn.register["device_name"].is_string());
+    assert_eq!(conn.register["protocol_version"], 2);
+    let capabilities = &conn.register["capabilities"];
+    assert_eq!(
+        capabilities["streaming_tools"],
+        json!(["run_coding_agent", "list_files", "search_files"])
+    );
+    assert!(capabilities["tools"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("read_file")));
+    assert_eq!(h.link.device_id.read().await.as_deref(), Some(DEVICE_ID));
+    assert_eq!(h.status().await, ConnectionStatus::Connected);
+
+    conn.request("req-1", "file_info", json!({ "path": file }));
+    let response = conn.recv().await;
+    assert_eq!(response["type"], "response");
+    assert_eq!(response["request_id"], "req-1");
+    assert_eq!(response["status"], "success");
+    assert_eq!(response["data"]["size"], 5);
+
+    conn.request("req-2", "read_file", json!({ "path": "/etc/hosts" }));
+    let refused = conn.recv().await;
+    assert_eq!(refused["status"], "error");
+    assert!(refused["error"].is_string());
+
+    let events = h.host.events();
+    assert!(
+        events.iter().any(|e| e == "tool:request_completed"),
+        "{events:?}"
+    );
+
+    let filed: Vec<_> = crate::metrics::snapshot(&h.state)
+        .tools
+        .into_iter()
+        .map(|t| (t.tool, t.outcome, t.duration.count))
+        .collect();
+    assert_eq!(
+        filed,
+        [
+            ("file_info".to_string(), "ok", 1),
+            ("read_file".to_string(), "error", 1)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn streamed_chunks_arrive_in_order_and_cancel_still_settles() {
+    let mut h = Harness::start(&[]).await;
+    let mut conn = h.connection().await;
+
+    conn.request("run-1", "run_coding_agent", json!({ "prompt": "fix it" }));
+    for seq in 0..2 {
+        let chunk = conn.recv().await;
+        assert_eq!(chunk["type"], "response_chunk");
+        assert_eq!(chunk["request_id"], "run-1");
+        assert_eq!(chunk["seq"], seq);
+    }
+    // An unknown id is a no-op; the run is still going.
+    conn.cancel("run-404");
+    conn.cancel("run-1");
+    let terminal = conn.recv().await;
+    assert_eq!(terminal["type"], "response");
+    assert_eq!(terminal["request_id"], "run-1");
+    assert_eq!(terminal["status"], "error");
+    assert_eq!(terminal["error"], "cancelled by server");
+    assert!(
+        !h.state.inflight.cancel("run-1"),
+        "the run left the registry"
+    );
+}
+
+#[tokio::test]
+async fn a_streamed_listing_arrives_in_chunks_then_sums_up() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        std::fs::write(dir.path().join(name), "x").unwrap();
+    }
+    let folder = dir.path().to_string_lossy().to_string();
+    let mut h = Harness::start_in(vec![folder.clone()], &[]).await;
+    let mut conn = h.connection().await;
+
+    conn.request(
+        "list-1",
+        "list_files",
+        json!({ "path": folder, "stream": true }),
+    );
+    let mut names = Vec::new();
+    let terminal = loop {
+        let frame = conn.recv().await;
+        if frame["type"] != "response_chunk" {
+            break frame;
+        }
+        assert_eq!(frame["request_id"], "list-1");
+        for file in frame["data"]["files"].as_array().unwrap() {
+            names.push(file["name"].as_str().unwrap().to_string());
+        }
+    };
+    names.sort();
+    assert_eq!(names, ["a.txt", "b.txt", "c.txt"]);
+    assert_eq!(terminal["status"], "success");
+    assert_eq!(
+        terminal["data"],
+        json!({ "streamed": true, "total_found": 3, "truncated": false })
+    );
+
+    // Without `stream` the listing is one response, as it always was.
+    conn.request("list-2", "list_files", json!({ "path": folder }));
+    let whole = conn.recv().await;
+    assert_eq!(whole["type"], "response");
+    assert_eq!(whole["data"]["files"].as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn chunks_are_held_back_from_an_engine_that_did_not_accept_them() {
+    let mut h = Harness::start(&[]).await;
+    let conn = h.connection().await;
+    // The first engine predated negotiation; the next one accepts less.
+    h.engine.accept_capabilities(json!({ "features": ["cancel"] }));
+    conn.close(1000);
+    let mut conn = h.connection().await;
+
+    conn.request("run-1", "run_coding_agent", json!({ "prompt": "fix it" }));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    conn.cancel("run-1");
+    let terminal = conn.recv().await;
+    assert_eq!(terminal["type"], "response", "no chunk went out first");
+    assert_eq!(terminal["error"], "cancelled by server");
+}
+
+#[tokio::test]
+async fn large_responses_are_compressed_once_the_engine_accepts_deflate() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("plate.csv");
+    std::fs::write(&file, "A1,0.42,ok\n".repeat(8 * 1024)).unwrap();
+    let folder = dir.path().to_string_lossy().to_string();
+    let mut h = Harness::start_in(vec![folder], &[]).await;
+    let conn = h.connection().await;
+    assert_eq!(conn.register["capabilities"]["compression"], json!(["deflate"]));
+    h.engine.accept_capabilities(json!({
+        "features": ["response_chunk", "cancel"],
+        "compression": "deflate",
+    }));
+    conn.close(1000);
+    let mut conn = h.connection().await;
+
+    conn.request("req-1", "read_file", json!({ "path": file }));
+    let response = conn.recv().await;
+    assert_eq!(response["status"], "success");
+    assert_eq!(response["data"]["content"].as_str().unwrap().len(), 88 * 1024);
+    assert!(response["bytes_saved"].as_u64().unwrap() > 80 * 1024, "{response}");
+    assert_eq!(conn.compressed_frames(), 1);
+
+    // Small frames stay text.
+    conn.request("req-2", "file_info", json!({ "path": file }));
+    let response = conn.recv().await;
+    assert_eq!(response["data"]["size"], 88 * 1024);
+    assert!(response.get("bytes_saved").is_none());
+    assert_eq!(conn.compressed_frames(), 1);
+}
+
+#[tokio::test]
+async fn frames_from_a_dropped_connection_are_replayed_after_reregister() {
+    let mut h = Harness::start(&[]).await;
+    let conn = h.connection().await;
+    h.engine.accept_capabilities(json!({ "features": ["response_chunk", "cancel", "resume"] }));
+    conn.close(1000);
+    let mut conn = h.connection().await;
+    conn.request("run-1", "run_coding_agent", json!({ "prompt": "fix it" }));
+    assert_eq!(conn.recv().await["seq"], 0);
+    assert_eq!(conn.recv().await["seq"], 1);
+
+    // The link drops and the run settles while the client is offline; the
+    // engine refuses the next handshakes to keep it there. The local stop
+    // stands in for anything that ends a run without the engine.
+    h.engine.reject_handshakes([503, 503, 503]);
+    conn.close(1000);
+    conn.closed().await;
+    assert!(h.state.inflight.cancel("run-1"));
+
+    let mut conn = h.connection().await;
+    assert_eq!(
+        conn.register["resume"],
+        json!([{ "request_id": "run-1", "settled": true }])
+    );
+    // Chunks the old connection never acknowledged may come again (the
+    // engine dedupes by seq); the terminal response must come.
+    let terminal = loop {
+        let frame = conn.recv().await;
+        assert_eq!(frame["request_id"], "run-1");
+        if frame["type"] == "response" {
+            break frame;
+        }
+    };
+    assert_eq!(terminal["error"], "cancelled by server");
+}
+
+#[tokio::test]
+async fn an_engine_without_resume_gets_only_frames_never_written() {
+    let mut h = Harness::start(&[]).await;
+    let conn = h.connection().await;
+    h.engine.accept_capabilities(json!({ "features": ["response_chunk", "cancel"] }));
+    conn.close(1000);
+    let conn = h.connection().await;
+    // A silent engine never Pongs: both chunks are written, none acknowledged.
+    conn.go_silent();
+    conn.request("run-1", "run_coding_agent", json!({ "prompt": "fix it" }));
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while h.link.journal.watermark() < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("both chunks should have been written");
+
+    h.engine.reject_handshakes([503, 503, 503]);
+    conn.close(1000);
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while h.status().await == ConnectionStatus::Connected {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("the client should have seen the close");
+    assert!(h.state.inflight.cancel("run-1"));
+
+    let mut conn = h.connection().await;
+    assert!(conn.register.get("resume").is_none(), "{}", conn.register);
+    // The chunks are not sent again; the response journaled while offline is.
+    let terminal = conn.recv().await;
+    assert_eq!(terminal["type"], "response", "replayed {terminal}");
+    assert_eq!(terminal["error"], "cancelled by server");
+}
+
+#[tokio::test]
+async fn pongs_acknowledge_what_the_engine_has_read() {
+    let mut h = Harness::start(&[]).await;
+    let mut conn = h.connection().await;
+    conn.request("req-1", "read_file", json!({ "path": "/etc/hosts" }));
+    assert_eq!(conn.recv().await["type"], "response");
+
+    tokio::time::timeout(fast().ping * 4, async {
+        while h.link.journal.buffered() > 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("the next Pong should have acknowledged the response");
+
+    conn.close(1000);
+    let conn = h.connection().await;
+    assert!(conn.register.get("resume").is_none(), "{}", conn.register);
+}
+
+#[tokio::test]
+async fn close_4011_reconnects_at_once_and_4010_unlinks() {
+    let mut h = Harness::start(&[]).await;
+    let conn = h.connection().await;
+    conn.close(4011);
+    let started = Instant::now();
+    let conn = h.connection().await;
+    // Token wait plus one backoff unit (attempt reset to 0), not a grown
+    // backoff.
+    assert!(
+        started.elapsed() < BACKOFF_UNIT * 8,
+        "{:?}",
+        started.elapsed()
+    );
+    assert!(h.host.events().iter().any(|e| e == "token_refresh_needed"));
+    assert!(!h.host.unlinked());
+
+    conn.close(4010);
+    h.stopped().await;
+    assert_eq!(h.status().await, ConnectionStatus::Revoked);
+    assert!(h.host.unlinked());
+    assert_eq!(*h.link.auth_token.read().await, None);
+    assert!(h.host.events().iter().any(|e| e == "token_invalid"));
+}
+
+#[tokio::test]
+async fn reconnect_backoff_grows_and_resets_after_a_session() {
+    let mut h = Harness::start(&[503, 503, 503, 503]).await;
+    let conn = h.connection().await;
+    let attempts = h.engine.attempts();
+    assert_eq!(attempts.len(), 5);
+    let failed = gaps(&attempts);
+    for pair in failed.windows(2) {
+        assert!(pair[1] > pair[0], "backoff must grow: {failed:?}");
+    }
+    assert!(failed[0] >= BACKOFF_UNIT, "{failed:?}");
+    assert!(
+        h.host.statuses().contains(&ConnectionStatus::Reconnecting),
+        "{:?}",
+        h.host.statuses()
+    );
+
+    // A normal close after a registered session starts over from the
+    // shortest wait.
+    conn.close(1000);
+    h.connection().await;
+    let reset = *gaps(&h.engine.attempts()).last().unwrap();
+    assert!(reset < failed[3], "reset {reset:?} vs grown {failed:?}");
+}
+
+#[tokio::test]
+async fn handshake_403s_revoke_only_after_a_consecutive_run() {
+    // One short of the threshold, then a good handshake: still paired.
+    let mut h = Harness::start(&[403, 403, 403, 403]).await;
+    h.connection().await;
+    assert!(!h.host.unlinked());
+    assert_eq!(*h.link.auth_token.read().await, Some(TOKEN.to_string()));
+    drop(h);
+
+    // A non-403 failure breaks the run, so four 403s, a 500 and four more
+    // 403s never add up to the threshold.
+    let mut h = Harness::start(&[403, 403, 403, 403, 500, 403, 403, 403, 403]).await;
+    h.connection().await;
+    assert!(!h.host.unlinked());
+    drop(h);
+
+    // Five in a row: revoked, unlinked, and no further attempts.
+    let mut h = Harness::start(&[403; 8]).await;
+    h.stopped().await;
+    assert_eq!(h.engine.attempts().len(), 5);
+    assert_eq!(h.status().await, ConnectionStatus::Revoked);
+    assert!(h.host.unlinked());
+    assert!(h.host.events().iter().any(|e| e == "token_invalid"));
+}
+
+#[tokio::test]
+async fn a_silent_link_is_dropped_and_a_live_one_kept() {
+    let mut h = Harness::start(&[]).await;
+    let conn = h.connection().await;
+    let timings = fast();
+
+    // Pongs keep a healthy link up across several liveness windows.
+    assert!(!h.engine.connects_within(timings.liveness_timeout * 3).await);
+
+    conn.go_silent();
+    let silent_at = Instant::now();
+    h.connection().await;
+    let elapsed = silent_at.elapsed();
+    assert!(
+        elapsed >= timings.liveness_timeout - timings.ping,
+        "reconnected before the liveness deadline: {elapsed:?}"
+    );
+    assert_eq!(h.engine.attempts().len(), 2);
+    assert_eq!(h.status().await, ConnectionStatus::Connected);
+}
+
+#[tokio::test]
+async fn folder_changes_are_pushed_and_carried_into_the_next_register() {
+    let mut h = Harness::start(&[]).await;
+    let mut conn = h.connection().await;
+
+    let folders = vec!["/Users/d/data".to_string(), "/Users/d/plates".to_string()];
+    *h.state.scoped_folders.write().await = folders.clone();
+    h.state.notify_folders_changed();
+    let update = conn.recv().await;
+    assert_eq!(update["type"], "update_folders");
+    assert_eq!(update["scoped_folders"], json!(folders));
+
+    conn.close(1000);
+    let conn = h.connection().await;
+    assert_eq!(conn.register["scoped_folders"], json!(folders));
+}
+
+#[tokio::test]
+async fn user_disconnect_stops_the_loop() {
+    let mut h = Harness::start(&[]).await;
+    let mut conn = h.connection().await;
+    h.link
+        .shutdown_requested
+        .store(true, std::sync::atomic::Ordering::SeqCst);
+    h.link.ws_shutdown.notify_one();
+    conn.closed().await;
+    h.stopped().await;
+    assert_eq!(h.status().await, ConnectionStatus::Disconnected);
+    assert_eq!(h.engine.attempts().len(), 1);
+}
+
+#[tokio::test]
+async fn an_edited_environment_moves_the_live_client_to_its_new_url() {
+    let mut h = Harness::start(&[]).await;
+    let mut conn = h.connection().await;
+
+    let mut moved = MockEngine::start().await;
+    h.link.environment.write().unwrap().ws_url = moved.url();
+    h.link.ws_reconnect.notify_one();
+
+    conn.closed().await;
+    let _moved = moved.next_connection().await;
+    assert_eq!(moved.attempts()[0].token.as_deref(), Some(TOKEN));
+    assert_eq!(h.engine.attempts().len(), 1, "no reconnect to the old engine");
+}
+
+#[tokio::test]
+async fn pairings_connect_side_by_side_each_with_its_own_folders() {
+    let dir = tempfile::tempdir().unwrap();
+    let (data, plates) = (dir.path().join("data"), dir.path().join("plates"));
+    for folder in [&data, &plates] {
+        std::fs::create_dir(folder).unwrap();
+        std::fs::write(folder.join("notes.txt"), "hello").unwrap();
+    }
+    let [data, plates] = [data, plates].map(|f| f.to_string_lossy().to_string());
+    let mut h = Harness::start_in(vec![data.clone(), plates.clone()], &[]).await;
+    let mut lab_conn = h.connection().await;
+
+    let mut personal = MockEngine::start().await;
+    let link = link_to(&h.state, "personal", &personal, Some(vec![plates.clone()])).await;
+    let client = WsClient::new(h.host.clone(), h.state.clone(), link).with_timings(fast());
+    let second = tokio::spawn(async move { client.run().await });
+    let mut personal_conn = personal.next_connection().await;
+
+    assert_eq!(lab_conn.register["scoped_folders"], json!([&data, &plates]));
+    assert_eq!(personal_conn.register["scoped_folders"], json!([&plates]));
+
+    // Requests are confined to what the pairing reaches.
+    let in_data = json!({ "path": format!("{data}/notes.txt") });
+    personal_conn.request("req-1", "file_info", in_data.clone());
+    assert_eq!(personal_conn.recv().await["status"], "error");
+    lab_conn.request("req-2", "file_info", in_data);
+    assert_eq!(lab_conn.recv().await["status"], "success");
+
+    // One folder change reaches both, each with its own view of it.
+    *h.state.scoped_folders.write().await = vec![data.clone()];
+    h.state.notify_folders_changed();
+    assert_eq!(lab_conn.recv().await["scoped_folders"], json!([&data]));
+    assert_eq!(personal_conn.recv().await["scoped_folders"], json!([]));
+
+    // Dropping one leaves the other up.
+    personal_conn.close(1000);
+    let _back = personal.next_connection().await;
+    assert_eq!(h.engine.attempts().len(), 1);
+    assert_eq!(h.status().await, ConnectionStatus::Connected);
+    second.abort();
+}
@@ -0,0 +1,113 @@
+//! What the connection loop needs from the app around it.
+//!
+//! [`WsClient`](super::WsClient) owns the socket; everything else it touches —
+//! the tray, window events, saved settings, unlinking on revocation, and the
+//! streaming tools that need an `AppHandle` — goes through [`Host`]. The app
+//! passes its `AppHandle`; the protocol tests (`e2e_tests.rs`) pass a
+//! recording stand-in and talk to a mock engine.
+
+use std::future::Future;
+
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use crate::state::{AppState, ConnectionStatus};
+use crate::tools::coding_agent::readiness::CliReadiness;
+use crate::tools::coding_agent::usage::UsageSummary;
+use crate::ws::inflight::CancelSignal;
+use crate::ws::ToolStream;
+
+/// The coding-agent fields of `register` (ENG-1536).
+#[derive(Default)]
+pub struct CodingRegistration {
+    pub agents: Option<Vec<CliReadiness>>,
+    pub default_cli: Option<String>,
+    pub usage: Option<UsageSummary>,
+}
+
+pub trait Host: Clone + Send + Sync + 'static {
+    /// Reflect a pairing's new connection status in the tray and the window.
+    fn status_changed(&self, state: &AppState, pairing: &str, status: &ConnectionStatus);
+
+    /// Best effort: the app may have no window open.
+    fn emit_event(&self, event: &str, payload: Value);
+
+    /// Read at every register, so reconnecting refreshes it.
+    fn coding_registration(&self) -> impl Future<Output = CodingRegistration> + Send;
+
+    /// Forget a pairing after its engine revoked the device token.
+    fn unlink(
+        &self,
+        state: &AppState,
+        pairing: &str,
+    ) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// Run a streaming tool that needs the app: one `tools::is_streaming`
+    /// claims and `tools::streams_locally` does not.
+    fn dispatch_streaming(
+        &self,
+        state: &AppState,
+        tool: &str,
+        params: Value,
+        scoped_folders: &[String],
+        stream: &ToolStream,
+        cancel: CancelSignal,
+    ) -> impl Future<Output = Result<(Value, Option<u64>), String>> + Send;
+}
+
+impl Host for AppHandle {
+    fn status_changed(&self, state: &AppState, pairing: &str, status: &ConnectionStatus) {
+        crate::tray::update_tray_status(self, state);
+        let _ = self.emit(
+            "ws:status_changed",
+            serde_json::json!({ "pairing": pairing, "status": status }),
+        );
+    }
+
+    fn emit_event(&self, event: &str, payload: Value) {
+        let _ = self.emit(event, payload);
+    }
+
+    async fn coding_registration(&self) -> CodingRegistration {
+        use crate::tools::coding_agent::readiness::{detect, effective_default};
+
+        // Free signals only, no version spawn here — keep the connect
+        // handshake snappy.
+        let settings = crate::config::load_settings(self);
+        let claude = detect(
+            "claude",
+            settings.claude_binary_path.as_deref(),
+            settings.claude_auth_ok,
+            false,
+        );
+        let codex = detect("codex", None, settings.codex_auth_ok, false);
+        let (claude, codex) = tokio::join!(claude, codex);
+        let agents = vec![claude, codex];
+        // Report the EFFECTIVE default (explicit setting, else the CLI the
+        // user actually has) so the web's "via <CLI>" never names a CLI this
+        // machine wouldn't run.
+        let default_cli = effective_default(settings.default_cli.as_deref(), &agents);
+        CodingRegistration {
+            default_cli: Some(default_cli.to_string()),
+            agents: Some(agents),
+            usage: Some(crate::tools::coding_agent::usage::current_summary(self)),
+        }
+    }
+
+    async fn unlink(&self, state: &AppState, pairing: &str) -> Result<(), String> {
+        crate::commands::remove_pairing_link(self, state, pairing).await
+    }
+
+    async fn dispatch_streaming(
+        &self,
+        state: &AppState,
+        tool: &str,
+        params: Value,
+        scoped_folders: &[String],
+        stream: &ToolStream,
+        cancel: CancelSignal,
+    ) -> Result<(Value, Option<u64>), String> {
+        crate::tools::dispatch_streaming(self, state, tool, params, scoped_folders, stream, cancel)
+            .await
+    }
+}
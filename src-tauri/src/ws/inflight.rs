@@ -5,11 +5,14 @@
 //! The registry is shared state (lives in [`crate::state::AppState`]) because
 //! cancellation can arrive from two directions: the engine (WS `cancel`
 //! message) and the local UI (tray "Stop run", sub-issue ENG-1528).
+//! Coding runs also take input through it, addressed by the same
+//! request_id: follow-up turns of an interactive session (WS `turn`) and
+//! answers to relayed permission prompts (WS `permission_decision`).
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
-use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore, TryAcquireError};
+use tokio::sync::{mpsc, watch, OwnedSemaphorePermit, Semaphore, TryAcquireError};
 
 /// Receiver half of a request's cancellation signal.
 ///
@@ -44,6 +47,25 @@ impl CancelSignal {
     }
 }
 
+/// Input for a running coding CLI, from the engine.
+#[derive(Debug, PartialEq)]
+pub enum RunInput {
+    /// Another user message for an interactive session.
+    Prompt(String),
+    /// No more turns: close the CLI's stdin so it finishes and exits.
+    End,
+    /// The web user's answer to a relayed permission prompt.
+    Decision {
+        permission_id: String,
+        allow: bool,
+        message: Option<String>,
+    },
+}
+
+/// Receiver half of a run's input queue. Yields None once the request
+/// finishes (its sender is dropped with the registry entry).
+pub type InputReceiver = mpsc::UnboundedReceiver<RunInput>;
+
 /// Guard for the single concurrent coding-run slot. Dropping it frees the slot.
 pub struct CodingRunGuard {
     _permit: OwnedSemaphorePermit,
@@ -52,6 +74,9 @@ pub struct CodingRunGuard {
 /// Registry of in-flight requests + the coding-run concurrency cap.
 pub struct InflightRegistry {
     requests: Mutex<HashMap<String, watch::Sender<bool>>>,
+    /// Input queues of in-flight coding runs that read stdin (interactive
+    /// sessions, relayed permission prompts).
+    inputs: Mutex<HashMap<String, mpsc::UnboundedSender<RunInput>>>,
     /// One coding run at a time per device: local CLIs are heavyweight (model
     /// inference, file edits in a workspace) and concurrent runs in the same
     /// scoped folders could interleave edits.
@@ -68,6 +93,7 @@ impl InflightRegistry {
     pub fn new() -> Self {
         Self {
             requests: Mutex::new(HashMap::new()),
+            inputs: Mutex::new(HashMap::new()),
             coding_slot: Arc::new(Semaphore::new(1)),
         }
     }
@@ -94,12 +120,36 @@ impl InflightRegistry {
         }
     }
 
+    /// Accept input for `request_id` — a run that reads stdin.
+    pub fn open_input(&self, request_id: &str) -> InputReceiver {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.inputs
+            .lock()
+            .expect("inflight lock poisoned")
+            .insert(request_id.to_string(), tx);
+        rx
+    }
+
+    /// Queue input for a run. Returns false when the id is unknown,
+    /// finished, or a run that takes no input.
+    pub fn send_input(&self, request_id: &str, input: RunInput) -> bool {
+        let map = self.inputs.lock().expect("inflight lock poisoned");
+        match map.get(request_id) {
+            Some(tx) => tx.send(input).is_ok(),
+            None => false,
+        }
+    }
+
     /// Remove a finished request. Idempotent.
     pub fn finish(&self, request_id: &str) {
         self.requests
             .lock()
             .expect("inflight lock poisoned")
             .remove(request_id);
+        self.inputs
+            .lock()
+            .expect("inflight lock poisoned")
+            .remove(request_id);
     }
 
     /// Cancel everything currently in flight (app shutdown / user disconnect).
@@ -175,6 +225,21 @@ mod tests {
             .expect("cancelled() must resolve when the sender is dropped");
     }
 
+    #[tokio::test]
+    async fn input_reaches_only_runs_that_read_it_until_they_finish() {
+        let reg = InflightRegistry::new();
+        reg.register("req-1");
+        assert!(!reg.send_input("req-1", RunInput::End), "single-shot runs take no input");
+
+        let mut input = reg.open_input("req-1");
+        assert!(reg.send_input("req-1", RunInput::Prompt("and the docs".into())));
+        assert_eq!(input.recv().await, Some(RunInput::Prompt("and the docs".into())));
+
+        reg.finish("req-1");
+        assert!(!reg.send_input("req-1", RunInput::End));
+        assert_eq!(input.recv().await, None, "finish closes the queue");
+    }
+
     #[test]
     fn coding_run_slot_is_exclusive_and_freed_on_drop() {
         let reg = InflightRegistry::new();
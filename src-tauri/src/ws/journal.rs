@@ -0,0 +1,408 @@
+//! Outbound journal: the `response` and `response_chunk` frames of engine
+//! requests, kept until the engine has them, whichever connection carries
+//! them.
+//!
+//! Request tasks outlive the socket they arrived on — a drop mid-run used to
+//! send their frames into a channel nobody read, and the engine waited out
+//! its own timeout. Now every frame goes into this journal; the live
+//! connection writes what it has not sent yet, and the next one, after
+//! `register` (whose `resume` field lists the affected requests), writes
+//! everything not yet acknowledged again. The engine dedupes replays by
+//! `request_id` / `seq`. Only an engine that accepted the `resume` feature
+//! gets replays and the list; any other gets the frames never written.
+//!
+//! Acknowledgement rides the liveness Pings: each Ping carries the id of the
+//! last frame written before it, and the Pong echoing it back proves the
+//! engine read the socket past that point (TCP keeps order), so those frames
+//! are dropped. No engine change beyond standard Pong echoing.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::Mutex;
+
+use tokio::sync::Notify;
+
+use crate::ws::protocol::{OutgoingMessage, ResumedRequest};
+
+/// Unsent frames a connected journal holds before `push` waits — the same
+/// backpressure the per-connection channel gave streamed chunks.
+const MAX_UNSENT: usize = 256;
+/// Upper bound on buffered frame bytes. Past it the oldest chunks are dropped
+/// (the engine sees a `seq` gap); terminal responses are always kept.
+const MAX_BYTES: usize = 16 * 1024 * 1024;
+
+/// One journaled frame, serialized.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub id: u64,
+    pub request_id: String,
+    /// The request's `response`, as opposed to a chunk.
+    pub terminal: bool,
+    pub text: String,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: VecDeque<Frame>,
+    next_id: u64,
+    /// Entries with `id <= sent_through` were written on the live connection.
+    sent_through: u64,
+    bytes: usize,
+    connected: bool,
+    /// Requests whose terminal response is not journaled yet.
+    open: BTreeSet<String>,
+}
+
+impl Inner {
+    fn unsent(&self) -> usize {
+        self.entries
+            .iter()
+            .rev()
+            .take_while(|e| e.id > self.sent_through)
+            .count()
+    }
+
+    /// Forget the frames up to and including `id`.
+    fn drop_through(&mut self, id: u64) {
+        while self.entries.front().is_some_and(|e| e.id <= id) {
+            let dropped = self.entries.pop_front().expect("front exists");
+            self.bytes -= dropped.text.len();
+        }
+    }
+
+    /// Drop the oldest chunks until under `MAX_BYTES`.
+    fn evict(&mut self) {
+        while self.bytes > MAX_BYTES {
+            let Some(at) = self.entries.iter().position(|e| !e.terminal) else {
+                return;
+            };
+            let dropped = self.entries.remove(at).expect("position is in range");
+            self.bytes -= dropped.text.len();
+            log::warn!(
+                "Outbound journal full; dropped a chunk of {}",
+                dropped.request_id
+            );
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Journal {
+    inner: Mutex<Inner>,
+    /// Wakes the connection loop: frames to write. Single waiter.
+    wake: Notify,
+    /// Wakes `push`ers held back by backpressure.
+    drained: Notify,
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An engine request started; it is listed in `resume` until its
+    /// terminal response is journaled.
+    pub fn open(&self, request_id: &str) {
+        self.lock().open.insert(request_id.to_string());
+    }
+
+    /// Journal a frame of an engine request. Only `response` and
+    /// `response_chunk` belong here; anything else is ignored. Waits while a
+    /// connected socket has `MAX_UNSENT` frames still to write; never waits
+    /// while offline.
+    pub async fn push(&self, msg: &OutgoingMessage) {
+        let (request_id, terminal) = match msg {
+            OutgoingMessage::Response { request_id, .. } => (request_id, true),
+            OutgoingMessage::ResponseChunk { request_id, .. } => (request_id, false),
+            _ => return,
+        };
+        let text = match serde_json::to_string(msg) {
+            Ok(text) => text,
+            Err(e) => {
+                log::error!("Failed to serialize outgoing frame: {e}");
+                return;
+            }
+        };
+        loop {
+            let drained = self.drained.notified();
+            tokio::pin!(drained);
+            drained.as_mut().enable();
+            {
+                let inner = self.lock();
+                if !inner.connected || inner.unsent() < MAX_UNSENT {
+                    break;
+                }
+            }
+            drained.await;
+        }
+        {
+            let mut inner = self.lock();
+            inner.next_id += 1;
+            let id = inner.next_id;
+            inner.bytes += text.len();
+            if terminal {
+                inner.open.remove(request_id);
+            }
+            inner.entries.push_back(Frame {
+                id,
+                request_id: request_id.clone(),
+                terminal,
+                text,
+            });
+            inner.evict();
+        }
+        self.wake.notify_one();
+    }
+
+    /// What `register` reports: requests still running, and settled ones
+    /// whose response has not been acknowledged.
+    pub fn resume(&self) -> Vec<ResumedRequest> {
+        let inner = self.lock();
+        let settled: BTreeSet<&String> = inner
+            .entries
+            .iter()
+            .filter(|e| e.terminal)
+            .map(|e| &e.request_id)
+            .collect();
+        let running = inner.open.iter().map(|id| (id, false));
+        running
+            .chain(settled.into_iter().map(|id| (id, true)))
+            .map(|(request_id, settled)| ResumedRequest {
+                request_id: request_id.clone(),
+                settled,
+            })
+            .collect()
+    }
+
+    /// A connection registered. With `replay` (the engine accepted
+    /// `resume`) everything unacknowledged goes out on it; without, only
+    /// frames never written do, and the written ones are dropped: that
+    /// engine would not dedupe them.
+    pub fn attach(&self, replay: bool) {
+        {
+            let mut inner = self.lock();
+            inner.connected = true;
+            if replay {
+                inner.sent_through = inner.entries.front().map_or(inner.next_id, |e| e.id - 1);
+            } else {
+                let written = inner.sent_through;
+                inner.drop_through(written);
+            }
+        }
+        self.wake.notify_one();
+    }
+
+    /// The connection is gone: stop holding `push`ers back.
+    pub fn detach(&self) {
+        self.lock().connected = false;
+        self.drained.notify_waiters();
+    }
+
+    /// Resolves when there may be frames to write.
+    pub async fn ready(&self) {
+        self.wake.notified().await
+    }
+
+    /// The next frame to write on this connection.
+    pub fn next_unsent(&self) -> Option<Frame> {
+        let inner = self.lock();
+        inner
+            .entries
+            .iter()
+            .find(|e| e.id > inner.sent_through)
+            .cloned()
+    }
+
+    pub fn mark_sent(&self, id: u64) {
+        self.lock().sent_through = id;
+        self.drained.notify_waiters();
+    }
+
+    /// Id of the last frame written; goes out as the Ping payload.
+    pub fn watermark(&self) -> u64 {
+        self.lock().sent_through
+    }
+
+    /// The engine has read through frame `id` (a Pong echoed it).
+    pub fn ack_through(&self, id: u64) {
+        let mut inner = self.lock();
+        let id = id.min(inner.sent_through);
+        inner.drop_through(id);
+    }
+
+    /// Forget every frame and open request: they belong to an engine this
+    /// app no longer talks to (the user switched environments).
+    pub fn discard(&self) {
+        {
+            let mut inner = self.lock();
+            inner.entries.clear();
+            inner.bytes = 0;
+            inner.open.clear();
+        }
+        self.drained.notify_waiters();
+    }
+
+    /// Wake the loop again after a partial drain.
+    pub fn rewake(&self) {
+        self.wake.notify_one();
+    }
+
+    #[cfg(test)]
+    pub fn buffered(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().expect("journal lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws::protocol::ResponseStatus;
+    use std::time::Duration;
+
+    fn chunk(request_id: &str, seq: u64) -> OutgoingMessage {
+        OutgoingMessage::ResponseChunk {
+            request_id: request_id.into(),
+            seq,
+            data: serde_json::json!({ "text": "x".repeat(10) }),
+        }
+    }
+
+    fn response(request_id: &str) -> OutgoingMessage {
+        OutgoingMessage::Response {
+            request_id: request_id.into(),
+            status: ResponseStatus::Success,
+            data: None,
+            error: None,
+            bytes_transferred: None,
+            bytes_saved: None,
+        }
+    }
+
+    fn drain(journal: &Journal) -> Vec<u64> {
+        let mut sent = Vec::new();
+        while let Some(frame) = journal.next_unsent() {
+            journal.mark_sent(frame.id);
+            sent.push(frame.id);
+        }
+        sent
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_frames_are_replayed_on_the_next_connection() {
+        let journal = Journal::new();
+        journal.attach(true);
+        journal.open("req-1");
+        journal.push(&chunk("req-1", 0)).await;
+        journal.push(&chunk("req-1", 1)).await;
+        assert_eq!(drain(&journal), [1, 2]);
+        let ping = journal.watermark();
+        journal.push(&chunk("req-1", 2)).await;
+        assert_eq!(drain(&journal), [3]);
+        // The Pong for the Ping sent after frame 2 arrives, then the link drops.
+        journal.ack_through(ping);
+        journal.detach();
+
+        journal.push(&response("req-1")).await;
+        let resume = journal.resume();
+        assert_eq!(resume.len(), 1);
+        assert!(resume[0].request_id == "req-1" && resume[0].settled);
+
+        journal.attach(true);
+        assert_eq!(
+            drain(&journal),
+            [3, 4],
+            "frame 3 was written but never acked"
+        );
+        journal.ack_through(journal.watermark());
+        assert_eq!(journal.buffered(), 0);
+        assert!(journal.resume().is_empty());
+    }
+
+    #[tokio::test]
+    async fn without_replay_only_frames_never_written_go_out() {
+        let journal = Journal::new();
+        journal.attach(false);
+        journal.push(&chunk("req-1", 0)).await;
+        assert_eq!(drain(&journal), [1]);
+        journal.detach();
+        journal.push(&response("req-1")).await;
+
+        journal.attach(false);
+        assert_eq!(drain(&journal), [2], "frame 1 was written already");
+        journal.ack_through(journal.watermark());
+        assert_eq!(journal.buffered(), 0);
+    }
+
+    #[tokio::test]
+    async fn running_requests_are_resumed_even_when_fully_acked() {
+        let journal = Journal::new();
+        journal.open("run-1");
+        journal.open("req-2");
+        journal.push(&response("req-2")).await;
+        let resume = journal.resume();
+        let listed: Vec<(&str, bool)> = resume
+            .iter()
+            .map(|r| (r.request_id.as_str(), r.settled))
+            .collect();
+        assert_eq!(listed, [("run-1", false), ("req-2", true)]);
+    }
+
+    #[tokio::test]
+    async fn only_a_connected_journal_applies_backpressure() {
+        let journal = std::sync::Arc::new(Journal::new());
+        for seq in 0..MAX_UNSENT as u64 + 10 {
+            journal.push(&chunk("run-1", seq)).await;
+        }
+        journal.attach(true);
+        let pusher = {
+            let journal = journal.clone();
+            tokio::spawn(async move { journal.push(&chunk("run-1", 999)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!pusher.is_finished(), "push must wait for the socket");
+        drain(&journal);
+        tokio::time::timeout(Duration::from_secs(1), pusher)
+            .await
+            .expect("push released once drained")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn overflow_drops_old_chunks_but_keeps_responses() {
+        let journal = Journal::new();
+        journal.push(&response("req-0")).await;
+        let big = OutgoingMessage::ResponseChunk {
+            request_id: "run-1".into(),
+            seq: 0,
+            data: serde_json::json!("x".repeat(MAX_BYTES / 3)),
+        };
+        for _ in 0..3 {
+            journal.push(&big).await;
+        }
+        assert_eq!(journal.buffered(), 3, "one chunk evicted");
+        assert!(journal.resume().iter().any(|r| r.request_id == "req-0"));
+    }
+
+    #[tokio::test]
+    async fn discarded_requests_are_neither_replayed_nor_resumed() {
+        let journal = Journal::new();
+        journal.open("run-1");
+        journal.push(&chunk("run-1", 0)).await;
+        journal.push(&response("req-2")).await;
+        journal.discard();
+        assert!(journal.resume().is_empty());
+        journal.attach(true);
+        assert!(drain(&journal).is_empty());
+    }
+
+    #[tokio::test]
+    async fn other_frames_are_not_journaled() {
+        let journal = Journal::new();
+        journal.push(&OutgoingMessage::Heartbeat { metrics: None }).await;
+        assert_eq!(journal.buffered(), 0);
+        assert_eq!(journal.next_unsent(), None);
+    }
+}
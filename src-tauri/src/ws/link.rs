@@ -0,0 +1,226 @@
+//! One pairing's connection state, and the set of them the app runs.
+//!
+//! Everything that belongs to a single engine connection lives on its
+//! [`Link`]: the token, status, device id, the shutdown/reconnect signals,
+//! the outbound queue and the journal. App-wide state — scoped folders,
+//! in-flight runs, the network route — stays on `AppState`, which holds the
+//! links in [`Links`]. Each link is driven by its own `WsClient`.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tokio::sync::{mpsc, Notify};
+
+use crate::environment::Profile;
+use crate::pairing::Pairing;
+use crate::state::ConnectionStatus;
+use crate::ws::journal::Journal;
+use crate::ws::protocol::OutgoingMessage;
+
+pub struct Link {
+    pub id: String,
+    /// Name and folder subset can change while connected.
+    pub pairing: RwLock<Pairing>,
+    /// The server this pairing talks to. Read on every connect attempt, so
+    /// editing the environment takes effect on the next one.
+    pub environment: RwLock<Profile>,
+    pub auth_token: tokio::sync::RwLock<Option<String>>,
+    /// std lock: the tray reads every link's status synchronously.
+    pub ws_status: RwLock<ConnectionStatus>,
+    pub device_id: tokio::sync::RwLock<Option<String>>,
+    pub ws_shutdown: Notify,
+    /// Set to true when the user explicitly disconnects, so the WS run loop stops
+    /// reconnecting. A lone `ws_shutdown` notify permit can be consumed by the
+    /// message loop before the run loop checks it, so this flag — not the notify —
+    /// is the durable source of truth for "the user asked to stay disconnected."
+    /// Cleared on the next connect. Distinguishes a deliberate disconnect (stay
+    /// down) from a dropped socket (auto-reconnect).
+    pub shutdown_requested: AtomicBool,
+    /// Asks the running WS client to drop its connection and reconnect at
+    /// once, e.g. after its environment was edited. The one-loop alternative
+    /// to disconnect-then-connect (see `claim_pairing_code`).
+    pub ws_reconnect: Notify,
+    /// Tells the WS client the folders this pairing reaches changed, so it
+    /// pushes the new list to the backend. The WS client is the only waiter
+    /// (see `AppState::notify_folders_changed`).
+    pub folders_changed: Notify,
+    /// Sender into the live connection's outbound queue, set while connected
+    /// (ENG-1536). Lets settings changes push a readiness_update without
+    /// waiting for a reconnect. None when disconnected — pushes are simply
+    /// skipped; the next register carries fresh state anyway.
+    pub ws_outbound: Arc<RwLock<Option<mpsc::Sender<OutgoingMessage>>>>,
+    /// Response and chunk frames of this engine's requests until it has
+    /// them, across reconnects (see `ws::journal`).
+    pub journal: Arc<Journal>,
+    /// Whether the engine accepted the `resume` feature at the last
+    /// registration. `register` goes out before this connection's
+    /// negotiation, so its `resume` list is sent only to an engine that took
+    /// it the time before.
+    pub engine_resumes: AtomicBool,
+    /// Last Ping-to-Pong round-trip of the live connection; None until the
+    /// first Pong of each connection (see `diagnostics`).
+    pub heartbeat_rtt: RwLock<Option<Duration>>,
+}
+
+impl Link {
+    pub fn new(pairing: Pairing, environment: Profile) -> Self {
+        Self {
+            id: pairing.id.clone(),
+            pairing: RwLock::new(pairing),
+            environment: RwLock::new(environment),
+            auth_token: tokio::sync::RwLock::new(None),
+            ws_status: RwLock::new(ConnectionStatus::Disconnected),
+            device_id: tokio::sync::RwLock::new(None),
+            ws_shutdown: Notify::new(),
+            shutdown_requested: AtomicBool::new(false),
+            ws_reconnect: Notify::new(),
+            folders_changed: Notify::new(),
+            ws_outbound: Arc::new(RwLock::new(None)),
+            journal: Arc::new(Journal::new()),
+            engine_resumes: AtomicBool::new(false),
+            heartbeat_rtt: RwLock::new(None),
+        }
+    }
+
+    pub fn pairing(&self) -> Pairing {
+        self.pairing.read().expect("pairing lock poisoned").clone()
+    }
+
+    pub fn environment(&self) -> Profile {
+        self.environment
+            .read()
+            .expect("environment lock poisoned")
+            .clone()
+    }
+
+    pub fn status(&self) -> ConnectionStatus {
+        self.ws_status.read().expect("status lock poisoned").clone()
+    }
+
+    /// A run loop is up, connected or trying to be.
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self.status(),
+            ConnectionStatus::Connected
+                | ConnectionStatus::Connecting
+                | ConnectionStatus::Reconnecting
+        )
+    }
+
+    pub fn heartbeat_rtt(&self) -> Option<Duration> {
+        *self.heartbeat_rtt.read().expect("rtt lock poisoned")
+    }
+
+    pub fn outbound(&self) -> Option<mpsc::Sender<OutgoingMessage>> {
+        self.ws_outbound
+            .read()
+            .expect("ws_outbound lock poisoned")
+            .clone()
+    }
+}
+
+/// The live links, in pairing order.
+#[derive(Default)]
+pub struct Links {
+    inner: RwLock<Vec<Arc<Link>>>,
+}
+
+impl Links {
+    pub fn all(&self) -> Vec<Arc<Link>> {
+        self.inner.read().expect("links lock poisoned").clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Link>> {
+        self.all().into_iter().find(|l| l.id == id)
+    }
+
+    pub fn insert(&self, link: Arc<Link>) {
+        let mut links = self.inner.write().expect("links lock poisoned");
+        match links.iter_mut().find(|l| l.id == link.id) {
+            Some(existing) => *existing = link,
+            None => links.push(link),
+        }
+    }
+
+    pub fn remove(&self, id: &str) -> Option<Arc<Link>> {
+        let mut links = self.inner.write().expect("links lock poisoned");
+        let at = links.iter().position(|l| l.id == id)?;
+        Some(links.remove(at))
+    }
+
+    /// The server and token of every link that has one — where per-device
+    /// registrations (the Benchling connector) have to go.
+    pub async fn paired(&self) -> Vec<(Profile, String)> {
+        let mut paired = Vec::new();
+        for link in self.all() {
+            if let Some(token) = link.auth_token.read().await.clone() {
+                if !token.is_empty() {
+                    paired.push((link.environment(), token));
+                }
+            }
+        }
+        paired
+    }
+}
+
+/// The one status that sums up several links: the best any of them has
+/// reached, so "Connected" means at least one account is reachable.
+pub fn overall_status(statuses: impl IntoIterator<Item = ConnectionStatus>) -> ConnectionStatus {
+    let rank = |s: &ConnectionStatus| match s {
+        ConnectionStatus::Connected => 4,
+        ConnectionStatus::Connecting => 3,
+        ConnectionStatus::Reconnecting => 2,
+        ConnectionStatus::Revoked => 1,
+        ConnectionStatus::Disconnected => 0,
+    };
+    statuses
+        .into_iter()
+        .max_by_key(rank)
+        .unwrap_or(ConnectionStatus::Disconnected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(id: &str) -> Arc<Link> {
+        Arc::new(Link::new(
+            Pairing {
+                id: id.into(),
+                name: id.into(),
+                environment: crate::environment::PRODUCTION.into(),
+                folders: None,
+            },
+            crate::environment::default_profile(),
+        ))
+    }
+
+    #[test]
+    fn links_keep_pairing_order_and_replace_by_id() {
+        let links = Links::default();
+        links.insert(link("a"));
+        links.insert(link("b"));
+        let replacement = link("a");
+        links.insert(replacement.clone());
+
+        let ids: Vec<_> = links.all().iter().map(|l| l.id.clone()).collect();
+        assert_eq!(ids, ["a", "b"]);
+        assert!(Arc::ptr_eq(&links.get("a").unwrap(), &replacement));
+
+        assert!(links.remove("a").is_some());
+        assert!(links.remove("a").is_none());
+        assert_eq!(links.all().len(), 1);
+    }
+
+    #[test]
+    fn overall_status_is_the_best_any_link_reached() {
+        use ConnectionStatus::*;
+        assert_eq!(overall_status([]), Disconnected);
+        assert_eq!(overall_status([Revoked, Reconnecting]), Reconnecting);
+        assert_eq!(
+            overall_status([Disconnected, Connected, Connecting]),
+            Connected
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! Stand-in engine for the protocol tests (test-only).
+//!
+//! A real tokio-tungstenite server on loopback that speaks the engine's side
+//! of the desktop protocol: it reads the `beakr-v1, bearer.<token>`
+//! subprotocol, answers `register` with `registered`, and hands each
+//! connection to the test, which sends requests, cancels and close codes and
+//! reads back whatever the client sends. Handshakes can be scripted to fail
+//! with an HTTP status — the 403 a revoked token (or an engine mid-restart)
+//! gets — and a connection can go silent: held open but never read again, so
+//! the client's Pings go unanswered, the way a half-open socket behaves.
+//! Binary frames (negotiated compression, see `codec.rs`) are inflated before
+//! the test sees them, and counted.
+
+use std::collections::VecDeque;
+use std::io::Read;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{HeaderValue, StatusCode};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+
+/// What `registered` carries.
+pub const DEVICE_ID: &str = "dev-mock";
+/// Longest a test waits for any one thing the client should do.
+const WAIT_CEILING: Duration = Duration::from_secs(10);
+
+/// One WebSocket handshake the client attempted.
+#[derive(Clone, Debug)]
+pub struct Attempt {
+    pub at: Instant,
+    /// The bearer token from the subprotocol header, if any.
+    pub token: Option<String>,
+    /// 101 when accepted, else the scripted rejection.
+    pub status: u16,
+}
+
+#[derive(Default)]
+struct Handshakes {
+    /// Statuses for the next handshakes, in order; accepted once empty.
+    script: VecDeque<u16>,
+    attempts: Vec<Attempt>,
+    /// What `registered` echoes as `capabilities`; none, like an engine that
+    /// predates negotiation.
+    accepted: Option<Value>,
+}
+
+pub struct MockEngine {
+    url: String,
+    handshakes: Arc<Mutex<Handshakes>>,
+    connections: mpsc::UnboundedReceiver<EngineConnection>,
+    accept_task: tokio::task::JoinHandle<()>,
+}
+
+impl MockEngine {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "ws://{}/v1/desktop-agent/ws",
+            listener.local_addr().unwrap()
+        );
+        let handshakes = Arc::new(Mutex::new(Handshakes::default()));
+        let (conn_tx, connections) = mpsc::unbounded_channel();
+        let accept_task = tokio::spawn({
+            let handshakes = handshakes.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(serve(stream, handshakes.clone(), conn_tx.clone()));
+                }
+            }
+        });
+        Self {
+            url,
+            handshakes,
+            connections,
+            accept_task,
+        }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// Fail the next handshakes with these HTTP statuses, one per attempt.
+    pub fn reject_handshakes(&self, statuses: impl IntoIterator<Item = u16>) {
+        self.lock().script.extend(statuses);
+    }
+
+    /// Answer later registrations with protocol 2 and these capabilities.
+    pub fn accept_capabilities(&self, accepted: Value) {
+        self.lock().accepted = Some(accepted);
+    }
+
+    pub fn attempts(&self) -> Vec<Attempt> {
+        self.lock().attempts.clone()
+    }
+
+    /// The next connection that got through registration.
+    pub async fn next_connection(&mut self) -> EngineConnection {
+        tokio::time::timeout(WAIT_CEILING, self.connections.recv())
+            .await
+            .expect("client did not connect and register in time")
+            .expect("mock engine stopped")
+    }
+
+    /// Whether the client registers again within `wait`.
+    pub async fn connects_within(&mut self, wait: Duration) -> bool {
+        tokio::time::timeout(wait, self.connections.recv())
+            .await
+            .is_ok()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Handshakes> {
+        self.handshakes.lock().unwrap()
+    }
+}
+
+impl Drop for MockEngine {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+enum Command {
+    Send(Value),
+    Close(u16),
+    GoSilent,
+}
+
+/// The engine's end of one registered connection.
+pub struct EngineConnection {
+    /// The client's `register` frame.
+    pub register: Value,
+    frames: mpsc::UnboundedReceiver<Value>,
+    commands: mpsc::UnboundedSender<Command>,
+    compressed: Arc<AtomicUsize>,
+}
+
+impl EngineConnection {
+    pub fn send(&self, frame: Value) {
+        let _ = self.commands.send(Command::Send(frame));
+    }
+
+    pub fn request(&self, request_id: &str, tool: &str, params: Value) {
+        self.send(json!({
+            "type": "request", "request_id": request_id, "tool": tool, "params": params,
+        }));
+    }
+
+    pub fn cancel(&self, request_id: &str) {
+        self.send(json!({ "type": "cancel", "request_id": request_id }));
+    }
+
+    /// Close with an application close code (4010 revoked, 4011 expired).
+    pub fn close(&self, code: u16) {
+        let _ = self.commands.send(Command::Close(code));
+    }
+
+    /// Stop reading: no more Pongs, and nothing the client sends arrives.
+    pub fn go_silent(&self) {
+        let _ = self.commands.send(Command::GoSilent);
+    }
+
+    /// The next frame from the client, heartbeats skipped.
+    pub async fn recv(&mut self) -> Value {
+        loop {
+            let frame = tokio::time::timeout(WAIT_CEILING, self.frames.recv())
+                .await
+                .expect("no frame from the client in time")
+                .expect("connection closed while waiting for a frame");
+            if frame["type"] != "heartbeat" {
+                return frame;
+            }
+        }
+    }
+
+    /// How many frames so far arrived compressed.
+    pub fn compressed_frames(&self) -> usize {
+        self.compressed.load(Ordering::SeqCst)
+    }
+
+    /// Wait for the connection to end, from either side.
+    pub async fn closed(&mut self) {
+        tokio::time::timeout(WAIT_CEILING, async {
+            while self.frames.recv().await.is_some() {}
+        })
+        .await
+        .expect("connection still open");
+    }
+}
+
+async fn serve(
+    stream: TcpStream,
+    handshakes: Arc<Mutex<Handshakes>>,
+    connections: mpsc::UnboundedSender<EngineConnection>,
+) {
+    // Tungstenite's callback signature; the rejection is an HTTP response.
+    #[allow(clippy::result_large_err)]
+    let callback = |request: &Request, mut response: Response| {
+        let token = request
+            .headers()
+            .get("Sec-WebSocket-Protocol")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').find_map(|p| p.trim().strip_prefix("bearer.")))
+            .map(str::to_string);
+        let mut handshakes = handshakes.lock().unwrap();
+        let status = handshakes.script.pop_front().unwrap_or(101);
+        handshakes.attempts.push(Attempt {
+            at: Instant::now(),
+            token,
+            status,
+        });
+        if status != 101 {
+            let mut rejection = ErrorResponse::new(None);
+            *rejection.status_mut() = StatusCode::from_u16(status).unwrap();
+            return Err(rejection);
+        }
+        response.headers_mut().insert(
+            "Sec-WebSocket-Protocol",
+            HeaderValue::from_static("beakr-v1"),
+        );
+        Ok(response)
+    };
+    let Ok(ws) = tokio_tungstenite::accept_hdr_async(stream, callback).await else {
+        return;
+    };
+    let (mut write, mut read) = ws.split();
+
+    // Pings before register (a diagnostics probe) are answered by
+    // tungstenite, as a real engine's WebSocket library would.
+    let register = loop {
+        match read.next().await {
+            Some(Ok(Message::Text(text))) => break serde_json::from_str::<Value>(&text).unwrap(),
+            Some(Ok(Message::Ping(_))) => continue,
+            _ => return,
+        }
+    };
+    assert_eq!(register["type"], "register", "first frame must be register");
+    let mut registered = json!({ "type": "registered", "device_id": DEVICE_ID });
+    if let Some(accepted) = handshakes.lock().unwrap().accepted.clone() {
+        registered["protocol_version"] = json!(2);
+        registered["capabilities"] = accepted;
+    }
+    if write
+        .send(Message::Text(registered.to_string()))
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    let (frames_tx, frames) = mpsc::unbounded_channel();
+    let (commands, mut commands_rx) = mpsc::unbounded_channel();
+    let compressed = Arc::new(AtomicUsize::new(0));
+    let _ = connections.send(EngineConnection {
+        register,
+        frames,
+        commands,
+        compressed: compressed.clone(),
+    });
+
+    let mut silent = false;
+    loop {
+        tokio::select! {
+            command = commands_rx.recv() => match command {
+                Some(Command::Send(frame)) => {
+                    if write.send(Message::Text(frame.to_string())).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Command::Close(code)) => {
+                    let frame = CloseFrame { code: CloseCode::from(code), reason: "".into() };
+                    let _ = write.send(Message::Close(Some(frame))).await;
+                }
+                Some(Command::GoSilent) => silent = true,
+                // The test dropped its handle: hang up.
+                None => return,
+            },
+            // Reading is also what answers Pings, so a silent engine never
+            // Pongs.
+            frame = read.next(), if !silent => match frame {
+                Some(Ok(Message::Text(text))) => {
+                    let _ = frames_tx.send(serde_json::from_str(&text).unwrap());
+                }
+                Some(Ok(Message::Binary(bytes))) => {
+                    let mut text = String::new();
+                    flate2::read::DeflateDecoder::new(bytes.as_slice())
+                        .read_to_string(&mut text)
+                        .unwrap();
+                    compressed.fetch_add(1, Ordering::SeqCst);
+                    let _ = frames_tx.send(serde_json::from_str(&text).unwrap());
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
@@ -1,7 +1,16 @@
 mod client;
+pub mod codec;
+#[cfg(test)]
+mod e2e_tests;
+mod host;
 pub mod inflight;
+pub mod journal;
+pub mod link;
+#[cfg(test)]
+pub(crate) mod mock_engine;
 pub mod protocol;
 
+pub(crate) use client::handshake_request;
 pub use client::os_version;
 pub use client::WsClient;
 
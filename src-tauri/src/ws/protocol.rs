@@ -1,5 +1,133 @@
 use serde::{Deserialize, Serialize};
 
+use crate::ws::codec;
+
+/// Version of this message set. 1 is the protocol as it was before `register`
+/// carried a version; 2 adds negotiation.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Largest frame this client reads: tungstenite's default frame limit, which
+/// the connection uses.
+pub const MAX_FRAME_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Behaviors beyond request/response, by the name `capabilities.features`
+/// lists them under.
+pub mod feature {
+    /// `response_chunk` frames ahead of a streaming tool's response.
+    pub const RESPONSE_CHUNK: &str = "response_chunk";
+    /// `cancel` for an in-flight request.
+    pub const CANCEL: &str = "cancel";
+    /// `turn` for an interactive coding session.
+    pub const TURN: &str = "turn";
+    /// `permission_decision` for a relayed permission prompt.
+    pub const PERMISSION_DECISION: &str = "permission_decision";
+    /// `register.resume` and frame replay after a reconnect.
+    pub const RESUME: &str = "resume";
+    /// `heartbeat.metrics`: an aggregated `metrics::Snapshot` of the
+    /// pairing's tool timings and reconnects, once the user turned sharing on.
+    pub const METRICS: &str = "metrics";
+
+    /// Everything this client speaks.
+    pub const ALL: &[&str] = &[
+        RESPONSE_CHUNK,
+        CANCEL,
+        TURN,
+        PERMISSION_DECISION,
+        RESUME,
+        METRICS,
+    ];
+    /// What an engine that predates negotiation is assumed to speak: the
+    /// features that shipped before it, as additive messages.
+    pub const LEGACY: &[&str] = &[RESPONSE_CHUNK, CANCEL, TURN, PERMISSION_DECISION];
+}
+
+/// What `register` offers the engine.
+#[derive(Debug, Clone, Serialize)]
+pub struct Capabilities {
+    /// Every tool a `request` may name.
+    pub tools: Vec<String>,
+    /// The subset of `tools` that streams `response_chunk`s.
+    pub streaming_tools: Vec<String>,
+    pub features: Vec<String>,
+    pub max_frame_bytes: u64,
+    /// Frame encodings this client reads and writes, best first (see
+    /// `ws::codec`).
+    pub compression: Vec<String>,
+}
+
+impl Capabilities {
+    pub fn current() -> Self {
+        Self {
+            tools: crate::tools::names().iter().map(|t| t.to_string()).collect(),
+            streaming_tools: crate::tools::STREAMING
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            features: feature::ALL.iter().map(|f| f.to_string()).collect(),
+            max_frame_bytes: MAX_FRAME_BYTES,
+            compression: codec::SUPPORTED.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+}
+
+/// The engine's answer to [`Capabilities`] in `registered`: the part of the
+/// offer it will use.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AcceptedCapabilities {
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// The largest frame the engine reads.
+    #[serde(default)]
+    pub max_frame_bytes: Option<u64>,
+    #[serde(default)]
+    pub compression: Option<String>,
+}
+
+/// What one connection runs with, settled at `registered`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Negotiated {
+    pub protocol_version: u32,
+    pub features: Vec<String>,
+    pub max_frame_bytes: Option<u64>,
+    pub compression: Option<String>,
+}
+
+impl Negotiated {
+    /// An engine that echoes no capabilities predates negotiation: version
+    /// 1 with the legacy features. Otherwise the features both sides have,
+    /// at the lower version.
+    pub fn from_registered(
+        protocol_version: Option<u32>,
+        accepted: Option<AcceptedCapabilities>,
+    ) -> Self {
+        let version = protocol_version.unwrap_or(1).min(PROTOCOL_VERSION);
+        match accepted {
+            None => Self {
+                protocol_version: version,
+                features: feature::LEGACY.iter().map(|f| f.to_string()).collect(),
+                max_frame_bytes: None,
+                compression: None,
+            },
+            Some(accepted) => Self {
+                protocol_version: version,
+                features: accepted
+                    .features
+                    .into_iter()
+                    .filter(|f| feature::ALL.contains(&f.as_str()))
+                    .collect(),
+                max_frame_bytes: accepted.max_frame_bytes,
+                compression: accepted
+                    .compression
+                    .filter(|c| codec::SUPPORTED.contains(&c.as_str())),
+            },
+        }
+    }
+
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
 /// Messages sent FROM the desktop client TO the server.
 #[derive(Debug, Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -25,6 +153,22 @@ pub enum OutgoingMessage {
         /// 2026-07-17). None = claude (the pre-picker behavior).
         #[serde(skip_serializing_if = "Option::is_none")]
         coding_agent_default: Option<String>,
+        /// Today's and this month's coding-run token usage per CLI, read
+        /// from the local usage records. Additive; the engine may show it
+        /// next to the device.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        coding_usage: Option<crate::tools::coding_agent::usage::UsageSummary>,
+        /// Requests from an earlier connection this device still owes the
+        /// engine frames for (see `journal.rs`). Their unacknowledged chunks
+        /// and responses are replayed right after `registered`, so a request
+        /// listed here should be kept waiting, not failed. Empty unless the
+        /// engine accepted `resume` at the previous registration.
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        resume: Vec<ResumedRequest>,
+        /// [`PROTOCOL_VERSION`]; the engine answers with the version it
+        /// speaks, at most this one.
+        protocol_version: u32,
+        capabilities: Box<Capabilities>,
     },
     /// Pushed when readiness-affecting settings change while connected (the
     /// Default CLI radio), so the web's display updates without a reconnect.
@@ -33,7 +177,12 @@ pub enum OutgoingMessage {
         #[serde(skip_serializing_if = "Option::is_none")]
         coding_agent_default: Option<String>,
     },
-    Heartbeat,
+    Heartbeat {
+        /// Only when `metrics` was negotiated and sharing is on, and only
+        /// the pairing's own counters (`metrics::pairing_snapshot`).
+        #[serde(skip_serializing_if = "Option::is_none")]
+        metrics: Option<Box<crate::metrics::Snapshot>>,
+    },
     Response {
         request_id: String,
         status: ResponseStatus,
@@ -43,6 +192,10 @@ pub enum OutgoingMessage {
         error: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         bytes_transferred: Option<u64>,
+        /// How much smaller this response went on the wire than its JSON,
+        /// when it was compressed (see `ws::codec`).
+        #[serde(skip_serializing_if = "Option::is_none")]
+        bytes_saved: Option<u64>,
     },
     /// One incremental chunk of a long-running request (ENG-1527). Additive:
     /// a request that streams sends 0..n `ResponseChunk`s and ALWAYS finishes
@@ -58,6 +211,15 @@ pub enum OutgoingMessage {
     },
 }
 
+/// One entry of `Register::resume`.
+#[derive(Debug, Serialize)]
+pub struct ResumedRequest {
+    pub request_id: String,
+    /// True when the request has finished and its terminal `response` is
+    /// among the replayed frames; false while it is still running.
+    pub settled: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ResponseStatus {
@@ -69,8 +231,14 @@ pub enum ResponseStatus {
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum IncomingMessage {
+    /// Both negotiation fields are absent from an engine that predates
+    /// them; see [`Negotiated::from_registered`].
     Registered {
         device_id: String,
+        #[serde(default)]
+        protocol_version: Option<u32>,
+        #[serde(default)]
+        capabilities: Option<AcceptedCapabilities>,
     },
     Request {
         request_id: String,
@@ -84,6 +252,30 @@ pub enum IncomingMessage {
     Cancel {
         request_id: String,
     },
+    /// A follow-up user turn for an interactive coding session (a
+    /// `run_coding_agent` request sent with `interactive: true`). `prompt`
+    /// is fed to the still-running CLI; `end_session` closes its input after
+    /// that, so the request settles with the last turn's result. A turn for
+    /// an unknown, finished or single-shot request is a no-op, like cancel.
+    Turn {
+        request_id: String,
+        #[serde(default)]
+        prompt: Option<String>,
+        #[serde(default)]
+        end_session: bool,
+    },
+    /// The web user's answer to a `permission_request` chunk of a coding run
+    /// started with `relay_permissions: true`. `permission_id` is the id the
+    /// chunk carried; `message` tells the agent why, on a deny. A decision
+    /// for a prompt that is no longer pending (timed out, run finished) is
+    /// a no-op.
+    PermissionDecision {
+        request_id: String,
+        permission_id: String,
+        allow: bool,
+        #[serde(default)]
+        message: Option<String>,
+    },
 }
 
 #[cfg(test)]
@@ -104,6 +296,15 @@ mod tests {
         assert_eq!(v["data"]["text"], "hello");
     }
 
+    #[test]
+    fn heartbeat_without_metrics_is_unchanged_on_the_wire() {
+        let msg = OutgoingMessage::Heartbeat { metrics: None };
+        assert_eq!(
+            serde_json::to_string(&msg).unwrap(),
+            r#"{"type":"heartbeat"}"#
+        );
+    }
+
     #[test]
     fn cancel_deserializes_from_engine_envelope() {
         let incoming: IncomingMessage =
@@ -114,6 +315,81 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn turn_deserializes_with_optional_fields() {
+        let incoming: IncomingMessage = serde_json::from_str(
+            r#"{"type":"turn","request_id":"req-9","prompt":"now the docs"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            incoming,
+            IncomingMessage::Turn { prompt: Some(p), end_session: false, .. } if p == "now the docs"
+        ));
+        let incoming: IncomingMessage =
+            serde_json::from_str(r#"{"type":"turn","request_id":"req-9","end_session":true}"#)
+                .unwrap();
+        assert!(matches!(
+            incoming,
+            IncomingMessage::Turn { prompt: None, end_session: true, .. }
+        ));
+    }
+
+    #[test]
+    fn permission_decision_deserializes_without_a_message() {
+        let incoming: IncomingMessage = serde_json::from_str(
+            r#"{"type":"permission_decision","request_id":"req-9","permission_id":"perm-1","allow":true}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            incoming,
+            IncomingMessage::PermissionDecision { permission_id, allow: true, message: None, .. }
+                if permission_id == "perm-1"
+        ));
+    }
+
+    #[test]
+    fn registered_without_negotiation_means_a_legacy_engine() {
+        let incoming: IncomingMessage =
+            serde_json::from_str(r#"{"type":"registered","device_id":"dev-1"}"#).unwrap();
+        let IncomingMessage::Registered {
+            protocol_version,
+            capabilities,
+            ..
+        } = incoming
+        else {
+            panic!("expected registered");
+        };
+        let legacy = Negotiated::from_registered(protocol_version, capabilities);
+        assert_eq!(legacy.protocol_version, 1);
+        assert!(legacy.supports(feature::RESPONSE_CHUNK) && legacy.supports(feature::CANCEL));
+        assert!(!legacy.supports(feature::RESUME));
+        assert_eq!(legacy.max_frame_bytes, None);
+    }
+
+    #[test]
+    fn negotiation_keeps_only_features_both_sides_have() {
+        let incoming: IncomingMessage = serde_json::from_str(
+            r#"{"type":"registered","device_id":"dev-1","protocol_version":7,
+                "capabilities":{"features":["cancel","resume","telepathy"],
+                                "max_frame_bytes":1048576,"compression":"brotli"}}"#,
+        )
+        .unwrap();
+        let IncomingMessage::Registered {
+            protocol_version,
+            capabilities,
+            ..
+        } = incoming
+        else {
+            panic!("expected registered");
+        };
+        let agreed = Negotiated::from_registered(protocol_version, capabilities);
+        assert_eq!(agreed.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(agreed.features, ["cancel", "resume"]);
+        assert!(!agreed.supports(feature::RESPONSE_CHUNK));
+        assert_eq!(agreed.max_frame_bytes, Some(1_048_576));
+        assert_eq!(agreed.compression, None, "an encoding we never offered");
+    }
+
     #[test]
     fn terminal_response_shape_is_unchanged() {
         // Regression guard: the additive chunk variant must not alter the
@@ -124,11 +400,13 @@ mod tests {
             data: Some(serde_json::json!({"ok": true})),
             error: None,
             bytes_transferred: None,
+            bytes_saved: None,
         };
         let v: serde_json::Value = serde_json::to_value(&msg).unwrap();
         assert_eq!(v["type"], "response");
         assert_eq!(v["status"], "success");
         assert!(v.get("error").is_none());
         assert!(v.get("bytes_transferred").is_none());
+        assert!(v.get("bytes_saved").is_none());
     }
 }
@@ -1,5 +1,6 @@
 import { useEffect, useState } from "react";
 import { useCodingRun } from "../hooks/useCodingRun";
+import RunProgress from "./RunProgress";
 
 function basename(path: string): string {
   const parts = path.replace(/\/+$/, "").split("/");
@@ -20,10 +21,12 @@ function formatElapsed(ms: number): string {
  * convention), a live elapsed timer, and Stop. After Stop is pressed the card
  * says "Stopping…" and stays until the child process is confirmed dead — the
  * card never claims a run is gone while it may still be editing files.
+ * "Progress" unfolds the run's live output in place.
  */
 export default function ActiveRunCard() {
   const { run, stop, watch } = useCodingRun();
   const [now, setNow] = useState(() => Date.now());
+  const [showProgress, setShowProgress] = useState(false);
 
   useEffect(() => {
     if (!run) return;
@@ -73,6 +76,21 @@ export default function ActiveRunCard() {
             {formatElapsed(now - run.started_at_ms)}
           </div>
         </div>
+        <button
+          onClick={() => setShowProgress((shown) => !shown)}
+          style={{
+            padding: "0.4rem 0.9rem",
+            borderRadius: 6,
+            border: "1px solid #d1d5db",
+            backgroundColor: showProgress ? "#f3f4f6" : "#fff",
+            color: "#374151",
+            fontSize: 13,
+            fontWeight: 600,
+            cursor: "pointer",
+          }}
+        >
+          Progress
+        </button>
         {run.log_path && (
           <button
             onClick={() => void watch().catch(() => undefined)}
@@ -108,6 +126,7 @@ export default function ActiveRunCard() {
           {stopping ? "Stopping…" : "Stop"}
         </button>
       </div>
+      {showProgress && <RunProgress requestId={run.request_id} />}
     </div>
   );
 }
@@ -3,6 +3,7 @@ import { useActivityFeed, type ActivityEvent } from "../hooks/useActivityFeed";
 const TOOL_LABELS: Record<string, string> = {
   list_files: "Listing files",
   search_files: "Searching files",
+  semantic_search_files: "Searching documents",
   read_file: "Reading file",
   file_info: "Getting file info",
 };
@@ -12,6 +13,7 @@ function toolIcon(tool: string): string {
     case "list_files":
       return "\u{1F4C2}";
     case "search_files":
+    case "semantic_search_files":
       return "\u{1F50D}";
     case "read_file":
       return "\u{1F4C4}";
@@ -10,10 +10,77 @@ interface CliReadiness {
   ready: boolean;
 }
 
+interface PermissionProfile {
+  name: string;
+  tools: string[];
+  bash_allow: string[];
+  builtin: boolean;
+}
+
 interface CodingAgentInfo {
   has_api_key: boolean;
   claude_binary_path: string | null;
   default_cli: string | null;
+  permission_profile: string;
+  permission_profiles: PermissionProfile[];
+  coding_sandbox: boolean;
+  sandbox_supported: boolean;
+  usage_budget: UsageBudget;
+  permission_relay: boolean;
+  coding_desktop_tools: boolean;
+}
+
+interface UsageBudget {
+  daily_tokens: number | null;
+  monthly_tokens: number | null;
+}
+
+interface UsageTotals {
+  runs: number;
+  input_tokens: number;
+  output_tokens: number;
+  cache_read_tokens: number;
+  cache_write_tokens: number;
+  total_cost_usd?: number;
+}
+
+interface CodingUsage {
+  budget: UsageBudget;
+  remaining_tokens: number | null;
+  summary: {
+    today: Record<string, UsageTotals>;
+    this_month: Record<string, UsageTotals>;
+  };
+}
+
+/** Tokens a budget counts — cache reads are excluded (see usage.rs). */
+function budgetTokens(t: UsageTotals): number {
+  return t.input_tokens + t.output_tokens + t.cache_write_tokens;
+}
+
+function describeUsage(period: Record<string, UsageTotals>): string {
+  const entries = Object.entries(period);
+  if (entries.length === 0) return "no runs";
+  return entries
+    .map(([cli, t]) => {
+      const label = CLI_META[cli as "claude" | "codex"]?.label ?? cli;
+      const cost = t.total_cost_usd !== undefined ? ` · $${t.total_cost_usd.toFixed(2)}` : "";
+      return `${label}: ${budgetTokens(t).toLocaleString()} tokens in ${t.runs} run${t.runs === 1 ? "" : "s"}${cost}`;
+    })
+    .join("; ");
+}
+
+function parseCap(input: string): number | null {
+  const n = Number(input.replace(/[,_\s]/g, ""));
+  return input.trim() === "" || !Number.isFinite(n) ? null : Math.floor(n);
+}
+
+function describeProfile(p: PermissionProfile): string {
+  const writes = p.tools.some((t) => ["Edit", "Write", "MultiEdit", "NotebookEdit"].includes(t));
+  const files = writes ? "Read and edit files" : "Read files only";
+  return p.bash_allow.length > 0
+    ? `${files}; may run: ${p.bash_allow.join(", ")}`
+    : `${files}; no shell commands`;
 }
 
 const CLI_META: Record<
@@ -69,9 +136,18 @@ function statusFor(r: CliReadiness): { dot: string; text: string; guidance?: str
 export default function CodingAgentSettings() {
   const [readiness, setReadiness] = useState<CliReadiness[] | null>(null);
   const [defaultCli, setDefaultCli] = useState<string>("claude");
+  const [profile, setProfile] = useState<string>("default");
+  const [profiles, setProfiles] = useState<PermissionProfile[]>([]);
+  const [sandbox, setSandbox] = useState(true);
+  const [sandboxSupported, setSandboxSupported] = useState(false);
+  const [relay, setRelay] = useState(false);
+  const [desktopTools, setDesktopTools] = useState(true);
   const [hasKey, setHasKey] = useState(false);
   const [editingKey, setEditingKey] = useState(false);
   const [keyInput, setKeyInput] = useState("");
+  const [usage, setUsage] = useState<CodingUsage | null>(null);
+  const [dailyCap, setDailyCap] = useState("");
+  const [monthlyCap, setMonthlyCap] = useState("");
   const [error, setError] = useState<string | null>(null);
 
   const refresh = useCallback(() => {
@@ -82,8 +158,19 @@ export default function CodingAgentSettings() {
       .then((info) => {
         setHasKey(info.has_api_key);
         setDefaultCli(info.default_cli ?? "claude");
+        setProfile(info.permission_profile);
+        setProfiles(info.permission_profiles);
+        setSandbox(info.coding_sandbox);
+        setSandboxSupported(info.sandbox_supported);
+        setRelay(info.permission_relay);
+        setDesktopTools(info.coding_desktop_tools);
+        setDailyCap(info.usage_budget.daily_tokens?.toString() ?? "");
+        setMonthlyCap(info.usage_budget.monthly_tokens?.toString() ?? "");
       })
       .catch(() => setError("Could not load coding-agent settings."));
+    invoke<CodingUsage>("get_coding_usage")
+      .then(setUsage)
+      .catch(() => setUsage(null));
   }, []);
 
   useEffect(() => {
@@ -118,6 +205,60 @@ export default function CodingAgentSettings() {
     }
   };
 
+  const pickProfile = async (name: string) => {
+    const prev = profile;
+    setProfile(name);
+    try {
+      await invoke("set_coding_agent_settings", { permissionProfile: name });
+    } catch (e) {
+      setProfile(prev);
+      setError(typeof e === "string" ? e : "Could not save the permission profile.");
+    }
+  };
+
+  const toggleSandbox = async (enabled: boolean) => {
+    setSandbox(enabled);
+    try {
+      await invoke("set_coding_agent_settings", { codingSandbox: enabled });
+    } catch (e) {
+      setSandbox(!enabled);
+      setError(typeof e === "string" ? e : "Could not save the sandbox setting.");
+    }
+  };
+
+  const toggleRelay = async (enabled: boolean) => {
+    setRelay(enabled);
+    try {
+      await invoke("set_coding_agent_settings", { permissionRelay: enabled });
+    } catch (e) {
+      setRelay(!enabled);
+      setError(typeof e === "string" ? e : "Could not save the permission-prompt setting.");
+    }
+  };
+
+  const toggleDesktopTools = async (enabled: boolean) => {
+    setDesktopTools(enabled);
+    try {
+      await invoke("set_coding_agent_settings", { codingDesktopTools: enabled });
+    } catch (e) {
+      setDesktopTools(!enabled);
+      setError(typeof e === "string" ? e : "Could not save the desktop-tools setting.");
+    }
+  };
+
+  const saveBudget = async () => {
+    const budget = { daily_tokens: parseCap(dailyCap), monthly_tokens: parseCap(monthlyCap) };
+    try {
+      await invoke("set_coding_agent_settings", { usageBudget: budget });
+      setError(null);
+      setUsage(await invoke<CodingUsage>("get_coding_usage"));
+    } catch (e) {
+      setError(typeof e === "string" ? e : "Could not save the usage budget.");
+    }
+  };
+
+  const activeProfile = profiles.find((p) => p.name === profile);
+
   const installedClis = (readiness ?? []).filter((r) => r.installed);
   const noneDetected = readiness !== null && installedClis.length === 0;
 
@@ -276,6 +417,144 @@ export default function CodingAgentSettings() {
         </div>
       )}
 
+      {installedClis.length > 0 && profiles.length > 0 && (
+        <div style={{ marginTop: "0.75rem" }}>
+          <div style={{ fontSize: "0.8rem", fontWeight: 600, marginBottom: "0.35rem" }}>
+            Permissions
+          </div>
+          <p style={{ fontSize: "0.74rem", color: "#666", margin: "0 0 0.4rem 0" }}>
+            The most a coding run on this Mac may do. Beakr can ask for less
+            on a given run, never more.
+          </p>
+          <select
+            value={profile}
+            onChange={(e) => pickProfile(e.target.value)}
+            style={{
+              padding: "0.3rem",
+              border: "1px solid #ddd",
+              borderRadius: 6,
+              fontSize: "0.85rem",
+            }}
+          >
+            {profiles.map((p) => (
+              <option key={p.name} value={p.name}>
+                {p.name}
+              </option>
+            ))}
+          </select>
+          {activeProfile && (
+            <p style={{ fontSize: "0.74rem", color: "#4b5563", margin: "0.35rem 0 0 0" }}>
+              {describeProfile(activeProfile)}
+            </p>
+          )}
+          {sandboxSupported && (
+            <label
+              style={{
+                fontSize: "0.8rem",
+                display: "flex",
+                alignItems: "center",
+                gap: 6,
+                marginTop: "0.5rem",
+              }}
+            >
+              <input
+                type="checkbox"
+                checked={sandbox}
+                onChange={(e) => toggleSandbox(e.target.checked)}
+              />
+              Sandbox runs: writes only in the project, network only to the
+              model API
+            </label>
+          )}
+          <label
+            style={{
+              fontSize: "0.8rem",
+              display: "flex",
+              alignItems: "center",
+              gap: 6,
+              marginTop: "0.5rem",
+            }}
+          >
+            <input
+              type="checkbox"
+              checked={relay}
+              onChange={(e) => toggleRelay(e.target.checked)}
+            />
+            Ask in Beakr before using tools outside the profile (Claude only)
+          </label>
+          <label
+            style={{
+              fontSize: "0.8rem",
+              display: "flex",
+              alignItems: "center",
+              gap: 6,
+              marginTop: "0.5rem",
+            }}
+          >
+            <input
+              type="checkbox"
+              checked={desktopTools}
+              onChange={(e) => toggleDesktopTools(e.target.checked)}
+            />
+            Let runs read your other shared folders and Benchling (read-only)
+          </label>
+        </div>
+      )}
+
+      {installedClis.length > 0 && (
+        <div style={{ marginTop: "0.75rem" }}>
+          <div style={{ fontSize: "0.8rem", fontWeight: 600, marginBottom: "0.35rem" }}>
+            Usage
+          </div>
+          {usage && (
+            <p style={{ fontSize: "0.74rem", color: "#4b5563", margin: "0 0 0.4rem 0" }}>
+              Today — {describeUsage(usage.summary.today)}
+              <br />
+              This month — {describeUsage(usage.summary.this_month)}
+              {usage.remaining_tokens !== null && (
+                <>
+                  <br />
+                  {usage.remaining_tokens.toLocaleString()} tokens left in your budget
+                </>
+              )}
+            </p>
+          )}
+          <p style={{ fontSize: "0.74rem", color: "#666", margin: "0 0 0.4rem 0" }}>
+            Optional caps across all CLIs. A run that would go past one is
+            stopped; leave a field empty for no cap.
+          </p>
+          <div style={{ display: "flex", gap: "0.75rem", alignItems: "center" }}>
+            {(
+              [
+                ["Tokens per day", dailyCap, setDailyCap],
+                ["Tokens per month", monthlyCap, setMonthlyCap],
+              ] as const
+            ).map(([label, value, set]) => (
+              <label
+                key={label}
+                style={{ fontSize: "0.78rem", display: "flex", alignItems: "center", gap: 6 }}
+              >
+                {label}
+                <input
+                  inputMode="numeric"
+                  value={value}
+                  placeholder="no cap"
+                  onChange={(e) => set(e.target.value)}
+                  onBlur={saveBudget}
+                  style={{
+                    width: "7rem",
+                    padding: "0.25rem 0.4rem",
+                    border: "1px solid #ddd",
+                    borderRadius: 6,
+                    fontSize: "0.8rem",
+                  }}
+                />
+              </label>
+            ))}
+          </div>
+        </div>
+      )}
+
       {error && (
         <p
           role="alert"
@@ -1,35 +1,253 @@
-import { useConnectionStatus } from "../hooks/useConnectionStatus";
+import { usePairings, Pairing } from "../hooks/usePairings";
 import { invoke } from "@tauri-apps/api/core";
-import { useState } from "react";
+import { useCallback, useEffect, useState } from "react";
+import EnvironmentSettings from "./EnvironmentSettings";
 
-export default function ConnectionStatus() {
-  const { status, deviceId } = useConnectionStatus();
-  const [actionError, setActionError] = useState<string | null>(null);
+const statusText: Record<string, string> = {
+  disconnected: "Disconnected",
+  connecting: "Connecting…",
+  connected: "Connected",
+  reconnecting: "Reconnecting…",
+  revoked: "Device Revoked",
+};
+
+const buttonStyle = {
+  fontSize: "0.8rem",
+  padding: "0.25rem 0.75rem",
+  border: "1px solid #ddd",
+  borderRadius: 6,
+  background: "white",
+  cursor: "pointer",
+} as const;
 
-  const isConnected = status === "connected";
-  const isRevoked = status === "revoked";
+const primaryButtonStyle = {
+  ...buttonStyle,
+  border: "none",
+  background: "#1a1a2e",
+  color: "white",
+} as const;
+
+const inputStyle = {
+  padding: "0.25rem 0.4rem",
+  border: "1px solid #ddd",
+  borderRadius: 6,
+  fontSize: "0.8rem",
+} as const;
+
+interface PairingRowProps {
+  pairing: Pairing;
+  scopedFolders: string[];
+  run: (command: string, args: Record<string, unknown>) => Promise<boolean>;
+}
 
+/** One account: its name, its connection, and which scoped folders it reaches. */
+function PairingRow({ pairing, scopedFolders, run }: PairingRowProps) {
+  const [editingFolders, setEditingFolders] = useState(false);
+  const [newName, setNewName] = useState<string | null>(null);
+
+  const isConnected = pairing.status === "connected";
+  const isRevoked = pairing.status === "revoked";
   const dotColor = isConnected ? "#22c55e" : isRevoked ? "#ef4444" : "#9ca3af";
+  const reached = pairing.folders ?? scopedFolders;
+
+  const setFolders = (folders: string[] | null) =>
+    run("set_pairing_folders", { id: pairing.id, folders });
+
+  const rename = async () => {
+    if (newName === null) return;
+    if (newName.trim() && newName.trim() !== pairing.name) {
+      if (!(await run("rename_pairing", { id: pairing.id, name: newName }))) return;
+    }
+    setNewName(null);
+  };
 
-  const statusText: Record<string, string> = {
-    disconnected: "Disconnected",
-    connecting: "Connecting…",
-    connected: "Connected",
-    reconnecting: "Reconnecting…",
-    revoked: "Device Revoked",
+  const toggleFolder = (folder: string) => {
+    const next = reached.includes(folder)
+      ? reached.filter((f) => f !== folder)
+      : [...reached, folder];
+    setFolders(next);
   };
 
-  const runConnectionAction = async (command: "connect_ws" | "disconnect_ws") => {
+  return (
+    <div style={{ padding: "0.6rem 0", borderTop: "1px solid #eee" }}>
+      <div style={{ display: "flex", alignItems: "center", gap: "0.5rem" }}>
+        <span
+          style={{
+            width: 10,
+            height: 10,
+            borderRadius: "50%",
+            backgroundColor: dotColor,
+            display: "inline-block",
+            flexShrink: 0,
+          }}
+        />
+        <div style={{ flex: 1, minWidth: 0 }}>
+          {newName === null ? (
+            <div style={{ fontSize: "0.9rem", fontWeight: 500 }}>
+              {pairing.name}{" "}
+              <span style={{ color: "#666", fontWeight: 400 }}>
+                · {statusText[pairing.status] || pairing.status}
+              </span>
+            </div>
+          ) : (
+            <div style={{ display: "flex", gap: 6 }}>
+              <input
+                value={newName}
+                autoFocus
+                onChange={(e) => setNewName(e.target.value)}
+                onKeyDown={(e) => {
+                  if (e.key === "Enter") rename();
+                  if (e.key === "Escape") setNewName(null);
+                }}
+                style={{ ...inputStyle, flex: 1, minWidth: 0 }}
+              />
+              <button style={primaryButtonStyle} onClick={rename}>
+                Save
+              </button>
+              <button style={buttonStyle} onClick={() => setNewName(null)}>
+                Cancel
+              </button>
+            </div>
+          )}
+          <div style={{ fontSize: "0.74rem", color: "#999" }} title={pairing.ws_url}>
+            {pairing.environment_name} ·{" "}
+            {pairing.folders === null
+              ? "all folders"
+              : `${reached.length} of ${scopedFolders.length} folders`}
+          </div>
+        </div>
+        {isConnected ? (
+          <button style={buttonStyle} onClick={() => run("disconnect_ws", { pairing: pairing.id })}>
+            Disconnect
+          </button>
+        ) : !isRevoked ? (
+          <button
+            style={primaryButtonStyle}
+            onClick={() => run("connect_ws", { pairing: pairing.id })}
+          >
+            Connect
+          </button>
+        ) : null}
+        {newName === null && (
+          <button style={buttonStyle} onClick={() => setNewName(pairing.name)}>
+            Rename
+          </button>
+        )}
+        <button style={buttonStyle} onClick={() => setEditingFolders(!editingFolders)}>
+          Folders
+        </button>
+        <button style={buttonStyle} onClick={() => run("remove_pairing", { id: pairing.id })}>
+          Remove
+        </button>
+      </div>
+
+      {editingFolders && (
+        <div
+          style={{
+            marginTop: "0.5rem",
+            marginLeft: 18,
+            fontSize: "0.8rem",
+            display: "flex",
+            flexDirection: "column",
+            gap: 4,
+          }}
+        >
+          <label>
+            <input
+              type="checkbox"
+              checked={pairing.folders === null}
+              onChange={(e) => setFolders(e.target.checked ? null : scopedFolders)}
+            />{" "}
+            All allowed folders, including ones added later
+          </label>
+          {pairing.folders !== null &&
+            scopedFolders.map((folder) => (
+              <label key={folder} style={{ wordBreak: "break-all" }}>
+                <input
+                  type="checkbox"
+                  checked={reached.includes(folder)}
+                  onChange={() => toggleFolder(folder)}
+                />{" "}
+                <code>{folder}</code>
+              </label>
+            ))}
+        </div>
+      )}
+
+      {pairing.device_id && (
+        <p
+          style={{
+            fontSize: "0.75rem",
+            color: "#999",
+            margin: "0.3rem 0 0 18px",
+            fontFamily: "monospace",
+          }}
+        >
+          Device ID: {pairing.device_id}
+        </p>
+      )}
+    </div>
+  );
+}
+
+/**
+ * The accounts this device is paired with (see `pairing` in the Rust crate),
+ * each with its own connection and folder subset, and a form to pair with
+ * another one.
+ */
+export default function ConnectionStatus() {
+  const { pairings, refresh } = usePairings();
+  const [scopedFolders, setScopedFolders] = useState<string[]>([]);
+  const [adding, setAdding] = useState(false);
+  const [name, setName] = useState("");
+  const [code, setCode] = useState("");
+  const [pairing, setPairing] = useState(false);
+  const [actionError, setActionError] = useState<string | null>(null);
+
+  const loadFolders = useCallback(() => {
+    invoke<string[]>("get_scoped_folders")
+      .then(setScopedFolders)
+      .catch(() => {});
+  }, []);
+
+  useEffect(() => {
+    loadFolders();
+  }, [loadFolders]);
+
+  // The last account removed: back to the pairing screen.
+  useEffect(() => {
+    if (pairings?.length === 0) window.location.reload();
+  }, [pairings]);
+
+  const run = async (command: string, args: Record<string, unknown>) => {
     setActionError(null);
     try {
-      await invoke(command);
+      await invoke(command, args);
+      return true;
     } catch (e) {
       setActionError(typeof e === "string" ? e : "Connection action failed");
+      return false;
+    } finally {
+      refresh();
+      loadFolders();
+    }
+  };
+
+  const addAccount = async () => {
+    setPairing(true);
+    const ok = await run("claim_pairing_code", {
+      code: code.trim().toUpperCase(),
+      name: name.trim() || null,
+    });
+    setPairing(false);
+    if (ok) {
+      setAdding(false);
+      setName("");
+      setCode("");
     }
   };
 
-  const handleConnect = () => runConnectionAction("connect_ws");
-  const handleDisconnect = () => runConnectionAction("disconnect_ws");
+  if (!pairings?.length) return null;
 
   return (
     <div
@@ -45,68 +263,59 @@ export default function ConnectionStatus() {
           display: "flex",
           alignItems: "center",
           justifyContent: "space-between",
+          marginBottom: "0.25rem",
         }}
       >
-        <div style={{ display: "flex", alignItems: "center", gap: "0.5rem" }}>
-          <span
-            style={{
-              width: 10,
-              height: 10,
-              borderRadius: "50%",
-              backgroundColor: dotColor,
-              display: "inline-block",
-            }}
-          />
-          <span style={{ fontSize: "0.9rem", fontWeight: 500 }}>
-            {statusText[status] || status}
-          </span>
-        </div>
-
-        {isConnected ? (
-          <button
-            onClick={handleDisconnect}
-            style={{
-              fontSize: "0.8rem",
-              padding: "0.25rem 0.75rem",
-              border: "1px solid #ddd",
-              borderRadius: 6,
-              background: "white",
-              cursor: "pointer",
-            }}
-          >
-            Disconnect
-          </button>
-        ) : !isRevoked ? (
-          <button
-            onClick={handleConnect}
-            style={{
-              fontSize: "0.8rem",
-              padding: "0.25rem 0.75rem",
-              border: "none",
-              borderRadius: 6,
-              background: "#1a1a2e",
-              color: "white",
-              cursor: "pointer",
-            }}
-          >
-            Connect
+        <span style={{ fontSize: "0.9rem", fontWeight: 600 }}>Accounts</span>
+        {!adding && (
+          <button style={buttonStyle} onClick={() => setAdding(true)}>
+            Add account…
           </button>
-        ) : null}
+        )}
       </div>
 
-      {deviceId && (
-        <p
-          style={{
-            fontSize: "0.75rem",
-            color: "#999",
-            marginTop: "0.5rem",
-            marginBottom: 0,
-            fontFamily: "monospace",
-          }}
-        >
-          Device ID: {deviceId}
-        </p>
+      {pairings.map((p) => (
+        <PairingRow key={p.id} pairing={p} scopedFolders={scopedFolders} run={run} />
+      ))}
+
+      {adding && (
+        <div style={{ paddingTop: "0.6rem", borderTop: "1px solid #eee", fontSize: "0.8rem" }}>
+          <p style={{ color: "#666", marginTop: 0 }}>
+            In the other account's Beakr web app, go to Settings → Local Files and
+            click "Generate Pairing Code".
+          </p>
+          <div style={{ display: "flex", gap: 6 }}>
+            <input
+              value={name}
+              placeholder="Name, e.g. Lab"
+              onChange={(e) => setName(e.target.value)}
+              style={{ ...inputStyle, flex: 1 }}
+            />
+            <input
+              value={code}
+              placeholder="XXXXXX"
+              maxLength={6}
+              onChange={(e) =>
+                setCode(e.target.value.replace(/[^a-zA-Z0-9]/g, "").slice(0, 6).toUpperCase())
+              }
+              onKeyDown={(e) => e.key === "Enter" && code.length === 6 && addAccount()}
+              style={{ ...inputStyle, width: "6em", fontFamily: "monospace" }}
+            />
+            <button
+              style={primaryButtonStyle}
+              onClick={addAccount}
+              disabled={pairing || code.length !== 6}
+            >
+              {pairing ? "Pairing…" : "Pair"}
+            </button>
+            <button style={buttonStyle} onClick={() => setAdding(false)}>
+              Cancel
+            </button>
+          </div>
+          <EnvironmentSettings compact />
+        </div>
       )}
+
       {actionError && (
         <p
           role="alert"
@@ -0,0 +1,340 @@
+import { Fragment, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { save } from "@tauri-apps/plugin-dialog";
+
+type Outcome = "pass" | "warn" | "fail" | "skipped";
+
+interface Check {
+  name: string;
+  outcome: Outcome;
+  elapsed_ms: number | null;
+  detail: string;
+}
+
+interface PairingReport {
+  id: string;
+  name: string;
+  environment: string;
+  ws_url: string;
+  status: string;
+  device_id: string | null;
+  checks: Check[];
+}
+
+interface Report {
+  generated_at: string;
+  app_version: string;
+  network: string;
+  benchling: Check;
+  pairings: PairingReport[];
+}
+
+interface ToolMetrics {
+  tool: string;
+  outcome: "ok" | "error" | "cancelled";
+  error?: string;
+  duration: { count: number; p50_ms: number | null; p95_ms: number | null };
+}
+
+interface Metrics {
+  snapshot: {
+    since: string;
+    tools: ToolMetrics[];
+    reconnects: Record<string, number>;
+  };
+  share: boolean;
+}
+
+/** A quantile estimate: the bucket's upper bound, or past the last one. */
+const estimate = (bound: number | null) => (bound === null ? "> 10 s" : `≤ ${bound} ms`);
+
+const outcomeColor: Record<Outcome, string> = {
+  pass: "#22c55e",
+  warn: "#f59e0b",
+  fail: "#ef4444",
+  skipped: "#9ca3af",
+};
+
+const checkLabel: Record<string, string> = {
+  url: "Server URL",
+  dns: "DNS",
+  proxy: "Route",
+  tcp: "TCP connect",
+  tls: "TLS handshake",
+  handshake: "WebSocket handshake",
+  heartbeat: "Heartbeat",
+  clock: "Clock",
+  benchling: "Benchling",
+};
+
+const LEVELS = ["error", "warn", "info", "debug", "trace"];
+
+const buttonStyle = {
+  fontSize: "0.74rem",
+  padding: "0.2rem 0.5rem",
+  border: "1px solid #ddd",
+  borderRadius: 6,
+  background: "white",
+  cursor: "pointer",
+  color: "#4b5563",
+} as const;
+
+function CheckRow({ check }: { check: Check }) {
+  return (
+    <>
+      <span style={{ display: "flex", alignItems: "center", gap: 6, fontWeight: 600 }}>
+        <span
+          style={{
+            width: 8,
+            height: 8,
+            borderRadius: "50%",
+            backgroundColor: outcomeColor[check.outcome],
+            display: "inline-block",
+            flexShrink: 0,
+          }}
+        />
+        {checkLabel[check.name] ?? check.name}
+      </span>
+      <span style={{ color: check.outcome === "fail" ? "#dc2626" : "#4b5563", wordBreak: "break-word" }}>
+        {check.detail}
+        {check.elapsed_ms !== null && (
+          <span style={{ color: "#999" }}> · {check.elapsed_ms} ms</span>
+        )}
+      </span>
+    </>
+  );
+}
+
+const gridStyle = {
+  padding: "0.75rem",
+  border: "1px solid #e5e7eb",
+  borderRadius: 8,
+  fontSize: "0.8rem",
+  display: "grid",
+  gridTemplateColumns: "auto 1fr",
+  gap: "0.3rem 0.75rem",
+  marginTop: "0.5rem",
+} as const;
+
+/**
+ * Connection diagnostics (see `diagnostics` in the Rust crate): each
+ * account's path to its server checked step by step, and a redacted copy
+ * for support. Also the app log's level and export (see `logging`).
+ */
+export default function DiagnosticsSettings() {
+  const [report, setReport] = useState<Report | null>(null);
+  const [running, setRunning] = useState(false);
+  const [notice, setNotice] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [logLevel, setLogLevel] = useState<string | null>(null);
+  const [metrics, setMetrics] = useState<Metrics | null>(null);
+
+  const loadMetrics = () =>
+    invoke<Metrics>("get_metrics")
+      .then(setMetrics)
+      .catch(() => {});
+
+  useEffect(() => {
+    invoke<string>("get_log_level")
+      .then(setLogLevel)
+      .catch(() => {});
+    loadMetrics();
+  }, []);
+
+  const changeSharing = async (share: boolean) => {
+    setError(null);
+    try {
+      await invoke("set_share_metrics", { share });
+      setMetrics((m) => (m ? { ...m, share } : m));
+    } catch (e) {
+      setError(typeof e === "string" ? e : "Could not change metrics sharing.");
+    }
+  };
+
+  const changeLogLevel = async (level: string) => {
+    setError(null);
+    try {
+      await invoke("set_log_level", { level });
+      setLogLevel(level);
+    } catch (e) {
+      setError(typeof e === "string" ? e : "Could not change the log level.");
+    }
+  };
+
+  const exportLogs = async () => {
+    const stamp = new Date().toISOString().slice(0, 10);
+    const path = await save({
+      defaultPath: `beakr-logs-${stamp}.jsonl`,
+      filters: [{ name: "JSON lines", extensions: ["jsonl"] }],
+    });
+    if (!path) return;
+    setError(null);
+    try {
+      await invoke("export_logs", { path });
+      setNotice(`Saved to ${path}. Tokens, cookies and prompts are removed.`);
+    } catch (e) {
+      setError(typeof e === "string" ? e : "Could not save the logs.");
+    }
+  };
+
+  const runChecks = async () => {
+    setRunning(true);
+    setError(null);
+    setNotice(null);
+    try {
+      setReport(await invoke<Report>("run_diagnostics"));
+    } catch (e) {
+      setError(typeof e === "string" ? e : "Could not run the diagnostics.");
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  const exportBundle = async () => {
+    const stamp = new Date().toISOString().slice(0, 10);
+    const path = await save({
+      defaultPath: `beakr-diagnostics-${stamp}.json`,
+      filters: [{ name: "JSON", extensions: ["json"] }],
+    });
+    if (!path) return;
+    setRunning(true);
+    setError(null);
+    try {
+      await invoke("export_support_bundle", { path });
+      setNotice(`Saved to ${path}. Tokens and cookies are removed.`);
+    } catch (e) {
+      setError(typeof e === "string" ? e : "Could not save the support bundle.");
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  return (
+    <section style={{ marginTop: "1.5rem" }}>
+      <h2 style={{ fontSize: "1rem", fontWeight: 600, marginBottom: "0.25rem" }}>Diagnostics</h2>
+      <p style={{ fontSize: "0.78rem", color: "#666", marginTop: 0, marginBottom: "0.5rem" }}>
+        When an account won't connect, check each step of the way to its
+        server. Connected accounts are not interrupted.
+      </p>
+      <div style={{ display: "flex", gap: 6 }}>
+        <button style={buttonStyle} onClick={runChecks} disabled={running}>
+          {running ? "Checking…" : "Run checks"}
+        </button>
+        <button style={buttonStyle} onClick={exportBundle} disabled={running}>
+          Export support bundle…
+        </button>
+        <button style={buttonStyle} onClick={exportLogs}>
+          Export logs…
+        </button>
+        {logLevel && (
+          <label style={{ fontSize: "0.74rem", color: "#4b5563", marginLeft: "auto" }}>
+            Log level{" "}
+            <select
+              value={logLevel}
+              onChange={(e) => changeLogLevel(e.target.value)}
+              style={{ fontSize: "0.74rem", borderRadius: 6, border: "1px solid #ddd" }}
+            >
+              {LEVELS.map((l) => (
+                <option key={l} value={l}>
+                  {l}
+                </option>
+              ))}
+            </select>
+          </label>
+        )}
+      </div>
+
+      {report && (
+        <>
+          {report.pairings.map((p) => (
+            <div key={p.id} style={gridStyle}>
+              <span style={{ fontWeight: 600, gridColumn: "1 / -1" }}>
+                {p.name}{" "}
+                <span style={{ color: "#999", fontWeight: 400 }}>
+                  · {p.environment} · {p.status}
+                </span>
+              </span>
+              {p.checks.map((c) => (
+                <CheckRow key={c.name} check={c} />
+              ))}
+            </div>
+          ))}
+          <div style={gridStyle}>
+            <CheckRow check={report.benchling} />
+          </div>
+          <p style={{ fontSize: "0.74rem", color: "#666", margin: "0.4rem 0 0 0" }}>
+            Connecting {report.network}.
+          </p>
+        </>
+      )}
+
+      {metrics && (
+        <div style={{ marginTop: "0.75rem" }}>
+          <div style={{ display: "flex", alignItems: "center", gap: 6 }}>
+            <span style={{ fontSize: "0.8rem", fontWeight: 600 }}>Tool timings</span>
+            <span style={{ fontSize: "0.74rem", color: "#999" }}>
+              since {new Date(metrics.snapshot.since).toLocaleString()}
+            </span>
+            <button style={{ ...buttonStyle, marginLeft: "auto" }} onClick={loadMetrics}>
+              Refresh
+            </button>
+          </div>
+          {metrics.snapshot.tools.length === 0 ? (
+            <p style={{ fontSize: "0.74rem", color: "#666", margin: "0.4rem 0 0 0" }}>
+              No tool calls yet.
+            </p>
+          ) : (
+            <div style={{ ...gridStyle, gridTemplateColumns: "1fr auto auto auto auto" }}>
+              {["Tool", "Outcome", "Calls", "p50", "p95"].map((h) => (
+                <span key={h} style={{ fontWeight: 600 }}>
+                  {h}
+                </span>
+              ))}
+              {metrics.snapshot.tools.map((t) => (
+                <Fragment key={`${t.tool}/${t.outcome}/${t.error ?? ""}`}>
+                  <span>{t.tool}</span>
+                  <span style={{ color: t.outcome === "error" ? "#dc2626" : "#4b5563" }}>
+                    {t.error ? `${t.outcome} (${t.error})` : t.outcome}
+                  </span>
+                  <span>{t.duration.count}</span>
+                  <span>{estimate(t.duration.p50_ms)}</span>
+                  <span>{estimate(t.duration.p95_ms)}</span>
+                </Fragment>
+              ))}
+            </div>
+          )}
+          <label
+            style={{
+              display: "flex",
+              alignItems: "center",
+              gap: 6,
+              fontSize: "0.78rem",
+              color: "#4b5563",
+              marginTop: "0.5rem",
+            }}
+          >
+            <input
+              type="checkbox"
+              checked={metrics.share}
+              onChange={(e) => changeSharing(e.target.checked)}
+            />
+            Share performance metrics with the server (timings and error
+            codes only, no paths or content)
+          </label>
+        </div>
+      )}
+
+      {notice && (
+        <p style={{ fontSize: "0.74rem", color: "#666", margin: "0.4rem 0 0 0" }}>{notice}</p>
+      )}
+      {error && (
+        <p
+          role="alert"
+          style={{ color: "#dc2626", fontSize: "0.78rem", marginTop: "0.5rem", marginBottom: 0 }}
+        >
+          {error}
+        </p>
+      )}
+    </section>
+  );
+}
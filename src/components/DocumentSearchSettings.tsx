@@ -0,0 +1,81 @@
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+interface ModelStatus {
+  ready: boolean;
+  downloading: boolean;
+}
+
+const buttonStyle = {
+  fontSize: "0.74rem",
+  padding: "0.2rem 0.5rem",
+  border: "1px solid #ddd",
+  borderRadius: 6,
+  background: "white",
+  cursor: "pointer",
+  color: "#4b5563",
+} as const;
+
+/**
+ * Search by meaning (see `semantic` in the Rust crate): the small model it
+ * runs on this Mac is downloaded here, over the same proxy as everything
+ * else, before the tool can answer.
+ */
+export default function DocumentSearchSettings() {
+  const [status, setStatus] = useState<ModelStatus | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const refresh = useCallback(() => {
+    invoke<ModelStatus>("get_semantic_model")
+      .then(setStatus)
+      .catch(() => setError("Could not check the document search model."));
+  }, []);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  const download = async () => {
+    setError(null);
+    setStatus((s) => (s ? { ...s, downloading: true } : s));
+    try {
+      await invoke("download_semantic_model");
+    } catch (e) {
+      setError(typeof e === "string" ? e : "Could not download the model.");
+    }
+    refresh();
+  };
+
+  if (!status) return null;
+
+  return (
+    <section style={{ marginTop: "1.5rem" }}>
+      <h2 style={{ fontSize: "1rem", fontWeight: 600, marginBottom: "0.25rem" }}>
+        Document search
+      </h2>
+      <p style={{ fontSize: "0.78rem", color: "#666", marginTop: 0, marginBottom: "0.5rem" }}>
+        Lets Beakr find documents in your shared folders by what they are
+        about, not only the words in them. The model runs on this Mac; your
+        documents are not uploaded.
+      </p>
+      <div style={{ display: "flex", alignItems: "center", gap: 6, fontSize: "0.8rem" }}>
+        {status.ready ? (
+          <span style={{ color: "#4b5563" }}>Model downloaded.</span>
+        ) : (
+          <button style={buttonStyle} onClick={download} disabled={status.downloading}>
+            {status.downloading ? "Downloading…" : "Download model (about 90 MB)"}
+          </button>
+        )}
+      </div>
+
+      {error && (
+        <p
+          role="alert"
+          style={{ color: "#dc2626", fontSize: "0.78rem", marginTop: "0.5rem", marginBottom: 0 }}
+        >
+          {error}
+        </p>
+      )}
+    </section>
+  );
+}
@@ -0,0 +1,230 @@
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+interface Environment {
+  id: string;
+  name: string;
+  ws_url: string;
+  builtin: boolean;
+  paired: boolean;
+}
+
+interface Environments {
+  active: string;
+  profiles: Environment[];
+}
+
+interface EnvironmentSettingsProps {
+  /** Just the picker, for the pairing screen. */
+  compact?: boolean;
+}
+
+const buttonStyle = {
+  fontSize: "0.74rem",
+  padding: "0.2rem 0.5rem",
+  border: "1px solid #ddd",
+  borderRadius: 6,
+  background: "white",
+  cursor: "pointer",
+  color: "#4b5563",
+} as const;
+
+const inputStyle = {
+  padding: "0.25rem 0.4rem",
+  border: "1px solid #ddd",
+  borderRadius: 6,
+  fontSize: "0.8rem",
+} as const;
+
+/** "Acme Staging" → "acme-staging": the id the Rust side accepts. */
+function slug(name: string): string {
+  return name
+    .toLowerCase()
+    .replace(/[^a-z0-9]+/g, "-")
+    .replace(/^-+|-+$/g, "");
+}
+
+/**
+ * Which Beakr server the next pairing code is claimed from (see `environment`
+ * in the Rust crate): production, a local backend, or one the user adds —
+ * staging or a self-hosted deployment. Accounts already paired keep theirs.
+ */
+export default function EnvironmentSettings({ compact = false }: EnvironmentSettingsProps) {
+  const [envs, setEnvs] = useState<Environments | null>(null);
+  const [adding, setAdding] = useState(false);
+  const [name, setName] = useState("");
+  const [url, setUrl] = useState("");
+  const [error, setError] = useState<string | null>(null);
+
+  const refresh = useCallback(() => {
+    invoke<Environments>("get_environments")
+      .then(setEnvs)
+      .catch(() => setError("Could not load the server environments."));
+  }, []);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  const run = async (command: string, args: Record<string, unknown>) => {
+    try {
+      await invoke(command, args);
+      setError(null);
+      return true;
+    } catch (e) {
+      setError(typeof e === "string" ? e : "Could not update the server environments.");
+      return false;
+    }
+  };
+
+  const switchTo = async (id: string) => {
+    await run("set_environment", { id });
+    refresh();
+  };
+
+  const add = async () => {
+    const profile = { id: slug(name), name: name.trim(), ws_url: url.trim() };
+    if (await run("save_environment", { profile })) {
+      setAdding(false);
+      setName("");
+      setUrl("");
+    }
+    refresh();
+  };
+
+  const remove = async (id: string) => {
+    await run("delete_environment", { id });
+    refresh();
+  };
+
+  if (!envs) return null;
+
+  const active = envs.profiles.find((p) => p.id === envs.active);
+  const removable = envs.profiles.filter(
+    (p) => !p.builtin && p.id !== envs.active && !p.paired,
+  );
+
+  const picker = (
+    <div style={{ display: "flex", alignItems: "center", gap: 6, fontSize: "0.8rem" }}>
+      <span style={{ fontWeight: 600 }}>Server</span>
+      <select
+        value={envs.active}
+        onChange={(e) => switchTo(e.target.value)}
+        style={{ ...inputStyle, flex: 1 }}
+        title={active?.ws_url}
+      >
+        {envs.profiles.map((p) => (
+          <option key={p.id} value={p.id}>
+            {p.name}
+            {p.paired ? " · paired" : ""}
+          </option>
+        ))}
+      </select>
+      {!adding && (
+        <button style={buttonStyle} onClick={() => setAdding(true)}>
+          Add…
+        </button>
+      )}
+    </div>
+  );
+
+  const addForm = adding && (
+    <div
+      style={{
+        marginTop: "0.5rem",
+        padding: "0.75rem",
+        border: "1px solid #e5e7eb",
+        borderRadius: 8,
+        fontSize: "0.8rem",
+        display: "grid",
+        gridTemplateColumns: "auto 1fr",
+        gap: "0.4rem 0.75rem",
+        alignItems: "center",
+      }}
+    >
+      <span style={{ fontWeight: 600 }}>Name</span>
+      <input
+        value={name}
+        placeholder="Staging"
+        onChange={(e) => setName(e.target.value)}
+        style={inputStyle}
+      />
+      <span style={{ fontWeight: 600 }}>URL</span>
+      <input
+        value={url}
+        placeholder="wss://beakr.example.org/v1/desktop-agent/ws"
+        onChange={(e) => setUrl(e.target.value)}
+        onKeyDown={(e) => e.key === "Enter" && add()}
+        style={inputStyle}
+      />
+      <span />
+      <div style={{ display: "flex", gap: 4 }}>
+        <button style={buttonStyle} onClick={add} disabled={!slug(name) || !url.trim()}>
+          Save
+        </button>
+        <button style={buttonStyle} onClick={() => setAdding(false)}>
+          Cancel
+        </button>
+      </div>
+    </div>
+  );
+
+  const errorLine = error && (
+    <p
+      role="alert"
+      style={{ color: "#dc2626", fontSize: "0.78rem", marginTop: "0.5rem", marginBottom: 0 }}
+    >
+      {error}
+    </p>
+  );
+
+  if (compact) {
+    return (
+      <div style={{ marginTop: "1rem" }}>
+        {picker}
+        {addForm}
+        {errorLine}
+      </div>
+    );
+  }
+
+  return (
+    <section style={{ marginTop: "1.5rem" }}>
+      <h2 style={{ fontSize: "1rem", fontWeight: 600, marginBottom: "0.25rem" }}>
+        Server Environment
+      </h2>
+      <p style={{ fontSize: "0.78rem", color: "#666", marginTop: 0, marginBottom: "0.75rem" }}>
+        The Beakr server the next account is paired on. Accounts already paired
+        stay on theirs.
+      </p>
+
+      {picker}
+      {active && (
+        <p style={{ fontSize: "0.74rem", color: "#666", margin: "0.4rem 0 0 0" }}>
+          <code style={{ wordBreak: "break-all" }}>{active.ws_url}</code>
+        </p>
+      )}
+      {addForm}
+
+      {removable.length > 0 && (
+        <div style={{ marginTop: "0.5rem", display: "flex", flexDirection: "column", gap: 4 }}>
+          {removable.map((p) => (
+            <div
+              key={p.id}
+              style={{ display: "flex", alignItems: "center", gap: 6, fontSize: "0.8rem" }}
+            >
+              <span style={{ flex: 1 }}>
+                {p.name} <code style={{ color: "#666" }}>{p.ws_url}</code>
+              </span>
+              <button style={buttonStyle} onClick={() => remove(p.id)}>
+                Remove
+              </button>
+            </div>
+          ))}
+        </div>
+      )}
+
+      {errorLine}
+    </section>
+  );
+}
@@ -0,0 +1,183 @@
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+interface McpServerInfo {
+  enabled: boolean;
+  port: number;
+  running: boolean;
+  url: string;
+  token: string | null;
+}
+
+const buttonStyle = {
+  fontSize: "0.74rem",
+  padding: "0.2rem 0.5rem",
+  border: "1px solid #ddd",
+  borderRadius: 6,
+  background: "white",
+  cursor: "pointer",
+  color: "#4b5563",
+} as const;
+
+/**
+ * The optional local MCP server (see `mcp` in the Rust crate): lets MCP
+ * clients on this Mac use the file and Benchling tools, within the same
+ * shared folders the engine is limited to. Shows the URL and bearer token
+ * the user pastes into their client's config.
+ */
+export default function McpServerSettings() {
+  const [info, setInfo] = useState<McpServerInfo | null>(null);
+  const [portInput, setPortInput] = useState("");
+  const [showToken, setShowToken] = useState(false);
+  const [copied, setCopied] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const refresh = useCallback(() => {
+    invoke<McpServerInfo>("get_mcp_server")
+      .then((i) => {
+        setInfo(i);
+        setPortInput(i.port.toString());
+      })
+      .catch(() => setError("Could not load the MCP server settings."));
+  }, []);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  const update = async (args: { enabled?: boolean; port?: number; rotateToken?: boolean }) => {
+    try {
+      await invoke("set_mcp_server", args);
+      setError(null);
+    } catch (e) {
+      setError(typeof e === "string" ? e : "Could not apply the MCP server settings.");
+    }
+    refresh();
+  };
+
+  const savePort = () => {
+    const port = Number(portInput);
+    if (!info || port === info.port) return;
+    if (!Number.isInteger(port) || port < 1024 || port > 65535) {
+      setError("Pick a port from 1024 to 65535.");
+      setPortInput(info.port.toString());
+      return;
+    }
+    update({ port });
+  };
+
+  const copy = async (label: string, text: string) => {
+    try {
+      await navigator.clipboard.writeText(text);
+      setCopied(label);
+      setTimeout(() => setCopied(null), 1500);
+    } catch {
+      setError("Could not copy to the clipboard.");
+    }
+  };
+
+  if (!info) return null;
+
+  return (
+    <section style={{ marginTop: "1.5rem" }}>
+      <h2 style={{ fontSize: "1rem", fontWeight: 600, marginBottom: "0.25rem" }}>
+        Local MCP Server
+      </h2>
+      <p style={{ fontSize: "0.78rem", color: "#666", marginTop: 0, marginBottom: "0.75rem" }}>
+        Let agents and editors on this Mac read your shared folders and
+        Benchling through Beakr, with the same limits Beakr has. Only clients
+        with the token below can connect.
+      </p>
+
+      <label style={{ fontSize: "0.85rem", display: "flex", alignItems: "center", gap: 6 }}>
+        <input
+          type="checkbox"
+          checked={info.enabled}
+          onChange={(e) => update({ enabled: e.target.checked })}
+        />
+        Serve tools to local MCP clients
+        {info.enabled && (
+          <span style={{ marginLeft: "auto", fontSize: "0.8rem", color: "#4b5563" }}>
+            {info.running ? "Running" : "Not running"}
+          </span>
+        )}
+      </label>
+
+      {info.enabled && (
+        <div
+          style={{
+            marginTop: "0.5rem",
+            padding: "0.75rem",
+            border: "1px solid #e5e7eb",
+            borderRadius: 8,
+            fontSize: "0.8rem",
+            display: "grid",
+            gridTemplateColumns: "auto 1fr auto",
+            gap: "0.4rem 0.75rem",
+            alignItems: "center",
+          }}
+        >
+          <span style={{ fontWeight: 600 }}>Port</span>
+          <input
+            inputMode="numeric"
+            value={portInput}
+            onChange={(e) => setPortInput(e.target.value)}
+            onBlur={savePort}
+            onKeyDown={(e) => e.key === "Enter" && savePort()}
+            style={{
+              width: "6rem",
+              padding: "0.25rem 0.4rem",
+              border: "1px solid #ddd",
+              borderRadius: 6,
+              fontSize: "0.8rem",
+            }}
+          />
+          <span />
+
+          <span style={{ fontWeight: 600 }}>URL</span>
+          <code style={{ wordBreak: "break-all" }}>{info.url}</code>
+          <button style={buttonStyle} onClick={() => copy("url", info.url)}>
+            {copied === "url" ? "Copied" : "Copy"}
+          </button>
+
+          <span style={{ fontWeight: 600 }}>Token</span>
+          <code style={{ wordBreak: "break-all" }}>
+            {info.token ? (showToken ? info.token : "••••••••••••••••") : "—"}
+          </code>
+          <div style={{ display: "flex", gap: 4 }}>
+            <button style={buttonStyle} onClick={() => setShowToken(!showToken)}>
+              {showToken ? "Hide" : "Show"}
+            </button>
+            {info.token && (
+              <button style={buttonStyle} onClick={() => copy("token", info.token ?? "")}>
+                {copied === "token" ? "Copied" : "Copy"}
+              </button>
+            )}
+            <button
+              style={buttonStyle}
+              onClick={() => update({ rotateToken: true })}
+              title="Clients using the old token stop working"
+            >
+              Rotate
+            </button>
+          </div>
+        </div>
+      )}
+
+      {info.enabled && (
+        <p style={{ fontSize: "0.74rem", color: "#666", margin: "0.4rem 0 0 0" }}>
+          Clients send the token as <code>Authorization: Bearer &lt;token&gt;</code>.
+        </p>
+      )}
+
+      {error && (
+        <p
+          role="alert"
+          style={{ color: "#dc2626", fontSize: "0.78rem", marginTop: "0.5rem", marginBottom: 0 }}
+        >
+          {error}
+        </p>
+      )}
+    </section>
+  );
+}
@@ -0,0 +1,223 @@
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { open } from "@tauri-apps/plugin-dialog";
+
+type ProxyMode = "system" | "manual" | "pac" | "off";
+
+interface ProxySettings {
+  mode: ProxyMode;
+  url: string | null;
+  pac_url: string | null;
+  bypass: string[];
+}
+
+interface NetworkInfo {
+  proxy: ProxySettings;
+  ca_bundles: string[];
+  active: string;
+}
+
+const MODES: { value: ProxyMode; label: string }[] = [
+  { value: "system", label: "Use system settings" },
+  { value: "manual", label: "Manual proxy" },
+  { value: "pac", label: "Auto-config (PAC) URL" },
+  { value: "off", label: "No proxy" },
+];
+
+const buttonStyle = {
+  fontSize: "0.74rem",
+  padding: "0.2rem 0.5rem",
+  border: "1px solid #ddd",
+  borderRadius: 6,
+  background: "white",
+  cursor: "pointer",
+  color: "#4b5563",
+} as const;
+
+const inputStyle = {
+  padding: "0.25rem 0.4rem",
+  border: "1px solid #ddd",
+  borderRadius: 6,
+  fontSize: "0.8rem",
+} as const;
+
+/**
+ * Proxy and extra CA certificates (see `network` in the Rust crate) for the
+ * engine connection, Benchling and pairing. For networks that only let
+ * traffic out through a proxy, or that inspect TLS with their own
+ * certificate authority.
+ */
+export default function NetworkSettings() {
+  const [info, setInfo] = useState<NetworkInfo | null>(null);
+  const [url, setUrl] = useState("");
+  const [pacUrl, setPacUrl] = useState("");
+  const [bypass, setBypass] = useState("");
+  const [error, setError] = useState<string | null>(null);
+
+  const refresh = useCallback(() => {
+    invoke<NetworkInfo>("get_network_settings")
+      .then((i) => {
+        setInfo(i);
+        setUrl(i.proxy.url ?? "");
+        setPacUrl(i.proxy.pac_url ?? "");
+        setBypass(i.proxy.bypass.join(", "));
+      })
+      .catch(() => setError("Could not load the network settings."));
+  }, []);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  const update = async (args: { proxy?: ProxySettings; caBundles?: string[] }) => {
+    try {
+      await invoke("set_network_settings", args);
+      setError(null);
+    } catch (e) {
+      setError(typeof e === "string" ? e : "Could not apply the network settings.");
+    }
+    refresh();
+  };
+
+  const saveProxy = (changes: Partial<ProxySettings>) => {
+    if (!info) return;
+    const proxy: ProxySettings = {
+      ...info.proxy,
+      url: url.trim() || null,
+      pac_url: pacUrl.trim() || null,
+      bypass: bypass
+        .split(",")
+        .map((h) => h.trim())
+        .filter(Boolean),
+      ...changes,
+    };
+    if (JSON.stringify(proxy) === JSON.stringify(info.proxy)) return;
+    update({ proxy });
+  };
+
+  const addBundle = async () => {
+    if (!info) return;
+    const selected = await open({
+      multiple: false,
+      filters: [{ name: "PEM certificates", extensions: ["pem", "crt", "cer"] }],
+    });
+    if (typeof selected !== "string" || info.ca_bundles.includes(selected)) return;
+    update({ caBundles: [...info.ca_bundles, selected] });
+  };
+
+  if (!info) return null;
+
+  const mode = info.proxy.mode;
+
+  return (
+    <section style={{ marginTop: "1.5rem" }}>
+      <h2 style={{ fontSize: "1rem", fontWeight: 600, marginBottom: "0.25rem" }}>Network</h2>
+      <p style={{ fontSize: "0.78rem", color: "#666", marginTop: 0, marginBottom: "0.75rem" }}>
+        For networks where traffic has to go through a proxy, or where TLS is
+        inspected with your organization's own certificates. Changes apply to
+        the next connection.
+      </p>
+
+      <div
+        style={{
+          padding: "0.75rem",
+          border: "1px solid #e5e7eb",
+          borderRadius: 8,
+          fontSize: "0.8rem",
+          display: "grid",
+          gridTemplateColumns: "auto 1fr",
+          gap: "0.4rem 0.75rem",
+          alignItems: "center",
+        }}
+      >
+        <span style={{ fontWeight: 600 }}>Proxy</span>
+        <select
+          value={mode}
+          onChange={(e) => saveProxy({ mode: e.target.value as ProxyMode })}
+          style={inputStyle}
+        >
+          {MODES.map((m) => (
+            <option key={m.value} value={m.value}>
+              {m.label}
+            </option>
+          ))}
+        </select>
+
+        {mode === "manual" && (
+          <>
+            <span style={{ fontWeight: 600 }}>URL</span>
+            <input
+              value={url}
+              placeholder="http://proxy.example:3128 or socks5://…"
+              onChange={(e) => setUrl(e.target.value)}
+              onBlur={() => saveProxy({})}
+              onKeyDown={(e) => e.key === "Enter" && saveProxy({})}
+              style={inputStyle}
+            />
+          </>
+        )}
+
+        {mode === "pac" && (
+          <>
+            <span style={{ fontWeight: 600 }}>PAC URL</span>
+            <input
+              value={pacUrl}
+              placeholder="http://wpad.example/proxy.pac"
+              onChange={(e) => setPacUrl(e.target.value)}
+              onBlur={() => saveProxy({})}
+              onKeyDown={(e) => e.key === "Enter" && saveProxy({})}
+              style={inputStyle}
+            />
+          </>
+        )}
+
+        {(mode === "manual" || mode === "pac") && (
+          <>
+            <span style={{ fontWeight: 600 }}>Bypass</span>
+            <input
+              value={bypass}
+              placeholder="corp.example, 10.0.0.5"
+              onChange={(e) => setBypass(e.target.value)}
+              onBlur={() => saveProxy({})}
+              onKeyDown={(e) => e.key === "Enter" && saveProxy({})}
+              style={inputStyle}
+            />
+          </>
+        )}
+
+        <span style={{ fontWeight: 600, alignSelf: "start" }}>CA certificates</span>
+        <div style={{ display: "flex", flexDirection: "column", gap: 4 }}>
+          {info.ca_bundles.map((path) => (
+            <div key={path} style={{ display: "flex", alignItems: "center", gap: 6 }}>
+              <code style={{ wordBreak: "break-all", flex: 1 }}>{path}</code>
+              <button
+                style={buttonStyle}
+                onClick={() => update({ caBundles: info.ca_bundles.filter((p) => p !== path) })}
+              >
+                Remove
+              </button>
+            </div>
+          ))}
+          <div>
+            <button style={buttonStyle} onClick={addBundle}>
+              Add PEM file…
+            </button>
+          </div>
+        </div>
+      </div>
+
+      <p style={{ fontSize: "0.74rem", color: "#666", margin: "0.4rem 0 0 0" }}>
+        Connecting {info.active}.
+      </p>
+
+      {error && (
+        <p
+          role="alert"
+          style={{ color: "#dc2626", fontSize: "0.78rem", marginTop: "0.5rem", marginBottom: 0 }}
+        >
+          {error}
+        </p>
+      )}
+    </section>
+  );
+}
@@ -1,6 +1,7 @@
-import { useState, useEffect } from "react";
+import { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import BeakrLogo from "./BeakrLogo";
+import EnvironmentSettings from "./EnvironmentSettings";
 
 interface PairingScreenProps {
   onPaired: () => void;
@@ -31,18 +32,6 @@ export default function PairingScreen({ onPaired }: PairingScreenProps) {
     }
   };
 
-  // Determine which environment we're pointing at (read from Rust side)
-  const [envLabel, setEnvLabel] = useState("...");
-  useEffect(() => {
-    invoke<string>("get_ws_url")
-      .then((url) => {
-        if (url.includes("sandbox")) setEnvLabel("Sandbox");
-        else if (url.includes("localhost")) setEnvLabel("Local Dev");
-        else setEnvLabel("Production");
-      })
-      .catch(() => setEnvLabel("Unknown"));
-  }, []);
-
   return (
     <div
       style={{
@@ -168,16 +157,8 @@ export default function PairingScreen({ onPaired }: PairingScreenProps) {
           {loading ? "Pairing..." : "Pair Device"}
         </button>
 
-        <p
-          style={{
-            fontSize: "0.75rem",
-            color: "#9ca3af",
-            textAlign: "center",
-            marginTop: "1rem",
-          }}
-        >
-          Environment: {envLabel}
-        </p>
+        {/* Which server the code is claimed from; switchable before pairing. */}
+        <EnvironmentSettings compact />
       </div>
     </div>
   );
@@ -0,0 +1,204 @@
+import { useState } from "react";
+import { useRunHistory, type OpenedRun, type RunRecord } from "../hooks/useRunHistory";
+
+function basename(path: string): string {
+  const parts = path.replace(/\/+$/, "").split("/");
+  return parts[parts.length - 1] || path;
+}
+
+const OUTCOME_COLORS: Record<RunRecord["outcome"], string> = {
+  succeeded: "#22c55e",
+  failed: "#ef4444",
+  cancelled: "#9ca3af",
+};
+
+function describeRun(run: RunRecord): string {
+  const when = new Date(run.started_at_ms).toLocaleString();
+  const files = run.files_changed.length;
+  const cost = run.total_cost_usd !== undefined ? ` · $${run.total_cost_usd.toFixed(2)}` : "";
+  return `${run.cli} · ${basename(run.working_dir)} · ${when} · ${files} file${files === 1 ? "" : "s"}${cost}`;
+}
+
+/**
+ * Past coding runs (local history): search, open a run's log, and resume its
+ * CLI session with a follow-up prompt. A resumed run is an ordinary run —
+ * it shows in the active run card and lands back in this list when it ends.
+ */
+export default function RunHistory() {
+  const [query, setQuery] = useState("");
+  const { runs, open, resume } = useRunHistory(query);
+  const [opened, setOpened] = useState<OpenedRun | null>(null);
+  const [followUp, setFollowUp] = useState("");
+  const [error, setError] = useState<string | null>(null);
+
+  const toggle = async (run: RunRecord) => {
+    setError(null);
+    setFollowUp("");
+    if (opened?.request_id === run.request_id) {
+      setOpened(null);
+      return;
+    }
+    try {
+      setOpened(await open(run.request_id));
+    } catch (e) {
+      setError(typeof e === "string" ? e : "Could not open that run.");
+    }
+  };
+
+  const submitResume = async () => {
+    if (!opened) return;
+    try {
+      await resume(opened.request_id, followUp);
+      setFollowUp("");
+      setOpened(null);
+    } catch (e) {
+      setError(typeof e === "string" ? e : "Could not resume that run.");
+    }
+  };
+
+  return (
+    <section style={{ marginTop: "1.5rem" }}>
+      <h2 style={{ fontSize: "1rem", fontWeight: 600, marginBottom: "0.5rem" }}>
+        Past coding runs
+      </h2>
+      <input
+        value={query}
+        onChange={(e) => setQuery(e.target.value)}
+        placeholder="Search prompts, folders, files…"
+        style={{
+          width: "100%",
+          boxSizing: "border-box",
+          padding: "0.35rem 0.5rem",
+          border: "1px solid #ddd",
+          borderRadius: 6,
+          fontSize: "0.85rem",
+          marginBottom: "0.5rem",
+        }}
+      />
+      {runs.length === 0 && (
+        <p style={{ fontSize: "0.78rem", color: "#666", margin: 0 }}>
+          {query ? "No runs match." : "No coding runs yet."}
+        </p>
+      )}
+      {runs.map((run) => (
+        <div
+          key={run.request_id}
+          style={{ borderTop: "1px solid #eee", padding: "0.45rem 0" }}
+        >
+          <button
+            onClick={() => void toggle(run)}
+            style={{
+              display: "flex",
+              gap: 8,
+              alignItems: "baseline",
+              width: "100%",
+              background: "none",
+              border: "none",
+              padding: 0,
+              textAlign: "left",
+              cursor: "pointer",
+            }}
+          >
+            <span
+              title={run.outcome}
+              style={{
+                width: 8,
+                height: 8,
+                borderRadius: "50%",
+                backgroundColor: OUTCOME_COLORS[run.outcome],
+                flexShrink: 0,
+              }}
+            />
+            <span style={{ minWidth: 0 }}>
+              <span
+                style={{
+                  display: "block",
+                  fontSize: "0.85rem",
+                  whiteSpace: "nowrap",
+                  overflow: "hidden",
+                  textOverflow: "ellipsis",
+                }}
+              >
+                {run.prompt}
+              </span>
+              <span style={{ display: "block", fontSize: "0.72rem", color: "#6b7280" }}>
+                {describeRun(run)}
+              </span>
+            </span>
+          </button>
+
+          {opened?.request_id === run.request_id && (
+            <div style={{ marginTop: "0.5rem" }}>
+              {opened.error && (
+                <p style={{ fontSize: "0.75rem", color: "#dc2626", margin: "0 0 0.4rem 0" }}>
+                  {opened.error}
+                </p>
+              )}
+              {opened.log !== null ? (
+                <pre
+                  style={{
+                    maxHeight: 240,
+                    overflow: "auto",
+                    fontSize: "0.7rem",
+                    background: "#f9fafb",
+                    border: "1px solid #eee",
+                    borderRadius: 6,
+                    padding: "0.5rem",
+                    margin: "0 0 0.5rem 0",
+                    whiteSpace: "pre-wrap",
+                  }}
+                >
+                  {opened.log_truncated ? "…\n" : ""}
+                  {opened.log}
+                </pre>
+              ) : (
+                <p style={{ fontSize: "0.75rem", color: "#666", margin: "0 0 0.5rem 0" }}>
+                  The log for this run is no longer on disk.
+                </p>
+              )}
+              {opened.session_id && (
+                <div style={{ display: "flex", gap: 6 }}>
+                  <input
+                    value={followUp}
+                    onChange={(e) => setFollowUp(e.target.value)}
+                    placeholder="Follow-up prompt to continue this session"
+                    style={{
+                      flex: 1,
+                      padding: "0.3rem 0.5rem",
+                      border: "1px solid #ddd",
+                      borderRadius: 6,
+                      fontSize: "0.8rem",
+                    }}
+                  />
+                  <button
+                    onClick={() => void submitResume()}
+                    disabled={!followUp.trim()}
+                    style={{
+                      padding: "0.3rem 0.8rem",
+                      borderRadius: 6,
+                      border: "1px solid #d1d5db",
+                      background: "white",
+                      fontSize: "0.8rem",
+                      fontWeight: 600,
+                      cursor: followUp.trim() ? "pointer" : "default",
+                    }}
+                  >
+                    Resume
+                  </button>
+                </div>
+              )}
+            </div>
+          )}
+        </div>
+      ))}
+      {error && (
+        <p
+          role="alert"
+          style={{ color: "#dc2626", fontSize: "0.78rem", marginTop: "0.5rem", marginBottom: 0 }}
+        >
+          {error}
+        </p>
+      )}
+    </section>
+  );
+}
@@ -0,0 +1,104 @@
+import { useEffect, useRef } from "react";
+import { useRunChunks, type RunChunk } from "../hooks/useRunChunks";
+
+/** One line per activity; text fragments run together the way they stream. */
+function renderChunks(chunks: RunChunk[]): string {
+  let out = "";
+  const line = (s: string) => {
+    if (out && !out.endsWith("\n")) out += "\n";
+    out += `${s}\n`;
+  };
+  for (const chunk of chunks) {
+    switch (chunk.kind) {
+      case "text":
+        out += chunk.text ?? "";
+        break;
+      case "tool":
+        if (chunk.text) line(`→ ${chunk.text}`);
+        break;
+      case "file_changed":
+        if (chunk.path) line(`   [${chunk.change ?? "changed"}] ${chunk.path}`);
+        break;
+      case "status":
+        if (chunk.text) line(`[${chunk.text}]`);
+        break;
+      case "turn":
+        if (chunk.text) line(`\n> ${chunk.text}\n`);
+        break;
+      case "permission_request":
+        if (chunk.permission) {
+          const { tool, input } = chunk.permission;
+          const what = typeof input.command === "string" ? input.command : JSON.stringify(input);
+          line(`[asks to use ${tool}: ${what}]`);
+        }
+        break;
+      case "permission_decision":
+        if (chunk.decision) {
+          const { allow, message } = chunk.decision;
+          line(allow ? "[allowed]" : `[denied${message ? `: ${message}` : ""}]`);
+        }
+        break;
+      case "session":
+        line(`[session started: ${[chunk.cli, chunk.model].filter(Boolean).join(" · ")}]`);
+        break;
+    }
+  }
+  return out;
+}
+
+function describeSpend(chunks: RunChunk[]): string | null {
+  const usage = [...chunks].reverse().find((c) => c.kind === "usage")?.usage;
+  const cost = [...chunks].reverse().find((c) => c.kind === "cost")?.total_cost_usd;
+  const parts: string[] = [];
+  if (usage) {
+    parts.push(`${usage.input_tokens + usage.cache_write_tokens} in · ${usage.output_tokens} out`);
+  }
+  if (cost !== undefined) parts.push(`$${cost.toFixed(2)}`);
+  return parts.length ? parts.join(" · ") : null;
+}
+
+/**
+ * The live progress of a coding run inside the app window — the same
+ * stream the Terminal view tails, without leaving Beakr. Follows the bottom
+ * while the user hasn't scrolled up.
+ */
+export default function RunProgress({ requestId }: { requestId: string }) {
+  const chunks = useRunChunks(requestId);
+  const pre = useRef<HTMLPreElement>(null);
+  const following = useRef(true);
+
+  useEffect(() => {
+    const el = pre.current;
+    if (el && following.current) el.scrollTop = el.scrollHeight;
+  }, [chunks]);
+
+  const spend = describeSpend(chunks);
+
+  return (
+    <div style={{ marginTop: "0.75rem" }}>
+      <pre
+        ref={pre}
+        onScroll={(e) => {
+          const el = e.currentTarget;
+          following.current = el.scrollHeight - el.scrollTop - el.clientHeight < 24;
+        }}
+        style={{
+          maxHeight: 260,
+          overflow: "auto",
+          fontSize: 11,
+          background: "#fff",
+          border: "1px solid #e5e7eb",
+          borderRadius: 6,
+          padding: "0.5rem",
+          margin: 0,
+          whiteSpace: "pre-wrap",
+        }}
+      >
+        {chunks.length ? renderChunks(chunks) : "Waiting for the first output…"}
+      </pre>
+      {spend && (
+        <div style={{ fontSize: 11, color: "#6b7280", marginTop: 4 }}>{spend}</div>
+      )}
+    </div>
+  );
+}
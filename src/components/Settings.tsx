@@ -4,8 +4,14 @@ import BeakrLogo from "./BeakrLogo";
 import ConnectionStatus from "./ConnectionStatus";
 import ActiveRunCard from "./ActiveRunCard";
 import ActivityFeed from "./ActivityFeed";
+import RunHistory from "./RunHistory";
 import FolderPicker from "./FolderPicker";
 import CodingAgentSettings from "./CodingAgentSettings";
+import McpServerSettings from "./McpServerSettings";
+import DocumentSearchSettings from "./DocumentSearchSettings";
+import NetworkSettings from "./NetworkSettings";
+import EnvironmentSettings from "./EnvironmentSettings";
+import DiagnosticsSettings from "./DiagnosticsSettings";
 import SessionConnect from "./SessionConnect";
 import UpdateBanner from "./UpdateBanner";
 import { useUpdater } from "../hooks/useUpdater";
@@ -70,7 +76,7 @@ export default function Settings({ onUnlink }: SettingsProps) {
             cursor: "pointer",
           }}
         >
-          Unlink Device
+          Unlink All
         </button>
       </div>
 
@@ -82,6 +88,8 @@ export default function Settings({ onUnlink }: SettingsProps) {
 
       <ActivityFeed />
 
+      <RunHistory />
+
       <section style={{ marginTop: "1.5rem" }}>
         <h2 style={{ fontSize: "1rem", fontWeight: 600, marginBottom: "0.75rem" }}>
           Device Name
@@ -160,6 +168,16 @@ export default function Settings({ onUnlink }: SettingsProps) {
 
       <SessionConnect provider="benchling" displayName="Benchling" />
 
+      <McpServerSettings />
+
+      <DocumentSearchSettings />
+
+      <NetworkSettings />
+
+      <EnvironmentSettings />
+
+      <DiagnosticsSettings />
+
       <footer
         style={{
           marginTop: "1.5rem",
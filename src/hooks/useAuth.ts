@@ -1,54 +1,32 @@
-import { useEffect, useRef, useState } from "react";
+import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 
 /**
- * Manages device token lifecycle:
- * 1. On mount: read stored token, pass to Rust, auto-connect
- * 2. Listen for `token_invalid` event from Rust (revoked) → clear store
+ * Whether this device is paired with any account:
+ * 1. On mount: ask Rust, which connects every pairing on startup
+ * 2. Listen for `token_invalid` from Rust (a pairing was revoked and removed)
+ *    → check again; the last one gone sends the window to pairing
  *
  * No periodic refresh needed — device tokens are long-lived.
  */
 export function useAuth() {
   const [hasToken, setHasToken] = useState<boolean | null>(null);
-  const didConnect = useRef(false);
 
   useEffect(() => {
     let cancelled = false;
 
-    async function init() {
-      try {
-        // Check if we have a stored token
-        const token = await invoke<string | null>("get_stored_token");
-        if (cancelled) return;
+    const check = () =>
+      invoke<unknown[]>("get_pairings")
+        .then((pairings) => {
+          if (!cancelled) setHasToken(pairings.length > 0);
+        })
+        .catch(() => {
+          if (!cancelled) setHasToken(false);
+        });
 
-        if (token) {
-          // Pass token to Rust WS client and connect
-          await invoke("set_auth_token", { token });
-          setHasToken(true);
-
-          if (!didConnect.current) {
-            didConnect.current = true;
-            invoke("connect_ws").catch(() => {
-              didConnect.current = false;
-            });
-          }
-        } else {
-          setHasToken(false);
-        }
-      } catch {
-        if (!cancelled) setHasToken(false);
-      }
-    }
-
-    init();
-
-    // Listen for token_invalid event (device revoked on server)
-    const unlisten = listen("token_invalid", () => {
-      invoke("clear_token").catch(() => {});
-      setHasToken(false);
-      didConnect.current = false;
-    });
+    check();
+    const unlisten = listen("token_invalid", check);
 
     return () => {
       cancelled = true;
@@ -58,13 +36,11 @@ export function useAuth() {
 
   const clearToken = async () => {
     try {
-      await invoke("disconnect_ws");
-      await invoke("clear_token");
+      await invoke("remove_all_pairings");
     } catch {
       // ignore
     }
     setHasToken(false);
-    didConnect.current = false;
   };
 
   return { hasToken, clearToken };
@@ -0,0 +1,54 @@
+import { useCallback, useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/core";
+
+export interface Pairing {
+  id: string;
+  name: string;
+  environment: string;
+  environment_name: string;
+  ws_url: string;
+  /** The scoped folders this account reaches; null for all of them. */
+  folders: string[] | null;
+  status: string;
+  device_id: string | null;
+}
+
+/**
+ * The accounts this device is paired with and each one's live connection
+ * status, kept current from `ws:status_changed`.
+ */
+export function usePairings() {
+  const [pairings, setPairings] = useState<Pairing[] | null>(null);
+
+  const refresh = useCallback(() => {
+    invoke<Pairing[]>("get_pairings")
+      .then(setPairings)
+      .catch(() => setPairings([]));
+  }, []);
+
+  useEffect(() => {
+    refresh();
+
+    const unlistenStatus = listen<{ pairing: string; status: string }>(
+      "ws:status_changed",
+      (event) => {
+        const { pairing, status } = event.payload;
+        setPairings((current) =>
+          current?.map((p) => (p.id === pairing ? { ...p, status } : p)) ?? current,
+        );
+        // The device id arrives with the connection.
+        if (status === "connected") refresh();
+      },
+    );
+    // A revoked pairing has been removed on the Rust side.
+    const unlistenRevoked = listen("token_invalid", refresh);
+
+    return () => {
+      unlistenStatus.then((fn) => fn());
+      unlistenRevoked.then((fn) => fn());
+    };
+  }, [refresh]);
+
+  return { pairings, refresh };
+}
@@ -0,0 +1,75 @@
+import { listen } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect, useState } from "react";
+
+export interface RunChunk {
+  kind: string;
+  text?: string;
+  path?: string;
+  change?: string;
+  command?: string;
+  total_cost_usd?: number;
+  usage?: {
+    input_tokens: number;
+    output_tokens: number;
+    cache_read_tokens: number;
+    cache_write_tokens: number;
+  };
+  cli?: string;
+  model?: string;
+  permission?: { id: string; tool: string; input: Record<string, unknown> };
+  decision?: { allow: boolean; message?: string };
+}
+
+interface LiveChunk {
+  request_id: string;
+  seq: number;
+  chunk: RunChunk;
+}
+
+/**
+ * Every chunk of one coding run, in order: what the run already streamed
+ * (get_coding_run_chunks, so a window opened mid-run catches up) followed by
+ * coding_run:chunk events. Events are buffered until the backfill lands and
+ * stitched by sequence number, so nothing is shown twice or dropped.
+ */
+export function useRunChunks(requestId: string | null) {
+  const [chunks, setChunks] = useState<RunChunk[]>([]);
+
+  useEffect(() => {
+    setChunks([]);
+    if (!requestId) return;
+    let cancelled = false;
+    let lastSeq: number | null = null;
+    const pending: LiveChunk[] = [];
+
+    const append = (entries: LiveChunk[]) => {
+      const fresh = entries.filter((e) => lastSeq === null || e.seq > lastSeq);
+      if (fresh.length === 0) return;
+      lastSeq = fresh[fresh.length - 1].seq;
+      setChunks((prev) => [...prev, ...fresh.map((e) => e.chunk)]);
+    };
+
+    const unlisten = listen<LiveChunk>("coding_run:chunk", (event) => {
+      if (event.payload.request_id !== requestId) return;
+      if (lastSeq === null) pending.push(event.payload);
+      else append([event.payload]);
+    });
+
+    invoke<LiveChunk[]>("get_coding_run_chunks", { requestId })
+      .catch(() => [] as LiveChunk[])
+      .then((backfill) => {
+        if (cancelled) return;
+        lastSeq = 0;
+        append(backfill);
+        append(pending);
+      });
+
+    return () => {
+      cancelled = true;
+      unlisten.then((fn) => fn());
+    };
+  }, [requestId]);
+
+  return chunks;
+}
@@ -0,0 +1,55 @@
+import { listen } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/core";
+import { useCallback, useEffect, useState } from "react";
+
+export interface RunRecord {
+  request_id: string;
+  cli: string;
+  working_dir: string;
+  prompt: string;
+  session_id?: string;
+  started_at_ms: number;
+  ended_at_ms: number;
+  outcome: "succeeded" | "failed" | "cancelled";
+  error?: string;
+  files_changed: string[];
+  total_cost_usd?: number;
+  log_path?: string;
+}
+
+export interface OpenedRun extends RunRecord {
+  /** Tail of the run's plain-text log; null once the log is gone. */
+  log: string | null;
+  log_truncated: boolean;
+}
+
+/**
+ * Past coding runs from the local history, filtered by `query`. Refreshes
+ * whenever a run ends (the active run clearing), so a finished run shows up
+ * without a reload.
+ */
+export function useRunHistory(query: string) {
+  const [runs, setRuns] = useState<RunRecord[]>([]);
+
+  const refresh = useCallback(() => {
+    invoke<RunRecord[]>("list_coding_runs", { query, limit: 50 })
+      .then(setRuns)
+      .catch(() => setRuns([]));
+  }, [query]);
+
+  useEffect(() => {
+    refresh();
+    const unlisten = listen<{ active: boolean }>("coding_run:changed", (event) => {
+      if (!event.payload.active) refresh();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [refresh]);
+
+  const open = (requestId: string) => invoke<OpenedRun>("open_coding_run", { requestId });
+  const resume = (requestId: string, prompt: string) =>
+    invoke<string>("resume_coding_run", { requestId, prompt });
+
+  return { runs, open, resume };
+}